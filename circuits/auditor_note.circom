@@ -0,0 +1,88 @@
+pragma circom 2.1.6;
+
+include "circomlib/circuits/poseidon.circom";
+include "circomlib/circuits/bitify.circom";
+include "circomlib/circuits/escalarmulfix.circom";
+include "circomlib/circuits/escalarmulany.circom";
+
+/*
+ * Auditor Note Circuit
+ *
+ * Proves that an auditor ciphertext encrypts the opening of a commitment
+ * to the pool's auditor key (Baby Jubjub), without revealing the opening:
+ *
+ *   ephemeralKey  = ephemeralSecret * Base8
+ *   shared        = ephemeralSecret * auditorPublicKey
+ *   ciphertext[i] = [publicKey, amount, nonce][i] + Poseidon(shared.x, shared.y, i)
+ *
+ * Public inputs:
+ * - auditorPublicKey: Pool's auditor key (x, y)
+ * - commitment: Poseidon(publicKey, amount, nonce) being appended
+ * - ephemeralKey: Sender's ephemeral key (x, y)
+ * - ciphertext: Masked publicKey, amount, nonce
+ *
+ * Private inputs:
+ * - publicKey, amount, nonce: Opening of the commitment
+ * - ephemeralSecret: Ephemeral scalar, below the Base8 subgroup order
+ */
+
+template AuditorNote() {
+    // Public inputs
+    signal input auditorPublicKey[2];
+    signal input commitment;
+    signal input ephemeralKey[2];
+    signal input ciphertext[3];
+
+    // Private inputs
+    signal input publicKey;
+    signal input amount;
+    signal input nonce;
+    signal input ephemeralSecret;
+
+    // 1. Verify commitment opening
+    component commitmentHasher = Poseidon(3);
+    commitmentHasher.inputs[0] <== publicKey;
+    commitmentHasher.inputs[1] <== amount;
+    commitmentHasher.inputs[2] <== nonce;
+    commitment === commitmentHasher.out;
+
+    // Amounts are u64, which is what the auditor decodes
+    component amountBits = Num2Bits(64);
+    amountBits.in <== amount;
+
+    // 2. Ephemeral key = ephemeralSecret * Base8 (the subgroup order is below 2^251)
+    component secretBits = Num2Bits(251);
+    secretBits.in <== ephemeralSecret;
+
+    var BASE8[2] = [
+        5299619240641551281634865583518297030282874472190772894086521144482721001553,
+        16950150798460657717958625567821834550301663161624707787222815936182638968203
+    ];
+    component ephemeral = EscalarMulFix(251, BASE8);
+    for (var i = 0; i < 251; i++) {
+        ephemeral.e[i] <== secretBits.out[i];
+    }
+    ephemeralKey[0] === ephemeral.out[0];
+    ephemeralKey[1] === ephemeral.out[1];
+
+    // 3. Shared point = ephemeralSecret * auditorPublicKey
+    component shared = EscalarMulAny(251);
+    for (var i = 0; i < 251; i++) {
+        shared.e[i] <== secretBits.out[i];
+    }
+    shared.p[0] <== auditorPublicKey[0];
+    shared.p[1] <== auditorPublicKey[1];
+
+    // 4. Verify ciphertext[i] = plaintext[i] + Poseidon(shared.x, shared.y, i)
+    var plaintext[3] = [publicKey, amount, nonce];
+    component masks[3];
+    for (var i = 0; i < 3; i++) {
+        masks[i] = Poseidon(3);
+        masks[i].inputs[0] <== shared.out[0];
+        masks[i].inputs[1] <== shared.out[1];
+        masks[i].inputs[2] <== i;
+        ciphertext[i] === plaintext[i] + masks[i].out;
+    }
+}
+
+component main {public [auditorPublicKey, commitment, ephemeralKey, ciphertext]} = AuditorNote();
@@ -34,7 +34,7 @@ BLUE='\033[0;34m'
 NC='\033[0m' # No Color
 
 # Circuit names
-CIRCUITS=("transfer" "balance" "ring_signature")
+CIRCUITS=("transfer" "balance" "ring_signature" "auditor_note")
 
 for CIRCUIT in "${CIRCUITS[@]}"; do
     echo -e "${BLUE}Building ${CIRCUIT} circuit...${NC}"
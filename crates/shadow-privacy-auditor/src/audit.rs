@@ -0,0 +1,431 @@
+//! Ledger replay for the auditor
+//!
+//! A pool with an auditor logs an `AuditorNoteCreated` event for every
+//! commitment it appends, after the Deposit, Withdraw or PrivateTransfer
+//! event of the same instruction. The auditor pairs the two to learn how
+//! the note was created, decrypts the note and checks that it opens the
+//! commitment.
+//!
+//! `AuditorSet` events tell which pools this key audits; notes of pools
+//! audited by another key are counted and skipped. Pools whose `AuditorSet`
+//! is older than the replayed ledger are tried anyway.
+
+use crate::error::AuditError;
+use serde::{Serialize, Serializer};
+use shadow_privacy::{serde_helpers, PrivacyEvent, TreeLeaf};
+use shadow_privacy_indexer::{logs::parse_events, LedgerSource, LedgerTransaction};
+use shadow_privacy_merkle::FieldBytes;
+use shadow_privacy_note::{decrypt_for_auditor, AuditorSecretKey};
+use solana_program::pubkey::Pubkey;
+use std::collections::HashMap;
+
+/// Instruction that created an audited note
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoteKind {
+    Deposit,
+    /// Change note of a withdrawal
+    Withdraw,
+    PrivateTransfer,
+}
+
+impl NoteKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NoteKind::Deposit => "deposit",
+            NoteKind::Withdraw => "withdraw",
+            NoteKind::PrivateTransfer => "private_transfer",
+        }
+    }
+}
+
+/// A decrypted note that opens its commitment
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuditRecord {
+    pub slot: u64,
+    pub signature: String,
+    #[serde(serialize_with = "serde_helpers::pubkey")]
+    pub pool: Pubkey,
+    pub kind: NoteKind,
+    /// Depositor of a deposit, recipient of the withdrawal a change note
+    /// came from; private transfers have none
+    #[serde(serialize_with = "pubkey_option")]
+    pub counterparty: Option<Pubkey>,
+    pub leaf_index: u64,
+    #[serde(serialize_with = "serde_helpers::hex")]
+    pub commitment: FieldBytes,
+    /// Circuit public key owning the note
+    #[serde(serialize_with = "serde_helpers::hex")]
+    pub owner: FieldBytes,
+    pub amount: u64,
+    #[serde(serialize_with = "serde_helpers::hex")]
+    pub nonce: FieldBytes,
+}
+
+/// An auditor note this key could not open
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuditFailure {
+    pub slot: u64,
+    pub signature: String,
+    #[serde(serialize_with = "serde_helpers::pubkey")]
+    pub pool: Pubkey,
+    pub leaf_index: u64,
+    #[serde(serialize_with = "serde_helpers::hex")]
+    pub commitment: FieldBytes,
+    pub reason: String,
+}
+
+/// Counters from one sync
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AuditStats {
+    pub transactions: usize,
+    pub notes: usize,
+    pub failures: usize,
+    /// Auditor notes of pools audited by another key
+    pub foreign: usize,
+}
+
+/// Audited notes rebuilt from chain data and the auditor's secret key
+pub struct Auditor {
+    program_id: Pubkey,
+    secret_key: AuditorSecretKey,
+    public_key: [[u8; 32]; 2],
+    /// Latest `AuditorSet` seen for each pool
+    auditor_keys: HashMap<Pubkey, Option<[[u8; 32]; 2]>>,
+    records: Vec<AuditRecord>,
+    failures: Vec<AuditFailure>,
+}
+
+impl Auditor {
+    pub fn new(program_id: Pubkey, secret_key: AuditorSecretKey) -> Self {
+        Auditor {
+            program_id,
+            public_key: secret_key.public_key(),
+            secret_key,
+            auditor_keys: HashMap::new(),
+            records: Vec::new(),
+            failures: Vec::new(),
+        }
+    }
+
+    pub fn public_key(&self) -> [[u8; 32]; 2] {
+        self.public_key
+    }
+
+    /// Notes decrypted so far, in ledger order
+    pub fn records(&self) -> &[AuditRecord] {
+        &self.records
+    }
+
+    pub fn failures(&self) -> &[AuditFailure] {
+        &self.failures
+    }
+
+    /// Drain `source`, decrypting every auditor note in it
+    pub fn sync<S: LedgerSource + ?Sized>(&mut self, source: &mut S) -> Result<AuditStats, AuditError> {
+        let mut stats = AuditStats::default();
+        while let Some(tx) = source.next_transaction()? {
+            self.apply_transaction(&tx, &mut stats);
+        }
+        Ok(stats)
+    }
+
+    /// Apply one full transaction
+    pub fn audit_transaction(&mut self, tx: &LedgerTransaction) -> AuditStats {
+        let mut stats = AuditStats::default();
+        self.apply_transaction(tx, &mut stats);
+        stats
+    }
+
+    fn apply_transaction(&mut self, tx: &LedgerTransaction, stats: &mut AuditStats) {
+        stats.transactions += 1;
+        if !tx.success {
+            return;
+        }
+
+        // Latest note-creating event per pool in this transaction
+        let mut created: HashMap<Pubkey, (NoteKind, Option<Pubkey>)> = HashMap::new();
+        for event in parse_events(&self.program_id, &tx.logs).events {
+            match event {
+                PrivacyEvent::Deposit { pool, depositor, .. } => {
+                    created.insert(pool, (NoteKind::Deposit, Some(depositor)));
+                }
+                PrivacyEvent::Withdraw { pool, recipient, .. } => {
+                    created.insert(pool, (NoteKind::Withdraw, Some(recipient)));
+                }
+                PrivacyEvent::PrivateTransfer { pool, .. } => {
+                    created.insert(pool, (NoteKind::PrivateTransfer, None));
+                }
+                PrivacyEvent::AuditorSet { pool, auditor_key } => {
+                    self.auditor_keys.insert(pool, auditor_key);
+                }
+                PrivacyEvent::AuditorNoteCreated {
+                    pool,
+                    leaf,
+                    ephemeral_key,
+                    ciphertext,
+                } => {
+                    if matches!(self.auditor_keys.get(&pool), Some(key) if *key != Some(self.public_key)) {
+                        stats.foreign += 1;
+                        continue;
+                    }
+                    let note = AuditorNote {
+                        pool,
+                        leaf,
+                        ephemeral_key,
+                        ciphertext,
+                        created: created.remove(&pool),
+                    };
+                    self.audit_note(tx, note, stats);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn audit_note(&mut self, tx: &LedgerTransaction, note: AuditorNote, stats: &mut AuditStats) {
+        let decrypted = decrypt_for_auditor(
+            &self.secret_key,
+            &note.ephemeral_key,
+            &note.ciphertext,
+            &note.leaf.commitment,
+        );
+        let failure = |reason: String| AuditFailure {
+            slot: tx.slot,
+            signature: tx.signature.clone(),
+            pool: note.pool,
+            leaf_index: note.leaf.index,
+            commitment: note.leaf.commitment,
+            reason,
+        };
+
+        match (decrypted, note.created) {
+            (Ok(opening), Some((kind, counterparty))) => {
+                stats.notes += 1;
+                self.records.push(AuditRecord {
+                    slot: tx.slot,
+                    signature: tx.signature.clone(),
+                    pool: note.pool,
+                    kind,
+                    counterparty,
+                    leaf_index: note.leaf.index,
+                    commitment: note.leaf.commitment,
+                    owner: opening.owner,
+                    amount: opening.amount,
+                    nonce: opening.nonce,
+                });
+            }
+            (Ok(_), None) => {
+                stats.failures += 1;
+                self.failures.push(failure("no Deposit, Withdraw or PrivateTransfer event".to_string()));
+            }
+            (Err(error), _) => {
+                stats.failures += 1;
+                self.failures.push(failure(error.to_string()));
+            }
+        }
+    }
+}
+
+/// An `AuditorNoteCreated` event with the event that created its note
+struct AuditorNote {
+    pool: Pubkey,
+    leaf: TreeLeaf,
+    ephemeral_key: [[u8; 32]; 2],
+    ciphertext: [[u8; 32]; 3],
+    created: Option<(NoteKind, Option<Pubkey>)>,
+}
+
+fn pubkey_option<S: Serializer>(key: &Option<Pubkey>, serializer: S) -> Result<S::Ok, S::Error> {
+    match key {
+        Some(key) => serializer.collect_str(key),
+        None => serializer.serialize_none(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use borsh::BorshSerialize;
+    use rand_core::OsRng;
+    use shadow_privacy_merkle::{field_from_u64, public_key, Note};
+    use shadow_privacy_note::encrypt_for_auditor;
+    use std::collections::VecDeque;
+
+    fn ledger_transaction(program_id: &Pubkey, slot: u64, success: bool, events: &[PrivacyEvent]) -> LedgerTransaction {
+        let mut logs = vec![format!("Program {} invoke [1]", program_id)];
+        for event in events {
+            logs.push(format!("Program data: {}", STANDARD.encode(event.try_to_vec().unwrap())));
+        }
+        logs.push(format!("Program {} success", program_id));
+        LedgerTransaction {
+            slot,
+            signature: format!("sig{}", slot),
+            success,
+            logs,
+        }
+    }
+
+    fn note(owner_seed: u64, amount: u64, nonce: u64) -> Note {
+        Note {
+            owner: public_key(&field_from_u64(owner_seed)).unwrap(),
+            amount,
+            nonce: field_from_u64(nonce),
+        }
+    }
+
+    /// `AuditorNoteCreated` for `note`, encrypted to `auditor_key`
+    fn auditor_note_created(pool: Pubkey, index: u64, note: &Note, auditor_key: &[[u8; 32]; 2]) -> PrivacyEvent {
+        let encryption = encrypt_for_auditor(auditor_key, note, &mut OsRng).unwrap();
+        PrivacyEvent::AuditorNoteCreated {
+            pool,
+            leaf: TreeLeaf { index, commitment: note.commitment().unwrap() },
+            ephemeral_key: encryption.ephemeral_key,
+            ciphertext: encryption.ciphertext,
+        }
+    }
+
+    fn deposit(pool: Pubkey, depositor: Pubkey, index: u64, note: &Note) -> PrivacyEvent {
+        PrivacyEvent::Deposit {
+            pool,
+            depositor,
+            leaf: TreeLeaf { index, commitment: note.commitment().unwrap() },
+            root: [0; 32],
+            amount: note.amount,
+        }
+    }
+
+    #[test]
+    fn test_notes_tagged_with_creating_instruction() {
+        let program_id = Pubkey::new_unique();
+        let pool = Pubkey::new_unique();
+        let depositor = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let secret_key = AuditorSecretKey::generate(&mut OsRng);
+        let key = secret_key.public_key();
+        let (deposited, change, transferred) = (note(1, 100, 1), note(1, 40, 2), note(2, 100, 3));
+
+        let mut ledger = VecDeque::from([
+            ledger_transaction(&program_id, 1, true, &[PrivacyEvent::AuditorSet { pool, auditor_key: Some(key) }]),
+            ledger_transaction(
+                &program_id,
+                2,
+                true,
+                &[deposit(pool, depositor, 0, &deposited), auditor_note_created(pool, 0, &deposited, &key)],
+            ),
+            ledger_transaction(
+                &program_id,
+                3,
+                true,
+                &[
+                    PrivacyEvent::Withdraw {
+                        pool,
+                        nullifier: [9; 32],
+                        recipient,
+                        amount: 60,
+                        fee: 0,
+                        record_rent: 0,
+                        change: Some(TreeLeaf { index: 1, commitment: change.commitment().unwrap() }),
+                        root: [0; 32],
+                        delayed: false,
+                    },
+                    auditor_note_created(pool, 1, &change, &key),
+                ],
+            ),
+            ledger_transaction(
+                &program_id,
+                4,
+                true,
+                &[
+                    PrivacyEvent::PrivateTransfer {
+                        pool,
+                        key_image: [8; 32],
+                        ring_size: 11,
+                        leaf: TreeLeaf { index: 2, commitment: transferred.commitment().unwrap() },
+                        root: [0; 32],
+                    },
+                    auditor_note_created(pool, 2, &transferred, &key),
+                ],
+            ),
+            // Rolled back
+            ledger_transaction(
+                &program_id,
+                5,
+                false,
+                &[deposit(pool, depositor, 3, &deposited), auditor_note_created(pool, 3, &deposited, &key)],
+            ),
+        ]);
+
+        let mut auditor = Auditor::new(program_id, secret_key);
+        let stats = auditor.sync(&mut ledger).unwrap();
+        assert_eq!(stats, AuditStats { transactions: 5, notes: 3, failures: 0, foreign: 0 });
+
+        let records = auditor.records();
+        let summary: Vec<_> = records.iter().map(|record| (record.kind, record.counterparty, record.amount)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                (NoteKind::Deposit, Some(depositor), 100),
+                (NoteKind::Withdraw, Some(recipient), 40),
+                (NoteKind::PrivateTransfer, None, 100),
+            ]
+        );
+        assert_eq!(records[2].owner, transferred.owner);
+        assert_eq!(records[2].nonce, transferred.nonce);
+        assert_eq!((records[1].slot, records[1].leaf_index), (3, 1));
+    }
+
+    #[test]
+    fn test_foreign_and_forged_notes() {
+        let program_id = Pubkey::new_unique();
+        let (audited, other_pool, unknown_pool) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let depositor = Pubkey::new_unique();
+        let secret_key = AuditorSecretKey::generate(&mut OsRng);
+        let key = secret_key.public_key();
+        let other_key = AuditorSecretKey::generate(&mut OsRng).public_key();
+        let deposited = note(1, 100, 1);
+
+        // A note whose ciphertext hides another opening than its commitment
+        let mut forged = auditor_note_created(audited, 1, &note(1, 1, 1), &key);
+        if let PrivacyEvent::AuditorNoteCreated { leaf, .. } = &mut forged {
+            leaf.commitment = deposited.commitment().unwrap();
+        }
+
+        let mut ledger = VecDeque::from([
+            ledger_transaction(
+                &program_id,
+                1,
+                true,
+                &[
+                    PrivacyEvent::AuditorSet { pool: audited, auditor_key: Some(key) },
+                    PrivacyEvent::AuditorSet { pool: other_pool, auditor_key: Some(other_key) },
+                ],
+            ),
+            ledger_transaction(
+                &program_id,
+                2,
+                true,
+                &[deposit(other_pool, depositor, 0, &deposited), auditor_note_created(other_pool, 0, &deposited, &other_key)],
+            ),
+            ledger_transaction(&program_id, 3, true, &[deposit(audited, depositor, 1, &deposited), forged]),
+            // Audited since before the ledger starts
+            ledger_transaction(
+                &program_id,
+                4,
+                true,
+                &[deposit(unknown_pool, depositor, 0, &deposited), auditor_note_created(unknown_pool, 0, &deposited, &key)],
+            ),
+            // No creating event
+            ledger_transaction(&program_id, 5, true, &[auditor_note_created(audited, 2, &deposited, &key)]),
+        ]);
+
+        let mut auditor = Auditor::new(program_id, secret_key);
+        let stats = auditor.sync(&mut ledger).unwrap();
+        assert_eq!(stats, AuditStats { transactions: 5, notes: 1, failures: 2, foreign: 1 });
+        assert_eq!(auditor.records()[0].pool, unknown_pool);
+
+        let failures: Vec<_> = auditor.failures().iter().map(|failure| (failure.slot, failure.leaf_index)).collect();
+        assert_eq!(failures, vec![(3, 1), (5, 2)]);
+    }
+}
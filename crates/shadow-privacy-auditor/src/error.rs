@@ -0,0 +1,17 @@
+use shadow_privacy_indexer::IndexerError;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum AuditError {
+    #[error("Ledger source error: {0}")]
+    Source(#[from] IndexerError),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Key file {path}: {reason}")]
+    KeyFile { path: String, reason: String },
+
+    #[error("{0}")]
+    InvalidArgument(String),
+}
@@ -0,0 +1,68 @@
+//! Auditor key files
+//!
+//! A key file holds the secret scalar as 64 hex characters (32 little-endian
+//! bytes). Public keys are written as 128 hex characters, x then y, which is
+//! what `shadow-admin set-auditor --key` takes.
+
+use crate::error::AuditError;
+use shadow_privacy_note::AuditorSecretKey;
+use std::{fs, io::Write, path::Path};
+
+pub fn encode_public_key(key: &[[u8; 32]; 2]) -> String {
+    hex::encode([key[0], key[1]].concat())
+}
+
+pub fn read_secret_key(path: &Path) -> Result<AuditorSecretKey, AuditError> {
+    let key_error = |reason: String| AuditError::KeyFile {
+        path: path.display().to_string(),
+        reason,
+    };
+
+    let contents = fs::read_to_string(path).map_err(|error| key_error(error.to_string()))?;
+    let bytes: [u8; 32] = hex::decode(contents.trim())
+        .map_err(|error| key_error(error.to_string()))?
+        .try_into()
+        .map_err(|_| key_error("expected 32 bytes".to_string()))?;
+    AuditorSecretKey::from_bytes(bytes).map_err(|error| key_error(error.to_string()))
+}
+
+/// Write a new key file, readable by the owner only; never overwrites
+pub fn write_secret_key(path: &Path, key: &AuditorSecretKey) -> Result<(), AuditError> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let mut file = options.open(path).map_err(|error| AuditError::KeyFile {
+        path: path.display().to_string(),
+        reason: error.to_string(),
+    })?;
+    writeln!(file, "{}", hex::encode(key.to_bytes()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_core::OsRng;
+
+    #[test]
+    fn test_key_file_round_trip() {
+        let dir = std::env::temp_dir().join(format!("shadow-audit-key-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("auditor.key");
+        let _ = fs::remove_file(&path);
+
+        let key = AuditorSecretKey::generate(&mut OsRng);
+        write_secret_key(&path, &key).unwrap();
+        assert_eq!(read_secret_key(&path).unwrap().public_key(), key.public_key());
+
+        // Existing keys are never replaced
+        let other = AuditorSecretKey::generate(&mut OsRng);
+        assert!(matches!(write_secret_key(&path, &other), Err(AuditError::KeyFile { .. })));
+
+        fs::write(&path, "00".repeat(32)).unwrap();
+        assert!(matches!(read_secret_key(&path), Err(AuditError::KeyFile { .. })));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,21 @@
+// Shadow Privacy Auditor
+//
+// Tooling for the auditor of a compliance-enabled pool:
+// - Baby Jubjub key generation and key files; the public key is what the
+//   pool authority registers with SetAuditor
+// - Ledger replay that decrypts every `AuditorNoteCreated` note with the
+//   auditor's secret key, checks it opens its commitment, and tags it with
+//   the Deposit, Withdraw (change) or PrivateTransfer that created it
+// - Reports with one row per note and totals per owner, as JSON or CSV
+//
+// Transactions come from any indexer `LedgerSource`.
+
+pub mod audit;
+pub mod error;
+pub mod key;
+pub mod report;
+
+pub use audit::{AuditFailure, AuditRecord, AuditStats, Auditor, NoteKind};
+pub use error::AuditError;
+pub use key::{encode_public_key, read_secret_key, write_secret_key};
+pub use report::{OwnerTotal, Report};
@@ -0,0 +1,170 @@
+// Shadow Privacy audit tool
+//
+// For the auditor of a compliance-enabled pool: generate the auditor key the
+// pool authority registers with `shadow-admin set-auditor`, then decrypt every
+// audited note in a ledger dump and report notes and per-owner totals as
+// JSON or CSV.
+
+use clap::{Parser, Subcommand, ValueEnum};
+use rand_core::OsRng;
+use shadow_privacy_auditor::{encode_public_key, read_secret_key, write_secret_key, AuditError, Auditor, Report};
+use shadow_privacy_indexer::JsonDumpSource;
+use shadow_privacy_note::AuditorSecretKey;
+use solana_program::pubkey::Pubkey;
+use std::{fs, path::PathBuf, str::FromStr};
+
+#[derive(Parser)]
+#[command(name = "shadow-audit", about = "Shadow Privacy auditor keys and note reports")]
+struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Generate an auditor key file and print its public key
+    Keygen {
+        /// Key file to create (never overwritten)
+        #[arg(long, short = 'o')]
+        out: PathBuf,
+    },
+
+    /// Print the public key of a key file
+    Pubkey {
+        #[arg(long, short = 'k')]
+        key: PathBuf,
+    },
+
+    /// Decrypt the audited notes in a ledger dump and report them
+    Report {
+        #[arg(long, short = 'k')]
+        key: PathBuf,
+        /// JSON array of getTransaction results, oldest first
+        #[arg(long)]
+        ledger: PathBuf,
+        #[arg(long, value_parser = parse_pubkey, default_value_t = shadow_privacy::id())]
+        program_id: Pubkey,
+        /// Only report these pools [default: all]
+        #[arg(long = "pool", value_parser = parse_pubkey)]
+        pools: Vec<Pubkey>,
+        #[arg(long, value_enum, default_value_t = Format::Json)]
+        format: Format,
+        /// Table to write in CSV format
+        #[arg(long, value_enum, default_value_t = Table::Notes)]
+        table: Table,
+        /// Output file [default: stdout]
+        #[arg(long, short = 'o')]
+        out: Option<PathBuf>,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Json,
+    Csv,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Table {
+    Notes,
+    Owners,
+}
+
+fn parse_pubkey(value: &str) -> Result<Pubkey, AuditError> {
+    Pubkey::from_str(value).map_err(|_| AuditError::InvalidArgument(format!("invalid address {}", value)))
+}
+
+fn run(cli: Cli) -> Result<(), AuditError> {
+    match cli.command {
+        Commands::Keygen { out } => {
+            let key = AuditorSecretKey::generate(&mut OsRng);
+            write_secret_key(&out, &key)?;
+            println!("Wrote {}", out.display());
+            println!("Public key: {}", encode_public_key(&key.public_key()));
+            println!("Register it with: shadow-admin set-auditor --pool <POOL> --key <PUBLIC KEY>");
+        }
+        Commands::Pubkey { key } => println!("{}", encode_public_key(&read_secret_key(&key)?.public_key())),
+        Commands::Report {
+            key,
+            ledger,
+            program_id,
+            pools,
+            format,
+            table,
+            out,
+        } => {
+            let mut auditor = Auditor::new(program_id, read_secret_key(&key)?);
+            let stats = auditor.sync(&mut JsonDumpSource::from_path(&ledger)?)?;
+            eprintln!(
+                "{} transactions: {} notes, {} failed, {} for other auditors",
+                stats.transactions, stats.notes, stats.failures, stats.foreign
+            );
+
+            let selected = |pool: &Pubkey| pools.is_empty() || pools.contains(pool);
+            let report = Report::new(
+                auditor.records().iter().filter(|note| selected(&note.pool)).cloned().collect(),
+                auditor.failures().iter().filter(|failure| selected(&failure.pool)).cloned().collect(),
+            );
+            let output = match (format, table) {
+                (Format::Json, _) => report.to_json() + "\n",
+                (Format::Csv, Table::Notes) => report.notes_csv(),
+                (Format::Csv, Table::Owners) => report.owners_csv(),
+            };
+            match out {
+                Some(path) => fs::write(path, output)?,
+                None => print!("{}", output),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(error) = run(Cli::parse()) {
+        eprintln!("Error: {}", error);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+
+        let pool = Pubkey::new_unique().to_string();
+        let cli = Cli::try_parse_from([
+            "shadow-audit",
+            "report",
+            "--key",
+            "auditor.key",
+            "--ledger",
+            "ledger.json",
+            "--pool",
+            &pool,
+            "--format",
+            "csv",
+            "--table",
+            "owners",
+        ])
+        .unwrap();
+        match cli.command {
+            Commands::Report {
+                program_id,
+                pools,
+                format,
+                table,
+                ..
+            } => {
+                assert_eq!(program_id, shadow_privacy::id());
+                assert_eq!(pools.len(), 1);
+                assert!(format == Format::Csv && table == Table::Owners);
+            }
+            _ => panic!("expected report"),
+        }
+        assert!(Cli::try_parse_from(["shadow-audit", "report", "--key", "auditor.key"]).is_err());
+    }
+}
@@ -0,0 +1,172 @@
+//! Audit reports
+//!
+//! A report lists every audited note, totals per owner public key and the
+//! notes that failed to open. JSON carries all three; CSV carries one table
+//! at a time.
+
+use crate::audit::{AuditFailure, AuditRecord, NoteKind};
+use serde::Serialize;
+use shadow_privacy::serde_helpers;
+use shadow_privacy_merkle::FieldBytes;
+use std::collections::BTreeMap;
+
+/// Notes and amounts one owner public key received
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct OwnerTotal {
+    #[serde(serialize_with = "serde_helpers::hex")]
+    pub owner: FieldBytes,
+    pub notes: u64,
+    pub deposited: u128,
+    pub withdraw_change: u128,
+    pub transferred: u128,
+    pub total: u128,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Report {
+    pub notes: Vec<AuditRecord>,
+    /// Ordered by owner
+    pub owners: Vec<OwnerTotal>,
+    pub failures: Vec<AuditFailure>,
+}
+
+impl Report {
+    pub fn new(notes: Vec<AuditRecord>, failures: Vec<AuditFailure>) -> Self {
+        let mut owners: BTreeMap<FieldBytes, OwnerTotal> = BTreeMap::new();
+        for note in &notes {
+            let total = owners.entry(note.owner).or_insert_with(|| OwnerTotal {
+                owner: note.owner,
+                ..OwnerTotal::default()
+            });
+            let amount = u128::from(note.amount);
+            total.notes += 1;
+            total.total += amount;
+            match note.kind {
+                NoteKind::Deposit => total.deposited += amount,
+                NoteKind::Withdraw => total.withdraw_change += amount,
+                NoteKind::PrivateTransfer => total.transferred += amount,
+            }
+        }
+
+        Report {
+            notes,
+            owners: owners.into_values().collect(),
+            failures,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("report serializes to JSON")
+    }
+
+    /// One row per note
+    pub fn notes_csv(&self) -> String {
+        let mut csv =
+            "slot,signature,pool,kind,counterparty,leaf_index,commitment,owner,amount,nonce\n".to_string();
+        for note in &self.notes {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{},{}\n",
+                note.slot,
+                note.signature,
+                note.pool,
+                note.kind.as_str(),
+                note.counterparty.map(|key| key.to_string()).unwrap_or_default(),
+                note.leaf_index,
+                hex::encode(note.commitment),
+                hex::encode(note.owner),
+                note.amount,
+                hex::encode(note.nonce),
+            ));
+        }
+        csv
+    }
+
+    /// One row per owner
+    pub fn owners_csv(&self) -> String {
+        let mut csv = "owner,notes,deposited,withdraw_change,transferred,total\n".to_string();
+        for owner in &self.owners {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{}\n",
+                hex::encode(owner.owner),
+                owner.notes,
+                owner.deposited,
+                owner.withdraw_change,
+                owner.transferred,
+                owner.total,
+            ));
+        }
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::pubkey::Pubkey;
+
+    fn record(owner: u8, kind: NoteKind, amount: u64, counterparty: Option<Pubkey>) -> AuditRecord {
+        AuditRecord {
+            slot: 7,
+            signature: "sig7".to_string(),
+            pool: Pubkey::new_from_array([1; 32]),
+            kind,
+            counterparty,
+            leaf_index: 0,
+            commitment: [2; 32],
+            owner: [owner; 32],
+            amount,
+            nonce: [3; 32],
+        }
+    }
+
+    #[test]
+    fn test_owner_totals() {
+        let report = Report::new(
+            vec![
+                record(5, NoteKind::Deposit, u64::MAX, None),
+                record(4, NoteKind::PrivateTransfer, 10, None),
+                record(5, NoteKind::Withdraw, 2, None),
+                record(5, NoteKind::Deposit, 1, None),
+            ],
+            Vec::new(),
+        );
+
+        assert_eq!(report.owners.len(), 2);
+        assert_eq!(report.owners[0].owner, [4; 32]);
+        assert_eq!(report.owners[0].transferred, 10);
+        assert_eq!(
+            report.owners[1],
+            OwnerTotal {
+                owner: [5; 32],
+                notes: 3,
+                deposited: u128::from(u64::MAX) + 1,
+                withdraw_change: 2,
+                transferred: 0,
+                total: u128::from(u64::MAX) + 3,
+            }
+        );
+    }
+
+    #[test]
+    fn test_output_formats() {
+        let depositor = Pubkey::new_from_array([9; 32]);
+        let report = Report::new(vec![record(5, NoteKind::Deposit, 100, Some(depositor))], Vec::new());
+
+        let notes = report.notes_csv();
+        let row: Vec<&str> = notes.lines().nth(1).unwrap().split(',').collect();
+        assert_eq!(row.len(), 10);
+        assert_eq!(row[2], Pubkey::new_from_array([1; 32]).to_string());
+        assert_eq!(row[3..5], ["deposit", depositor.to_string().as_str()]);
+        assert_eq!(row[8], "100");
+
+        let owners = report.owners_csv();
+        assert_eq!(owners.lines().nth(1), Some(format!("{},1,100,0,0,100", hex::encode([5u8; 32])).as_str()));
+
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(json["notes"][0]["kind"], "deposit");
+        assert_eq!(json["notes"][0]["counterparty"], depositor.to_string());
+        assert_eq!(json["notes"][0]["owner"], hex::encode([5u8; 32]));
+        assert_eq!(json["owners"][0]["total"], 100);
+        assert!(json["failures"].as_array().unwrap().is_empty());
+    }
+}
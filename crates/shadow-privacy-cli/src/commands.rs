@@ -0,0 +1,458 @@
+//! Admin command implementations
+
+use crate::{error::CliError, profile::VkPaths};
+use shadow_privacy::state::{
+    CircuitType, ConfigParams, PoolState, ProtocolConfig, RelayerAccount, VerificationKeyAccount,
+};
+use shadow_privacy_client::{
+    build_transaction, decode_account_json, fetch_account, fetch_optional_account, instruction,
+    pda, ClientError, ComputeBudget,
+};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Everything a command needs to talk to one cluster
+pub struct Context {
+    pub rpc: RpcClient,
+    pub program_id: Pubkey,
+    pub keypair_path: PathBuf,
+    /// Pays fees and signs as authority, relayer or reporter; read-only
+    /// commands work without one
+    pub keypair: Result<Keypair, String>,
+    /// Extra requests (priority fee) on top of what each instruction needs
+    pub budget: ComputeBudget,
+    pub vk_paths: VkPaths,
+}
+
+impl Context {
+    pub fn signer(&self) -> Result<&Keypair, CliError> {
+        self.keypair.as_ref().map_err(|reason| CliError::Keypair {
+            path: self.keypair_path.display().to_string(),
+            reason: reason.clone(),
+        })
+    }
+
+    fn send(&self, instructions: &[Instruction]) -> Result<Signature, CliError> {
+        let signer = self.signer()?;
+        let blockhash = self.rpc.get_latest_blockhash()?;
+        let transaction = build_transaction(
+            &self.program_id,
+            instructions,
+            &self.budget,
+            &signer.pubkey(),
+            &[signer],
+            blockhash,
+        );
+        Ok(self.rpc.send_and_confirm_transaction_with_spinner(&transaction)?)
+    }
+}
+
+pub fn init_config(ctx: &Context, params: ConfigParams) -> Result<(), CliError> {
+    if !params.is_valid() {
+        return Err(CliError::InvalidArgument("invalid config parameters".to_string()));
+    }
+
+    let signature = ctx.send(&[instruction::initialize_config(&ctx.program_id, &ctx.signer()?.pubkey(), params)])?;
+    println!("Config: {}", pda::config_address(&ctx.program_id));
+    println!("Signature: {}", signature);
+    Ok(())
+}
+
+pub fn init_pool(ctx: &Context, denomination: u64, tree_depth: u8) -> Result<(), CliError> {
+    let authority = ctx.signer()?.pubkey();
+    let pool = pda::pool_address(&ctx.program_id, &authority, denomination);
+
+    let signature = ctx.send(&[instruction::initialize_pool(&ctx.program_id, &authority, tree_depth, denomination)])?;
+    println!("Pool: {}", pool);
+    println!("Vault: {}", pda::vault_address(&ctx.program_id, &pool));
+    println!("Signature: {}", signature);
+    Ok(())
+}
+
+/// File name the ceremony scripts give a circuit's snarkjs verification key
+fn vk_file_name(circuit_type: CircuitType) -> &'static str {
+    match circuit_type {
+        CircuitType::Transfer => "transfer_verification_key.json",
+        CircuitType::Balance => "balance_verification_key.json",
+        CircuitType::RingSignature => "ring_signature_verification_key.json",
+        CircuitType::AuditorNote => "auditor_note_verification_key.json",
+    }
+}
+
+/// Verification key file for a circuit: `vk_dir` if given, else the profile's path
+pub fn vk_path(paths: &VkPaths, vk_dir: Option<&Path>, circuit_type: CircuitType) -> Result<PathBuf, CliError> {
+    if let Some(dir) = vk_dir {
+        return Ok(dir.join(vk_file_name(circuit_type)));
+    }
+
+    let configured = match circuit_type {
+        CircuitType::Transfer => &paths.transfer,
+        CircuitType::Balance => &paths.balance,
+        CircuitType::RingSignature => &paths.ring_signature,
+        CircuitType::AuditorNote => &paths.auditor_note,
+    };
+    configured.clone().ok_or_else(|| {
+        CliError::InvalidArgument(format!(
+            "no {:?} verification key in the profile; pass --vk-dir",
+            circuit_type
+        ))
+    })
+}
+
+/// Convert a snarkjs verification key to the on-chain encoding
+pub fn load_vk(path: &Path) -> Result<Vec<u8>, CliError> {
+    let vk_error = |reason: String| CliError::VerificationKey {
+        path: path.display().to_string(),
+        reason,
+    };
+
+    let json = fs::read_to_string(path).map_err(|error| vk_error(error.to_string()))?;
+    let vk_data = vk_converter::convert_snarkjs_vk(&json).map_err(|error| vk_error(error.to_string()))?;
+    if vk_data.len() > VerificationKeyAccount::MAX_VK_SIZE {
+        return Err(vk_error(format!(
+            "{} bytes exceeds the {} byte limit",
+            vk_data.len(),
+            VerificationKeyAccount::MAX_VK_SIZE
+        )));
+    }
+    Ok(vk_data)
+}
+
+/// Convert and store verification keys, one transaction per circuit
+///
+/// Every key is converted before anything is sent, so a bad file does not
+/// leave the pool with only some of its keys replaced.
+pub fn upload_vks(
+    ctx: &Context,
+    pool: &Pubkey,
+    circuits: &[CircuitType],
+    vk_dir: Option<&Path>,
+) -> Result<(), CliError> {
+    let mut keys = Vec::with_capacity(circuits.len());
+    for &circuit_type in circuits {
+        let path = vk_path(&ctx.vk_paths, vk_dir, circuit_type)?;
+        keys.push((circuit_type, load_vk(&path)?, path));
+    }
+
+    for (circuit_type, vk_data, path) in keys {
+        let vk_len = vk_data.len();
+        let signature = ctx.send(&[instruction::store_verification_key(
+            &ctx.program_id,
+            pool,
+            &ctx.signer()?.pubkey(),
+            None,
+            circuit_type,
+            vk_data,
+        )])?;
+        println!(
+            "{:?} VK ({} bytes from {}): {}",
+            circuit_type,
+            vk_len,
+            path.display(),
+            pda::verification_key_address(&ctx.program_id, pool, circuit_type)
+        );
+        println!("  Signature: {}", signature);
+    }
+    Ok(())
+}
+
+pub fn propose_authority(ctx: &Context, pool: &Pubkey, new_authority: &Pubkey) -> Result<(), CliError> {
+    let signature = ctx.send(&[instruction::propose_authority(
+        &ctx.program_id,
+        pool,
+        &ctx.signer()?.pubkey(),
+        new_authority,
+    )])?;
+    println!("Proposed {} as authority of {}", new_authority, pool);
+    println!("The new authority must run accept-authority to complete the transfer");
+    println!("Signature: {}", signature);
+    Ok(())
+}
+
+pub fn accept_authority(ctx: &Context, pool: &Pubkey) -> Result<(), CliError> {
+    let signature = ctx.send(&[instruction::accept_authority(&ctx.program_id, pool, &ctx.signer()?.pubkey())])?;
+    println!("{} is now authority of {}", ctx.signer()?.pubkey(), pool);
+    println!("Signature: {}", signature);
+    Ok(())
+}
+
+/// Register the pool's auditor key, or clear it with `None`
+pub fn set_auditor(ctx: &Context, pool: &Pubkey, auditor_key: Option<[[u8; 32]; 2]>) -> Result<(), CliError> {
+    let signature = ctx.send(&[instruction::set_auditor(
+        &ctx.program_id,
+        pool,
+        &ctx.signer()?.pubkey(),
+        auditor_key,
+    )])?;
+    match auditor_key {
+        Some(key) => {
+            println!("Auditor of {} is now {}", pool, encode_auditor_key(&key));
+            let address = pda::verification_key_address(&ctx.program_id, pool, CircuitType::AuditorNote);
+            if fetch_optional_account::<VerificationKeyAccount>(&ctx.rpc, &ctx.program_id, &address)?.is_none() {
+                println!("Deposits, transfers and change notes fail until the AuditorNote VK is uploaded");
+            }
+        }
+        None => println!("Cleared the auditor of {}", pool),
+    }
+    println!("Signature: {}", signature);
+    Ok(())
+}
+
+pub fn pause(ctx: &Context, pool: &Pubkey, operations: u8, expires_at: Option<i64>) -> Result<(), CliError> {
+    let signature = ctx.send(&[instruction::pause(
+        &ctx.program_id,
+        pool,
+        &ctx.signer()?.pubkey(),
+        operations,
+        expires_at,
+    )])?;
+    println!("Paused {} on {}", describe_operations(operations), pool);
+    println!("Signature: {}", signature);
+    Ok(())
+}
+
+pub fn unpause(ctx: &Context, pool: &Pubkey, operations: u8) -> Result<(), CliError> {
+    let signature = ctx.send(&[instruction::unpause(&ctx.program_id, pool, &ctx.signer()?.pubkey(), operations)])?;
+    println!("Unpaused {} on {}", describe_operations(operations), pool);
+    println!("Signature: {}", signature);
+    Ok(())
+}
+
+pub fn register_relayer(ctx: &Context, endpoint: String, stake: u64) -> Result<(), CliError> {
+    let wallet = ctx.signer()?.pubkey();
+    let signature = ctx.send(&[instruction::register_relayer(&ctx.program_id, &wallet, endpoint, stake)])?;
+    println!("Relayer account: {}", pda::relayer_address(&ctx.program_id, &wallet));
+    println!("Signature: {}", signature);
+    Ok(())
+}
+
+pub fn relayer_heartbeat(ctx: &Context) -> Result<(), CliError> {
+    let signature = ctx.send(&[instruction::update_heartbeat(&ctx.program_id, &ctx.signer()?.pubkey())])?;
+    println!("Signature: {}", signature);
+    Ok(())
+}
+
+pub fn report_relay(ctx: &Context, relayer: &Pubkey, success: bool) -> Result<(), CliError> {
+    let signature = ctx.send(&[instruction::report_relay(&ctx.program_id, relayer, &ctx.signer()?.pubkey(), success)])?;
+    println!("Signature: {}", signature);
+    show_relayer(ctx, relayer)
+}
+
+pub fn migrate_relayer(ctx: &Context) -> Result<(), CliError> {
+    let signature = ctx.send(&[instruction::migrate_relayer(&ctx.program_id, &ctx.signer()?.pubkey())])?;
+    println!("Signature: {}", signature);
+    Ok(())
+}
+
+pub fn show_relayer(ctx: &Context, wallet: &Pubkey) -> Result<(), CliError> {
+    let address = pda::relayer_address(&ctx.program_id, wallet);
+    let relayer: RelayerAccount = fetch_account(&ctx.rpc, &ctx.program_id, &address)?;
+    let now = unix_timestamp();
+
+    println!("Relayer {}", relayer.relayer);
+    println!("  Account: {}", address);
+    println!("  Endpoint: {}", relayer.endpoint());
+    println!("  Active: {}", relayer.is_active());
+    println!("  Stake: {} lamports", relayer.stake);
+    println!("  Relays: {} ok, {} failed", relayer.successful_relays, relayer.failed_relays);
+    match relayer.reputation_score(now) {
+        Some(score) => println!("  Reputation: {}/100", score),
+        None => println!("  Reputation: unscored"),
+    }
+    println!("  Last heartbeat: {} ({}s ago)", relayer.last_heartbeat, now - relayer.last_heartbeat);
+    Ok(())
+}
+
+pub fn status(ctx: &Context, pool_address: &Pubkey) -> Result<(), CliError> {
+    let pool: PoolState = fetch_account(&ctx.rpc, &ctx.program_id, pool_address)?;
+    let now = unix_timestamp();
+    let vault_balance = ctx.rpc.get_balance(&pool.vault)?;
+
+    println!("Pool {}", pool_address);
+    println!("  Authority: {}", pool.authority);
+    if let Some(pending) = pool.pending_authority() {
+        println!("  Pending authority: {}", pending);
+    }
+    match pool.guardian() {
+        Some(guardian) => println!("  Guardian: {}", guardian),
+        None => println!("  Guardian: none"),
+    }
+    match pool.auditor_key() {
+        Some(key) => println!("  Auditor: {}", encode_auditor_key(&key)),
+        None => println!("  Auditor: none"),
+    }
+    println!("  Denomination: {} lamports", pool.denomination);
+    println!("  Vault: {} ({} lamports, TVL {})", pool.vault, vault_balance, pool.tvl);
+    println!("  Tree depth: {}, commitments: {}", pool.tree_depth, pool.commitment_count);
+    println!("  Merkle root: {}", hex::encode(pool.merkle_root));
+    println!("  Nullifiers: {}, key images: {}", pool.nullifier_count, pool.key_image_count);
+
+    let paused = match pool.is_paused(PoolState::PAUSE_ALL, now) {
+        true => pool.paused,
+        false => 0,
+    };
+    match (paused, pool.pause_expires_at) {
+        (0, _) => println!("  Paused: nothing"),
+        (flags, 0) => println!("  Paused: {} (until unpaused)", describe_operations(flags)),
+        (flags, expires_at) => println!("  Paused: {} (until {})", describe_operations(flags), expires_at),
+    }
+
+    for circuit_type in [
+        CircuitType::Transfer,
+        CircuitType::Balance,
+        CircuitType::RingSignature,
+        CircuitType::AuditorNote,
+    ] {
+        let address = pda::verification_key_address(&ctx.program_id, pool_address, circuit_type);
+        match fetch_optional_account::<VerificationKeyAccount>(&ctx.rpc, &ctx.program_id, &address)? {
+            Some(vk) => println!(
+                "  {:?} VK: {} ({} bytes, stored at {})",
+                circuit_type,
+                address,
+                vk.vk_data.len(),
+                vk.stored_at
+            ),
+            None => println!("  {:?} VK: missing", circuit_type),
+        }
+    }
+
+    let config_address = pda::config_address(&ctx.program_id);
+    match fetch_optional_account::<ProtocolConfig>(&ctx.rpc, &ctx.program_id, &config_address)? {
+        Some(config) => println!("Config {} (admin {}): {:?}", config_address, config.admin, config.params),
+        None => println!("Config {}: not initialized", config_address),
+    }
+    Ok(())
+}
+
+/// Print any program account as JSON, detecting its type from the header
+pub fn decode(ctx: &Context, address: &Pubkey) -> Result<(), CliError> {
+    let account = ctx
+        .rpc
+        .get_account_with_commitment(address, ctx.rpc.commitment())?
+        .value
+        .ok_or(ClientError::AccountNotFound(*address))?;
+    let value = decode_account_json(&ctx.program_id, address, &account)?;
+    println!("{}", serde_json::to_string_pretty(&value).expect("JSON value serializes"));
+    Ok(())
+}
+
+/// Run `solana-test-validator` with the program preloaded at `program_id`
+///
+/// Blocks until the validator exits.
+pub fn local_validator(program_id: &Pubkey, program_so: &Path, ledger: &Path, reset: bool) -> Result<(), CliError> {
+    if !program_so.is_file() {
+        return Err(CliError::Validator(format!(
+            "{} not found (build it with cargo build-sbf)",
+            program_so.display()
+        )));
+    }
+
+    let mut command = Command::new("solana-test-validator");
+    command
+        .arg("--ledger")
+        .arg(ledger)
+        .arg("--bpf-program")
+        .arg(program_id.to_string())
+        .arg(program_so);
+    if reset {
+        command.arg("--reset");
+    }
+
+    println!("Starting local validator with {} at {}", program_so.display(), program_id);
+    let status = command
+        .status()
+        .map_err(|error| CliError::Validator(format!("failed to start solana-test-validator: {}", error)))?;
+    match status.success() {
+        true => Ok(()),
+        false => Err(CliError::Validator(format!("exited with {}", status))),
+    }
+}
+
+pub fn describe_operations(operations: u8) -> String {
+    if operations & PoolState::PAUSE_ALL == PoolState::PAUSE_ALL {
+        return "all operations".to_string();
+    }
+
+    let names: Vec<&str> = [
+        (PoolState::PAUSE_DEPOSITS, "deposits"),
+        (PoolState::PAUSE_WITHDRAWALS, "withdrawals"),
+        (PoolState::PAUSE_TRANSFERS, "transfers"),
+        (PoolState::PAUSE_VK_UPDATES, "vk-updates"),
+    ]
+    .iter()
+    .filter(|(flag, _)| operations & flag != 0)
+    .map(|(_, name)| *name)
+    .collect();
+    names.join(", ")
+}
+
+/// Inverse of [`parse_auditor_key`](crate::profile::parse_auditor_key)
+pub fn encode_auditor_key(key: &[[u8; 32]; 2]) -> String {
+    hex::encode([key[0], key[1]].concat())
+}
+
+pub fn unix_timestamp() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repo_path(relative: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("../..").join(relative)
+    }
+
+    #[test]
+    fn test_vk_path_prefers_directory() {
+        let paths = VkPaths {
+            transfer: Some(PathBuf::from("profile/transfer.json")),
+            ..VkPaths::default()
+        };
+
+        assert_eq!(
+            vk_path(&paths, None, CircuitType::Transfer).unwrap(),
+            PathBuf::from("profile/transfer.json")
+        );
+        assert_eq!(
+            vk_path(&paths, Some(Path::new("keys")), CircuitType::RingSignature).unwrap(),
+            PathBuf::from("keys/ring_signature_verification_key.json")
+        );
+        assert!(vk_path(&paths, None, CircuitType::Balance).is_err());
+    }
+
+    #[test]
+    fn test_checked_in_vks_fit_accounts() {
+        for circuit_type in [CircuitType::Transfer, CircuitType::Balance, CircuitType::RingSignature] {
+            let path = repo_path("web-dashboard/public/circuits").join(vk_file_name(circuit_type));
+            let vk_data = load_vk(&path).unwrap();
+            assert!(!vk_data.is_empty());
+        }
+        assert!(matches!(
+            load_vk(&repo_path("config/devnet.json")),
+            Err(CliError::VerificationKey { .. })
+        ));
+    }
+
+    #[test]
+    fn test_describe_operations() {
+        assert_eq!(describe_operations(PoolState::PAUSE_ALL), "all operations");
+        assert_eq!(
+            describe_operations(PoolState::PAUSE_DEPOSITS | PoolState::PAUSE_TRANSFERS),
+            "deposits, transfers"
+        );
+    }
+}
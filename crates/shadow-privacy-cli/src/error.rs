@@ -0,0 +1,35 @@
+use shadow_privacy_client::ClientError;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum CliError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid profile {0}")]
+    Profile(String),
+
+    #[error("{0}")]
+    InvalidArgument(String),
+
+    #[error("Failed to read keypair {path}: {reason}")]
+    Keypair { path: String, reason: String },
+
+    #[error("Verification key {path}: {reason}")]
+    VerificationKey { path: String, reason: String },
+
+    #[error(transparent)]
+    Client(#[from] ClientError),
+
+    #[error("RPC error: {0}")]
+    Rpc(Box<solana_client::client_error::ClientError>),
+
+    #[error("Local validator: {0}")]
+    Validator(String),
+}
+
+impl From<solana_client::client_error::ClientError> for CliError {
+    fn from(error: solana_client::client_error::ClientError) -> Self {
+        CliError::Rpc(Box::new(error))
+    }
+}
@@ -0,0 +1,373 @@
+// Shadow Privacy admin CLI
+//
+// Pool lifecycle from one binary: initialize config and pools, convert and
+// upload verification keys, rotate authority, set the auditor, pause/unpause,
+// manage relayers and inspect pool status. Cluster settings come from a profile
+// (config/<name>.json, or the built-in localnet) and can be overridden per
+// flag.
+
+mod commands;
+mod error;
+mod profile;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use commands::Context;
+use error::CliError;
+use profile::{default_keypair_path, parse_auditor_key, parse_commitment, parse_pubkey, Profile};
+use shadow_privacy::state::{CircuitType, ConfigParams, PoolState};
+use shadow_privacy_client::ComputeBudget;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{pubkey::Pubkey, signature::read_keypair_file, signer::Signer};
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(name = "shadow-admin", about = "Shadow Privacy pool administration")]
+struct Cli {
+    #[command(flatten)]
+    global: GlobalArgs,
+
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Args)]
+struct GlobalArgs {
+    /// Cluster profile: a name in --config-dir, or a path to a profile file
+    #[arg(long, short = 'p', global = true, default_value = "localnet")]
+    profile: String,
+
+    /// Directory holding <profile>.json files
+    #[arg(long, global = true, default_value = "config")]
+    config_dir: PathBuf,
+
+    /// RPC URL (overrides the profile)
+    #[arg(long, short = 'u', global = true)]
+    url: Option<String>,
+
+    /// Program id (overrides the profile)
+    #[arg(long, global = true, value_parser = parse_pubkey)]
+    program_id: Option<Pubkey>,
+
+    /// Commitment level (overrides the profile)
+    #[arg(long, global = true)]
+    commitment: Option<String>,
+
+    /// Keypair file that pays and signs [default: ~/.config/solana/id.json]
+    #[arg(long, short = 'k', global = true)]
+    keypair: Option<PathBuf>,
+
+    /// Priority fee in micro-lamports per compute unit
+    #[arg(long, global = true)]
+    priority_fee: Option<u64>,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Create the protocol config account (signed by the upgrade authority, who becomes config admin)
+    InitConfig {
+        #[arg(long, default_value_t = ConfigParams::default().min_relayer_stake)]
+        min_relayer_stake: u64,
+        /// Seconds since the last heartbeat for a relayer to count as online
+        #[arg(long, default_value_t = ConfigParams::default().relayer_online_window)]
+        relayer_online_window: i64,
+        #[arg(long, default_value_t = ConfigParams::default().max_ring_size)]
+        max_ring_size: u8,
+    },
+
+    /// Create a pool owned by the signer
+    InitPool {
+        /// Denomination in lamports
+        #[arg(long)]
+        denomination: u64,
+        #[arg(long, default_value_t = 20)]
+        tree_depth: u8,
+    },
+
+    /// Convert snarkjs verification keys and store them for a pool
+    UploadVks {
+        #[arg(long, value_parser = parse_pubkey)]
+        pool: Pubkey,
+        /// Circuits to upload [default: transfer, balance, ring-signature]
+        #[arg(long = "circuit", value_enum)]
+        circuits: Vec<Circuit>,
+        /// Directory with <circuit>_verification_key.json files (overrides the profile)
+        #[arg(long)]
+        vk_dir: Option<PathBuf>,
+    },
+
+    /// Propose a new pool authority (signed by the current authority)
+    ProposeAuthority {
+        #[arg(long, value_parser = parse_pubkey)]
+        pool: Pubkey,
+        #[arg(long, value_parser = parse_pubkey)]
+        new_authority: Pubkey,
+    },
+
+    /// Accept a pending authority transfer (signed by the new authority)
+    AcceptAuthority {
+        #[arg(long, value_parser = parse_pubkey)]
+        pool: Pubkey,
+    },
+
+    /// Register or clear the pool's auditor key (signed by the authority)
+    SetAuditor {
+        #[arg(long, value_parser = parse_pubkey)]
+        pool: Pubkey,
+        /// Baby Jubjub public key from `shadow-audit keygen` (x then y, little-endian hex)
+        #[arg(long, value_parser = parse_auditor_key, required_unless_present = "clear")]
+        key: Option<[[u8; 32]; 2]>,
+        /// Remove the auditor instead
+        #[arg(long, conflicts_with = "key")]
+        clear: bool,
+    },
+
+    /// Pause pool operations (signed by the guardian or authority)
+    Pause {
+        #[arg(long, value_parser = parse_pubkey)]
+        pool: Pubkey,
+        #[arg(long = "operation", value_enum, default_values_t = [Operation::All])]
+        operations: Vec<Operation>,
+        /// Lift the pause automatically after this many seconds
+        #[arg(long)]
+        expires_in: Option<i64>,
+    },
+
+    /// Unpause pool operations (signed by the authority)
+    Unpause {
+        #[arg(long, value_parser = parse_pubkey)]
+        pool: Pubkey,
+        #[arg(long = "operation", value_enum, default_values_t = [Operation::All])]
+        operations: Vec<Operation>,
+    },
+
+    /// Relayer registration and reputation
+    #[command(subcommand)]
+    Relayer(RelayerCommand),
+
+    /// Show pool state, verification keys and protocol config
+    Status {
+        #[arg(long, value_parser = parse_pubkey)]
+        pool: Pubkey,
+    },
+
+    /// Print any program account as JSON
+    Decode {
+        #[arg(value_parser = parse_pubkey)]
+        address: Pubkey,
+    },
+
+    /// Run solana-test-validator with the program preloaded
+    LocalValidator {
+        /// Program binary
+        #[arg(long, default_value = "programs/shadow-privacy/target/deploy/shadow_privacy.so")]
+        program_so: PathBuf,
+        #[arg(long, default_value = "test-ledger")]
+        ledger: PathBuf,
+        /// Start from a fresh ledger
+        #[arg(long)]
+        reset: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum RelayerCommand {
+    /// Register the signer as a relayer
+    Register {
+        #[arg(long)]
+        endpoint: String,
+        /// Stake in lamports
+        #[arg(long)]
+        stake: u64,
+    },
+    /// Refresh the signer's heartbeat
+    Heartbeat,
+    /// Report a relay (config admin; a relayer may report its own failures)
+    Report {
+        /// Relayer wallet
+        #[arg(long, value_parser = parse_pubkey)]
+        relayer: Pubkey,
+        /// Report a failed relay instead of a successful one
+        #[arg(long)]
+        failed: bool,
+    },
+    /// Migrate the signer's relayer account to the current layout
+    Migrate,
+    /// Show a relayer [default: the signer]
+    Show {
+        #[arg(value_parser = parse_pubkey)]
+        wallet: Option<Pubkey>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Circuit {
+    Transfer,
+    Balance,
+    RingSignature,
+    AuditorNote,
+}
+
+impl From<Circuit> for CircuitType {
+    fn from(circuit: Circuit) -> Self {
+        match circuit {
+            Circuit::Transfer => CircuitType::Transfer,
+            Circuit::Balance => CircuitType::Balance,
+            Circuit::RingSignature => CircuitType::RingSignature,
+            Circuit::AuditorNote => CircuitType::AuditorNote,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Operation {
+    Deposits,
+    Withdrawals,
+    Transfers,
+    VkUpdates,
+    All,
+}
+
+/// PoolState::PAUSE_* flags for the selected operations
+fn operation_flags(operations: &[Operation]) -> u8 {
+    operations.iter().fold(0, |flags, operation| {
+        flags
+            | match operation {
+                Operation::Deposits => PoolState::PAUSE_DEPOSITS,
+                Operation::Withdrawals => PoolState::PAUSE_WITHDRAWALS,
+                Operation::Transfers => PoolState::PAUSE_TRANSFERS,
+                Operation::VkUpdates => PoolState::PAUSE_VK_UPDATES,
+                Operation::All => PoolState::PAUSE_ALL,
+            }
+    })
+}
+
+fn context(global: &GlobalArgs, profile: Profile) -> Result<Context, CliError> {
+    let commitment = match &global.commitment {
+        Some(level) => parse_commitment(level)?,
+        None => profile.commitment,
+    };
+    let keypair_path = global.keypair.clone().unwrap_or_else(default_keypair_path);
+    let keypair = read_keypair_file(&keypair_path).map_err(|error| error.to_string());
+
+    let mut budget = ComputeBudget::default();
+    if let Some(price) = global.priority_fee {
+        budget = budget.with_unit_price(price);
+    }
+
+    Ok(Context {
+        rpc: RpcClient::new_with_commitment(global.url.clone().unwrap_or(profile.rpc_url), commitment),
+        program_id: global.program_id.unwrap_or(profile.program_id),
+        keypair_path,
+        keypair,
+        budget,
+        vk_paths: profile.vk_paths,
+    })
+}
+
+fn run(cli: Cli) -> Result<(), CliError> {
+    let profile = Profile::load(&cli.global.profile, &cli.global.config_dir)?;
+
+    if let Commands::LocalValidator { program_so, ledger, reset } = &cli.command {
+        let program_id = cli.global.program_id.unwrap_or(profile.program_id);
+        return commands::local_validator(&program_id, program_so, ledger, *reset);
+    }
+
+    let ctx = context(&cli.global, profile)?;
+    match cli.command {
+        Commands::InitConfig {
+            min_relayer_stake,
+            relayer_online_window,
+            max_ring_size,
+        } => commands::init_config(
+            &ctx,
+            ConfigParams {
+                min_relayer_stake,
+                relayer_online_window,
+                max_ring_size,
+                ..ConfigParams::default()
+            },
+        ),
+        Commands::InitPool { denomination, tree_depth } => commands::init_pool(&ctx, denomination, tree_depth),
+        Commands::UploadVks { pool, circuits, vk_dir } => {
+            let circuits: Vec<CircuitType> = match circuits.is_empty() {
+                true => vec![CircuitType::Transfer, CircuitType::Balance, CircuitType::RingSignature],
+                false => circuits.into_iter().map(Into::into).collect(),
+            };
+            commands::upload_vks(&ctx, &pool, &circuits, vk_dir.as_deref())
+        }
+        Commands::ProposeAuthority { pool, new_authority } => commands::propose_authority(&ctx, &pool, &new_authority),
+        Commands::AcceptAuthority { pool } => commands::accept_authority(&ctx, &pool),
+        Commands::SetAuditor { pool, key, .. } => commands::set_auditor(&ctx, &pool, key),
+        Commands::Pause {
+            pool,
+            operations,
+            expires_in,
+        } => {
+            let expires_at = expires_in.map(|seconds| commands::unix_timestamp() + seconds);
+            commands::pause(&ctx, &pool, operation_flags(&operations), expires_at)
+        }
+        Commands::Unpause { pool, operations } => commands::unpause(&ctx, &pool, operation_flags(&operations)),
+        Commands::Relayer(command) => match command {
+            RelayerCommand::Register { endpoint, stake } => commands::register_relayer(&ctx, endpoint, stake),
+            RelayerCommand::Heartbeat => commands::relayer_heartbeat(&ctx),
+            RelayerCommand::Report { relayer, failed } => commands::report_relay(&ctx, &relayer, !failed),
+            RelayerCommand::Migrate => commands::migrate_relayer(&ctx),
+            RelayerCommand::Show { wallet } => {
+                let wallet = match wallet {
+                    Some(wallet) => wallet,
+                    None => ctx.signer()?.pubkey(),
+                };
+                commands::show_relayer(&ctx, &wallet)
+            }
+        },
+        Commands::Status { pool } => commands::status(&ctx, &pool),
+        Commands::Decode { address } => commands::decode(&ctx, &address),
+        Commands::LocalValidator { .. } => unreachable!("handled above"),
+    }
+}
+
+fn main() {
+    if let Err(error) = run(Cli::parse()) {
+        eprintln!("Error: {}", error);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+
+        let cli = Cli::try_parse_from([
+            "shadow-admin",
+            "pause",
+            "--pool",
+            "11111111111111111111111111111111",
+            "--operation",
+            "deposits",
+            "--operation",
+            "withdrawals",
+            "--profile",
+            "devnet",
+        ])
+        .unwrap();
+        assert_eq!(cli.global.profile, "devnet");
+        match cli.command {
+            Commands::Pause { operations, .. } => assert_eq!(
+                operation_flags(&operations),
+                PoolState::PAUSE_DEPOSITS | PoolState::PAUSE_WITHDRAWALS
+            ),
+            _ => panic!("expected pause"),
+        }
+        assert_eq!(operation_flags(&[Operation::All]), PoolState::PAUSE_ALL);
+
+        let pool = "11111111111111111111111111111111";
+        let cli = Cli::try_parse_from(["shadow-admin", "set-auditor", "--pool", pool, "--clear"]).unwrap();
+        assert!(matches!(cli.command, Commands::SetAuditor { key: None, clear: true, .. }));
+        assert!(Cli::try_parse_from(["shadow-admin", "set-auditor", "--pool", pool]).is_err());
+    }
+}
@@ -0,0 +1,187 @@
+//! Cluster profiles
+//!
+//! A profile is one of the `config/<name>.json` environment files (only the
+//! `network` and `circuits` sections are read). `localnet` is built in, so a
+//! local test validator works without a file. Command-line flags override
+//! whatever the profile sets.
+
+use crate::error::CliError;
+use serde::Deserialize;
+use shadow_privacy::auditor::is_valid_auditor_key;
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+pub const LOCALNET_RPC_URL: &str = "http://127.0.0.1:8899";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProfileFile {
+    network: NetworkSection,
+    #[serde(default)]
+    circuits: CircuitSection,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NetworkSection {
+    rpc_url: String,
+    program_id: String,
+    #[serde(default)]
+    commitment: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CircuitSection {
+    #[serde(rename = "transferVK")]
+    transfer_vk: Option<PathBuf>,
+    #[serde(rename = "balanceVK")]
+    balance_vk: Option<PathBuf>,
+    #[serde(rename = "ringSignatureVK")]
+    ring_signature_vk: Option<PathBuf>,
+    #[serde(rename = "auditorNoteVK")]
+    auditor_note_vk: Option<PathBuf>,
+}
+
+/// Snarkjs verification key files for each circuit
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VkPaths {
+    pub transfer: Option<PathBuf>,
+    pub balance: Option<PathBuf>,
+    pub ring_signature: Option<PathBuf>,
+    pub auditor_note: Option<PathBuf>,
+}
+
+/// Resolved cluster settings
+#[derive(Debug, Clone)]
+pub struct Profile {
+    pub rpc_url: String,
+    pub program_id: Pubkey,
+    pub commitment: CommitmentConfig,
+    pub vk_paths: VkPaths,
+}
+
+impl Profile {
+    /// Built-in profile for `solana-test-validator` with the program preloaded at its declared id
+    pub fn localnet() -> Self {
+        Profile {
+            rpc_url: LOCALNET_RPC_URL.to_string(),
+            program_id: shadow_privacy::id(),
+            commitment: CommitmentConfig::confirmed(),
+            vk_paths: VkPaths::default(),
+        }
+    }
+
+    /// Load `name` from `config_dir/<name>.json`, or treat it as a path if it names a file
+    ///
+    /// `localnet` falls back to [`Profile::localnet`] when there is no file.
+    pub fn load(name: &str, config_dir: &Path) -> Result<Self, CliError> {
+        let path = match Path::new(name).is_file() {
+            true => PathBuf::from(name),
+            false => config_dir.join(format!("{}.json", name)),
+        };
+        if !path.is_file() && name == "localnet" {
+            return Ok(Self::localnet());
+        }
+
+        let contents = fs::read_to_string(&path)
+            .map_err(|error| CliError::Profile(format!("{}: {}", path.display(), error)))?;
+        Self::parse(name, &contents)
+    }
+
+    pub fn parse(name: &str, json: &str) -> Result<Self, CliError> {
+        let file: ProfileFile =
+            serde_json::from_str(json).map_err(|error| CliError::Profile(format!("{}: {}", name, error)))?;
+
+        Ok(Profile {
+            rpc_url: file.network.rpc_url,
+            program_id: parse_pubkey(&file.network.program_id)
+                .map_err(|_| CliError::Profile(format!("{}: invalid programId {}", name, file.network.program_id)))?,
+            commitment: match file.network.commitment {
+                Some(level) => parse_commitment(&level)?,
+                None => CommitmentConfig::confirmed(),
+            },
+            vk_paths: VkPaths {
+                transfer: file.circuits.transfer_vk,
+                balance: file.circuits.balance_vk,
+                ring_signature: file.circuits.ring_signature_vk,
+                auditor_note: file.circuits.auditor_note_vk,
+            },
+        })
+    }
+}
+
+pub fn parse_pubkey(value: &str) -> Result<Pubkey, CliError> {
+    Pubkey::from_str(value).map_err(|_| CliError::InvalidArgument(format!("invalid address {}", value)))
+}
+
+pub fn parse_commitment(value: &str) -> Result<CommitmentConfig, CliError> {
+    CommitmentConfig::from_str(value).map_err(|_| CliError::InvalidArgument(format!("invalid commitment {}", value)))
+}
+
+/// Auditor key as 128 hex characters: x then y, each 32 little-endian bytes
+pub fn parse_auditor_key(value: &str) -> Result<[[u8; 32]; 2], CliError> {
+    let invalid = || CliError::InvalidArgument(format!("invalid auditor key {}", value));
+    let bytes: [u8; 64] = hex::decode(value)
+        .map_err(|_| invalid())?
+        .try_into()
+        .map_err(|_| invalid())?;
+    let mut key = [[0u8; 32]; 2];
+    key[0].copy_from_slice(&bytes[..32]);
+    key[1].copy_from_slice(&bytes[32..]);
+    match is_valid_auditor_key(&key) {
+        true => Ok(key),
+        false => Err(invalid()),
+    }
+}
+
+/// Default keypair location used by the Solana CLI
+pub fn default_keypair_path() -> PathBuf {
+    let home = std::env::var_os("HOME").unwrap_or_else(|| ".".into());
+    Path::new(&home).join(".config/solana/id.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::commitment_config::CommitmentLevel;
+
+    #[test]
+    fn test_repo_profiles_parse() {
+        let config_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../config");
+
+        let devnet = Profile::load("devnet", &config_dir).unwrap();
+        assert_eq!(devnet.commitment.commitment, CommitmentLevel::Confirmed);
+        assert_eq!(
+            devnet.vk_paths.transfer,
+            Some(PathBuf::from("./circuits/build/transfer_verification_key.json"))
+        );
+
+        let localnet = Profile::load("localnet", &config_dir).unwrap();
+        assert_eq!(localnet.rpc_url, LOCALNET_RPC_URL);
+        assert_eq!(localnet.program_id, shadow_privacy::id());
+
+        // The mainnet example still has a placeholder program id
+        assert!(matches!(
+            Profile::load("mainnet.example", &config_dir),
+            Err(CliError::Profile(_))
+        ));
+        assert!(Profile::load("missing", &config_dir).is_err());
+    }
+
+    #[test]
+    fn test_parse_auditor_key() {
+        let key = shadow_privacy::auditor::BASE8.mul(&[7; 32]).to_bytes();
+        let encoded = hex::encode([key[0], key[1]].concat());
+        assert_eq!(parse_auditor_key(&encoded).unwrap(), key);
+
+        let off_curve = hex::encode([[1u8; 32], key[1]].concat());
+        for invalid in [&off_curve, &encoded[..64], "zz"] {
+            assert!(matches!(parse_auditor_key(invalid), Err(CliError::InvalidArgument(_))));
+        }
+    }
+}
@@ -0,0 +1,96 @@
+//! Typed account decoding and fetching
+//!
+//! Decoding checks the owner before the account header, so an account from
+//! another program is never mistaken for program state.
+
+use crate::error::ClientError;
+use shadow_privacy::state::ProgramAccount;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::Account;
+
+/// Decode `account` at `address` as `T`
+pub fn decode_account<T: ProgramAccount>(
+    program_id: &Pubkey,
+    address: &Pubkey,
+    account: &Account,
+) -> Result<T, ClientError> {
+    if account.owner != *program_id {
+        return Err(ClientError::InvalidOwner {
+            address: *address,
+            owner: account.owner,
+        });
+    }
+
+    T::load(&account.data).map_err(|error| ClientError::InvalidAccount {
+        address: *address,
+        error,
+    })
+}
+
+/// Fetch and decode the account at `address`
+#[cfg(feature = "rpc")]
+pub fn fetch_account<T: ProgramAccount>(
+    rpc: &solana_client::rpc_client::RpcClient,
+    program_id: &Pubkey,
+    address: &Pubkey,
+) -> Result<T, ClientError> {
+    fetch_optional_account(rpc, program_id, address)?.ok_or(ClientError::AccountNotFound(*address))
+}
+
+/// Fetch and decode the account at `address`, or None if it does not exist
+#[cfg(feature = "rpc")]
+pub fn fetch_optional_account<T: ProgramAccount>(
+    rpc: &solana_client::rpc_client::RpcClient,
+    program_id: &Pubkey,
+    address: &Pubkey,
+) -> Result<Option<T>, ClientError> {
+    let account = rpc
+        .get_account_with_commitment(address, rpc.commitment())?
+        .value;
+    account
+        .map(|account| decode_account(program_id, address, &account))
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use borsh::BorshSerialize;
+    use shadow_privacy::{error::PrivacyError, state::PoolState};
+
+    fn pool_account(program_id: &Pubkey, state: &PoolState) -> Account {
+        Account {
+            lamports: 1,
+            data: state.try_to_vec().unwrap(),
+            owner: *program_id,
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    #[test]
+    fn test_decode_checks_owner_and_header() {
+        let program_id = shadow_privacy::id();
+        let address = Pubkey::new_unique();
+        let state = PoolState::new(Pubkey::new_unique(), Pubkey::new_unique(), 20, 1_000_000);
+
+        let mut account = pool_account(&program_id, &state);
+        let decoded: PoolState = decode_account(&program_id, &address, &account).unwrap();
+        assert_eq!(decoded.authority, state.authority);
+        assert_eq!(decoded.denomination, 1_000_000);
+
+        account.data[8] = 0;
+        match decode_account::<PoolState>(&program_id, &address, &account) {
+            Err(ClientError::InvalidAccount { error, .. }) => {
+                assert_eq!(error, PrivacyError::AccountNeedsMigration.into())
+            }
+            other => panic!("expected a decode error, got {:?}", other.map(|_| ())),
+        }
+
+        account.owner = Pubkey::new_unique();
+        assert!(matches!(
+            decode_account::<PoolState>(&program_id, &address, &account),
+            Err(ClientError::InvalidOwner { .. })
+        ));
+    }
+}
@@ -0,0 +1,179 @@
+//! Account decoding to JSON
+//!
+//! The account type is detected from the header discriminator, so any
+//! program-owned account can be dumped without knowing what it is.
+//! Verification key accounts also get a summary of the Groth16 key.
+
+use crate::{accounts::decode_account, error::ClientError};
+use ark_bn254::{Bn254, Fq2, G1Affine, G2Affine};
+use ark_ec::AffineRepr;
+use ark_groth16::VerifyingKey;
+use ark_serialize::CanonicalDeserialize;
+use serde::Serialize;
+use serde_json::{json, Value};
+use shadow_privacy::state::{
+    AssetState, DelayedWithdrawal, KeyImageAccount, Multisig, MultisigProposal, NullifierAccount, PoolState,
+    ProgramAccount, ProtocolConfig, RelayerAccount, ShieldedNote, VerificationKeyAccount,
+};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::Account;
+
+/// Decode any program account at `address` to `{"type": ..., "account": {...}}`
+///
+/// Verification keys carry an extra `"vk"` summary (see [`summarize_vk`]).
+pub fn decode_account_json(program_id: &Pubkey, address: &Pubkey, account: &Account) -> Result<Value, ClientError> {
+    fn typed<T: ProgramAccount + Serialize>(
+        program_id: &Pubkey,
+        address: &Pubkey,
+        account: &Account,
+    ) -> Result<Value, ClientError> {
+        let state: T = decode_account(program_id, address, account)?;
+        Ok(json!({
+            "type": format!("{:?}", T::ACCOUNT_TYPE),
+            "account": serde_json::to_value(state).expect("state serializes to JSON"),
+        }))
+    }
+
+    let data = &account.data;
+    if PoolState::has_discriminator(data) {
+        typed::<PoolState>(program_id, address, account)
+    } else if AssetState::has_discriminator(data) {
+        typed::<AssetState>(program_id, address, account)
+    } else if ShieldedNote::has_discriminator(data) {
+        typed::<ShieldedNote>(program_id, address, account)
+    } else if NullifierAccount::has_discriminator(data) {
+        typed::<NullifierAccount>(program_id, address, account)
+    } else if KeyImageAccount::has_discriminator(data) {
+        typed::<KeyImageAccount>(program_id, address, account)
+    } else if VerificationKeyAccount::has_discriminator(data) {
+        let mut value = typed::<VerificationKeyAccount>(program_id, address, account)?;
+        let vk: VerificationKeyAccount = decode_account(program_id, address, account)?;
+        value["vk"] = summarize_vk(&vk.vk_data);
+        Ok(value)
+    } else if RelayerAccount::has_discriminator(data) {
+        typed::<RelayerAccount>(program_id, address, account)
+    } else if ProtocolConfig::has_discriminator(data) {
+        typed::<ProtocolConfig>(program_id, address, account)
+    } else if Multisig::has_discriminator(data) {
+        typed::<Multisig>(program_id, address, account)
+    } else if MultisigProposal::has_discriminator(data) {
+        typed::<MultisigProposal>(program_id, address, account)
+    } else if DelayedWithdrawal::has_discriminator(data) {
+        typed::<DelayedWithdrawal>(program_id, address, account)
+    } else if account.owner != *program_id {
+        Err(ClientError::InvalidOwner {
+            address: *address,
+            owner: account.owner,
+        })
+    } else {
+        Err(ClientError::UnknownAccountType(*address))
+    }
+}
+
+/// Summarize compressed `VerifyingKey<Bn254>` bytes: IC count and curve points
+///
+/// Coordinates are decimal strings, G2 coordinates as `[c0, c1]` pairs. Data
+/// that does not deserialize is reported as `{"error": ...}` instead of failing
+/// the whole decode.
+pub fn summarize_vk(vk_data: &[u8]) -> Value {
+    let vk = match VerifyingKey::<Bn254>::deserialize_compressed(vk_data) {
+        Ok(vk) => vk,
+        Err(error) => return json!({ "error": error.to_string() }),
+    };
+
+    json!({
+        "curve": "bn254",
+        "ic_count": vk.gamma_abc_g1.len(),
+        "public_inputs": vk.gamma_abc_g1.len().saturating_sub(1),
+        "alpha_g1": g1_json(&vk.alpha_g1),
+        "beta_g2": g2_json(&vk.beta_g2),
+        "gamma_g2": g2_json(&vk.gamma_g2),
+        "delta_g2": g2_json(&vk.delta_g2),
+        "ic": vk.gamma_abc_g1.iter().map(g1_json).collect::<Vec<_>>(),
+    })
+}
+
+fn g1_json(point: &G1Affine) -> Value {
+    match point.xy() {
+        Some((x, y)) => json!({ "x": x.to_string(), "y": y.to_string() }),
+        None => json!("infinity"),
+    }
+}
+
+fn g2_json(point: &G2Affine) -> Value {
+    fn fq2(value: &Fq2) -> Value {
+        json!([value.c0.to_string(), value.c1.to_string()])
+    }
+
+    match point.xy() {
+        Some((x, y)) => json!({ "x": fq2(x), "y": fq2(y) }),
+        None => json!("infinity"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use borsh::BorshSerialize;
+    use shadow_privacy::state::CircuitType;
+
+    fn program_account(program_id: &Pubkey, data: Vec<u8>) -> Account {
+        Account {
+            lamports: 1,
+            data,
+            owner: *program_id,
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    #[test]
+    fn test_decode_detects_account_type() {
+        let program_id = shadow_privacy::id();
+        let address = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let pool = PoolState::new(authority, Pubkey::new_unique(), 20, 1_000_000);
+
+        let account = program_account(&program_id, pool.try_to_vec().unwrap());
+        let value = decode_account_json(&program_id, &address, &account).unwrap();
+        assert_eq!(value["type"], "Pool");
+        assert_eq!(value["account"]["authority"], authority.to_string());
+        assert_eq!(value["account"]["denomination"], 1_000_000);
+
+        let account = program_account(&program_id, vec![0; 64]);
+        assert!(matches!(
+            decode_account_json(&program_id, &address, &account),
+            Err(ClientError::UnknownAccountType(_))
+        ));
+    }
+
+    #[test]
+    fn test_vk_summary() {
+        let json = include_str!("../../../web-dashboard/public/circuits/transfer_verification_key.json");
+        let vk_data = vk_converter::convert_snarkjs_vk(json).unwrap();
+        let snarkjs: Value = serde_json::from_str(json).unwrap();
+
+        let vk = VerificationKeyAccount {
+            discriminator: VerificationKeyAccount::DISCRIMINATOR,
+            version: VerificationKeyAccount::VERSION,
+            circuit_type: CircuitType::Transfer,
+            pool: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+            vk_data,
+            stored_at: 0,
+            bump: 255,
+        };
+
+        let program_id = shadow_privacy::id();
+        let account = program_account(&program_id, vk.try_to_vec().unwrap());
+        let value = decode_account_json(&program_id, &Pubkey::new_unique(), &account).unwrap();
+
+        assert_eq!(value["type"], "VerificationKey");
+        assert_eq!(value["account"]["circuit_type"], "Transfer");
+        assert_eq!(value["vk"]["ic_count"], snarkjs["IC"].as_array().unwrap().len());
+        assert_eq!(value["vk"]["alpha_g1"]["x"], snarkjs["vk_alpha_1"][0]);
+        assert_eq!(value["vk"]["beta_g2"]["x"][0], snarkjs["vk_beta_2"][0][0]);
+
+        assert!(summarize_vk(&[1, 2, 3])["error"].is_string());
+    }
+}
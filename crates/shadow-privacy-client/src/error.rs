@@ -0,0 +1,28 @@
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error("Account {0} not found")]
+    AccountNotFound(Pubkey),
+
+    #[error("Account {address} is owned by {owner}, not the Shadow Privacy program")]
+    InvalidOwner { address: Pubkey, owner: Pubkey },
+
+    #[error("Account {0} is not a known Shadow Privacy account type")]
+    UnknownAccountType(Pubkey),
+
+    #[error("Account {address} could not be decoded: {error}")]
+    InvalidAccount { address: Pubkey, error: ProgramError },
+
+    #[cfg(feature = "rpc")]
+    #[error("RPC error: {0}")]
+    Rpc(Box<solana_client::client_error::ClientError>),
+}
+
+#[cfg(feature = "rpc")]
+impl From<solana_client::client_error::ClientError> for ClientError {
+    fn from(error: solana_client::client_error::ClientError) -> Self {
+        ClientError::Rpc(Box::new(error))
+    }
+}
@@ -0,0 +1,643 @@
+//! Instruction builders
+//!
+//! One function per `PrivacyInstruction` variant. Each takes the program id
+//! first (so other deployments work too), derives every PDA the instruction
+//! needs from the pool or wallet, and lists accounts in the order documented
+//! on the variant.
+
+use crate::pda;
+use shadow_privacy::{
+    instruction::PrivacyInstruction,
+    state::{AccountType, AuditorNote, CircuitType, ConfigParams, PoolLimits, ProposalAccount},
+};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+
+fn build(program_id: &Pubkey, instruction: &PrivacyInstruction, accounts: Vec<AccountMeta>) -> Instruction {
+    Instruction::new_with_borsh(*program_id, instruction, accounts)
+}
+
+/// Create the pool PDA for `authority` and `denomination`, plus its vault
+pub fn initialize_pool(program_id: &Pubkey, authority: &Pubkey, tree_depth: u8, denomination: u64) -> Instruction {
+    let pool = pda::pool_address(program_id, authority, denomination);
+    build(
+        program_id,
+        &PrivacyInstruction::InitializePool { tree_depth, denomination },
+        vec![
+            AccountMeta::new(pool, false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new(pda::vault_address(program_id, &pool), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Deposit `amount` under `commitment`
+///
+/// `encrypted_note` is the note addressed to the depositor's own viewing
+/// key, so the deposit can be recovered from chain data. `auditor_note` is
+/// required exactly when the pool has an auditor.
+#[allow(clippy::too_many_arguments)]
+pub fn deposit(
+    program_id: &Pubkey,
+    pool: &Pubkey,
+    depositor: &Pubkey,
+    commitment: [u8; 32],
+    amount: u64,
+    encrypted_note: Vec<u8>,
+    auditor_note: Option<AuditorNote>,
+) -> Instruction {
+    build(
+        program_id,
+        &PrivacyInstruction::Deposit {
+            commitment,
+            amount,
+            encrypted_note,
+            auditor_note,
+        },
+        vec![
+            AccountMeta::new(*pool, false),
+            AccountMeta::new(*depositor, true),
+            AccountMeta::new(pda::vault_address(program_id, pool), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            auditor_verification_key(program_id, pool),
+        ],
+    )
+}
+
+/// Withdraw to `recipient`
+///
+/// The nullifier record's rent comes out of `amount`. The delayed withdrawal
+/// PDA is always passed, so withdrawals over the pool limits are queued
+/// instead of failing. `encrypted_note` is the change note, required with
+/// `new_commitment`; `auditor_note` covers it when the pool has an auditor.
+#[allow(clippy::too_many_arguments)]
+pub fn withdraw(
+    program_id: &Pubkey,
+    pool: &Pubkey,
+    proof: Vec<u8>,
+    root: [u8; 32],
+    nullifier: [u8; 32],
+    new_commitment: Option<[u8; 32]>,
+    recipient: &Pubkey,
+    amount: u64,
+    encrypted_note: Option<Vec<u8>>,
+    auditor_note: Option<AuditorNote>,
+) -> Instruction {
+    build(
+        program_id,
+        &PrivacyInstruction::Withdraw {
+            proof,
+            root,
+            nullifier,
+            new_commitment,
+            recipient: *recipient,
+            amount,
+            encrypted_note,
+            auditor_note,
+        },
+        vec![
+            AccountMeta::new(*pool, false),
+            AccountMeta::new(pda::vault_address(program_id, pool), false),
+            AccountMeta::new(*recipient, false),
+            AccountMeta::new_readonly(pda::verification_key_address(program_id, pool, CircuitType::Transfer), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(pda::config_address(program_id), false),
+            AccountMeta::new(pda::nullifier_address(program_id, pool, &nullifier), false),
+            AccountMeta::new(pda::delayed_withdrawal_address(program_id, pool, &nullifier), false),
+            auditor_verification_key(program_id, pool),
+        ],
+    )
+}
+
+/// Ring-signature transfer inside a pool
+///
+/// `payer` funds the key image record PDA; `recipient_commitment` is the
+/// account the recipient watches for the new note. `auditor_note` is
+/// required exactly when the pool has an auditor.
+#[allow(clippy::too_many_arguments)]
+pub fn private_transfer(
+    program_id: &Pubkey,
+    pool: &Pubkey,
+    payer: &Pubkey,
+    recipient_commitment: &Pubkey,
+    ring_signature: Vec<u8>,
+    key_image: [u8; 32],
+    ring_members: Vec<[u8; 32]>,
+    new_commitment: [u8; 32],
+    encrypted_amount: Vec<u8>,
+    auditor_note: Option<AuditorNote>,
+) -> Instruction {
+    build(
+        program_id,
+        &PrivacyInstruction::PrivateTransfer {
+            ring_signature,
+            key_image,
+            ring_members,
+            new_commitment,
+            encrypted_amount,
+            auditor_note,
+        },
+        vec![
+            AccountMeta::new(*pool, false),
+            AccountMeta::new(pda::key_image_address(program_id, pool, &key_image), false),
+            AccountMeta::new(*recipient_commitment, false),
+            AccountMeta::new_readonly(pda::config_address(program_id), false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            auditor_verification_key(program_id, pool),
+        ],
+    )
+}
+
+/// The pool's AuditorNote VK, passed whether or not the pool has an auditor
+fn auditor_verification_key(program_id: &Pubkey, pool: &Pubkey) -> AccountMeta {
+    AccountMeta::new_readonly(pda::verification_key_address(program_id, pool, CircuitType::AuditorNote), false)
+}
+
+pub fn verify_balance(
+    program_id: &Pubkey,
+    pool: &Pubkey,
+    user: &Pubkey,
+    proof: Vec<u8>,
+    min_balance: u64,
+    balance_commitment: [u8; 32],
+) -> Instruction {
+    build(
+        program_id,
+        &PrivacyInstruction::VerifyBalance {
+            proof,
+            min_balance,
+            balance_commitment,
+        },
+        vec![
+            AccountMeta::new_readonly(*pool, false),
+            AccountMeta::new_readonly(*user, false),
+            AccountMeta::new_readonly(pda::verification_key_address(program_id, pool, CircuitType::Balance), false),
+        ],
+    )
+}
+
+/// Create the asset PDA for `asset_id`
+pub fn issue_asset(
+    program_id: &Pubkey,
+    issuer: &Pubkey,
+    name: String,
+    symbol: String,
+    decimals: u8,
+    total_supply: u64,
+    asset_id: [u8; 32],
+) -> Instruction {
+    build(
+        program_id,
+        &PrivacyInstruction::IssueAsset {
+            name,
+            symbol,
+            decimals,
+            total_supply,
+            asset_id,
+        },
+        vec![
+            AccountMeta::new(*issuer, true),
+            AccountMeta::new(pda::asset_address(program_id, &asset_id), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Shielded asset transfer, proven against `vk_pool`'s Transfer key
+///
+/// The sender slot is the nullifier PDA under the asset address;
+/// `recipient_note` is the account the recipient watches for the new note.
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_asset(
+    program_id: &Pubkey,
+    vk_pool: &Pubkey,
+    recipient_note: &Pubkey,
+    proof: Vec<u8>,
+    asset_id: [u8; 32],
+    nullifier: [u8; 32],
+    new_commitment: [u8; 32],
+    encrypted_data: Vec<u8>,
+) -> Instruction {
+    let asset = pda::asset_address(program_id, &asset_id);
+    build(
+        program_id,
+        &PrivacyInstruction::TransferAsset {
+            proof,
+            asset_id,
+            nullifier,
+            new_commitment,
+            encrypted_data,
+        },
+        vec![
+            AccountMeta::new(asset, false),
+            AccountMeta::new(pda::nullifier_address(program_id, &asset, &nullifier), false),
+            AccountMeta::new(*recipient_note, false),
+            AccountMeta::new_readonly(pda::verification_key_address(program_id, vk_pool, CircuitType::Transfer), false),
+        ],
+    )
+}
+
+/// Store a pool's verification key
+///
+/// `payer` funds the VK account; without one the authority pays, which a
+/// multisig authority cannot do.
+pub fn store_verification_key(
+    program_id: &Pubkey,
+    pool: &Pubkey,
+    authority: &Pubkey,
+    payer: Option<&Pubkey>,
+    circuit_type: CircuitType,
+    vk_data: Vec<u8>,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(pda::verification_key_address(program_id, pool, circuit_type), false),
+        AccountMeta::new_readonly(*pool, false),
+        match payer {
+            Some(_) => AccountMeta::new_readonly(*authority, true),
+            None => AccountMeta::new(*authority, true),
+        },
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    if let Some(payer) = payer {
+        accounts.push(AccountMeta::new(*payer, true));
+    }
+
+    build(
+        program_id,
+        &PrivacyInstruction::StoreVerificationKey {
+            circuit_type: circuit_type as u8,
+            vk_data,
+        },
+        accounts,
+    )
+}
+
+pub fn register_relayer(program_id: &Pubkey, wallet: &Pubkey, endpoint: String, stake: u64) -> Instruction {
+    build(
+        program_id,
+        &PrivacyInstruction::RegisterRelayer { endpoint, stake },
+        vec![
+            AccountMeta::new(pda::relayer_address(program_id, wallet), false),
+            AccountMeta::new(*wallet, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(pda::config_address(program_id), false),
+        ],
+    )
+}
+
+pub fn update_heartbeat(program_id: &Pubkey, wallet: &Pubkey) -> Instruction {
+    build(
+        program_id,
+        &PrivacyInstruction::UpdateHeartbeat,
+        vec![
+            AccountMeta::new(pda::relayer_address(program_id, wallet), false),
+            AccountMeta::new_readonly(*wallet, true),
+        ],
+    )
+}
+
+/// Report a relay by `relayer_wallet`, signed by the config admin (or by
+/// the relayer itself for a failure)
+pub fn report_relay(program_id: &Pubkey, relayer_wallet: &Pubkey, reporter: &Pubkey, success: bool) -> Instruction {
+    build(
+        program_id,
+        &PrivacyInstruction::ReportRelay { success },
+        vec![
+            AccountMeta::new(pda::relayer_address(program_id, relayer_wallet), false),
+            AccountMeta::new_readonly(pda::config_address(program_id), false),
+            AccountMeta::new_readonly(*reporter, true),
+        ],
+    )
+}
+
+pub fn migrate_relayer(program_id: &Pubkey, wallet: &Pubkey) -> Instruction {
+    build(
+        program_id,
+        &PrivacyInstruction::MigrateRelayer,
+        vec![
+            AccountMeta::new(pda::relayer_address(program_id, wallet), false),
+            AccountMeta::new(*wallet, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+pub fn initialize_config(program_id: &Pubkey, admin: &Pubkey, params: ConfigParams) -> Instruction {
+    build(
+        program_id,
+        &PrivacyInstruction::InitializeConfig { params },
+        vec![
+            AccountMeta::new(pda::config_address(program_id), false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(pda::program_data_address(program_id), false),
+        ],
+    )
+}
+
+pub fn update_config(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    params: ConfigParams,
+    new_admin: Option<Pubkey>,
+) -> Instruction {
+    build(
+        program_id,
+        &PrivacyInstruction::UpdateConfig { params, new_admin },
+        vec![
+            AccountMeta::new(pda::config_address(program_id), false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+    )
+}
+
+/// Pool instruction signed by the pool authority (or guardian) only
+fn pool_admin(program_id: &Pubkey, pool: &Pubkey, signer: &Pubkey, instruction: &PrivacyInstruction) -> Instruction {
+    build(
+        program_id,
+        instruction,
+        vec![
+            AccountMeta::new(*pool, false),
+            AccountMeta::new_readonly(*signer, true),
+        ],
+    )
+}
+
+pub fn update_root(program_id: &Pubkey, pool: &Pubkey, authority: &Pubkey, new_root: [u8; 32]) -> Instruction {
+    pool_admin(program_id, pool, authority, &PrivacyInstruction::UpdateRoot { new_root })
+}
+
+pub fn propose_authority(program_id: &Pubkey, pool: &Pubkey, authority: &Pubkey, new_authority: &Pubkey) -> Instruction {
+    pool_admin(
+        program_id,
+        pool,
+        authority,
+        &PrivacyInstruction::ProposeAuthority {
+            new_authority: *new_authority,
+        },
+    )
+}
+
+pub fn accept_authority(program_id: &Pubkey, pool: &Pubkey, new_authority: &Pubkey) -> Instruction {
+    pool_admin(program_id, pool, new_authority, &PrivacyInstruction::AcceptAuthority)
+}
+
+pub fn set_guardian(program_id: &Pubkey, pool: &Pubkey, authority: &Pubkey, guardian: Option<Pubkey>) -> Instruction {
+    pool_admin(program_id, pool, authority, &PrivacyInstruction::SetGuardian { guardian })
+}
+
+/// Set or clear the auditor's Baby Jubjub key (x, y)
+pub fn set_auditor(
+    program_id: &Pubkey,
+    pool: &Pubkey,
+    authority: &Pubkey,
+    auditor_key: Option<[[u8; 32]; 2]>,
+) -> Instruction {
+    pool_admin(program_id, pool, authority, &PrivacyInstruction::SetAuditor { auditor_key })
+}
+
+/// Pause `operations` (PoolState::PAUSE_* flags); `signer` is the guardian or authority
+pub fn pause(
+    program_id: &Pubkey,
+    pool: &Pubkey,
+    signer: &Pubkey,
+    operations: u8,
+    expires_at: Option<i64>,
+) -> Instruction {
+    pool_admin(program_id, pool, signer, &PrivacyInstruction::Pause { operations, expires_at })
+}
+
+pub fn unpause(program_id: &Pubkey, pool: &Pubkey, authority: &Pubkey, operations: u8) -> Instruction {
+    pool_admin(program_id, pool, authority, &PrivacyInstruction::Unpause { operations })
+}
+
+pub fn set_pool_limits(program_id: &Pubkey, pool: &Pubkey, authority: &Pubkey, limits: PoolLimits) -> Instruction {
+    pool_admin(program_id, pool, authority, &PrivacyInstruction::SetPoolLimits { limits })
+}
+
+pub fn claim_delayed_withdrawal(
+    program_id: &Pubkey,
+    pool: &Pubkey,
+    nullifier: &[u8; 32],
+    recipient: &Pubkey,
+) -> Instruction {
+    build(
+        program_id,
+        &PrivacyInstruction::ClaimDelayedWithdrawal,
+        vec![
+            AccountMeta::new(*pool, false),
+            AccountMeta::new(pda::delayed_withdrawal_address(program_id, pool, nullifier), false),
+            AccountMeta::new(*recipient, false),
+        ],
+    )
+}
+
+pub fn migrate(program_id: &Pubkey, account: &Pubkey, payer: &Pubkey, account_type: AccountType) -> Instruction {
+    build(
+        program_id,
+        &PrivacyInstruction::Migrate { account_type },
+        vec![
+            AccountMeta::new(*account, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+pub fn create_multisig(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    create_key: &Pubkey,
+    threshold: u8,
+    signers: Vec<Pubkey>,
+) -> Instruction {
+    build(
+        program_id,
+        &PrivacyInstruction::CreateMultisig {
+            create_key: *create_key,
+            threshold,
+            signers,
+        },
+        vec![
+            AccountMeta::new(pda::multisig_address(program_id, create_key), false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Propose `instruction` for the multisig to sign
+///
+/// `index` must be the multisig's current `proposal_count`.
+pub fn create_proposal(
+    program_id: &Pubkey,
+    multisig: &Pubkey,
+    index: u64,
+    proposer: &Pubkey,
+    instruction: &Instruction,
+) -> Instruction {
+    build(
+        program_id,
+        &PrivacyInstruction::CreateProposal {
+            instruction_data: instruction.data.clone(),
+            accounts: instruction
+                .accounts
+                .iter()
+                .map(|meta| ProposalAccount {
+                    pubkey: meta.pubkey,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+                .collect(),
+        },
+        vec![
+            AccountMeta::new(*multisig, false),
+            AccountMeta::new(pda::proposal_address(program_id, multisig, index), false),
+            AccountMeta::new(*proposer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+pub fn approve_proposal(program_id: &Pubkey, multisig: &Pubkey, index: u64, member: &Pubkey) -> Instruction {
+    build(
+        program_id,
+        &PrivacyInstruction::ApproveProposal,
+        vec![
+            AccountMeta::new_readonly(*multisig, false),
+            AccountMeta::new(pda::proposal_address(program_id, multisig, index), false),
+            AccountMeta::new_readonly(*member, true),
+        ],
+    )
+}
+
+/// Execute proposal `index`, which wraps `instruction`
+///
+/// The wrapped accounts are passed without signer flags: the multisig signs
+/// through the program.
+pub fn execute_proposal(program_id: &Pubkey, multisig: &Pubkey, index: u64, instruction: &Instruction) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*multisig, false),
+        AccountMeta::new(pda::proposal_address(program_id, multisig, index), false),
+        AccountMeta::new_readonly(*program_id, false),
+    ];
+    accounts.extend(instruction.accounts.iter().map(|meta| AccountMeta {
+        pubkey: meta.pubkey,
+        is_signer: false,
+        is_writable: meta.is_writable,
+    }));
+
+    build(program_id, &PrivacyInstruction::ExecuteProposal, accounts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use borsh::BorshDeserialize;
+
+    #[test]
+    fn test_withdraw_derives_pool_accounts() {
+        let program_id = shadow_privacy::id();
+        let authority = Pubkey::new_unique();
+        let pool = pda::pool_address(&program_id, &authority, 1_000_000);
+        let recipient = Pubkey::new_unique();
+        let nullifier = [7u8; 32];
+
+        let ix = withdraw(&program_id, &pool, vec![1; 256], [2; 32], nullifier, None, &recipient, 1_000_000, None, None);
+        let keys: Vec<Pubkey> = ix.accounts.iter().map(|meta| meta.pubkey).collect();
+        assert_eq!(
+            keys,
+            vec![
+                pool,
+                pda::vault_address(&program_id, &pool),
+                recipient,
+                pda::verification_key_address(&program_id, &pool, CircuitType::Transfer),
+                system_program::id(),
+                pda::config_address(&program_id),
+                pda::nullifier_address(&program_id, &pool, &nullifier),
+                pda::delayed_withdrawal_address(&program_id, &pool, &nullifier),
+                pda::verification_key_address(&program_id, &pool, CircuitType::AuditorNote),
+            ]
+        );
+        assert!(ix.accounts.iter().all(|meta| !meta.is_signer));
+
+        match PrivacyInstruction::try_from_slice(&ix.data).unwrap() {
+            PrivacyInstruction::Withdraw { nullifier: decoded, recipient: to, .. } => {
+                assert_eq!(decoded, nullifier);
+                assert_eq!(to, recipient);
+            }
+            other => panic!("unexpected instruction {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_store_verification_key_payer() {
+        let program_id = shadow_privacy::id();
+        let pool = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+
+        let ix = store_verification_key(&program_id, &pool, &authority, None, CircuitType::Balance, vec![1]);
+        assert_eq!(ix.accounts.len(), 4);
+        assert_eq!(ix.accounts[0].pubkey, pda::verification_key_address(&program_id, &pool, CircuitType::Balance));
+        assert_eq!(ix.accounts[1], AccountMeta::new_readonly(pool, false));
+        assert!(ix.accounts[2].is_writable);
+
+        let payer = Pubkey::new_unique();
+        let ix = store_verification_key(&program_id, &pool, &authority, Some(&payer), CircuitType::Balance, vec![1]);
+        assert_eq!(ix.accounts.len(), 5);
+        assert!(!ix.accounts[2].is_writable);
+        assert_eq!(ix.accounts[4], AccountMeta::new(payer, true));
+    }
+
+    #[test]
+    fn test_private_transfer_records_key_image() {
+        let program_id = shadow_privacy::id();
+        let pool = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+        let key_image = [3u8; 32];
+
+        let ix = private_transfer(
+            &program_id,
+            &pool,
+            &payer,
+            &Pubkey::new_unique(),
+            vec![0; 64],
+            key_image,
+            vec![[1; 32]],
+            [2; 32],
+            vec![],
+            None,
+        );
+        assert_eq!(ix.accounts[1], AccountMeta::new(pda::key_image_address(&program_id, &pool, &key_image), false));
+        assert_eq!(ix.accounts[4], AccountMeta::new(payer, true));
+        assert_eq!(ix.accounts[5], AccountMeta::new_readonly(system_program::id(), false));
+    }
+
+    #[test]
+    fn test_proposal_wraps_instruction() {
+        let program_id = shadow_privacy::id();
+        let multisig = pda::multisig_address(&program_id, &Pubkey::new_unique());
+        let pool = Pubkey::new_unique();
+        let inner = update_root(&program_id, &pool, &multisig, [9; 32]);
+
+        let propose = create_proposal(&program_id, &multisig, 3, &Pubkey::new_unique(), &inner);
+        match PrivacyInstruction::try_from_slice(&propose.data).unwrap() {
+            PrivacyInstruction::CreateProposal { instruction_data, accounts } => {
+                assert_eq!(instruction_data, inner.data);
+                assert_eq!(accounts.len(), 2);
+                assert!(accounts[1].is_signer);
+            }
+            other => panic!("unexpected instruction {:?}", other),
+        }
+
+        let execute = execute_proposal(&program_id, &multisig, 3, &inner);
+        assert_eq!(execute.accounts[1].pubkey, pda::proposal_address(&program_id, &multisig, 3));
+        assert_eq!(execute.accounts[3], AccountMeta::new(pool, false));
+        assert_eq!(execute.accounts[4], AccountMeta::new_readonly(multisig, false));
+    }
+}
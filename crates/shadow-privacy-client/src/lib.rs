@@ -0,0 +1,23 @@
+// Shadow Privacy Client
+//
+// Rust SDK for the Shadow Privacy program:
+// - Instruction builders for every `PrivacyInstruction`, deriving the PDAs each needs
+// - PDA helpers (pool, vault, verification keys, nullifiers, key images, relayers, ...)
+// - Typed account decoding, and fetching over RPC with the `rpc` feature
+// - JSON dumps of any program account, with a summary of verification keys
+// - Transaction helpers that request compute units and heap for proof verification
+
+pub mod accounts;
+pub mod decode;
+pub mod error;
+pub mod instruction;
+pub mod pda;
+pub mod transaction;
+
+pub use accounts::decode_account;
+#[cfg(feature = "rpc")]
+pub use accounts::{fetch_account, fetch_optional_account};
+pub use decode::decode_account_json;
+pub use error::ClientError;
+pub use shadow_privacy::{self, id};
+pub use transaction::{build_transaction, with_compute_budget, ComputeBudget};
@@ -0,0 +1,70 @@
+//! Program-derived addresses
+//!
+//! Thin wrappers over the `derive_address` helpers in `shadow_privacy::state`
+//! that drop the bump, which clients never pass in instructions.
+
+use shadow_privacy::state::{
+    AssetState, CircuitType, DelayedWithdrawal, Multisig, MultisigProposal, PoolState,
+    ProtocolConfig, RelayerAccount, VerificationKeyAccount,
+};
+use solana_program::pubkey::Pubkey;
+
+/// Pool state PDA: ["pool", authority, denomination]
+pub fn pool_address(program_id: &Pubkey, authority: &Pubkey, denomination: u64) -> Pubkey {
+    PoolState::derive_address(authority, denomination, program_id).0
+}
+
+/// Vault PDA holding a pool's lamports: ["vault", pool]
+pub fn vault_address(program_id: &Pubkey, pool: &Pubkey) -> Pubkey {
+    PoolState::derive_vault_address(pool, program_id).0
+}
+
+/// Verification key PDA for one of a pool's circuits
+pub fn verification_key_address(program_id: &Pubkey, pool: &Pubkey, circuit_type: CircuitType) -> Pubkey {
+    VerificationKeyAccount::derive_address(pool, circuit_type, program_id).0
+}
+
+/// Nullifier record PDA: ["nullifier", pool, nullifier]
+pub fn nullifier_address(program_id: &Pubkey, pool: &Pubkey, nullifier: &[u8; 32]) -> Pubkey {
+    PoolState::derive_nullifier_pda(pool, nullifier, program_id).0
+}
+
+/// Key image record PDA: ["key_image", pool, key_image]
+pub fn key_image_address(program_id: &Pubkey, pool: &Pubkey, key_image: &[u8; 32]) -> Pubkey {
+    PoolState::derive_key_image_pda(pool, key_image, program_id).0
+}
+
+/// Delayed withdrawal PDA: ["withdrawal", pool, nullifier]
+pub fn delayed_withdrawal_address(program_id: &Pubkey, pool: &Pubkey, nullifier: &[u8; 32]) -> Pubkey {
+    DelayedWithdrawal::derive_address(pool, nullifier, program_id).0
+}
+
+/// Relayer account PDA: ["relayer", wallet]
+pub fn relayer_address(program_id: &Pubkey, wallet: &Pubkey) -> Pubkey {
+    RelayerAccount::derive_address(wallet, program_id).0
+}
+
+/// Protocol config PDA: ["config"]
+pub fn config_address(program_id: &Pubkey) -> Pubkey {
+    ProtocolConfig::derive_address(program_id).0
+}
+
+/// ProgramData of the program (upgradeable loader): [program_id]
+pub fn program_data_address(program_id: &Pubkey) -> Pubkey {
+    ProtocolConfig::derive_program_data_address(program_id).0
+}
+
+/// Asset state PDA: ["asset", asset_id]
+pub fn asset_address(program_id: &Pubkey, asset_id: &[u8; 32]) -> Pubkey {
+    AssetState::derive_address(asset_id, program_id).0
+}
+
+/// Multisig PDA: ["multisig", create_key]
+pub fn multisig_address(program_id: &Pubkey, create_key: &Pubkey) -> Pubkey {
+    Multisig::derive_address(create_key, program_id).0
+}
+
+/// Multisig proposal PDA: ["proposal", multisig, index]
+pub fn proposal_address(program_id: &Pubkey, multisig: &Pubkey, index: u64) -> Pubkey {
+    MultisigProposal::derive_address(multisig, index, program_id).0
+}
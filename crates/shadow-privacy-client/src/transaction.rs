@@ -0,0 +1,189 @@
+//! Transaction helpers with compute-budget and heap-frame requests
+//!
+//! Groth16 verification runs out of the default 200k compute units and 32KB
+//! heap, so proof-carrying instructions go out with larger requests.
+
+use borsh::BorshDeserialize;
+use shadow_privacy::instruction::PrivacyInstruction;
+use solana_program::{hash::Hash, instruction::Instruction, pubkey::Pubkey};
+use solana_sdk::{compute_budget::ComputeBudgetInstruction, signers::Signers, transaction::Transaction};
+
+/// Compute-budget requests prepended to a transaction (None keeps the runtime default)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ComputeBudget {
+    pub unit_limit: Option<u32>,
+    /// Priority fee in micro-lamports per compute unit
+    pub unit_price: Option<u64>,
+    /// Heap frame size in bytes (multiple of 1024, at most MAX_HEAP_FRAME_BYTES)
+    pub heap_frame_bytes: Option<u32>,
+}
+
+impl ComputeBudget {
+    pub const MAX_UNIT_LIMIT: u32 = 1_400_000;
+    pub const MAX_HEAP_FRAME_BYTES: u32 = 256 * 1024;
+
+    /// Ring signature verification over the largest allowed ring
+    pub const RING_SIGNATURE_UNIT_LIMIT: u32 = 400_000;
+
+    /// Budget for Groth16 verification: maximum units and heap
+    pub fn groth16() -> Self {
+        ComputeBudget {
+            unit_limit: Some(Self::MAX_UNIT_LIMIT),
+            unit_price: None,
+            heap_frame_bytes: Some(Self::MAX_HEAP_FRAME_BYTES),
+        }
+    }
+
+    /// Budget one program instruction needs
+    pub fn for_instruction(instruction: &PrivacyInstruction) -> Self {
+        match instruction {
+            // Auditor notes carry a Groth16 proof of their own
+            PrivacyInstruction::Withdraw { .. }
+            | PrivacyInstruction::VerifyBalance { .. }
+            | PrivacyInstruction::TransferAsset { .. }
+            | PrivacyInstruction::Deposit { auditor_note: Some(_), .. }
+            | PrivacyInstruction::PrivateTransfer { auditor_note: Some(_), .. } => Self::groth16(),
+            PrivacyInstruction::PrivateTransfer { .. } => ComputeBudget {
+                unit_limit: Some(Self::RING_SIGNATURE_UNIT_LIMIT),
+                ..Self::default()
+            },
+            _ => Self::default(),
+        }
+    }
+
+    /// Smallest budget covering every `program_id` instruction in `instructions`
+    ///
+    /// Instructions for other programs (or that fail to decode) add nothing.
+    pub fn for_instructions(program_id: &Pubkey, instructions: &[Instruction]) -> Self {
+        instructions
+            .iter()
+            .filter(|instruction| instruction.program_id == *program_id)
+            .filter_map(|instruction| PrivacyInstruction::try_from_slice(&instruction.data).ok())
+            .map(|instruction| Self::for_instruction(&instruction))
+            .fold(Self::default(), |budget, other| budget.max(&other))
+    }
+
+    /// Field-wise maximum of two budgets
+    pub fn max(&self, other: &Self) -> Self {
+        ComputeBudget {
+            unit_limit: self.unit_limit.max(other.unit_limit),
+            unit_price: self.unit_price.max(other.unit_price),
+            heap_frame_bytes: self.heap_frame_bytes.max(other.heap_frame_bytes),
+        }
+    }
+
+    pub fn with_unit_price(mut self, micro_lamports: u64) -> Self {
+        self.unit_price = Some(micro_lamports);
+        self
+    }
+
+    /// ComputeBudget program instructions for these requests
+    pub fn instructions(&self) -> Vec<Instruction> {
+        let mut instructions = Vec::new();
+        if let Some(bytes) = self.heap_frame_bytes {
+            instructions.push(ComputeBudgetInstruction::request_heap_frame(bytes));
+        }
+        if let Some(units) = self.unit_limit {
+            instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(units));
+        }
+        if let Some(price) = self.unit_price {
+            instructions.push(ComputeBudgetInstruction::set_compute_unit_price(price));
+        }
+        instructions
+    }
+}
+
+/// `instructions` preceded by the requests in `budget`
+pub fn with_compute_budget(budget: &ComputeBudget, instructions: &[Instruction]) -> Vec<Instruction> {
+    let mut all = budget.instructions();
+    all.extend_from_slice(instructions);
+    all
+}
+
+/// Signed transaction for `instructions` with the budget they need
+///
+/// `budget` is merged with the per-instruction defaults from
+/// [`ComputeBudget::for_instructions`], so it can carry just a priority fee.
+pub fn build_transaction<T: Signers + ?Sized>(
+    program_id: &Pubkey,
+    instructions: &[Instruction],
+    budget: &ComputeBudget,
+    payer: &Pubkey,
+    signers: &T,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let budget = budget.max(&ComputeBudget::for_instructions(program_id, instructions));
+    Transaction::new_signed_with_payer(
+        &with_compute_budget(&budget, instructions),
+        Some(payer),
+        signers,
+        recent_blockhash,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction;
+    use shadow_privacy::state::AuditorNote;
+    use solana_sdk::{compute_budget, signature::Keypair, signer::Signer};
+
+    #[test]
+    fn test_budget_follows_instructions() {
+        let program_id = shadow_privacy::id();
+        let pool = Pubkey::new_unique();
+        let depositor = Pubkey::new_unique();
+
+        let deposit = instruction::deposit(&program_id, &pool, &depositor, [1; 32], 10, vec![], None);
+        assert_eq!(ComputeBudget::for_instructions(&program_id, std::slice::from_ref(&deposit)), ComputeBudget::default());
+
+        let withdraw =
+            instruction::withdraw(&program_id, &pool, vec![0; 256], [0; 32], [1; 32], None, &depositor, 10, None, None);
+        let budget = ComputeBudget::for_instructions(&program_id, &[deposit, withdraw.clone()]);
+        assert_eq!(budget, ComputeBudget::groth16());
+
+        // A deposit into an audited pool proves its auditor note
+        let auditor_note = AuditorNote {
+            ephemeral_key: [[0; 32]; 2],
+            ciphertext: [[0; 32]; 3],
+            proof: vec![0; 128],
+        };
+        let audited = instruction::deposit(&program_id, &pool, &depositor, [1; 32], 10, vec![], Some(auditor_note));
+        assert_eq!(ComputeBudget::for_instructions(&program_id, &[audited]), ComputeBudget::groth16());
+
+        // Instructions for other programs are ignored
+        let mut foreign = withdraw;
+        foreign.program_id = Pubkey::new_unique();
+        assert_eq!(ComputeBudget::for_instructions(&program_id, &[foreign]), ComputeBudget::default());
+    }
+
+    #[test]
+    fn test_transaction_prepends_budget() {
+        let program_id = shadow_privacy::id();
+        let payer = Keypair::new();
+        let pool = Pubkey::new_unique();
+        let withdraw =
+            instruction::withdraw(&program_id, &pool, vec![0; 256], [0; 32], [1; 32], None, &payer.pubkey(), 10, None, None);
+
+        let tx = build_transaction(
+            &program_id,
+            &[withdraw],
+            &ComputeBudget::default().with_unit_price(5),
+            &payer.pubkey(),
+            &[&payer],
+            Hash::default(),
+        );
+        tx.verify().unwrap();
+
+        let message = &tx.message;
+        let programs: Vec<Pubkey> = message
+            .instructions
+            .iter()
+            .map(|ix| message.account_keys[ix.program_id_index as usize])
+            .collect();
+        assert_eq!(
+            programs,
+            vec![compute_budget::id(), compute_budget::id(), compute_budget::id(), program_id]
+        );
+    }
+}
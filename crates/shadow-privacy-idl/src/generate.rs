@@ -0,0 +1,830 @@
+//! IDL generation from the program sources
+//!
+//! - Instructions: `PrivacyInstruction` variants, their fields and the
+//!   `#[account(...)]` attributes checked by `ShankInstruction`
+//! - Accounts: every type with an `impl ProgramAccount` in `state.rs`
+//! - Types: whatever the instructions and accounts reference, resolving
+//!   array lengths such as `PoolState::MAX_CACHE_ENTRIES`
+//! - Errors: `PrivacyError` variants and their `#[error]` messages
+
+use crate::{error::IdlError, idl::*};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use syn::{
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    Attribute, Expr, Fields, GenericArgument, Ident, Item, ItemEnum, Lit, LitInt, LitStr,
+    PathArguments, Token, Type,
+};
+
+pub const PROGRAM_NAME: &str = "shadow_privacy";
+const INSTRUCTION_ENUM: &str = "PrivacyInstruction";
+const ERROR_ENUM: &str = "PrivacyError";
+const ACCOUNT_TRAIT: &str = "ProgramAccount";
+
+/// Generate the IDL for the program crate at `program_dir`
+pub fn generate(program_dir: &Path) -> Result<Idl, IdlError> {
+    let src = program_dir.join("src");
+    let instruction_file = parse_file(src.join("instruction.rs"))?;
+    let state_file = parse_file(src.join("state.rs"))?;
+    let error_file = parse_file(src.join("error.rs"))?;
+    let lib_file = parse_file(src.join("lib.rs"))?;
+
+    let mut types = TypeRegistry::new(&state_file)?;
+    let instructions = instructions(find_enum(&instruction_file, INSTRUCTION_ENUM)?, &mut types)?;
+
+    let account_names = account_names(&state_file);
+    let accounts = account_names
+        .iter()
+        .map(|name| types.type_def(name))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Resolving a type can reference more types, so walk the list as it grows
+    let mut defined = Vec::new();
+    let mut next = 0;
+    while next < types.referenced.len() {
+        let name = types.referenced[next].clone();
+        if !account_names.contains(&name) {
+            defined.push(types.type_def(&name)?);
+        }
+        next += 1;
+    }
+
+    Ok(Idl {
+        version: package_version(&program_dir.join("Cargo.toml"))?,
+        name: PROGRAM_NAME.to_string(),
+        instructions,
+        accounts,
+        types: defined,
+        errors: errors(find_enum(&error_file, ERROR_ENUM)?)?,
+        metadata: IdlMetadata {
+            origin: "shank".to_string(),
+            address: program_address(&lib_file)?,
+        },
+    })
+}
+
+/// Pretty-printed IDL JSON, as checked in
+pub fn to_json(idl: &Idl) -> Result<String, IdlError> {
+    Ok(serde_json::to_string_pretty(idl)? + "\n")
+}
+
+fn read(path: &Path) -> Result<String, IdlError> {
+    fs::read_to_string(path).map_err(|source| IdlError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn parse_file(path: PathBuf) -> Result<syn::File, IdlError> {
+    let contents = read(&path)?;
+    syn::parse_file(&contents).map_err(|source| IdlError::Parse { path, source })
+}
+
+fn find_enum<'a>(file: &'a syn::File, name: &str) -> Result<&'a ItemEnum, IdlError> {
+    file.items
+        .iter()
+        .find_map(|item| match item {
+            Item::Enum(item) if item.ident == name => Some(item),
+            _ => None,
+        })
+        .ok_or_else(|| IdlError::Unsupported(format!("enum {} not found", name)))
+}
+
+/// `snake_case` to the camelCase Shank uses for args, fields and accounts
+fn mixed_case(name: &str) -> String {
+    let mut words = name.split('_').filter(|word| !word.is_empty());
+    let mut out = words.next().unwrap_or_default().to_string();
+    for word in words {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            out.extend(first.to_uppercase());
+            out.push_str(chars.as_str());
+        }
+    }
+    out
+}
+
+fn instructions(
+    item: &ItemEnum,
+    types: &mut TypeRegistry,
+) -> Result<Vec<IdlInstruction>, IdlError> {
+    item.variants
+        .iter()
+        .enumerate()
+        .map(|(index, variant)| {
+            let context = format!("{}::{}", INSTRUCTION_ENUM, variant.ident);
+            if variant.discriminant.is_some() {
+                return Err(IdlError::Unsupported(format!(
+                    "{}: explicit discriminant",
+                    context
+                )));
+            }
+
+            let args = match &variant.fields {
+                Fields::Named(fields) => fields
+                    .named
+                    .iter()
+                    .map(|field| {
+                        let name = field.ident.as_ref().expect("named field").to_string();
+                        Ok(IdlField {
+                            name: mixed_case(&name),
+                            ty: types.idl_type(&field.ty, &context)?,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, IdlError>>()?,
+                Fields::Unit => Vec::new(),
+                Fields::Unnamed(_) => {
+                    return Err(IdlError::Unsupported(format!(
+                        "{}: tuple variants",
+                        context
+                    )))
+                }
+            };
+
+            Ok(IdlInstruction {
+                name: variant.ident.to_string(),
+                accounts: instruction_accounts(&variant.attrs, &context)?,
+                args,
+                discriminant: IdlDiscriminant {
+                    ty: IdlType::Primitive("u8".to_string()),
+                    value: u8::try_from(index).map_err(|_| {
+                        IdlError::Unsupported("more than 256 instructions".to_string())
+                    })?,
+                },
+            })
+        })
+        .collect()
+}
+
+/// One argument of `#[account(0, writable, signer, optional, name = "..", desc = "..")]`
+enum AccountArg {
+    Index(LitInt),
+    Flag(Ident),
+    Value(Ident, LitStr),
+}
+
+impl Parse for AccountArg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(LitInt) {
+            return Ok(AccountArg::Index(input.parse()?));
+        }
+
+        let ident: Ident = input.parse()?;
+        if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+            Ok(AccountArg::Value(ident, input.parse()?))
+        } else {
+            Ok(AccountArg::Flag(ident))
+        }
+    }
+}
+
+fn instruction_accounts(attrs: &[Attribute], context: &str) -> Result<Vec<IdlAccount>, IdlError> {
+    let unsupported = |message: String| IdlError::Unsupported(format!("{}: {}", context, message));
+    let mut accounts = Vec::new();
+
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("account")) {
+        let args = attr
+            .parse_args_with(Punctuated::<AccountArg, Token![,]>::parse_terminated)
+            .map_err(|error| unsupported(error.to_string()))?;
+
+        let mut account = IdlAccount {
+            name: String::new(),
+            is_mut: false,
+            is_signer: false,
+            is_optional: false,
+            desc: None,
+        };
+        for arg in args {
+            match arg {
+                AccountArg::Index(index) => {
+                    let index: usize = index
+                        .base10_parse()
+                        .map_err(|error| unsupported(error.to_string()))?;
+                    if index != accounts.len() {
+                        return Err(unsupported(format!(
+                            "account {} listed at position {}",
+                            index,
+                            accounts.len()
+                        )));
+                    }
+                }
+                AccountArg::Flag(flag) => match flag.to_string().as_str() {
+                    "writable" | "write" | "writ" | "mut" | "w" => account.is_mut = true,
+                    "signer" | "sign" | "sig" | "s" => account.is_signer = true,
+                    "optional" | "option" | "opt" => account.is_optional = true,
+                    other => return Err(unsupported(format!("unknown account flag {}", other))),
+                },
+                AccountArg::Value(key, value) => match key.to_string().as_str() {
+                    "name" => account.name = mixed_case(&value.value()),
+                    "desc" | "description" => account.desc = Some(value.value()),
+                    other => return Err(unsupported(format!("unknown account key {}", other))),
+                },
+            }
+        }
+
+        if account.name.is_empty() {
+            return Err(unsupported(format!(
+                "account {} has no name",
+                accounts.len()
+            )));
+        }
+        accounts.push(account);
+    }
+
+    Ok(accounts)
+}
+
+fn account_names(state: &syn::File) -> Vec<String> {
+    state
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Impl(item) => {
+                let (_, trait_path, _) = item.trait_.as_ref()?;
+                if !trait_path.is_ident(ACCOUNT_TRAIT) {
+                    return None;
+                }
+                match &*item.self_ty {
+                    Type::Path(path) => path.path.get_ident().map(Ident::to_string),
+                    _ => None,
+                }
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// State types and associated constants, plus the types referenced so far
+struct TypeRegistry<'a> {
+    items: HashMap<String, &'a Item>,
+    consts: HashMap<(String, String), usize>,
+    referenced: Vec<String>,
+}
+
+impl<'a> TypeRegistry<'a> {
+    fn new(state: &'a syn::File) -> Result<Self, IdlError> {
+        let mut items = HashMap::new();
+        let mut consts = HashMap::new();
+
+        for item in &state.items {
+            match item {
+                Item::Struct(item_struct) => {
+                    items.insert(item_struct.ident.to_string(), item);
+                }
+                Item::Enum(item_enum) => {
+                    items.insert(item_enum.ident.to_string(), item);
+                }
+                Item::Impl(item_impl) if item_impl.trait_.is_none() => {
+                    let Type::Path(self_ty) = &*item_impl.self_ty else {
+                        continue;
+                    };
+                    let Some(self_name) = self_ty.path.get_ident() else {
+                        continue;
+                    };
+                    for impl_item in &item_impl.items {
+                        if let syn::ImplItem::Const(constant) = impl_item {
+                            if let Expr::Lit(expr) = &constant.expr {
+                                if let Lit::Int(value) = &expr.lit {
+                                    let value =
+                                        value.base10_parse().map_err(|source| IdlError::Parse {
+                                            path: PathBuf::from("state.rs"),
+                                            source,
+                                        })?;
+                                    consts.insert(
+                                        (self_name.to_string(), constant.ident.to_string()),
+                                        value,
+                                    );
+                                }
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(TypeRegistry {
+            items,
+            consts,
+            referenced: Vec::new(),
+        })
+    }
+
+    fn idl_type(&mut self, ty: &Type, context: &str) -> Result<IdlType, IdlError> {
+        let unsupported =
+            || IdlError::Unsupported(format!("{}: unsupported type {}", context, quote_type(ty)));
+
+        match ty {
+            Type::Array(array) => {
+                let len = self.array_len(&array.len).ok_or_else(unsupported)?;
+                Ok(IdlType::Array {
+                    array: (Box::new(self.idl_type(&array.elem, context)?), len),
+                })
+            }
+            Type::Path(path) => {
+                let segment = path.path.segments.last().ok_or_else(unsupported)?;
+                let name = segment.ident.to_string();
+                match (&segment.arguments, name.as_str()) {
+                    (
+                        PathArguments::None,
+                        primitive @ ("u8" | "u16" | "u32" | "u64" | "u128" | "i8" | "i16" | "i32"
+                        | "i64" | "i128" | "bool"),
+                    ) => Ok(IdlType::Primitive(primitive.to_string())),
+                    (PathArguments::None, "String") => Ok(IdlType::Primitive("string".to_string())),
+                    (PathArguments::None, "Pubkey") => {
+                        Ok(IdlType::Primitive("publicKey".to_string()))
+                    }
+                    (PathArguments::None, _) if self.items.contains_key(&name) => {
+                        if !self.referenced.contains(&name) {
+                            self.referenced.push(name.clone());
+                        }
+                        Ok(IdlType::Defined { defined: name })
+                    }
+                    (PathArguments::AngleBracketed(args), "Vec" | "Option") => {
+                        let inner = match args.args.first() {
+                            Some(GenericArgument::Type(inner)) if args.args.len() == 1 => inner,
+                            _ => return Err(unsupported()),
+                        };
+                        let inner = self.idl_type(inner, context)?;
+                        Ok(match (name.as_str(), inner) {
+                            ("Vec", IdlType::Primitive(byte)) if byte == "u8" => {
+                                IdlType::Primitive("bytes".to_string())
+                            }
+                            ("Vec", inner) => IdlType::Vec {
+                                vec: Box::new(inner),
+                            },
+                            (_, inner) => IdlType::Option {
+                                option: Box::new(inner),
+                            },
+                        })
+                    }
+                    _ => Err(unsupported()),
+                }
+            }
+            _ => Err(unsupported()),
+        }
+    }
+
+    /// Literal lengths and `Type::CONST` lengths defined in `state.rs`
+    fn array_len(&self, len: &Expr) -> Option<usize> {
+        match len {
+            Expr::Lit(expr) => match &expr.lit {
+                Lit::Int(value) => value.base10_parse().ok(),
+                _ => None,
+            },
+            Expr::Path(path) => {
+                let segments: Vec<String> = path
+                    .path
+                    .segments
+                    .iter()
+                    .map(|segment| segment.ident.to_string())
+                    .collect();
+                match segments.as_slice() {
+                    [owner, name] => self.consts.get(&(owner.clone(), name.clone())).copied(),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn type_def(&mut self, name: &str) -> Result<IdlTypeDef, IdlError> {
+        let item = *self
+            .items
+            .get(name)
+            .ok_or_else(|| IdlError::Unsupported(format!("type {} not found in state.rs", name)))?;
+
+        let ty = match item {
+            Item::Struct(item) => {
+                let Fields::Named(fields) = &item.fields else {
+                    return Err(IdlError::Unsupported(format!(
+                        "{}: only named fields are supported",
+                        name
+                    )));
+                };
+                let fields = fields
+                    .named
+                    .iter()
+                    .map(|field| {
+                        let field_name = field.ident.as_ref().expect("named field").to_string();
+                        Ok(IdlField {
+                            name: mixed_case(&field_name),
+                            ty: self.idl_type(&field.ty, &format!("{}.{}", name, field_name))?,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, IdlError>>()?;
+                IdlTypeDefTy::Struct { fields }
+            }
+            Item::Enum(item) => {
+                let variants = item
+                    .variants
+                    .iter()
+                    .map(|variant| match variant.fields {
+                        Fields::Unit => Ok(IdlEnumVariant {
+                            name: variant.ident.to_string(),
+                        }),
+                        _ => Err(IdlError::Unsupported(format!(
+                            "{}::{}: only unit variants are supported",
+                            name, variant.ident
+                        ))),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                IdlTypeDefTy::Enum { variants }
+            }
+            _ => unreachable!("only structs and enums are registered"),
+        };
+
+        Ok(IdlTypeDef {
+            name: name.to_string(),
+            ty,
+        })
+    }
+}
+
+fn quote_type(ty: &Type) -> String {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .iter()
+            .map(|segment| segment.ident.to_string())
+            .collect::<Vec<_>>()
+            .join("::"),
+        _ => "expression".to_string(),
+    }
+}
+
+fn errors(item: &ItemEnum) -> Result<Vec<IdlErrorCode>, IdlError> {
+    item.variants
+        .iter()
+        .enumerate()
+        .map(|(code, variant)| {
+            let context = format!("{}::{}", ERROR_ENUM, variant.ident);
+            if variant.discriminant.is_some() {
+                return Err(IdlError::Unsupported(format!(
+                    "{}: explicit discriminant",
+                    context
+                )));
+            }
+
+            let msg = variant
+                .attrs
+                .iter()
+                .find(|attr| attr.path().is_ident("error"))
+                .and_then(|attr| attr.parse_args::<LitStr>().ok())
+                .ok_or_else(|| {
+                    IdlError::Unsupported(format!("{}: missing #[error(\"...\")]", context))
+                })?;
+
+            Ok(IdlErrorCode {
+                code: code as u32,
+                name: variant.ident.to_string(),
+                msg: msg.value(),
+            })
+        })
+        .collect()
+}
+
+/// Address from `declare_id!("...")`
+fn program_address(lib: &syn::File) -> Result<String, IdlError> {
+    lib.items
+        .iter()
+        .find_map(|item| match item {
+            Item::Macro(item)
+                if item
+                    .mac
+                    .path
+                    .segments
+                    .last()
+                    .is_some_and(|segment| segment.ident == "declare_id") =>
+            {
+                item.mac.parse_body::<LitStr>().ok()
+            }
+            _ => None,
+        })
+        .map(|address| address.value())
+        .ok_or_else(|| IdlError::Unsupported("declare_id! not found in lib.rs".to_string()))
+}
+
+/// `[package] version` from the program manifest
+fn package_version(manifest: &Path) -> Result<String, IdlError> {
+    let contents = read(manifest)?;
+    let mut in_package = false;
+
+    for line in contents.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_package = line == "[package]";
+        } else if let (true, Some(value)) = (in_package, line.strip_prefix("version")) {
+            let value = value.trim_start().trim_start_matches('=').trim();
+            return Ok(value.trim_matches('"').to_string());
+        }
+    }
+
+    Err(IdlError::Unsupported(format!(
+        "{}: no package version",
+        manifest.display()
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mixed_case() {
+        assert_eq!(mixed_case("tree_depth"), "treeDepth");
+        assert_eq!(mixed_case("_padding"), "padding");
+        assert_eq!(mixed_case("system_program"), "systemProgram");
+        assert_eq!(mixed_case("vault"), "vault");
+    }
+
+    #[test]
+    fn test_account_attrs() {
+        let variant: syn::Variant = syn::parse_quote! {
+            #[account(0, writable, name = "pool", desc = "Pool state")]
+            #[account(1, signer, optional, name = "system_program")]
+            Foo
+        };
+        let accounts = instruction_accounts(&variant.attrs, "Foo").unwrap();
+        assert_eq!(accounts[0].name, "pool");
+        assert!(accounts[0].is_mut && !accounts[0].is_signer);
+        assert_eq!(accounts[1].name, "systemProgram");
+        assert!(accounts[1].is_signer && accounts[1].is_optional);
+        assert_eq!(accounts[1].desc, None);
+
+        let variant: syn::Variant = syn::parse_quote! {
+            #[account(1, name = "pool")]
+            Foo
+        };
+        assert!(instruction_accounts(&variant.attrs, "Foo").is_err());
+    }
+
+    /// Rust-side checks: the IDL must describe the layouts the program actually uses
+    mod rust_types {
+        use super::*;
+        use borsh::{BorshDeserialize, BorshSerialize};
+        use num_traits::FromPrimitive;
+        use shadow_privacy::{state::*, PrivacyError, PrivacyInstruction};
+        use shadow_privacy_client::instruction as ix;
+        use solana_program::{instruction::Instruction, pubkey::Pubkey};
+
+        fn program_dir() -> PathBuf {
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("../../programs/shadow-privacy")
+        }
+
+        fn idl() -> Idl {
+            generate(&program_dir()).unwrap()
+        }
+
+        /// Borsh-encode a sample value of `ty`, driven only by the IDL
+        fn encode_sample(idl: &Idl, ty: &IdlType, seed: &mut u8, out: &mut Vec<u8>) {
+            let mut next = || {
+                *seed = seed.wrapping_add(1);
+                *seed
+            };
+            match ty {
+                IdlType::Primitive(name) => match name.as_str() {
+                    "bool" => out.push(1),
+                    "string" => {
+                        out.extend(3u32.to_le_bytes());
+                        out.extend(b"abc");
+                    }
+                    "bytes" => {
+                        out.extend(2u32.to_le_bytes());
+                        out.extend([next(), next()]);
+                    }
+                    "publicKey" => out.extend([next(); 32]),
+                    int => {
+                        let bits: usize = int[1..].parse().unwrap();
+                        out.extend(std::iter::repeat_n(next(), bits / 8));
+                    }
+                },
+                IdlType::Array {
+                    array: (inner, len),
+                } => {
+                    for _ in 0..*len {
+                        encode_sample(idl, inner, seed, out);
+                    }
+                }
+                IdlType::Vec { vec } => {
+                    out.extend(1u32.to_le_bytes());
+                    encode_sample(idl, vec, seed, out);
+                }
+                IdlType::Option { option } => {
+                    out.push(1);
+                    encode_sample(idl, option, seed, out);
+                }
+                IdlType::Defined { defined } => {
+                    let def = idl
+                        .types
+                        .iter()
+                        .chain(&idl.accounts)
+                        .find(|def| &def.name == defined)
+                        .unwrap();
+                    match &def.ty {
+                        IdlTypeDefTy::Struct { fields } => {
+                            for field in fields {
+                                encode_sample(idl, &field.ty, seed, out);
+                            }
+                        }
+                        // The last variant also checks the variant count
+                        IdlTypeDefTy::Enum { variants } => out.push(variants.len() as u8 - 1),
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn test_checked_in_idl_is_current() {
+            let checked_in = fs::read_to_string(program_dir().join(crate::IDL_PATH)).unwrap();
+            assert_eq!(
+                to_json(&idl()).unwrap(),
+                checked_in,
+                "IDL is stale; run `cargo run --bin shadow-privacy-idl` from the repo root"
+            );
+            let parsed: Idl = serde_json::from_str(&checked_in).unwrap();
+            assert_eq!(parsed, idl());
+        }
+
+        #[test]
+        fn test_instruction_layouts() {
+            let idl = idl();
+            for instruction in &idl.instructions {
+                let mut data = vec![instruction.discriminant.value];
+                let mut seed = 0;
+                for arg in &instruction.args {
+                    encode_sample(&idl, &arg.ty, &mut seed, &mut data);
+                }
+
+                let decoded = PrivacyInstruction::try_from_slice(&data)
+                    .unwrap_or_else(|error| panic!("{}: {}", instruction.name, error));
+                assert!(
+                    format!("{:?}", decoded).starts_with(&instruction.name),
+                    "{}",
+                    instruction.name
+                );
+                assert_eq!(decoded.try_to_vec().unwrap(), data, "{}", instruction.name);
+            }
+        }
+
+        #[test]
+        fn test_account_layouts() {
+            fn check<T: ProgramAccount>(idl: &Idl, def: &IdlTypeDef) {
+                let IdlTypeDefTy::Struct { fields } = &def.ty else {
+                    panic!("{} is not a struct", def.name)
+                };
+                assert_eq!(fields[0].name, "discriminator");
+                assert_eq!(fields[1].name, "version");
+
+                let mut data = Vec::new();
+                let mut seed = 0;
+                for field in fields {
+                    encode_sample(idl, &field.ty, &mut seed, &mut data);
+                }
+                data[..8].copy_from_slice(&T::DISCRIMINATOR);
+                data[8] = T::VERSION;
+
+                let account =
+                    T::load(&data).unwrap_or_else(|error| panic!("{}: {}", def.name, error));
+                assert_eq!(account.try_to_vec().unwrap(), data, "{}", def.name);
+            }
+
+            let idl = idl();
+            for def in &idl.accounts {
+                match def.name.as_str() {
+                    "PoolState" => check::<PoolState>(&idl, def),
+                    "AssetState" => check::<AssetState>(&idl, def),
+                    "ShieldedNote" => check::<ShieldedNote>(&idl, def),
+                    "NullifierAccount" => check::<NullifierAccount>(&idl, def),
+                    "KeyImageAccount" => check::<KeyImageAccount>(&idl, def),
+                    "VerificationKeyAccount" => check::<VerificationKeyAccount>(&idl, def),
+                    "RelayerAccount" => check::<RelayerAccount>(&idl, def),
+                    "ProtocolConfig" => check::<ProtocolConfig>(&idl, def),
+                    "Multisig" => check::<Multisig>(&idl, def),
+                    "MultisigProposal" => check::<MultisigProposal>(&idl, def),
+                    "DelayedWithdrawal" => check::<DelayedWithdrawal>(&idl, def),
+                    other => panic!("account type {} has no layout check", other),
+                }
+            }
+        }
+
+        #[test]
+        fn test_error_codes() {
+            let errors = idl().errors;
+            for error in &errors {
+                let rust = PrivacyError::from_u32(error.code).unwrap();
+                assert_eq!(format!("{:?}", rust), error.name);
+                assert_eq!(rust.to_string(), error.msg);
+            }
+            assert!(PrivacyError::from_u32(errors.len() as u32).is_none());
+        }
+
+        #[test]
+        fn test_client_builders_match_accounts() {
+            let program_id = shadow_privacy::id();
+            let key = Pubkey::new_unique();
+            let target = ix::update_root(&program_id, &key, &key, [0; 32]);
+
+            let builders: Vec<Instruction> = vec![
+                ix::initialize_pool(&program_id, &key, 20, 1),
+                ix::deposit(&program_id, &key, &key, [0; 32], 1, vec![], None),
+                ix::withdraw(&program_id, &key, vec![], [0; 32], [0; 32], None, &key, 1, None, None),
+                ix::private_transfer(
+                    &program_id,
+                    &key,
+                    &key,
+                    &key,
+                    vec![],
+                    [0; 32],
+                    vec![],
+                    [0; 32],
+                    vec![],
+                    None,
+                ),
+                ix::verify_balance(&program_id, &key, &key, vec![], 1, [0; 32]),
+                ix::issue_asset(&program_id, &key, "a".into(), "A".into(), 0, 1, [0; 32]),
+                ix::transfer_asset(
+                    &program_id,
+                    &key,
+                    &key,
+                    vec![],
+                    [0; 32],
+                    [0; 32],
+                    [0; 32],
+                    vec![],
+                ),
+                ix::store_verification_key(
+                    &program_id,
+                    &key,
+                    &key,
+                    None,
+                    CircuitType::Transfer,
+                    vec![],
+                ),
+                ix::store_verification_key(
+                    &program_id,
+                    &key,
+                    &key,
+                    Some(&key),
+                    CircuitType::Balance,
+                    vec![],
+                ),
+                ix::register_relayer(&program_id, &key, "e".into(), 1),
+                ix::update_heartbeat(&program_id, &key),
+                ix::report_relay(&program_id, &key, &key, true),
+                ix::migrate_relayer(&program_id, &key),
+                ix::initialize_config(&program_id, &key, ConfigParams::default()),
+                ix::update_config(&program_id, &key, ConfigParams::default(), None),
+                target.clone(),
+                ix::propose_authority(&program_id, &key, &key, &key),
+                ix::accept_authority(&program_id, &key, &key),
+                ix::set_guardian(&program_id, &key, &key, None),
+                ix::pause(&program_id, &key, &key, 1, None),
+                ix::unpause(&program_id, &key, &key, 1),
+                ix::set_pool_limits(&program_id, &key, &key, PoolLimits::default()),
+                ix::claim_delayed_withdrawal(&program_id, &key, &[0; 32], &key),
+                ix::migrate(&program_id, &key, &key, AccountType::Relayer),
+                ix::create_multisig(&program_id, &key, &key, 1, vec![key]),
+                ix::create_proposal(&program_id, &key, 0, &key, &target),
+                ix::approve_proposal(&program_id, &key, 0, &key),
+                ix::execute_proposal(&program_id, &key, 0, &target),
+                ix::set_auditor(&program_id, &key, &key, None),
+            ];
+
+            let idl = idl();
+            let mut covered = std::collections::HashSet::new();
+            for built in builders {
+                let instruction = &idl.instructions[built.data[0] as usize];
+                covered.insert(instruction.name.clone());
+
+                let required = instruction
+                    .accounts
+                    .iter()
+                    .filter(|account| !account.is_optional)
+                    .count();
+                assert!(
+                    built.accounts.len() >= required,
+                    "{}: missing accounts",
+                    instruction.name
+                );
+                // Without an optional payer another signer pays, and has to be writable
+                let payer_omitted = built.accounts.len() < instruction.accounts.len();
+                for (meta, account) in built.accounts.iter().zip(&instruction.accounts) {
+                    let context = format!("{}.{}", instruction.name, account.name);
+                    assert_eq!(meta.is_signer, account.is_signer, "{}", context);
+                    assert!(
+                        meta.is_writable == account.is_mut || (payer_omitted && meta.is_signer),
+                        "{}",
+                        context
+                    );
+                }
+            }
+            assert_eq!(covered.len(), idl.instructions.len());
+        }
+    }
+}
@@ -0,0 +1,20 @@
+// Shadow Privacy IDL
+//
+// Generates a Shank-style IDL for the Shadow Privacy program from its sources,
+// so clients in other languages stop hand-copying the instruction layout:
+// - Instructions with their Borsh args and account lists (signer/writable/optional)
+// - Account and custom type layouts
+// - Error codes and messages
+//
+// The generated IDL is checked in at programs/shadow-privacy/idl/shadow_privacy.json.
+
+pub mod error;
+pub mod generate;
+pub mod idl;
+
+pub use error::IdlError;
+pub use generate::{generate, to_json};
+pub use idl::Idl;
+
+/// Location of the checked-in IDL, relative to the program directory
+pub const IDL_PATH: &str = "idl/shadow_privacy.json";
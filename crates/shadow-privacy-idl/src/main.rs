@@ -0,0 +1,63 @@
+//! `shadow-privacy-idl`: write or check the program IDL
+
+use clap::Parser;
+use shadow_privacy_idl::{generate, to_json, IdlError, IDL_PATH};
+use std::{fs, path::PathBuf, process};
+
+#[derive(Parser)]
+#[command(about = "Generate the Shadow Privacy program IDL")]
+struct Cli {
+    /// Program crate directory
+    #[arg(long, default_value = "programs/shadow-privacy")]
+    program_dir: PathBuf,
+
+    /// Output file [default: <PROGRAM_DIR>/idl/shadow_privacy.json]
+    #[arg(long)]
+    out: Option<PathBuf>,
+
+    /// Fail if the output file is not up to date instead of writing it
+    #[arg(long)]
+    check: bool,
+}
+
+fn run(cli: Cli) -> Result<bool, IdlError> {
+    let out = cli.out.unwrap_or_else(|| cli.program_dir.join(IDL_PATH));
+    let json = to_json(&generate(&cli.program_dir)?)?;
+
+    if cli.check {
+        let current = fs::read_to_string(&out).unwrap_or_default();
+        if current != json {
+            eprintln!(
+                "{} is out of date; run shadow-privacy-idl to regenerate it",
+                out.display()
+            );
+            return Ok(false);
+        }
+        println!("{} is up to date", out.display());
+        return Ok(true);
+    }
+
+    if let Some(dir) = out.parent() {
+        fs::create_dir_all(dir).map_err(|source| IdlError::Io {
+            path: dir.to_path_buf(),
+            source,
+        })?;
+    }
+    fs::write(&out, json).map_err(|source| IdlError::Io {
+        path: out.clone(),
+        source,
+    })?;
+    println!("Wrote {}", out.display());
+    Ok(true)
+}
+
+fn main() {
+    match run(Cli::parse()) {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use shadow_privacy_merkle::MerkleError;
+use solana_program::pubkey::Pubkey;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum IndexerError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid ledger JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Ledger source error: {0}")]
+    Source(String),
+
+    #[error("Pool {pool}: expected leaf {expected}, got {got} (missing transactions?)")]
+    LeafGap { pool: Pubkey, expected: u64, got: u64 },
+
+    #[error("Pool {pool}: commitment tree of depth {depth} is full")]
+    TreeFull { pool: Pubkey, depth: u8 },
+
+    #[error("Invalid tree depth {0}")]
+    InvalidTreeDepth(u8),
+
+    #[error("Merkle tree error: {0}")]
+    Merkle(#[from] MerkleError),
+}
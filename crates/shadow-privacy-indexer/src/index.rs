@@ -0,0 +1,472 @@
+//! Pool and relayer state rebuilt from program events
+
+use crate::{
+    error::IndexerError,
+    logs::parse_events,
+    source::{LedgerSource, LedgerTransaction},
+};
+use shadow_privacy::{events::TreeLeaf, PrivacyEvent};
+use shadow_privacy_merkle::{tree_leaf, MerkleError, MerklePath, SparseMerkleTree};
+use solana_program::pubkey::Pubkey;
+use std::collections::{BTreeMap, HashSet};
+
+/// Tree depth assumed for pools created before `PoolInitialized` was emitted
+pub const DEFAULT_TREE_DEPTH: u8 = 20;
+
+/// A pool root as of some transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RootRecord {
+    pub slot: u64,
+    pub signature: String,
+    /// Root stored in the pool account
+    pub onchain_root: [u8; 32],
+    /// Root of the indexer's commitment tree
+    pub tree_root: [u8; 32],
+    pub leaf_count: u64,
+}
+
+/// Everything known about one pool
+///
+/// The commitment tree is the circomlib Poseidon tree the program, clients
+/// and circuits build, so its roots and paths match theirs.
+#[derive(Debug, Clone)]
+pub struct PoolIndex {
+    pub pool: Pubkey,
+    pub authority: Option<Pubkey>,
+    pub vault: Option<Pubkey>,
+    pub denomination: Option<u64>,
+    pub tree: SparseMerkleTree,
+    pub nullifiers: HashSet<[u8; 32]>,
+    pub key_images: HashSet<[u8; 32]>,
+    /// One entry per root change, oldest first
+    pub root_history: Vec<RootRecord>,
+}
+
+impl PoolIndex {
+    fn new(pool: Pubkey, tree_depth: u8) -> Result<Self, IndexerError> {
+        Ok(PoolIndex {
+            pool,
+            authority: None,
+            vault: None,
+            denomination: None,
+            tree: SparseMerkleTree::new(tree_depth).map_err(|_| IndexerError::InvalidTreeDepth(tree_depth))?,
+            nullifiers: HashSet::new(),
+            key_images: HashSet::new(),
+            root_history: Vec::new(),
+        })
+    }
+
+    /// Latest on-chain root, if any change has been seen
+    pub fn onchain_root(&self) -> Option<[u8; 32]> {
+        self.root_history.last().map(|record| record.onchain_root)
+    }
+
+    /// Whether `root` was ever this pool's on-chain or tree root
+    pub fn is_known_root(&self, root: &[u8; 32]) -> bool {
+        self.root_history
+            .iter()
+            .any(|record| record.onchain_root == *root || record.tree_root == *root)
+    }
+
+    /// Path for a leaf already in the tree
+    pub fn merkle_path(&self, leaf_index: u64) -> Option<MerklePath> {
+        if leaf_index >= self.tree.len() {
+            return None;
+        }
+        self.tree.path(leaf_index).ok()
+    }
+
+    pub fn is_nullifier_spent(&self, nullifier: &[u8; 32]) -> bool {
+        self.nullifiers.contains(nullifier)
+    }
+
+    pub fn is_key_image_spent(&self, key_image: &[u8; 32]) -> bool {
+        self.key_images.contains(key_image)
+    }
+
+    fn append(&mut self, leaf: &TreeLeaf) -> Result<(), IndexerError> {
+        if leaf.index != self.tree.len() {
+            return Err(IndexerError::LeafGap {
+                pool: self.pool,
+                expected: self.tree.len(),
+                got: leaf.index,
+            });
+        }
+        self.tree.insert(tree_leaf(&leaf.commitment)).map_err(|e| match e {
+            MerkleError::TreeFull(depth) => IndexerError::TreeFull { pool: self.pool, depth },
+            e => IndexerError::Merkle(e),
+        })?;
+        Ok(())
+    }
+
+    fn record_root(&mut self, tx: &LedgerTransaction, onchain_root: [u8; 32]) {
+        self.root_history.push(RootRecord {
+            slot: tx.slot,
+            signature: tx.signature.clone(),
+            onchain_root,
+            tree_root: self.tree.root(),
+            leaf_count: self.tree.len(),
+        });
+    }
+}
+
+/// A registered relayer as of the latest event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelayerRecord {
+    pub relayer: Pubkey,
+    pub relayer_account: Pubkey,
+    pub stake: u64,
+    pub endpoint: String,
+    pub registered_at: i64,
+    pub last_heartbeat: i64,
+    pub successful_relays: u64,
+    pub failed_relays: u64,
+    /// Decayed score from the latest report, None while unscored
+    pub reputation: Option<u8>,
+}
+
+/// Counters from one [`Indexer::index`] run
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct IndexStats {
+    pub transactions: usize,
+    pub failed_transactions: usize,
+    pub events: usize,
+    /// Program data lines that did not decode as a known event
+    pub undecoded: usize,
+}
+
+/// Replays program events into per-pool and relayer state
+pub struct Indexer {
+    program_id: Pubkey,
+    pools: BTreeMap<Pubkey, PoolIndex>,
+    relayers: BTreeMap<Pubkey, RelayerRecord>,
+}
+
+impl Indexer {
+    pub fn new(program_id: Pubkey) -> Self {
+        Indexer {
+            program_id,
+            pools: BTreeMap::new(),
+            relayers: BTreeMap::new(),
+        }
+    }
+
+    pub fn program_id(&self) -> &Pubkey {
+        &self.program_id
+    }
+
+    pub fn pool(&self, pool: &Pubkey) -> Option<&PoolIndex> {
+        self.pools.get(pool)
+    }
+
+    pub fn pools(&self) -> impl Iterator<Item = &PoolIndex> {
+        self.pools.values()
+    }
+
+    /// Relayer by wallet address
+    pub fn relayer(&self, relayer: &Pubkey) -> Option<&RelayerRecord> {
+        self.relayers.get(relayer)
+    }
+
+    pub fn relayers(&self) -> impl Iterator<Item = &RelayerRecord> {
+        self.relayers.values()
+    }
+
+    pub fn merkle_path(&self, pool: &Pubkey, leaf_index: u64) -> Option<MerklePath> {
+        self.pools.get(pool)?.merkle_path(leaf_index)
+    }
+
+    /// Drain `source`, applying every successful transaction
+    pub fn index<S: LedgerSource + ?Sized>(&mut self, source: &mut S) -> Result<IndexStats, IndexerError> {
+        let mut stats = IndexStats::default();
+        while let Some(tx) = source.next_transaction()? {
+            stats.transactions += 1;
+            if !tx.success {
+                stats.failed_transactions += 1;
+                continue;
+            }
+
+            let parsed = parse_events(&self.program_id, &tx.logs);
+            stats.undecoded += parsed.undecoded;
+            for event in &parsed.events {
+                self.apply_event(&tx, event)?;
+                stats.events += 1;
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Apply one event emitted by `tx`
+    pub fn apply_event(&mut self, tx: &LedgerTransaction, event: &PrivacyEvent) -> Result<(), IndexerError> {
+        match event {
+            PrivacyEvent::PoolInitialized {
+                pool,
+                authority,
+                vault,
+                tree_depth,
+                denomination,
+            } => {
+                let mut index = PoolIndex::new(*pool, *tree_depth)?;
+                index.authority = Some(*authority);
+                index.vault = Some(*vault);
+                index.denomination = Some(*denomination);
+                // New pools start with the empty tree's root
+                let root = index.tree.root();
+                index.record_root(tx, root);
+                self.pools.insert(*pool, index);
+            }
+            PrivacyEvent::Deposit { pool, leaf, root, .. } => {
+                let index = self.pool_mut(pool)?;
+                index.append(leaf)?;
+                index.record_root(tx, *root);
+            }
+            PrivacyEvent::Withdraw {
+                pool,
+                nullifier,
+                change,
+                root,
+                ..
+            } => {
+                let index = self.pool_mut(pool)?;
+                index.nullifiers.insert(*nullifier);
+                if let Some(leaf) = change {
+                    index.append(leaf)?;
+                    index.record_root(tx, *root);
+                }
+            }
+            PrivacyEvent::PrivateTransfer {
+                pool,
+                key_image,
+                leaf,
+                root,
+                ..
+            } => {
+                let index = self.pool_mut(pool)?;
+                index.key_images.insert(*key_image);
+                index.append(leaf)?;
+                index.record_root(tx, *root);
+            }
+            PrivacyEvent::RootUpdated { pool, root } => {
+                self.pool_mut(pool)?.record_root(tx, *root);
+            }
+            PrivacyEvent::AuthorityAccepted { pool, authority, .. } => {
+                self.pool_mut(pool)?.authority = Some(*authority);
+            }
+            PrivacyEvent::RelayerRegistered {
+                relayer,
+                relayer_account,
+                stake,
+                endpoint,
+                registered_at,
+            } => {
+                self.relayers.insert(
+                    *relayer,
+                    RelayerRecord {
+                        relayer: *relayer,
+                        relayer_account: *relayer_account,
+                        stake: *stake,
+                        endpoint: endpoint.clone(),
+                        registered_at: *registered_at,
+                        last_heartbeat: *registered_at,
+                        successful_relays: 0,
+                        failed_relays: 0,
+                        reputation: None,
+                    },
+                );
+            }
+            PrivacyEvent::RelayerHeartbeat { relayer, timestamp } => {
+                if let Some(record) = self.relayers.get_mut(relayer) {
+                    record.last_heartbeat = *timestamp;
+                }
+            }
+            PrivacyEvent::RelayReported {
+                relayer,
+                successful_relays,
+                failed_relays,
+                reputation,
+                ..
+            } => {
+                if let Some(record) = self.relayers.get_mut(relayer) {
+                    record.successful_relays = *successful_relays;
+                    record.failed_relays = *failed_relays;
+                    record.reputation = *reputation;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Pool for an event, created with defaults if its creation was not seen
+    fn pool_mut(&mut self, pool: &Pubkey) -> Result<&mut PoolIndex, IndexerError> {
+        if !self.pools.contains_key(pool) {
+            self.pools.insert(*pool, PoolIndex::new(*pool, DEFAULT_TREE_DEPTH)?);
+        }
+        Ok(self.pools.get_mut(pool).expect("inserted above"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shadow_privacy_merkle::field_from_u64;
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use borsh::BorshSerialize;
+    use std::collections::VecDeque;
+
+    fn transaction(program_id: &Pubkey, slot: u64, success: bool, events: &[PrivacyEvent]) -> LedgerTransaction {
+        let mut logs = vec![format!("Program {} invoke [1]", program_id)];
+        for event in events {
+            logs.push(format!("Program data: {}", STANDARD.encode(event.try_to_vec().unwrap())));
+        }
+        logs.push(format!("Program {} success", program_id));
+        LedgerTransaction {
+            slot,
+            signature: format!("sig{}", slot),
+            success,
+            logs,
+        }
+    }
+
+    fn deposit(pool: Pubkey, index: u64, commitment: [u8; 32]) -> PrivacyEvent {
+        PrivacyEvent::Deposit {
+            pool,
+            depositor: Pubkey::new_unique(),
+            leaf: TreeLeaf { index, commitment },
+            root: [index as u8 + 100; 32],
+            amount: 1_000_000,
+        }
+    }
+
+    #[test]
+    fn test_rebuilds_pool_and_relayers() {
+        let program_id = Pubkey::new_unique();
+        let pool = Pubkey::new_unique();
+        let relayer = Pubkey::new_unique();
+
+        let init = PrivacyEvent::PoolInitialized {
+            pool,
+            authority: Pubkey::new_unique(),
+            vault: Pubkey::new_unique(),
+            tree_depth: 4,
+            denomination: 1_000_000,
+        };
+        let withdraw = PrivacyEvent::Withdraw {
+            pool,
+            nullifier: [9u8; 32],
+            recipient: Pubkey::new_unique(),
+            amount: 1_000_000,
+            fee: 0,
+            record_rent: 0,
+            change: Some(TreeLeaf { index: 2, commitment: [3u8; 32] }),
+            root: [42u8; 32],
+            delayed: false,
+        };
+        let transfer = PrivacyEvent::PrivateTransfer {
+            pool,
+            key_image: [8u8; 32],
+            ring_size: 4,
+            leaf: TreeLeaf { index: 3, commitment: [4u8; 32] },
+            root: [43u8; 32],
+        };
+        let registered = PrivacyEvent::RelayerRegistered {
+            relayer,
+            relayer_account: Pubkey::new_unique(),
+            stake: 100,
+            endpoint: "https://relayer.example".to_string(),
+            registered_at: 10,
+        };
+        let reported = PrivacyEvent::RelayReported {
+            relayer,
+            reported_by: Pubkey::new_unique(),
+            success: true,
+            successful_relays: 1,
+            failed_relays: 0,
+            reputation: None,
+        };
+
+        let mut source: VecDeque<_> = vec![
+            transaction(&program_id, 1, true, &[init, registered]),
+            transaction(&program_id, 2, true, &[deposit(pool, 0, [1u8; 32])]),
+            // Rolled back: must not reach the tree
+            transaction(&program_id, 3, false, &[deposit(pool, 1, [0xffu8; 32])]),
+            transaction(&program_id, 4, true, &[deposit(pool, 1, [2u8; 32])]),
+            transaction(&program_id, 5, true, &[withdraw, transfer, reported]),
+            // Another program's events are ignored
+            transaction(&Pubkey::new_unique(), 6, true, &[deposit(pool, 4, [5u8; 32])]),
+        ]
+        .into();
+
+        let mut indexer = Indexer::new(program_id);
+        let stats = indexer.index(&mut source).unwrap();
+        assert_eq!(stats.transactions, 6);
+        assert_eq!(stats.failed_transactions, 1);
+        assert_eq!(stats.events, 7);
+
+        let index = indexer.pool(&pool).unwrap();
+        assert_eq!(index.tree.len(), 4);
+        assert_eq!(index.tree.leaf(2), [3u8; 32]);
+        assert!(index.is_nullifier_spent(&[9u8; 32]));
+        assert!(index.is_key_image_spent(&[8u8; 32]));
+        assert_eq!(index.onchain_root(), Some([43u8; 32]));
+        assert!(index.is_known_root(&[42u8; 32]));
+
+        // Paths verify against the tree root recorded with each change
+        let latest = index.root_history.last().unwrap();
+        assert_eq!(latest.leaf_count, 4);
+        assert_eq!(latest.slot, 5);
+        let path = indexer.merkle_path(&pool, 1).unwrap();
+        assert!(path.verify(&[2u8; 32], &latest.tree_root).unwrap());
+        assert!(indexer.merkle_path(&pool, 4).is_none());
+        assert!(index.is_known_root(&latest.tree_root));
+
+        let record = indexer.relayer(&relayer).unwrap();
+        assert_eq!(record.endpoint, "https://relayer.example");
+        assert_eq!(record.successful_relays, 1);
+    }
+
+    #[test]
+    fn test_missing_leaf_is_reported() {
+        let program_id = Pubkey::new_unique();
+        let pool = Pubkey::new_unique();
+        let mut source: VecDeque<_> = vec![
+            transaction(&program_id, 1, true, &[deposit(pool, 0, [1u8; 32])]),
+            transaction(&program_id, 2, true, &[deposit(pool, 2, [2u8; 32])]),
+        ]
+        .into();
+
+        let mut indexer = Indexer::new(program_id);
+        match indexer.index(&mut source) {
+            Err(IndexerError::LeafGap { expected: 1, got: 2, .. }) => {}
+            other => panic!("expected a leaf gap, got {:?}", other),
+        }
+        // Pools first seen mid-history get the default depth
+        assert_eq!(indexer.pool(&pool).unwrap().tree.depth(), DEFAULT_TREE_DEPTH);
+    }
+
+    #[test]
+    fn test_tree_matches_program_tree() {
+        let program_id = Pubkey::new_unique();
+        let pool = Pubkey::new_unique();
+        let mut source: VecDeque<_> = vec![
+            transaction(&program_id, 1, true, &[deposit(pool, 0, field_from_u64(1))]),
+            // Accepted before commitments had to be field elements
+            transaction(&program_id, 2, true, &[deposit(pool, 1, [0xffu8; 32])]),
+            transaction(&program_id, 3, true, &[deposit(pool, 2, field_from_u64(3))]),
+        ]
+        .into();
+
+        let mut indexer = Indexer::new(program_id);
+        indexer.index(&mut source).unwrap();
+
+        // The non-field commitment keeps its position as an empty leaf
+        let mut reference = SparseMerkleTree::new(DEFAULT_TREE_DEPTH).unwrap();
+        for leaf in [field_from_u64(1), [0u8; 32], field_from_u64(3)] {
+            reference.insert(leaf).unwrap();
+        }
+        let index = indexer.pool(&pool).unwrap();
+        assert_eq!(index.tree.root(), reference.root());
+        assert_eq!(index.tree.leaf(1), [0u8; 32]);
+        assert_eq!(indexer.merkle_path(&pool, 2), reference.path(2).ok());
+    }
+}
@@ -0,0 +1,24 @@
+// Shadow Privacy Indexer
+//
+// Rebuilds off-chain pool state from the events the program logs:
+// - Poseidon commitment tree per pool (shadow-privacy-merkle's SparseMerkleTree),
+//   with Merkle paths for any leaf
+// - Nullifier and key image sets
+// - Root history
+// - Relayer table
+//
+// Transactions come from a `LedgerSource`: a JSON dump of `getTransaction`
+// results, an in-memory queue, or (with the `program-test` feature) a
+// solana-program-test bank.
+
+pub mod error;
+pub mod index;
+pub mod logs;
+pub mod source;
+
+pub use error::IndexerError;
+pub use index::{IndexStats, Indexer, PoolIndex, RelayerRecord, RootRecord};
+pub use source::{JsonDumpSource, LedgerSource, LedgerTransaction};
+
+#[cfg(feature = "program-test")]
+pub use source::ProgramTestSource;
@@ -0,0 +1,95 @@
+//! Extract program events from transaction logs
+//!
+//! The program writes each [`PrivacyEvent`] with `sol_log_data`, which the
+//! runtime logs as `Program data: <base64>`. Data lines are attributed to the
+//! program on top of the invoke stack, so events from other programs (or from
+//! another deployment of this one) are ignored.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use borsh::BorshDeserialize;
+use shadow_privacy::PrivacyEvent;
+use solana_program::pubkey::Pubkey;
+use std::str::FromStr;
+
+/// Result of scanning one transaction's logs
+#[derive(Debug, Default)]
+pub struct ParsedLogs {
+    pub events: Vec<PrivacyEvent>,
+    /// Data lines from the program that did not decode as an event
+    pub undecoded: usize,
+}
+
+/// Collect the events `program_id` logged, in log order
+pub fn parse_events(program_id: &Pubkey, logs: &[String]) -> ParsedLogs {
+    let mut parsed = ParsedLogs::default();
+    let mut stack: Vec<Option<Pubkey>> = Vec::new();
+
+    for line in logs {
+        if let Some(data) = line.strip_prefix("Program data: ") {
+            if stack.last() != Some(&Some(*program_id)) {
+                continue;
+            }
+            match decode_event(data) {
+                Some(event) => parsed.events.push(event),
+                None => parsed.undecoded += 1,
+            }
+        } else if let Some(rest) = line.strip_prefix("Program ") {
+            let mut words = rest.split(' ');
+            let program = words.next().and_then(|word| Pubkey::from_str(word).ok());
+            match words.next() {
+                Some("invoke") => stack.push(program),
+                Some("success") | Some("failed:") => {
+                    stack.pop();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    parsed
+}
+
+/// Decode one `Program data:` payload (a single base64 field)
+pub fn decode_event(data: &str) -> Option<PrivacyEvent> {
+    let mut fields = data.split(' ');
+    let bytes = STANDARD.decode(fields.next()?).ok()?;
+    if fields.next().is_some() {
+        return None;
+    }
+    PrivacyEvent::try_from_slice(&bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use borsh::BorshSerialize;
+
+    fn data_line(event: &PrivacyEvent) -> String {
+        format!("Program data: {}", STANDARD.encode(event.try_to_vec().unwrap()))
+    }
+
+    #[test]
+    fn test_events_attributed_to_invoking_program() {
+        let program_id = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let ours = PrivacyEvent::RelayerHeartbeat { relayer: Pubkey::new_unique(), timestamp: 1 };
+        let theirs = PrivacyEvent::RelayerHeartbeat { relayer: Pubkey::new_unique(), timestamp: 2 };
+
+        let logs = vec![
+            format!("Program {} invoke [1]", program_id),
+            "Program log: Instruction: UpdateHeartbeat".to_string(),
+            format!("Program {} invoke [2]", other),
+            data_line(&theirs),
+            format!("Program {} success", other),
+            data_line(&ours),
+            "Program data: bm90IGFuIGV2ZW50".to_string(),
+            format!("Program {} consumed 1200 of 200000 compute units", program_id),
+            format!("Program {} success", program_id),
+            data_line(&theirs),
+        ];
+
+        let parsed = parse_events(&program_id, &logs);
+        assert_eq!(parsed.events, vec![ours]);
+        assert_eq!(parsed.undecoded, 1);
+    }
+}
@@ -0,0 +1,197 @@
+//! Ledger sources the indexer reads transactions from
+//!
+//! A source yields transactions in ledger order (oldest first). Only the
+//! logs are needed, since every state change is reported as an event.
+
+use crate::error::IndexerError;
+use serde::Deserialize;
+use std::{collections::VecDeque, fs::File, io::Read, path::Path};
+
+/// The parts of a confirmed transaction the indexer uses
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LedgerTransaction {
+    pub slot: u64,
+    /// First signature, base58
+    pub signature: String,
+    /// Failed transactions are skipped, their state changes were rolled back
+    pub success: bool,
+    pub logs: Vec<String>,
+}
+
+/// Anything that can replay program transactions in ledger order
+pub trait LedgerSource {
+    /// Next transaction, or None once the source is exhausted
+    fn next_transaction(&mut self) -> Result<Option<LedgerTransaction>, IndexerError>;
+}
+
+impl LedgerSource for VecDeque<LedgerTransaction> {
+    fn next_transaction(&mut self) -> Result<Option<LedgerTransaction>, IndexerError> {
+        Ok(self.pop_front())
+    }
+}
+
+/// A JSON array of `getTransaction` RPC results (`"encoding": "json"`)
+///
+/// Entries must be oldest first; `getSignaturesForAddress` lists newest first,
+/// so dumps built from it need reversing.
+pub struct JsonDumpSource {
+    transactions: VecDeque<LedgerTransaction>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcTransaction {
+    slot: u64,
+    transaction: RpcTransactionBody,
+    meta: Option<RpcTransactionMeta>,
+}
+
+#[derive(Deserialize)]
+struct RpcTransactionBody {
+    signatures: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcTransactionMeta {
+    err: Option<serde_json::Value>,
+    #[serde(default)]
+    log_messages: Option<Vec<String>>,
+}
+
+impl JsonDumpSource {
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, IndexerError> {
+        let entries: Vec<RpcTransaction> = serde_json::from_reader(reader)?;
+        Self::from_entries(entries)
+    }
+
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, IndexerError> {
+        Self::from_reader(std::io::BufReader::new(File::open(path)?))
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, IndexerError> {
+        Self::from_entries(serde_json::from_str(json)?)
+    }
+
+    fn from_entries(entries: Vec<RpcTransaction>) -> Result<Self, IndexerError> {
+        let mut transactions = VecDeque::with_capacity(entries.len());
+        for entry in entries {
+            let signature = entry
+                .transaction
+                .signatures
+                .into_iter()
+                .next()
+                .ok_or_else(|| IndexerError::Source(format!("unsigned transaction in slot {}", entry.slot)))?;
+            // Without meta there are no logs to index; treat it like a failure
+            let (success, logs) = match entry.meta {
+                Some(meta) => (meta.err.is_none(), meta.log_messages.unwrap_or_default()),
+                None => (false, Vec::new()),
+            };
+            transactions.push_back(LedgerTransaction {
+                slot: entry.slot,
+                signature,
+                success,
+                logs,
+            });
+        }
+        Ok(JsonDumpSource { transactions })
+    }
+}
+
+impl LedgerSource for JsonDumpSource {
+    fn next_transaction(&mut self) -> Result<Option<LedgerTransaction>, IndexerError> {
+        Ok(self.transactions.pop_front())
+    }
+}
+
+/// Records transactions sent to a `solana-program-test` bank
+///
+/// The banks client cannot list past transactions, so tests send them through
+/// [`ProgramTestSource::process_transaction`], which keeps their logs for the
+/// indexer.
+#[cfg(feature = "program-test")]
+pub struct ProgramTestSource {
+    banks_client: solana_program_test::BanksClient,
+    transactions: VecDeque<LedgerTransaction>,
+}
+
+#[cfg(feature = "program-test")]
+impl ProgramTestSource {
+    pub fn new(banks_client: solana_program_test::BanksClient) -> Self {
+        ProgramTestSource {
+            banks_client,
+            transactions: VecDeque::new(),
+        }
+    }
+
+    pub fn banks_client(&mut self) -> &mut solana_program_test::BanksClient {
+        &mut self.banks_client
+    }
+
+    /// Process `transaction` and record it, whether or not it succeeded
+    pub async fn process_transaction(
+        &mut self,
+        transaction: solana_sdk::transaction::Transaction,
+    ) -> Result<(), solana_program_test::BanksClientError> {
+        let signature = transaction.signatures[0].to_string();
+        let outcome = self
+            .banks_client
+            .process_transaction_with_metadata(transaction)
+            .await?;
+        let slot = self.banks_client.get_root_slot().await?;
+
+        self.transactions.push_back(LedgerTransaction {
+            slot,
+            signature,
+            success: outcome.result.is_ok(),
+            logs: outcome
+                .metadata
+                .map(|metadata| metadata.log_messages)
+                .unwrap_or_default(),
+        });
+
+        outcome.result.map_err(Into::into)
+    }
+}
+
+#[cfg(feature = "program-test")]
+impl LedgerSource for ProgramTestSource {
+    fn next_transaction(&mut self) -> Result<Option<LedgerTransaction>, IndexerError> {
+        Ok(self.transactions.pop_front())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_dump_source() {
+        let json = r#"[
+            {
+                "slot": 10,
+                "blockTime": 1700000000,
+                "transaction": { "signatures": ["sig1"], "message": {} },
+                "meta": { "err": null, "fee": 5000, "logMessages": ["Program log: a"] }
+            },
+            {
+                "slot": 11,
+                "transaction": { "signatures": ["sig2"] },
+                "meta": { "err": { "InstructionError": [0, { "Custom": 1 }] }, "logMessages": [] }
+            }
+        ]"#;
+
+        let mut source = JsonDumpSource::from_json(json).unwrap();
+        let first = source.next_transaction().unwrap().unwrap();
+        assert_eq!(first.slot, 10);
+        assert_eq!(first.signature, "sig1");
+        assert!(first.success);
+        assert_eq!(first.logs, vec!["Program log: a".to_string()]);
+
+        let second = source.next_transaction().unwrap().unwrap();
+        assert!(!second.success);
+        assert!(source.next_transaction().unwrap().is_none());
+
+        assert!(JsonDumpSource::from_json(r#"[{"slot": 1, "transaction": {"signatures": []}}]"#).is_err());
+    }
+}
@@ -0,0 +1,29 @@
+use solana_program::poseidon::PoseidonSyscallError;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum MerkleError {
+    #[error("Input is not a canonical BN254 scalar field element")]
+    NonCanonicalField,
+
+    #[error("Poseidon error: {0}")]
+    Poseidon(String),
+
+    #[error("Invalid tree depth {0}")]
+    InvalidTreeDepth(u8),
+
+    #[error("Commitment tree of depth {0} is full")]
+    TreeFull(u8),
+
+    #[error("Leaf index {index} is outside a tree of depth {depth}")]
+    IndexOutOfRange { index: u64, depth: u8 },
+}
+
+impl From<PoseidonSyscallError> for MerkleError {
+    fn from(error: PoseidonSyscallError) -> Self {
+        match error {
+            PoseidonSyscallError::InputLargerThanModulus => MerkleError::NonCanonicalField,
+            error => MerkleError::Poseidon(error.to_string()),
+        }
+    }
+}
@@ -0,0 +1,25 @@
+// Shadow Privacy Merkle
+//
+// circomlib-compatible Poseidon and the structures built on it, shared by
+// the program and off-chain clients:
+// - Poseidon(1..12) over BN254 with the same parameters as circomlib
+// - Commitment, nullifier, balance commitment and key image formulas of the circuits
+// - Incremental (frontier-only) and sparse Poseidon Merkle trees, with paths
+//   in the `pathElements`/`pathIndices` form transfer.circom takes
+//
+// Field elements are 32 little-endian bytes throughout, like the program's
+// public inputs. Golden vectors in tests/vectors/circom.json are checked
+// against the circuits' own witness generators by the prover crate.
+
+pub mod error;
+pub mod note;
+pub mod poseidon;
+pub mod tree;
+
+pub use error::MerkleError;
+pub use note::{balance_commitment, commitment, key_image, nullifier, public_key, Note};
+pub use poseidon::{field_from_hex, field_from_u64, hash_pair, is_canonical, poseidon, FieldBytes, FIELD_MODULUS};
+pub use tree::{
+    insert_into_frontier, tree_leaf, IncrementalMerkleTree, MerklePath, SparseMerkleTree, MAX_TREE_DEPTH, TREE_DEPTH,
+    ZERO_HASHES,
+};
@@ -0,0 +1,84 @@
+//! Commitment, nullifier and key image formulas of the circuits
+//!
+//! - public_key = Poseidon(private_key)                       (all circuits)
+//! - commitment = Poseidon(public_key, amount, nonce)         (transfer.circom)
+//! - nullifier  = Poseidon(commitment, private_key)           (transfer.circom)
+//! - balance commitment = commitment of the owner's key       (balance.circom)
+//! - key_image  = Poseidon(private_key, public_key)           (ring_signature.circom)
+
+use crate::{
+    error::MerkleError,
+    poseidon::{field_from_u64, poseidon, FieldBytes},
+};
+
+/// Public key the circuits derive from a private key
+pub fn public_key(private_key: &FieldBytes) -> Result<FieldBytes, MerkleError> {
+    poseidon(&[private_key])
+}
+
+/// Leaf for a note of `amount` owned by `public_key`
+pub fn commitment(public_key: &FieldBytes, amount: u64, nonce: &FieldBytes) -> Result<FieldBytes, MerkleError> {
+    poseidon(&[public_key, &field_from_u64(amount), nonce])
+}
+
+/// Nullifier published when the note behind `commitment` is spent
+pub fn nullifier(commitment: &FieldBytes, private_key: &FieldBytes) -> Result<FieldBytes, MerkleError> {
+    poseidon(&[commitment, private_key])
+}
+
+/// Commitment VerifyBalance proofs open
+pub fn balance_commitment(private_key: &FieldBytes, balance: u64, nonce: &FieldBytes) -> Result<FieldBytes, MerkleError> {
+    commitment(&public_key(private_key)?, balance, nonce)
+}
+
+/// Key image a ring signature by `private_key` publishes
+pub fn key_image(private_key: &FieldBytes) -> Result<FieldBytes, MerkleError> {
+    poseidon(&[private_key, &public_key(private_key)?])
+}
+
+/// A note as its owner knows it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Note {
+    /// Owner's public key
+    pub owner: FieldBytes,
+    pub amount: u64,
+    pub nonce: FieldBytes,
+}
+
+impl Note {
+    pub fn commitment(&self) -> Result<FieldBytes, MerkleError> {
+        commitment(&self.owner, self.amount, &self.nonce)
+    }
+
+    /// Nullifier for spending this note, given the owner's private key
+    pub fn nullifier(&self, private_key: &FieldBytes) -> Result<FieldBytes, MerkleError> {
+        nullifier(&self.commitment()?, private_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_note_matches_free_functions() {
+        let private_key = field_from_u64(7);
+        let note = Note {
+            owner: public_key(&private_key).unwrap(),
+            amount: 1_000,
+            nonce: field_from_u64(99),
+        };
+
+        let leaf = note.commitment().unwrap();
+        assert_eq!(leaf, balance_commitment(&private_key, 1_000, &note.nonce).unwrap());
+        assert_eq!(note.nullifier(&private_key).unwrap(), nullifier(&leaf, &private_key).unwrap());
+        assert_ne!(note.nullifier(&private_key).unwrap(), note.nullifier(&field_from_u64(8)).unwrap());
+    }
+
+    #[test]
+    fn test_key_image_depends_only_on_private_key() {
+        let image = key_image(&field_from_u64(5)).unwrap();
+        assert_eq!(image, key_image(&field_from_u64(5)).unwrap());
+        assert_ne!(image, key_image(&field_from_u64(6)).unwrap());
+    }
+}
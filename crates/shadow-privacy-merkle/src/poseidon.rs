@@ -0,0 +1,106 @@
+//! circomlib Poseidon over the BN254 scalar field
+//!
+//! Field elements are 32 little-endian bytes, the encoding the program and
+//! the prover use for public inputs. `poseidon(&[a, b])` equals circomlib's
+//! `Poseidon(2)` with `inputs[0] = a`, `inputs[1] = b`.
+
+use crate::error::MerkleError;
+use solana_program::poseidon::{hashv, Endianness, Parameters};
+
+/// Field element as 32 little-endian bytes
+pub type FieldBytes = [u8; 32];
+
+/// BN254 scalar field modulus, little-endian
+pub const FIELD_MODULUS: FieldBytes = field_from_hex("30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001");
+
+/// Largest arity circomlib's Poseidon (and the syscall) supports
+pub const MAX_POSEIDON_INPUTS: usize = 12;
+
+/// Poseidon hash of `inputs`, each a canonical field element
+pub fn poseidon(inputs: &[&FieldBytes]) -> Result<FieldBytes, MerkleError> {
+    let inputs: Vec<&[u8]> = inputs.iter().map(|input| &input[..]).collect();
+    Ok(hashv(Parameters::Bn254X5, Endianness::LittleEndian, &inputs)?.to_bytes())
+}
+
+/// `Poseidon(2)` of two tree nodes
+pub fn hash_pair(left: &FieldBytes, right: &FieldBytes) -> Result<FieldBytes, MerkleError> {
+    poseidon(&[left, right])
+}
+
+/// Whether `value` is below the field modulus
+pub fn is_canonical(value: &FieldBytes) -> bool {
+    for (byte, modulus) in value.iter().rev().zip(FIELD_MODULUS.iter().rev()) {
+        if byte != modulus {
+            return byte < modulus;
+        }
+    }
+    false
+}
+
+/// A `u64` as a field element (amounts, balances, leaf indices)
+pub fn field_from_u64(value: u64) -> FieldBytes {
+    let mut bytes = [0u8; 32];
+    bytes[..8].copy_from_slice(&value.to_le_bytes());
+    bytes
+}
+
+/// Field element from 64 big-endian hex digits, the way snarkjs and
+/// circomlibjs print them
+///
+/// Panics on malformed input; meant for constants.
+pub const fn field_from_hex(hex: &str) -> FieldBytes {
+    let digits = hex.as_bytes();
+    assert!(digits.len() == 64, "expected 64 hex digits");
+
+    let mut bytes = [0u8; 32];
+    let mut i = 0;
+    while i < 32 {
+        let high = hex_digit(digits[2 * i]);
+        let low = hex_digit(digits[2 * i + 1]);
+        bytes[31 - i] = (high << 4) | low;
+        i += 1;
+    }
+    bytes
+}
+
+const fn hex_digit(digit: u8) -> u8 {
+    match digit {
+        b'0'..=b'9' => digit - b'0',
+        b'a'..=b'f' => digit - b'a' + 10,
+        b'A'..=b'F' => digit - b'A' + 10,
+        _ => panic!("invalid hex digit"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_circomlibjs_reference_value() {
+        // circomlibjs: poseidon([1, 2])
+        let expected = field_from_hex("115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a");
+        assert_eq!(hash_pair(&field_from_u64(1), &field_from_u64(2)).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_canonical_bound() {
+        let mut below = FIELD_MODULUS;
+        below[0] -= 1;
+        assert!(is_canonical(&below));
+        assert!(is_canonical(&[0u8; 32]));
+        assert!(!is_canonical(&FIELD_MODULUS));
+        assert!(!is_canonical(&[0xff; 32]));
+
+        assert_eq!(poseidon(&[&FIELD_MODULUS]), Err(MerkleError::NonCanonicalField));
+        assert!(poseidon(&[&below]).is_ok());
+    }
+
+    #[test]
+    fn test_arity_limits() {
+        let one = field_from_u64(1);
+        assert!(matches!(poseidon(&[]), Err(MerkleError::Poseidon(_))));
+        assert!(poseidon(&[&one; MAX_POSEIDON_INPUTS]).is_ok());
+        assert!(matches!(poseidon(&[&one; MAX_POSEIDON_INPUTS + 1]), Err(MerkleError::Poseidon(_))));
+    }
+}
@@ -0,0 +1,393 @@
+//! Poseidon commitment trees
+//!
+//! Same shape as `privacy-integration/merkletree.ts` and the
+//! `MerkleTreeInclusionProof` template in transfer.circom: fixed depth,
+//! leaves appended left to right, node = Poseidon(left, right), and empty
+//! positions filled with the zero hash of their level (the empty leaf is 0).
+//!
+//! [`IncrementalMerkleTree`] keeps only the right-most frontier, O(depth)
+//! fixed-size state that fits in an account. [`SparseMerkleTree`] keeps every
+//! non-empty node, so it can produce a path for any position.
+
+use crate::{
+    error::MerkleError,
+    poseidon::{field_from_hex, hash_pair, is_canonical, FieldBytes},
+};
+use std::collections::BTreeMap;
+
+/// Depth of the transfer circuit's tree
+pub const TREE_DEPTH: usize = 20;
+
+/// Largest supported tree depth
+pub const MAX_TREE_DEPTH: usize = 32;
+
+/// Root of an empty subtree of each height: `ZERO_HASHES[0]` is the empty
+/// leaf, `ZERO_HASHES[n + 1] = Poseidon(ZERO_HASHES[n], ZERO_HASHES[n])`
+pub const ZERO_HASHES: [FieldBytes; MAX_TREE_DEPTH + 1] = [
+    field_from_hex("0000000000000000000000000000000000000000000000000000000000000000"),
+    field_from_hex("2098f5fb9e239eab3ceac3f27b81e481dc3124d55ffed523a839ee8446b64864"),
+    field_from_hex("1069673dcdb12263df301a6ff584a7ec261a44cb9dc68df067a4774460b1f1e1"),
+    field_from_hex("18f43331537ee2af2e3d758d50f72106467c6eea50371dd528d57eb2b856d238"),
+    field_from_hex("07f9d837cb17b0d36320ffe93ba52345f1b728571a568265caac97559dbc952a"),
+    field_from_hex("2b94cf5e8746b3f5c9631f4c5df32907a699c58c94b2ad4d7b5cec1639183f55"),
+    field_from_hex("2dee93c5a666459646ea7d22cca9e1bcfed71e6951b953611d11dda32ea09d78"),
+    field_from_hex("078295e5a22b84e982cf601eb639597b8b0515a88cb5ac7fa8a4aabe3c87349d"),
+    field_from_hex("2fa5e5f18f6027a6501bec864564472a616b2e274a41211a444cbe3a99f3cc61"),
+    field_from_hex("0e884376d0d8fd21ecb780389e941f66e45e7acce3e228ab3e2156a614fcd747"),
+    field_from_hex("1b7201da72494f1e28717ad1a52eb469f95892f957713533de6175e5da190af2"),
+    field_from_hex("1f8d8822725e36385200c0b201249819a6e6e1e4650808b5bebc6bface7d7636"),
+    field_from_hex("2c5d82f66c914bafb9701589ba8cfcfb6162b0a12acf88a8d0879a0471b5f85a"),
+    field_from_hex("14c54148a0940bb820957f5adf3fa1134ef5c4aaa113f4646458f270e0bfbfd0"),
+    field_from_hex("190d33b12f986f961e10c0ee44d8b9af11be25588cad89d416118e4bf4ebe80c"),
+    field_from_hex("22f98aa9ce704152ac17354914ad73ed1167ae6596af510aa5b3649325e06c92"),
+    field_from_hex("2a7c7c9b6ce5880b9f6f228d72bf6a575a526f29c66ecceef8b753d38bba7323"),
+    field_from_hex("2e8186e558698ec1c67af9c14d463ffc470043c9c2988b954d75dd643f36b992"),
+    field_from_hex("0f57c5571e9a4eab49e2c8cf050dae948aef6ead647392273546249d1c1ff10f"),
+    field_from_hex("1830ee67b5fb554ad5f63d4388800e1cfe78e310697d46e43c9ce36134f72cca"),
+    field_from_hex("2134e76ac5d21aab186c2be1dd8f84ee880a1e46eaf712f9d371b6df22191f3e"),
+    field_from_hex("19df90ec844ebc4ffeebd866f33859b0c051d8c958ee3aa88f8f8df3db91a5b1"),
+    field_from_hex("18cca2a66b5c0787981e69aefd84852d74af0e93ef4912b4648c05f722efe52b"),
+    field_from_hex("2388909415230d1b4d1304d2d54f473a628338f2efad83fadf05644549d2538d"),
+    field_from_hex("27171fb4a97b6cc0e9e8f543b5294de866a2af2c9c8d0b1d96e673e4529ed540"),
+    field_from_hex("2ff6650540f629fd5711a0bc74fc0d28dcb230b9392583e5f8d59696dde6ae21"),
+    field_from_hex("120c58f143d491e95902f7f5277778a2e0ad5168f6add75669932630ce611518"),
+    field_from_hex("1f21feb70d3f21b07bf853d5e5db03071ec495a0a565a21da2d665d279483795"),
+    field_from_hex("24be905fa71335e14c638cc0f66a8623a826e768068a9e968bb1a1dde18a72d2"),
+    field_from_hex("0f8666b62ed17491c50ceadead57d4cd597ef3821d65c328744c74e553dac26d"),
+    field_from_hex("0918d46bf52d98b034413f4a1a1c41594e7a7a3f6ae08cb43d1a2a230e1959ef"),
+    field_from_hex("1bbeb01b4c479ecde76917645e404dfa2e26f90d0afc5a65128513ad375c5ff2"),
+    field_from_hex("2f68a1c58e257e42a17a6c61dff5551ed560b9922ab119d5ac8e184c9734ead9"),
+];
+
+/// Leaf the commitment tree holds for `commitment`
+///
+/// The program once accepted commitments at or above the field modulus. They
+/// cannot be hashed (or ever spent), but still took a leaf index on-chain, so
+/// they keep their place as the empty leaf. Everything that rebuilds a pool
+/// tree from events goes through this.
+pub fn tree_leaf(commitment: &FieldBytes) -> FieldBytes {
+    if is_canonical(commitment) {
+        *commitment
+    } else {
+        ZERO_HASHES[0]
+    }
+}
+
+/// Sibling hashes from a leaf up to the root
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerklePath {
+    pub leaf_index: u64,
+    /// Sibling at each level, leaf level first (`pathElements`)
+    pub siblings: Vec<FieldBytes>,
+    /// 0 when the node at that level is a left child, 1 when it is a right child (`pathIndices`)
+    pub path_indices: Vec<u8>,
+}
+
+impl MerklePath {
+    /// Root reached by hashing `leaf` up this path
+    pub fn compute_root(&self, leaf: &FieldBytes) -> Result<FieldBytes, MerkleError> {
+        let mut node = *leaf;
+        for (sibling, &index) in self.siblings.iter().zip(&self.path_indices) {
+            node = match index {
+                0 => hash_pair(&node, sibling)?,
+                _ => hash_pair(sibling, &node)?,
+            };
+        }
+        Ok(node)
+    }
+
+    pub fn verify(&self, leaf: &FieldBytes, root: &FieldBytes) -> Result<bool, MerkleError> {
+        Ok(self.compute_root(leaf)? == *root)
+    }
+}
+
+/// Append-only tree that keeps the frontier and root, as a program would
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IncrementalMerkleTree<const DEPTH: usize> {
+    next_index: u64,
+    /// Left sibling waiting at each level for its right neighbour
+    filled_subtrees: [FieldBytes; DEPTH],
+    root: FieldBytes,
+}
+
+impl<const DEPTH: usize> IncrementalMerkleTree<DEPTH> {
+    /// An empty tree; fails if `DEPTH` is 0 or above [`MAX_TREE_DEPTH`]
+    pub fn new() -> Result<Self, MerkleError> {
+        if DEPTH == 0 || DEPTH > MAX_TREE_DEPTH {
+            return Err(MerkleError::InvalidTreeDepth(DEPTH as u8));
+        }
+
+        let mut filled_subtrees = [[0u8; 32]; DEPTH];
+        filled_subtrees.copy_from_slice(&ZERO_HASHES[..DEPTH]);
+        Ok(IncrementalMerkleTree {
+            next_index: 0,
+            filled_subtrees,
+            root: ZERO_HASHES[DEPTH],
+        })
+    }
+
+    pub fn root(&self) -> FieldBytes {
+        self.root
+    }
+
+    pub fn len(&self) -> u64 {
+        self.next_index
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.next_index == 0
+    }
+
+    pub fn capacity(&self) -> u64 {
+        1u64 << DEPTH
+    }
+
+    /// Append a leaf and return its index
+    pub fn insert(&mut self, leaf: FieldBytes) -> Result<u64, MerkleError> {
+        let index = self.next_index;
+        self.root = insert_into_frontier(&mut self.filled_subtrees, index, leaf)?;
+        self.next_index += 1;
+        Ok(index)
+    }
+}
+
+/// Append `leaf` at `index` to a tree kept as its frontier and return the
+/// new root
+///
+/// `filled_subtrees` holds one node per level (its length is the depth) and
+/// `index` is the number of leaves already in the tree. This is the step
+/// [`IncrementalMerkleTree`] takes, for callers that store the frontier
+/// themselves, like the program's pool accounts.
+pub fn insert_into_frontier(
+    filled_subtrees: &mut [FieldBytes],
+    index: u64,
+    leaf: FieldBytes,
+) -> Result<FieldBytes, MerkleError> {
+    let depth = filled_subtrees.len();
+    if depth == 0 || depth > MAX_TREE_DEPTH {
+        return Err(MerkleError::InvalidTreeDepth(depth as u8));
+    }
+    if index >= 1u64 << depth {
+        return Err(MerkleError::TreeFull(depth as u8));
+    }
+    if !is_canonical(&leaf) {
+        return Err(MerkleError::NonCanonicalField);
+    }
+
+    let mut position = index;
+    let mut node = leaf;
+    for (filled, zero) in filled_subtrees.iter_mut().zip(&ZERO_HASHES) {
+        node = match position % 2 {
+            0 => {
+                *filled = node;
+                hash_pair(&node, zero)?
+            }
+            _ => hash_pair(filled, &node)?,
+        };
+        position /= 2;
+    }
+    Ok(node)
+}
+
+/// Tree storing only non-empty nodes; paths are available for every
+/// position, filled or not
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseMerkleTree {
+    depth: u8,
+    /// One past the highest leaf set so far
+    next_index: u64,
+    /// (level, index) -> node, level 0 being the leaves
+    nodes: BTreeMap<(u8, u64), FieldBytes>,
+}
+
+impl SparseMerkleTree {
+    /// An empty tree; fails if `depth` is 0 or above [`MAX_TREE_DEPTH`]
+    pub fn new(depth: u8) -> Result<Self, MerkleError> {
+        if depth == 0 || depth as usize > MAX_TREE_DEPTH {
+            return Err(MerkleError::InvalidTreeDepth(depth));
+        }
+
+        Ok(SparseMerkleTree {
+            depth,
+            next_index: 0,
+            nodes: BTreeMap::new(),
+        })
+    }
+
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    /// One past the highest leaf set so far
+    pub fn len(&self) -> u64 {
+        self.next_index
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.next_index == 0
+    }
+
+    pub fn capacity(&self) -> u64 {
+        1u64 << self.depth
+    }
+
+    pub fn root(&self) -> FieldBytes {
+        self.node(self.depth, 0)
+    }
+
+    /// Leaf at `index`, the zero leaf if it was never set
+    pub fn leaf(&self, index: u64) -> FieldBytes {
+        self.node(0, index)
+    }
+
+    /// Append a leaf after the highest one set and return its index
+    pub fn insert(&mut self, leaf: FieldBytes) -> Result<u64, MerkleError> {
+        let index = self.next_index;
+        if index >= self.capacity() {
+            return Err(MerkleError::TreeFull(self.depth));
+        }
+        self.set(index, leaf)?;
+        Ok(index)
+    }
+
+    /// Write the leaf at `index`, e.g. when leaves arrive out of order
+    pub fn set(&mut self, index: u64, leaf: FieldBytes) -> Result<(), MerkleError> {
+        self.check_index(index)?;
+        if !is_canonical(&leaf) {
+            return Err(MerkleError::NonCanonicalField);
+        }
+
+        let mut position = index;
+        let mut node = leaf;
+        self.nodes.insert((0, position), node);
+        for level in 0..self.depth {
+            let sibling = self.node(level, position ^ 1);
+            node = match position % 2 {
+                0 => hash_pair(&node, &sibling)?,
+                _ => hash_pair(&sibling, &node)?,
+            };
+            position /= 2;
+            self.nodes.insert((level + 1, position), node);
+        }
+
+        self.next_index = self.next_index.max(index + 1);
+        Ok(())
+    }
+
+    /// Path from position `index` to the current root
+    pub fn path(&self, index: u64) -> Result<MerklePath, MerkleError> {
+        self.check_index(index)?;
+
+        let mut siblings = Vec::with_capacity(self.depth as usize);
+        let mut path_indices = Vec::with_capacity(self.depth as usize);
+        let mut position = index;
+        for level in 0..self.depth {
+            siblings.push(self.node(level, position ^ 1));
+            path_indices.push((position % 2) as u8);
+            position /= 2;
+        }
+
+        Ok(MerklePath {
+            leaf_index: index,
+            siblings,
+            path_indices,
+        })
+    }
+
+    fn node(&self, level: u8, index: u64) -> FieldBytes {
+        match self.nodes.get(&(level, index)) {
+            Some(node) => *node,
+            None => ZERO_HASHES[level as usize],
+        }
+    }
+
+    fn check_index(&self, index: u64) -> Result<(), MerkleError> {
+        if index >= self.capacity() {
+            return Err(MerkleError::IndexOutOfRange { index, depth: self.depth });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poseidon::{field_from_u64, FIELD_MODULUS};
+
+    #[test]
+    fn test_zero_hashes_chain() {
+        assert_eq!(ZERO_HASHES[0], [0u8; 32]);
+        for level in 0..MAX_TREE_DEPTH {
+            assert_eq!(ZERO_HASHES[level + 1], hash_pair(&ZERO_HASHES[level], &ZERO_HASHES[level]).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_tree_leaf_maps_non_canonical_to_empty() {
+        assert_eq!(tree_leaf(&field_from_u64(7)), field_from_u64(7));
+        assert_eq!(tree_leaf(&FIELD_MODULUS), ZERO_HASHES[0]);
+        assert_eq!(tree_leaf(&[0xff; 32]), ZERO_HASHES[0]);
+    }
+
+    #[test]
+    fn test_incremental_matches_sparse() {
+        let mut incremental = IncrementalMerkleTree::<5>::new().unwrap();
+        let mut sparse = SparseMerkleTree::new(5).unwrap();
+        assert_eq!(incremental.root(), sparse.root());
+
+        for n in 1..=13 {
+            assert_eq!(incremental.insert(field_from_u64(n)).unwrap(), n - 1);
+            assert_eq!(sparse.insert(field_from_u64(n)).unwrap(), n - 1);
+            assert_eq!(incremental.root(), sparse.root());
+        }
+    }
+
+    #[test]
+    fn test_root_matches_full_recomputation() {
+        let mut tree = IncrementalMerkleTree::<2>::new().unwrap();
+        for n in 1..=3 {
+            tree.insert(field_from_u64(n)).unwrap();
+        }
+
+        let left = hash_pair(&field_from_u64(1), &field_from_u64(2)).unwrap();
+        let right = hash_pair(&field_from_u64(3), &ZERO_HASHES[0]).unwrap();
+        assert_eq!(tree.root(), hash_pair(&left, &right).unwrap());
+
+        tree.insert(field_from_u64(4)).unwrap();
+        assert_eq!(tree.insert(field_from_u64(5)), Err(MerkleError::TreeFull(2)));
+        assert_eq!(tree.len(), 4);
+    }
+
+    #[test]
+    fn test_sparse_paths_verify_for_filled_and_empty_positions() {
+        let mut tree = SparseMerkleTree::new(TREE_DEPTH as u8).unwrap();
+        tree.set(3, field_from_u64(30)).unwrap();
+        tree.set(700_000, field_from_u64(40)).unwrap();
+        assert_eq!(tree.len(), 700_001);
+
+        let root = tree.root();
+        for index in [0, 3, 4, 700_000, tree.capacity() - 1] {
+            let path = tree.path(index).unwrap();
+            assert_eq!(path.siblings.len(), TREE_DEPTH);
+            assert!(path.verify(&tree.leaf(index), &root).unwrap());
+            assert!(!path.verify(&field_from_u64(99), &root).unwrap());
+        }
+
+        // Overwriting a leaf moves the root
+        tree.set(3, field_from_u64(31)).unwrap();
+        assert_ne!(tree.root(), root);
+    }
+
+    #[test]
+    fn test_invalid_depth_and_index() {
+        assert_eq!(IncrementalMerkleTree::<0>::new(), Err(MerkleError::InvalidTreeDepth(0)));
+        assert_eq!(IncrementalMerkleTree::<33>::new(), Err(MerkleError::InvalidTreeDepth(33)));
+        assert_eq!(SparseMerkleTree::new(0), Err(MerkleError::InvalidTreeDepth(0)));
+
+        let mut tree = SparseMerkleTree::new(3).unwrap();
+        assert_eq!(tree.path(8), Err(MerkleError::IndexOutOfRange { index: 8, depth: 3 }));
+        assert_eq!(tree.set(8, field_from_u64(1)), Err(MerkleError::IndexOutOfRange { index: 8, depth: 3 }));
+        assert_eq!(tree.insert(FIELD_MODULUS), Err(MerkleError::NonCanonicalField));
+        assert!(tree.is_empty());
+    }
+}
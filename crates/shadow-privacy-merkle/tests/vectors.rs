@@ -0,0 +1,99 @@
+//! Golden vectors in tests/vectors/circom.json
+//!
+//! The transfer and balance inputs are accepted by transfer.wasm and
+//! balance.wasm (see shadow-privacy-prover/tests/circom_vectors.rs), so
+//! matching them here means matching circom.
+
+use num_bigint::BigUint;
+use serde_json::Value;
+use shadow_privacy_merkle::{
+    balance_commitment, key_image, poseidon, public_key, FieldBytes, IncrementalMerkleTree, Note,
+    SparseMerkleTree, TREE_DEPTH, ZERO_HASHES,
+};
+
+fn vectors() -> Value {
+    serde_json::from_str(include_str!("vectors/circom.json")).unwrap()
+}
+
+fn field(value: &Value) -> FieldBytes {
+    let mut bytes = [0u8; 32];
+    let le = value.as_str().unwrap().parse::<BigUint>().unwrap().to_bytes_le();
+    bytes[..le.len()].copy_from_slice(&le);
+    bytes
+}
+
+fn fields(values: &Value) -> Vec<FieldBytes> {
+    values.as_array().unwrap().iter().map(field).collect()
+}
+
+fn number(value: &Value) -> u64 {
+    value.as_str().unwrap().parse().unwrap()
+}
+
+#[test]
+fn test_poseidon_vectors() {
+    for vector in vectors()["poseidon"].as_array().unwrap() {
+        let inputs = fields(&vector["inputs"]);
+        let refs: Vec<&FieldBytes> = inputs.iter().collect();
+        assert_eq!(poseidon(&refs).unwrap(), field(&vector["output"]));
+    }
+}
+
+#[test]
+fn test_transfer_vector() {
+    let vectors = vectors();
+    let transfer = &vectors["transfer"];
+    let input = &transfer["input"];
+    let private_key = field(&input["privateKey"]);
+    let leaf_index = transfer["leaf_index"].as_u64().unwrap();
+
+    let mut sparse = SparseMerkleTree::new(TREE_DEPTH as u8).unwrap();
+    let mut incremental = IncrementalMerkleTree::<TREE_DEPTH>::new().unwrap();
+    for leaf in fields(&transfer["leaves"]) {
+        sparse.insert(leaf).unwrap();
+        incremental.insert(leaf).unwrap();
+    }
+    assert_eq!(sparse.root(), field(&input["root"]));
+    assert_eq!(incremental.root(), field(&input["root"]));
+
+    let path = sparse.path(leaf_index).unwrap();
+    assert_eq!(path.siblings, fields(&input["pathElements"]));
+    let path_indices: Vec<u8> = input["pathIndices"].as_array().unwrap().iter().map(|i| number(i) as u8).collect();
+    assert_eq!(path.path_indices, path_indices);
+    // Beyond the filled leaves every sibling is an empty subtree
+    assert_eq!(path.siblings[3..], ZERO_HASHES[3..TREE_DEPTH]);
+
+    let note = Note {
+        owner: public_key(&private_key).unwrap(),
+        amount: number(&input["amount"]),
+        nonce: field(&input["oldNonce"]),
+    };
+    assert_eq!(note.owner, field(&transfer["public_key"]));
+    assert_eq!(note.commitment().unwrap(), sparse.leaf(leaf_index));
+    assert_eq!(note.nullifier(&private_key).unwrap(), field(&input["nullifier"]));
+
+    let output = Note {
+        owner: field(&input["recipientPublicKey"]),
+        amount: note.amount,
+        nonce: field(&input["nonce"]),
+    };
+    assert_eq!(output.commitment().unwrap(), field(&input["newCommitment"]));
+}
+
+#[test]
+fn test_balance_vector() {
+    let input = &vectors()["balance"]["input"];
+    let commitment = balance_commitment(
+        &field(&input["privateKey"]),
+        number(&input["actualBalance"]),
+        &field(&input["balanceNonce"]),
+    )
+    .unwrap();
+    assert_eq!(commitment, field(&input["balanceCommitment"]));
+}
+
+#[test]
+fn test_key_image_vector() {
+    let vector = &vectors()["key_image"];
+    assert_eq!(key_image(&field(&vector["privateKey"])).unwrap(), field(&vector["keyImage"]));
+}
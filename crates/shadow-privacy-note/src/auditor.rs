@@ -0,0 +1,270 @@
+//! Auditor notes
+//!
+//! Encryption of a note's opening to a pool auditor, in the format
+//! `shadow_privacy::auditor` defines and `auditor_note.circom` proves:
+//!
+//! ```text
+//! ephemeral_key = r · B8
+//! shared        = r · auditor_key
+//! ciphertext[i] = [owner, amount, nonce][i] + Poseidon(shared.x, shared.y, i)
+//! ```
+//!
+//! There is no authentication tag: the proof binds the ciphertext to the
+//! commitment on-chain, and decryption checks the recovered opening against
+//! the commitment again.
+
+use crate::error::NoteError;
+use ark_bn254::Fr;
+use ark_ff::{BigInteger, PrimeField};
+use rand_core::{CryptoRng, RngCore};
+use shadow_privacy::{
+    auditor::{is_valid_auditor_key, Point, BASE8, SUBGROUP_ORDER},
+    state::AuditorNote,
+};
+use shadow_privacy_merkle::{field_from_u64, is_canonical, poseidon, FieldBytes, Note};
+
+/// Auditor's secret scalar; the matching public key is registered with SetAuditor
+#[derive(Clone)]
+pub struct AuditorSecretKey([u8; 32]);
+
+impl AuditorSecretKey {
+    pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        AuditorSecretKey(random_scalar(rng))
+    }
+
+    /// Little-endian scalar, nonzero and below the subgroup order
+    pub fn from_bytes(bytes: [u8; 32]) -> Result<Self, NoteError> {
+        if !is_scalar(&bytes) {
+            return Err(NoteError::InvalidAuditorKey);
+        }
+        Ok(AuditorSecretKey(bytes))
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+
+    /// Baby Jubjub public key (x, y) to register with SetAuditor
+    pub fn public_key(&self) -> [[u8; 32]; 2] {
+        BASE8.mul(&self.0).to_bytes()
+    }
+}
+
+impl std::fmt::Debug for AuditorSecretKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("AuditorSecretKey").field(&self.public_key()).finish()
+    }
+}
+
+/// An auditor ciphertext, with the ephemeral scalar its proof needs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditorEncryption {
+    pub ephemeral_key: [[u8; 32]; 2],
+    pub ciphertext: [[u8; 32]; 3],
+    /// Ephemeral scalar r, a private input of the AuditorNote circuit
+    pub ephemeral_secret: [u8; 32],
+}
+
+impl AuditorEncryption {
+    /// Instruction argument carrying this ciphertext and its AuditorNote `proof`
+    pub fn into_auditor_note(self, proof: Vec<u8>) -> AuditorNote {
+        AuditorNote {
+            ephemeral_key: self.ephemeral_key,
+            ciphertext: self.ciphertext,
+            proof,
+        }
+    }
+}
+
+/// Encrypt the opening of `note` to `auditor_key`
+pub fn encrypt_for_auditor<R: RngCore + CryptoRng>(
+    auditor_key: &[[u8; 32]; 2],
+    note: &Note,
+    rng: &mut R,
+) -> Result<AuditorEncryption, NoteError> {
+    if !is_valid_auditor_key(auditor_key) {
+        return Err(NoteError::InvalidAuditorKey);
+    }
+    if !is_canonical(&note.owner) || !is_canonical(&note.nonce) {
+        return Err(NoteError::Malformed);
+    }
+    let auditor_key = Point::from_bytes(auditor_key).ok_or(NoteError::InvalidAuditorKey)?;
+
+    let ephemeral_secret = random_scalar(rng);
+    let masks = masks(&auditor_key.mul(&ephemeral_secret))?;
+    let plaintext = [note.owner, field_from_u64(note.amount), note.nonce];
+    let mut ciphertext = [[0u8; 32]; 3];
+    for (i, value) in plaintext.iter().enumerate() {
+        ciphertext[i] = field_bytes(field(value) + masks[i]);
+    }
+
+    Ok(AuditorEncryption {
+        ephemeral_key: BASE8.mul(&ephemeral_secret).to_bytes(),
+        ciphertext,
+        ephemeral_secret,
+    })
+}
+
+/// Recover the note behind `commitment` from its auditor ciphertext
+///
+/// Fails with `DecryptionFailed` unless the recovered opening hashes to
+/// `commitment`, which also catches ciphertexts for another auditor.
+pub fn decrypt_for_auditor(
+    secret_key: &AuditorSecretKey,
+    ephemeral_key: &[[u8; 32]; 2],
+    ciphertext: &[[u8; 32]; 3],
+    commitment: &FieldBytes,
+) -> Result<Note, NoteError> {
+    let ephemeral_key = Point::from_bytes(ephemeral_key).ok_or(NoteError::Malformed)?;
+    if !ciphertext.iter().all(is_canonical) {
+        return Err(NoteError::Malformed);
+    }
+
+    let masks = masks(&ephemeral_key.mul(&secret_key.0))?;
+    let mut plaintext = [[0u8; 32]; 3];
+    for (i, value) in ciphertext.iter().enumerate() {
+        plaintext[i] = field_bytes(field(value) - masks[i]);
+    }
+    let [owner, amount, nonce] = plaintext;
+
+    // Another key's masks leave a random field element here
+    if amount[8..].iter().any(|&byte| byte != 0) {
+        return Err(NoteError::DecryptionFailed);
+    }
+    let note = Note {
+        owner,
+        amount: u64::from_le_bytes(amount[..8].try_into().expect("8 bytes")),
+        nonce,
+    };
+    if note.commitment()? != *commitment {
+        return Err(NoteError::DecryptionFailed);
+    }
+    Ok(note)
+}
+
+/// Poseidon(shared.x, shared.y, i) for each plaintext element
+fn masks(shared: &Point) -> Result<[Fr; 3], NoteError> {
+    let [x, y] = shared.to_bytes();
+    let mut masks = [Fr::from(0u64); 3];
+    for (i, mask) in masks.iter_mut().enumerate() {
+        *mask = field(&poseidon(&[&x, &y, &field_from_u64(i as u64)])?);
+    }
+    Ok(masks)
+}
+
+/// Uniform nonzero scalar below the subgroup order, by rejection sampling
+fn random_scalar<R: RngCore + CryptoRng>(rng: &mut R) -> [u8; 32] {
+    loop {
+        let mut bytes = [0u8; 32];
+        rng.fill_bytes(&mut bytes);
+        // The order is just under 2^251
+        bytes[31] &= 0x07;
+        if is_scalar(&bytes) {
+            return bytes;
+        }
+    }
+}
+
+fn is_scalar(bytes: &[u8; 32]) -> bool {
+    bytes.iter().any(|&byte| byte != 0) && bytes.iter().rev().lt(SUBGROUP_ORDER.iter().rev())
+}
+
+fn field(bytes: &FieldBytes) -> Fr {
+    Fr::from_le_bytes_mod_order(bytes)
+}
+
+fn field_bytes(value: Fr) -> FieldBytes {
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(&value.into_bigint().to_bytes_le());
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_core::OsRng;
+    use shadow_privacy_merkle::public_key;
+
+    fn note() -> Note {
+        Note {
+            owner: public_key(&field_from_u64(7)).unwrap(),
+            amount: 1_000_000_000,
+            nonce: field_from_u64(99),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let auditor = AuditorSecretKey::generate(&mut OsRng);
+        let note = note();
+        let encryption = encrypt_for_auditor(&auditor.public_key(), &note, &mut OsRng).unwrap();
+        let commitment = note.commitment().unwrap();
+
+        let decrypted =
+            decrypt_for_auditor(&auditor, &encryption.ephemeral_key, &encryption.ciphertext, &commitment).unwrap();
+        assert_eq!(decrypted, note);
+        assert_eq!(BASE8.mul(&encryption.ephemeral_secret).to_bytes(), encryption.ephemeral_key);
+
+        // Fresh ephemeral keys each time
+        let again = encrypt_for_auditor(&auditor.public_key(), &note, &mut OsRng).unwrap();
+        assert_ne!(again.ciphertext, encryption.ciphertext);
+    }
+
+    #[test]
+    fn test_other_auditor_or_commitment_rejected() {
+        let auditor = AuditorSecretKey::generate(&mut OsRng);
+        let other = AuditorSecretKey::generate(&mut OsRng);
+        let note = note();
+        let encryption = encrypt_for_auditor(&auditor.public_key(), &note, &mut OsRng).unwrap();
+        let commitment = note.commitment().unwrap();
+        let decrypt = |key: &AuditorSecretKey, ciphertext: &[[u8; 32]; 3], commitment: &FieldBytes| {
+            decrypt_for_auditor(key, &encryption.ephemeral_key, ciphertext, commitment)
+        };
+
+        assert_eq!(decrypt(&other, &encryption.ciphertext, &commitment), Err(NoteError::DecryptionFailed));
+        assert_eq!(
+            decrypt(&auditor, &encryption.ciphertext, &field_from_u64(1)),
+            Err(NoteError::DecryptionFailed)
+        );
+
+        let mut tampered = encryption.ciphertext;
+        tampered[2][0] ^= 1;
+        assert_eq!(decrypt(&auditor, &tampered, &commitment), Err(NoteError::DecryptionFailed));
+    }
+
+    #[test]
+    fn test_malformed_rejected() {
+        let auditor = AuditorSecretKey::generate(&mut OsRng);
+        let note = note();
+        let encryption = encrypt_for_auditor(&auditor.public_key(), &note, &mut OsRng).unwrap();
+        let commitment = note.commitment().unwrap();
+
+        let mut off_curve = encryption.ephemeral_key;
+        off_curve[0][0] ^= 1;
+        let result = decrypt_for_auditor(&auditor, &off_curve, &encryption.ciphertext, &commitment);
+        assert_eq!(result, Err(NoteError::Malformed));
+
+        let mut non_canonical = encryption.ciphertext;
+        non_canonical[1] = [0xff; 32];
+        let result = decrypt_for_auditor(&auditor, &encryption.ephemeral_key, &non_canonical, &commitment);
+        assert_eq!(result, Err(NoteError::Malformed));
+
+        let result = encrypt_for_auditor(&[[0; 32]; 2], &note, &mut OsRng);
+        assert_eq!(result, Err(NoteError::InvalidAuditorKey));
+    }
+
+    #[test]
+    fn test_secret_key_bytes() {
+        let auditor = AuditorSecretKey::generate(&mut OsRng);
+        let restored = AuditorSecretKey::from_bytes(auditor.to_bytes()).unwrap();
+        assert_eq!(restored.public_key(), auditor.public_key());
+        assert!(is_valid_auditor_key(&auditor.public_key()));
+
+        for invalid in [[0u8; 32], SUBGROUP_ORDER, [0xff; 32]] {
+            assert_eq!(AuditorSecretKey::from_bytes(invalid).unwrap_err(), NoteError::InvalidAuditorKey);
+        }
+        let mut below_order = SUBGROUP_ORDER;
+        below_order[0] -= 1;
+        assert!(AuditorSecretKey::from_bytes(below_order).is_ok());
+    }
+}
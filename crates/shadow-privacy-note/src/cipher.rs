@@ -0,0 +1,243 @@
+//! Note encryption (ECIES over x25519 with ChaCha20-Poly1305)
+//!
+//! The byte layout is defined in `shadow_privacy::note`. For each note the
+//! sender draws an ephemeral x25519 key and derives
+//!
+//! ```text
+//! key = HKDF-SHA256(ikm = X25519(ephemeral, recipient),
+//!                   salt = ephemeral_public || recipient_public,
+//!                   info = "shadow-privacy/note/v1")
+//! ```
+//!
+//! The value part is sealed under nonce 0 and the memo part under nonce 1,
+//! both with the version byte and ephemeral public key as associated data.
+//! Keys are never reused across notes, so fixed nonces are safe.
+
+use crate::{
+    error::NoteError,
+    keys::{EncryptionKey, ViewingKey},
+};
+use chacha20poly1305::{
+    aead::{Aead, NewAead, Payload},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use hkdf::Hkdf;
+use rand_core::{CryptoRng, RngCore};
+use sha2::Sha256;
+use shadow_privacy::note::{
+    NoteCiphertext, EPHEMERAL_KEY_LEN, MAX_MEMO_LEN, NOTE_CIPHERTEXT_VERSION, NOTE_PLAINTEXT_LEN,
+};
+use x25519_dalek::{EphemeralSecret, PublicKey, SharedSecret};
+
+const KDF_INFO: &[u8] = b"shadow-privacy/note/v1";
+const VALUE_NONCE: [u8; 12] = [0; 12];
+const MEMO_NONCE: [u8; 12] = [1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+/// Asset id of notes in SOL pools, which have no asset
+pub const NATIVE_ASSET_ID: [u8; 32] = [0; 32];
+
+/// What the recipient learns from a note
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotePlaintext {
+    pub amount: u64,
+    /// Commitment nonce (a field element), needed to recompute and spend the commitment
+    pub nonce: [u8; 32],
+    /// Confidential asset id, [`NATIVE_ASSET_ID`] for pool notes
+    pub asset_id: [u8; 32],
+    /// Up to `MAX_MEMO_LEN` bytes; empty omits the memo part
+    pub memo: Vec<u8>,
+}
+
+impl NotePlaintext {
+    fn value_bytes(&self) -> [u8; NOTE_PLAINTEXT_LEN] {
+        let mut bytes = [0u8; NOTE_PLAINTEXT_LEN];
+        bytes[..8].copy_from_slice(&self.amount.to_le_bytes());
+        bytes[8..40].copy_from_slice(&self.nonce);
+        bytes[40..].copy_from_slice(&self.asset_id);
+        bytes
+    }
+}
+
+/// Encrypt `note` to `recipient`, returning the encoded ciphertext
+pub fn encrypt<R: RngCore + CryptoRng>(
+    recipient: &EncryptionKey,
+    note: &NotePlaintext,
+    rng: &mut R,
+) -> Result<Vec<u8>, NoteError> {
+    if note.memo.len() > MAX_MEMO_LEN {
+        return Err(NoteError::MemoTooLong { len: note.memo.len(), max: MAX_MEMO_LEN });
+    }
+
+    let ephemeral = EphemeralSecret::random_from_rng(rng);
+    let ephemeral_public = PublicKey::from(&ephemeral);
+    let recipient_public = PublicKey::from(recipient.to_bytes());
+    let cipher = note_cipher(ephemeral.diffie_hellman(&recipient_public), &ephemeral_public, &recipient_public)?;
+
+    let mut header = Vec::with_capacity(1 + EPHEMERAL_KEY_LEN);
+    header.push(NOTE_CIPHERTEXT_VERSION);
+    header.extend_from_slice(ephemeral_public.as_bytes());
+
+    let mut data = header.clone();
+    data.extend(seal(&cipher, &VALUE_NONCE, &note.value_bytes(), &header)?);
+    if !note.memo.is_empty() {
+        data.extend(seal(&cipher, &MEMO_NONCE, &note.memo, &header)?);
+    }
+    Ok(data)
+}
+
+/// Decrypt a note sent to `viewing_key`
+///
+/// `DecryptionFailed` is the expected outcome when scanning notes that
+/// belong to someone else.
+pub fn decrypt(viewing_key: &ViewingKey, data: &[u8]) -> Result<NotePlaintext, NoteError> {
+    if let Some(&version) = data.first() {
+        if version != NOTE_CIPHERTEXT_VERSION {
+            return Err(NoteError::UnsupportedVersion(version));
+        }
+    }
+    let parts = NoteCiphertext::parse(data)?;
+
+    let ephemeral_public = PublicKey::from(parts.ephemeral_key);
+    let recipient_public = PublicKey::from(viewing_key.encryption_key().to_bytes());
+    let cipher = note_cipher(viewing_key.secret().diffie_hellman(&ephemeral_public), &ephemeral_public, &recipient_public)?;
+
+    let header = &parts.value[..1 + EPHEMERAL_KEY_LEN];
+    let value = open(&cipher, &VALUE_NONCE, &parts.value[header.len()..], header)?;
+    let memo = match parts.memo.is_empty() {
+        true => Vec::new(),
+        false => open(&cipher, &MEMO_NONCE, parts.memo, header)?,
+    };
+
+    let mut nonce = [0u8; 32];
+    nonce.copy_from_slice(&value[8..40]);
+    let mut asset_id = [0u8; 32];
+    asset_id.copy_from_slice(&value[40..]);
+    Ok(NotePlaintext {
+        amount: u64::from_le_bytes(value[..8].try_into().unwrap()),
+        nonce,
+        asset_id,
+        memo,
+    })
+}
+
+fn note_cipher(shared: SharedSecret, ephemeral: &PublicKey, recipient: &PublicKey) -> Result<ChaCha20Poly1305, NoteError> {
+    // A low-order ephemeral key would make the shared secret predictable
+    if !shared.was_contributory() {
+        return Err(NoteError::WeakEphemeralKey);
+    }
+
+    let mut salt = [0u8; 64];
+    salt[..32].copy_from_slice(ephemeral.as_bytes());
+    salt[32..].copy_from_slice(recipient.as_bytes());
+    let mut key = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(&salt), shared.as_bytes())
+        .expand(KDF_INFO, &mut key)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+fn seal(cipher: &ChaCha20Poly1305, nonce: &[u8; 12], msg: &[u8], aad: &[u8]) -> Result<Vec<u8>, NoteError> {
+    cipher
+        .encrypt(Nonce::from_slice(nonce), Payload { msg, aad })
+        .map_err(|_| NoteError::Malformed)
+}
+
+fn open(cipher: &ChaCha20Poly1305, nonce: &[u8; 12], msg: &[u8], aad: &[u8]) -> Result<Vec<u8>, NoteError> {
+    cipher
+        .decrypt(Nonce::from_slice(nonce), Payload { msg, aad })
+        .map_err(|_| NoteError::DecryptionFailed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_core::OsRng;
+    use shadow_privacy::{
+        note::{MAX_NOTE_CIPHERTEXT_LEN, TAG_LEN, VALUE_CIPHERTEXT_LEN},
+        state::ShieldedNote,
+    };
+
+    fn note(memo: &[u8]) -> NotePlaintext {
+        NotePlaintext {
+            amount: 1_000_000_000,
+            nonce: [7u8; 32],
+            asset_id: [9u8; 32],
+            memo: memo.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let viewing_key = ViewingKey::generate(&mut OsRng);
+        for memo in [&b""[..], b"rent for march", &[0xab; MAX_MEMO_LEN]] {
+            let data = encrypt(&viewing_key.encryption_key(), &note(memo), &mut OsRng).unwrap();
+            assert_eq!(data.len(), VALUE_CIPHERTEXT_LEN + if memo.is_empty() { 0 } else { memo.len() + TAG_LEN });
+            assert_eq!(decrypt(&viewing_key, &data).unwrap(), note(memo));
+        }
+    }
+
+    #[test]
+    fn test_parts_fit_a_shielded_note() {
+        let viewing_key = ViewingKey::generate(&mut OsRng);
+        let data = encrypt(&viewing_key.encryption_key(), &note(&[1; MAX_MEMO_LEN]), &mut OsRng).unwrap();
+        assert_eq!(data.len(), MAX_NOTE_CIPHERTEXT_LEN);
+
+        let parts = NoteCiphertext::parse(&data).unwrap();
+        assert!(parts.value.len() <= ShieldedNote::MAX_ENCRYPTED_VALUE_LEN);
+        assert!(parts.memo.len() <= ShieldedNote::MAX_ENCRYPTED_MEMO_LEN);
+
+        let result = encrypt(&viewing_key.encryption_key(), &note(&[1; MAX_MEMO_LEN + 1]), &mut OsRng);
+        assert_eq!(result, Err(NoteError::MemoTooLong { len: MAX_MEMO_LEN + 1, max: MAX_MEMO_LEN }));
+    }
+
+    #[test]
+    fn test_other_key_cannot_decrypt() {
+        let recipient = ViewingKey::generate(&mut OsRng);
+        let other = ViewingKey::generate(&mut OsRng);
+        let data = encrypt(&recipient.encryption_key(), &note(b"memo"), &mut OsRng).unwrap();
+        assert_eq!(decrypt(&other, &data), Err(NoteError::DecryptionFailed));
+    }
+
+    #[test]
+    fn test_tampering_detected() {
+        let viewing_key = ViewingKey::generate(&mut OsRng);
+        let data = encrypt(&viewing_key.encryption_key(), &note(b"memo"), &mut OsRng).unwrap();
+
+        // Ephemeral key (associated data), value ciphertext and memo ciphertext
+        for position in [1, 40, data.len() - 1] {
+            let mut tampered = data.clone();
+            tampered[position] ^= 1;
+            assert_eq!(decrypt(&viewing_key, &tampered), Err(NoteError::DecryptionFailed));
+        }
+
+        // Swapping in another note's memo part
+        let other = encrypt(&viewing_key.encryption_key(), &note(b"memo"), &mut OsRng).unwrap();
+        let mut spliced = data[..VALUE_CIPHERTEXT_LEN].to_vec();
+        spliced.extend_from_slice(&other[VALUE_CIPHERTEXT_LEN..]);
+        assert_eq!(decrypt(&viewing_key, &spliced), Err(NoteError::DecryptionFailed));
+    }
+
+    #[test]
+    fn test_malformed_rejected() {
+        let viewing_key = ViewingKey::generate(&mut OsRng);
+        let data = encrypt(&viewing_key.encryption_key(), &note(b""), &mut OsRng).unwrap();
+
+        let mut future = data.clone();
+        future[0] = 2;
+        assert_eq!(decrypt(&viewing_key, &future), Err(NoteError::UnsupportedVersion(2)));
+        assert_eq!(decrypt(&viewing_key, &data[..data.len() - 1]), Err(NoteError::Malformed));
+        assert_eq!(decrypt(&viewing_key, &[]), Err(NoteError::Malformed));
+
+        // The all-zero ephemeral key is low order
+        let mut weak = data;
+        weak[1..33].fill(0);
+        assert_eq!(decrypt(&viewing_key, &weak), Err(NoteError::WeakEphemeralKey));
+    }
+
+    #[test]
+    fn test_viewing_key_bytes_round_trip() {
+        let viewing_key = ViewingKey::generate(&mut OsRng);
+        let restored = ViewingKey::from_bytes(viewing_key.to_bytes());
+        assert_eq!(restored.encryption_key(), viewing_key.encryption_key());
+    }
+}
@@ -0,0 +1,36 @@
+use shadow_privacy::PrivacyError;
+use shadow_privacy_merkle::MerkleError;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoteError {
+    #[error("Malformed note ciphertext")]
+    Malformed,
+
+    #[error("Unsupported note ciphertext version {0}")]
+    UnsupportedVersion(u8),
+
+    #[error("Memo of {len} bytes exceeds the {max}-byte limit")]
+    MemoTooLong { len: usize, max: usize },
+
+    #[error("Note was not encrypted to this key, or was tampered with")]
+    DecryptionFailed,
+
+    #[error("Ephemeral key is a low-order point")]
+    WeakEphemeralKey,
+
+    #[error("Not a valid auditor key")]
+    InvalidAuditorKey,
+}
+
+impl From<PrivacyError> for NoteError {
+    fn from(_: PrivacyError) -> Self {
+        NoteError::Malformed
+    }
+}
+
+impl From<MerkleError> for NoteError {
+    fn from(_: MerkleError) -> Self {
+        NoteError::Malformed
+    }
+}
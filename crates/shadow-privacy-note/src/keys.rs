@@ -0,0 +1,57 @@
+//! Note encryption keys
+//!
+//! A wallet's viewing key is an x25519 secret; senders encrypt notes to the
+//! matching encryption key. Neither is the circuit key pair (the Poseidon
+//! private/public key that owns commitments): holding the viewing key lets
+//! you read notes, not spend them.
+
+use rand_core::{CryptoRng, RngCore};
+use x25519_dalek::{PublicKey, StaticSecret};
+
+/// Secret half: decrypts notes sent to the matching [`EncryptionKey`]
+#[derive(Clone)]
+pub struct ViewingKey(StaticSecret);
+
+impl ViewingKey {
+    pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        ViewingKey(StaticSecret::random_from_rng(rng))
+    }
+
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        ViewingKey(StaticSecret::from(bytes))
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.to_bytes()
+    }
+
+    pub fn encryption_key(&self) -> EncryptionKey {
+        EncryptionKey(PublicKey::from(&self.0).to_bytes())
+    }
+
+    pub(crate) fn secret(&self) -> &StaticSecret {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for ViewingKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ViewingKey").field(&self.encryption_key()).finish()
+    }
+}
+
+/// Public half, published so senders can encrypt notes to its owner
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EncryptionKey(pub [u8; 32]);
+
+impl EncryptionKey {
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+}
+
+impl From<[u8; 32]> for EncryptionKey {
+    fn from(bytes: [u8; 32]) -> Self {
+        EncryptionKey(bytes)
+    }
+}
@@ -0,0 +1,23 @@
+// Shadow Privacy Note
+//
+// Off-chain side of the encrypted note format defined in
+// `shadow_privacy::note`:
+// - x25519 viewing keys and the encryption keys senders address notes to
+// - ECIES encryption of amount, commitment nonce, asset id and memo
+//   (ephemeral x25519 + HKDF-SHA256 + ChaCha20-Poly1305)
+// - Authenticated decryption that tells foreign notes apart from malformed ones
+// - Auditor notes: the note's opening encrypted to a pool auditor's Baby
+//   Jubjub key, in the form the AuditorNote circuit proves
+//
+// The program only checks the shape of a ciphertext; everything that needs a
+// key lives here.
+
+pub mod auditor;
+pub mod cipher;
+pub mod error;
+pub mod keys;
+
+pub use auditor::{decrypt_for_auditor, encrypt_for_auditor, AuditorEncryption, AuditorSecretKey};
+pub use cipher::{decrypt, encrypt, NotePlaintext, NATIVE_ASSET_ID};
+pub use error::NoteError;
+pub use keys::{EncryptionKey, ViewingKey};
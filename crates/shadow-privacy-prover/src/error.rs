@@ -0,0 +1,22 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ProverError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid zkey: {0}")]
+    Zkey(#[from] ark_serialize::SerializationError),
+
+    #[error("Witness calculator error: {0}")]
+    Witness(String),
+
+    #[error("Proving failed: {0}")]
+    Synthesis(#[from] ark_relations::gr1cs::SynthesisError),
+
+    #[error("Signal {0} is not a canonical field element")]
+    NonCanonicalField(String),
+
+    #[error("Circuit has {got} public inputs, inputs are for a circuit with {expected}")]
+    CircuitMismatch { expected: usize, got: usize },
+}
@@ -0,0 +1,161 @@
+use num_bigint::{BigInt, Sign};
+
+/// Field element as 32 little-endian bytes, the program's encoding
+pub type FieldBytes = [u8; 32];
+
+/// Merkle depth of transfer.circom
+pub const TRANSFER_LEVELS: usize = 20;
+
+/// Ring size of ring_signature.circom
+pub const RING_SIZE: usize = 11;
+
+/// Inputs for one circuit, in the form the witness calculator takes
+pub trait CircuitInputs {
+    /// Number of public inputs the circuit declares
+    const NUM_PUBLIC_INPUTS: usize;
+
+    /// Signal name and values, one entry per `signal input`
+    fn signals(&self) -> Vec<(String, Vec<BigInt>)>;
+}
+
+/// Spend a note and re-commit its amount to a recipient (transfer.circom)
+///
+/// The public values must match what the circuit derives from the private
+/// ones, or witness generation fails:
+/// - nullifier = Poseidon(Poseidon(publicKey, amount, oldNonce), privateKey)
+/// - new_commitment = Poseidon(recipientPublicKey, amount, nonce)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransferInputs {
+    pub root: FieldBytes,
+    pub nullifier: FieldBytes,
+    pub new_commitment: FieldBytes,
+
+    pub amount: u64,
+    pub private_key: FieldBytes,
+    /// Sibling at each level, leaf level first
+    pub path_elements: [FieldBytes; TRANSFER_LEVELS],
+    /// 0 when the node at that level is a left child, 1 when it is a right child
+    pub path_indices: [u8; TRANSFER_LEVELS],
+    pub recipient_public_key: FieldBytes,
+    pub nonce: FieldBytes,
+    pub old_nonce: FieldBytes,
+}
+
+impl CircuitInputs for TransferInputs {
+    const NUM_PUBLIC_INPUTS: usize = 3;
+
+    fn signals(&self) -> Vec<(String, Vec<BigInt>)> {
+        vec![
+            signal("root", &self.root),
+            signal("nullifier", &self.nullifier),
+            signal("newCommitment", &self.new_commitment),
+            ("amount".to_string(), vec![BigInt::from(self.amount)]),
+            signal("privateKey", &self.private_key),
+            ("pathElements".to_string(), self.path_elements.iter().map(field).collect()),
+            ("pathIndices".to_string(), self.path_indices.iter().map(|&bit| BigInt::from(bit)).collect()),
+            signal("recipientPublicKey", &self.recipient_public_key),
+            signal("nonce", &self.nonce),
+            signal("oldNonce", &self.old_nonce),
+        ]
+    }
+}
+
+/// Prove a committed balance is at least `min_balance` (balance.circom)
+///
+/// balance_commitment = Poseidon(Poseidon(privateKey), actualBalance, balanceNonce)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BalanceInputs {
+    pub min_balance: u64,
+    pub balance_commitment: FieldBytes,
+
+    pub actual_balance: u64,
+    pub balance_nonce: FieldBytes,
+    pub private_key: FieldBytes,
+}
+
+impl CircuitInputs for BalanceInputs {
+    const NUM_PUBLIC_INPUTS: usize = 2;
+
+    fn signals(&self) -> Vec<(String, Vec<BigInt>)> {
+        vec![
+            ("minBalance".to_string(), vec![BigInt::from(self.min_balance)]),
+            signal("balanceCommitment", &self.balance_commitment),
+            ("actualBalance".to_string(), vec![BigInt::from(self.actual_balance)]),
+            signal("balanceNonce", &self.balance_nonce),
+            signal("privateKey", &self.private_key),
+        ]
+    }
+}
+
+/// Sign `message` as one of `ring_public_keys` (ring_signature.circom)
+///
+/// key_image = Poseidon(privateKey, Poseidon(privateKey))
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RingInputs {
+    pub message: FieldBytes,
+    pub key_image: FieldBytes,
+    pub ring_public_keys: [FieldBytes; RING_SIZE],
+
+    pub private_key: FieldBytes,
+    pub ring_index: u8,
+}
+
+impl CircuitInputs for RingInputs {
+    const NUM_PUBLIC_INPUTS: usize = 2 + RING_SIZE;
+
+    fn signals(&self) -> Vec<(String, Vec<BigInt>)> {
+        vec![
+            signal("message", &self.message),
+            signal("keyImage", &self.key_image),
+            ("ringPublicKeys".to_string(), self.ring_public_keys.iter().map(field).collect()),
+            signal("privateKey", &self.private_key),
+            ("ringIndex".to_string(), vec![BigInt::from(self.ring_index)]),
+        ]
+    }
+}
+
+/// Encrypt a commitment's opening to the pool auditor (auditor_note.circom)
+///
+/// The public values must match what the circuit derives from the private
+/// ones, or witness generation fails:
+/// - commitment = Poseidon(publicKey, amount, nonce)
+/// - ephemeral_key = ephemeralSecret · Base8, shared = ephemeralSecret · auditor_public_key
+/// - ciphertext[i] = [publicKey, amount, nonce][i] + Poseidon(shared.x, shared.y, i)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuditorNoteInputs {
+    pub auditor_public_key: [FieldBytes; 2],
+    pub commitment: FieldBytes,
+    pub ephemeral_key: [FieldBytes; 2],
+    pub ciphertext: [FieldBytes; 3],
+
+    pub public_key: FieldBytes,
+    pub amount: u64,
+    pub nonce: FieldBytes,
+    /// Below the Baby Jubjub subgroup order
+    pub ephemeral_secret: FieldBytes,
+}
+
+impl CircuitInputs for AuditorNoteInputs {
+    const NUM_PUBLIC_INPUTS: usize = 8;
+
+    fn signals(&self) -> Vec<(String, Vec<BigInt>)> {
+        vec![
+            ("auditorPublicKey".to_string(), self.auditor_public_key.iter().map(field).collect()),
+            signal("commitment", &self.commitment),
+            ("ephemeralKey".to_string(), self.ephemeral_key.iter().map(field).collect()),
+            ("ciphertext".to_string(), self.ciphertext.iter().map(field).collect()),
+            signal("publicKey", &self.public_key),
+            ("amount".to_string(), vec![BigInt::from(self.amount)]),
+            signal("nonce", &self.nonce),
+            signal("ephemeralSecret", &self.ephemeral_secret),
+        ]
+    }
+}
+
+fn field(bytes: &FieldBytes) -> BigInt {
+    BigInt::from_bytes_le(Sign::Plus, bytes)
+}
+
+fn signal(name: &str, bytes: &FieldBytes) -> (String, Vec<BigInt>) {
+    (name.to_string(), vec![field(bytes)])
+}
@@ -0,0 +1,23 @@
+// Shadow Privacy Prover
+//
+// Native Groth16 proving for the circom circuits, for Rust services that
+// cannot shell out to snarkjs:
+// - Witness generation from the compiled circuit wasm (ark-circom)
+// - Proving keys read straight from snarkjs .zkey files
+// - Typed inputs for the transfer, balance, ring signature and auditor note
+//   circuits
+//
+// Proofs come out in the compressed arkworks encoding and public inputs as
+// 32-byte little-endian field elements, which is what the program's
+// verifier deserializes.
+
+pub mod error;
+pub mod inputs;
+pub mod prover;
+pub mod witness;
+
+pub use error::ProverError;
+pub use inputs::{
+    AuditorNoteInputs, BalanceInputs, CircuitInputs, FieldBytes, RingInputs, TransferInputs, RING_SIZE, TRANSFER_LEVELS,
+};
+pub use prover::{CircuitProver, GeneratedProof, PROOF_LEN};
@@ -0,0 +1,142 @@
+use crate::{
+    error::ProverError,
+    inputs::{CircuitInputs, FieldBytes},
+    witness::load_calculator,
+};
+use ark_bn254::{Bn254, Fr};
+use ark_circom::{read_zkey, CircomReduction, WitnessCalculator};
+use ark_ff::{BigInteger, PrimeField, UniformRand};
+use ark_groth16::{Groth16, Proof, ProvingKey, VerifyingKey};
+use ark_relations::utils::matrix::Matrix;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use num_bigint::{BigInt, BigUint};
+use rand::{CryptoRng, RngCore};
+use std::{fs::File, io::BufReader, path::Path};
+use wasmer::Store;
+
+/// Compressed Groth16 proof length (A, C in G1 and B in G2)
+pub const PROOF_LEN: usize = 128;
+
+/// A proof with the public inputs it was made for
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GeneratedProof {
+    /// Compressed arkworks encoding, as the program deserializes it
+    pub proof: [u8; PROOF_LEN],
+    /// Public inputs in circuit order, each a little-endian field element
+    pub public_inputs: Vec<FieldBytes>,
+}
+
+impl GeneratedProof {
+    /// Public inputs as the instruction builders take them
+    pub fn public_input_vecs(&self) -> Vec<Vec<u8>> {
+        self.public_inputs.iter().map(|input| input.to_vec()).collect()
+    }
+}
+
+/// Proves one circuit from its compiled wasm and snarkjs zkey
+pub struct CircuitProver {
+    store: Store,
+    calculator: WitnessCalculator,
+    proving_key: ProvingKey<Bn254>,
+    /// A, B and C, in the order the Groth16 prover takes them
+    matrices: [Matrix<Fr>; 3],
+    num_instance_variables: usize,
+    num_constraints: usize,
+}
+
+impl CircuitProver {
+    /// Load e.g. `transfer.wasm` and `transfer_final.zkey`
+    pub fn load(wasm: impl AsRef<Path>, zkey: impl AsRef<Path>) -> Result<Self, ProverError> {
+        let mut store = Store::default();
+        let calculator = load_calculator(&mut store, wasm)?;
+        let mut reader = BufReader::new(File::open(zkey)?);
+        let (proving_key, index) = read_zkey(&mut reader)?;
+        Ok(CircuitProver {
+            store,
+            calculator,
+            proving_key,
+            matrices: [index.a, index.b, index.c],
+            num_instance_variables: index.num_instance_variables,
+            num_constraints: index.num_constraints,
+        })
+    }
+
+    pub fn verifying_key(&self) -> &VerifyingKey<Bn254> {
+        &self.proving_key.vk
+    }
+
+    /// Verification key as StoreVerificationKey takes it
+    pub fn verifying_key_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.proving_key.vk.serialize_compressed(&mut bytes).expect("serializing to a Vec");
+        bytes
+    }
+
+    /// Number of public inputs, not counting the constant 1
+    pub fn num_public_inputs(&self) -> usize {
+        self.num_instance_variables - 1
+    }
+
+    pub fn prove<I: CircuitInputs>(&mut self, inputs: &I) -> Result<GeneratedProof, ProverError> {
+        self.prove_with_rng(inputs, &mut rand::thread_rng())
+    }
+
+    pub fn prove_with_rng<I: CircuitInputs, R: RngCore + CryptoRng>(
+        &mut self,
+        inputs: &I,
+        rng: &mut R,
+    ) -> Result<GeneratedProof, ProverError> {
+        if I::NUM_PUBLIC_INPUTS != self.num_public_inputs() {
+            return Err(ProverError::CircuitMismatch { expected: I::NUM_PUBLIC_INPUTS, got: self.num_public_inputs() });
+        }
+
+        let signals = inputs.signals();
+        let modulus = BigInt::from(BigUint::from(Fr::MODULUS));
+        for (name, values) in &signals {
+            // The calculator would silently reduce these; the verifier would not
+            if values.iter().any(|value| value >= &modulus) {
+                return Err(ProverError::NonCanonicalField(name.clone()));
+            }
+        }
+
+        let assignment = self
+            .calculator
+            .calculate_witness_element::<Fr, _>(&mut self.store, signals, true)
+            .map_err(|e| ProverError::Witness(format!("{e:#}")))?;
+
+        let r = Fr::rand(rng);
+        let s = Fr::rand(rng);
+        let proof = Groth16::<Bn254, CircomReduction>::create_proof_with_reduction_and_matrices(
+            &self.proving_key,
+            r,
+            s,
+            &self.matrices,
+            self.num_instance_variables,
+            self.num_constraints,
+            &assignment,
+        )?;
+
+        let mut proof_bytes = [0u8; PROOF_LEN];
+        proof.serialize_compressed(&mut proof_bytes[..])?;
+        let public_inputs = assignment[1..self.num_instance_variables].iter().map(field_bytes).collect();
+        Ok(GeneratedProof { proof: proof_bytes, public_inputs })
+    }
+
+    /// Check a proof against this circuit's verification key
+    pub fn verify(&self, proof: &GeneratedProof) -> Result<bool, ProverError> {
+        let parsed = Proof::<Bn254>::deserialize_compressed(&proof.proof[..])?;
+        let public_inputs = proof
+            .public_inputs
+            .iter()
+            .map(|input| Fr::deserialize_compressed(&input[..]))
+            .collect::<Result<Vec<_>, _>>()?;
+        let pvk = ark_groth16::prepare_verifying_key(&self.proving_key.vk);
+        Ok(Groth16::<Bn254>::verify_proof(&pvk, &parsed, &public_inputs)?)
+    }
+}
+
+fn field_bytes(value: &Fr) -> FieldBytes {
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(&value.into_bigint().to_bytes_le());
+    bytes
+}
@@ -0,0 +1,43 @@
+use crate::error::ProverError;
+use ark_circom::{Wasm, WitnessCalculator};
+use std::path::Path;
+use wasmer::{imports, Function, Instance, Memory, MemoryType, Module, RuntimeError, Store};
+
+/// Witness calculator for a circom 2 wasm that fails on unsatisfied asserts
+///
+/// ark-circom's own runtime ignores `exceptionHandler`, so a failed
+/// `===` or `assert` still returns a witness and only proving or
+/// verification would notice. snarkjs throws there; so do we.
+pub fn load_calculator(store: &mut Store, wasm: impl AsRef<Path>) -> Result<WitnessCalculator, ProverError> {
+    let module = Module::from_file(&*store, wasm).map_err(|e| ProverError::Witness(e.to_string()))?;
+    let memory = Memory::new(store, MemoryType::new(2000, None, false)).map_err(|e| ProverError::Witness(e.to_string()))?;
+    let imports = imports! {
+        "env" => {
+            "memory" => memory,
+        },
+        "runtime" => {
+            "error" => Function::new_typed(store, runtime_error),
+            "exceptionHandler" => Function::new_typed(store, exception_handler),
+            "logSetSignal" => Function::new_typed(store, |_: i32, _: i32| {}),
+            "logGetSignal" => Function::new_typed(store, |_: i32, _: i32| {}),
+            "logFinishComponent" => Function::new_typed(store, |_: i32| {}),
+            "logStartComponent" => Function::new_typed(store, |_: i32| {}),
+            "log" => Function::new_typed(store, |_: i32| {}),
+            "showSharedRWMemory" => Function::new_typed(store, || {}),
+            "printErrorMessage" => Function::new_typed(store, || {}),
+            "writeBufferMessage" => Function::new_typed(store, || {}),
+        }
+    };
+    let instance = Instance::new(store, &module, &imports).map_err(|e| ProverError::Witness(e.to_string()))?;
+    WitnessCalculator::new_from_wasm(store, Wasm::new(instance)).map_err(|e| ProverError::Witness(format!("{e:#}")))
+}
+
+/// Circom 1 runtime error
+fn runtime_error(code: i32, _: i32, _: i32, _: i32, _: i32, _: i32) -> Result<(), RuntimeError> {
+    Err(RuntimeError::new(format!("circuit runtime error {code}")))
+}
+
+/// Circom 2 failed assert (code 1), bad signal access or similar
+fn exception_handler(code: i32) -> Result<(), RuntimeError> {
+    Err(RuntimeError::new(format!("circuit exception {code}")))
+}
@@ -0,0 +1,116 @@
+//! End-to-end proofs with the balance circuit artifacts the dashboard ships
+
+use ark_bn254::{Bn254, Fr};
+use ark_ff::{BigInteger, PrimeField};
+use ark_groth16::{Proof, VerifyingKey};
+use ark_serialize::CanonicalDeserialize;
+use light_poseidon::{Poseidon, PoseidonBytesHasher};
+use rand::{rngs::StdRng, SeedableRng};
+use shadow_privacy_prover::{
+    BalanceInputs, CircuitProver, FieldBytes, ProverError, TransferInputs, TRANSFER_LEVELS,
+};
+use std::path::PathBuf;
+
+fn circuits_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../web-dashboard/public/circuits")
+}
+
+fn balance_prover() -> CircuitProver {
+    let dir = circuits_dir();
+    CircuitProver::load(dir.join("balance.wasm"), dir.join("balance_final.zkey")).unwrap()
+}
+
+fn poseidon(inputs: &[Fr]) -> Fr {
+    let bytes: Vec<FieldBytes> = inputs.iter().map(|&input| field_bytes(input)).collect();
+    let refs: Vec<&[u8]> = bytes.iter().map(|input| &input[..]).collect();
+    let hash = Poseidon::<ark_bn254_v05::Fr>::new_circom(inputs.len()).unwrap().hash_bytes_le(&refs).unwrap();
+    Fr::from_le_bytes_mod_order(&hash)
+}
+
+fn field_bytes(value: Fr) -> FieldBytes {
+    value.into_bigint().to_bytes_le().try_into().unwrap()
+}
+
+fn balance_inputs(actual_balance: u64, min_balance: u64) -> BalanceInputs {
+    let private_key = Fr::from(123_456_789u64);
+    let balance_nonce = Fr::from(42u64);
+    let commitment = poseidon(&[poseidon(&[private_key]), Fr::from(actual_balance), balance_nonce]);
+    BalanceInputs {
+        min_balance,
+        balance_commitment: field_bytes(commitment),
+        actual_balance,
+        balance_nonce: field_bytes(balance_nonce),
+        private_key: field_bytes(private_key),
+    }
+}
+
+#[test]
+fn test_balance_proof_verifies() {
+    let mut prover = balance_prover();
+    let inputs = balance_inputs(5_000, 1_000);
+    let proof = prover.prove_with_rng(&inputs, &mut StdRng::seed_from_u64(1)).unwrap();
+
+    assert_eq!(proof.public_inputs, vec![field_bytes(Fr::from(1_000u64)), inputs.balance_commitment]);
+    assert!(prover.verify(&proof).unwrap());
+}
+
+#[test]
+fn test_output_matches_program_encoding() {
+    let mut prover = balance_prover();
+    let proof = prover.prove(&balance_inputs(1_000, 1_000)).unwrap();
+
+    // The decoding verifier.rs applies to VerifyBalance and StoreVerificationKey data
+    let vk = VerifyingKey::<Bn254>::deserialize_compressed(&prover.verifying_key_bytes()[..]).unwrap();
+    let parsed = Proof::<Bn254>::deserialize_compressed(&proof.proof[..]).unwrap();
+    let public_inputs: Vec<Fr> = proof
+        .public_input_vecs()
+        .iter()
+        .map(|input| Fr::deserialize_compressed(&input[..]).unwrap())
+        .collect();
+    let pvk = ark_groth16::prepare_verifying_key(&vk);
+    assert!(ark_groth16::Groth16::<Bn254>::verify_proof(&pvk, &parsed, &public_inputs).unwrap());
+}
+
+#[test]
+fn test_insufficient_balance_has_no_witness() {
+    let mut prover = balance_prover();
+    let result = prover.prove(&balance_inputs(999, 1_000));
+    assert!(matches!(result, Err(ProverError::Witness(_))));
+}
+
+#[test]
+fn test_wrong_commitment_has_no_witness() {
+    let mut prover = balance_prover();
+    let mut inputs = balance_inputs(5_000, 1_000);
+    inputs.balance_commitment[0] ^= 1;
+    assert!(matches!(prover.prove(&inputs), Err(ProverError::Witness(_))));
+}
+
+#[test]
+fn test_non_canonical_field_rejected() {
+    let mut prover = balance_prover();
+    let mut inputs = balance_inputs(5_000, 1_000);
+    inputs.balance_nonce = [0xff; 32];
+    assert!(matches!(prover.prove(&inputs), Err(ProverError::NonCanonicalField(name)) if name == "balanceNonce"));
+}
+
+#[test]
+fn test_inputs_for_another_circuit_rejected() {
+    let mut prover = balance_prover();
+    let inputs = TransferInputs {
+        root: [0; 32],
+        nullifier: [0; 32],
+        new_commitment: [0; 32],
+        amount: 1,
+        private_key: [0; 32],
+        path_elements: [[0; 32]; TRANSFER_LEVELS],
+        path_indices: [0; TRANSFER_LEVELS],
+        recipient_public_key: [0; 32],
+        nonce: [0; 32],
+        old_nonce: [0; 32],
+    };
+    assert!(matches!(
+        prover.prove(&inputs),
+        Err(ProverError::CircuitMismatch { expected: 3, got: 2 })
+    ));
+}
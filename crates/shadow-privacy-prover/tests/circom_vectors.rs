@@ -0,0 +1,82 @@
+//! The Poseidon/Merkle golden vectors of shadow-privacy-merkle, run through
+//! the circuits' own witness generators
+//!
+//! Witness generation traps on any failed `===`, so a vector is accepted only
+//! if circom computes the same commitments, nullifier and root from it.
+
+use ark_bn254::Fr;
+use num_bigint::BigInt;
+use serde_json::Value;
+use shadow_privacy_prover::witness::load_calculator;
+use std::path::PathBuf;
+use wasmer::Store;
+
+fn vectors() -> Value {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../shadow-privacy-merkle/tests/vectors/circom.json");
+    serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+fn circuit(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(format!("../../web-dashboard/public/circuits/{name}.wasm"))
+}
+
+/// circom input JSON to witness calculator signals
+fn signals(input: &Value) -> Vec<(String, Vec<BigInt>)> {
+    let number = |value: &Value| value.as_str().unwrap().parse::<BigInt>().unwrap();
+    input
+        .as_object()
+        .unwrap()
+        .iter()
+        .map(|(name, value)| {
+            let values = match value.as_array() {
+                Some(values) => values.iter().map(number).collect(),
+                None => vec![number(value)],
+            };
+            (name.clone(), values)
+        })
+        .collect()
+}
+
+fn witness(circuit_name: &str, input: &Value) -> Result<Vec<Fr>, String> {
+    let mut store = Store::default();
+    let mut calculator = load_calculator(&mut store, circuit(circuit_name)).unwrap();
+    calculator
+        .calculate_witness_element::<Fr, _>(&mut store, signals(input), true)
+        .map_err(|e| format!("{e:#}"))
+}
+
+#[test]
+fn test_transfer_vector_accepted_by_circuit() {
+    let input = &vectors()["transfer"]["input"];
+    let witness = witness("transfer", input).unwrap();
+
+    let public: Vec<Fr> = ["root", "nullifier", "newCommitment"]
+        .iter()
+        .map(|name| input[name].as_str().unwrap().parse().unwrap())
+        .collect();
+    assert_eq!(witness[1..4], public[..]);
+}
+
+#[test]
+fn test_transfer_vector_tampering_rejected() {
+    let vectors = vectors();
+    for field in ["root", "nullifier", "newCommitment"] {
+        let mut input = vectors["transfer"]["input"].clone();
+        input[field] = Value::from("1");
+        assert!(witness("transfer", &input).is_err(), "{field}");
+    }
+
+    let mut input = vectors["transfer"]["input"].clone();
+    input["pathIndices"][0] = Value::from("0");
+    assert!(witness("transfer", &input).is_err());
+}
+
+#[test]
+fn test_balance_vector_accepted_by_circuit() {
+    let vectors = vectors();
+    assert!(witness("balance", &vectors["balance"]["input"]).is_ok());
+
+    let mut input = vectors["balance"]["input"].clone();
+    input["balanceNonce"] = Value::from("43");
+    assert!(witness("balance", &input).is_err());
+}
@@ -0,0 +1,41 @@
+//! Sync checkpoints
+//!
+//! A checkpoint is the scanner's state without its keys: the leaves of every
+//! pool tree, the wallet's notes and the last transaction applied. Wallets
+//! persist it after each sync and resume from `last_synced.slot`. It reveals
+//! the wallet's notes, so store it like the wallet itself.
+
+use crate::{error::ScanError, scanner::{OwnedNote, TxRef}};
+use borsh::{BorshDeserialize, BorshSerialize};
+use shadow_privacy_merkle::FieldBytes;
+use solana_program::pubkey::Pubkey;
+
+/// Current checkpoint layout
+pub const CHECKPOINT_VERSION: u8 = 1;
+
+/// Leaves of one pool's commitment tree, in order
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TreeCheckpoint {
+    pub pool: Pubkey,
+    pub depth: u8,
+    pub leaves: Vec<FieldBytes>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub version: u8,
+    pub program_id: Pubkey,
+    pub last_synced: Option<TxRef>,
+    pub trees: Vec<TreeCheckpoint>,
+    pub notes: Vec<OwnedNote>,
+}
+
+impl Checkpoint {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.try_to_vec().expect("checkpoints serialize into memory")
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ScanError> {
+        Checkpoint::try_from_slice(bytes).map_err(|error| ScanError::Checkpoint(error.to_string()))
+    }
+}
@@ -0,0 +1,249 @@
+//! Compact transactions for light wallets
+//!
+//! A server holding full transactions (an RPC node or an indexer) reduces
+//! each one to the pools, leaves, notes and nullifiers it touched, and serves
+//! those instead. With [`NoteDetail::Compact`] memo parts are dropped too: the
+//! value part alone decrypts to amount, nonce and asset ID, so a wallet only
+//! fetches the full transaction for notes it owns and wants the memo of.
+//!
+//! Compact transactions are Borsh-encoded on the wire.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use shadow_privacy::PrivacyEvent;
+use shadow_privacy_indexer::{logs::parse_events, LedgerTransaction};
+use solana_program::pubkey::Pubkey;
+
+/// How much of each note to keep
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NoteDetail {
+    /// Value and memo parts
+    #[default]
+    Full,
+    /// Value part only
+    Compact,
+}
+
+/// A pool created in the transaction
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CompactPool {
+    pub pool: Pubkey,
+    pub tree_depth: u8,
+}
+
+/// An encrypted note, in the two parts `NoteCreated` publishes
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CompactNote {
+    pub encrypted_value: Vec<u8>,
+    /// Empty when the note has no memo or the memo was dropped
+    pub encrypted_memo: Vec<u8>,
+}
+
+impl CompactNote {
+    /// The note ciphertext as the sender encoded it
+    pub fn ciphertext(&self) -> Vec<u8> {
+        [&self.encrypted_value[..], &self.encrypted_memo[..]].concat()
+    }
+}
+
+/// A commitment appended to a pool or asset
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CompactLeaf {
+    /// Pool, or asset state for asset notes
+    pub account: Pubkey,
+    /// Zero for pool leaves
+    pub asset_id: [u8; 32],
+    pub index: u64,
+    pub commitment: [u8; 32],
+    /// None for leaves published without a note (Withdraw change)
+    pub note: Option<CompactNote>,
+}
+
+impl CompactLeaf {
+    pub fn is_pool_leaf(&self) -> bool {
+        self.asset_id == [0u8; 32]
+    }
+}
+
+/// A nullifier spent in a pool or asset
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CompactSpend {
+    pub account: Pubkey,
+    pub nullifier: [u8; 32],
+}
+
+/// What a wallet needs from one transaction
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CompactTransaction {
+    pub slot: u64,
+    pub signature: String,
+    pub pools: Vec<CompactPool>,
+    /// Leaves in program order
+    pub leaves: Vec<CompactLeaf>,
+    pub spends: Vec<CompactSpend>,
+}
+
+impl CompactTransaction {
+    pub fn is_empty(&self) -> bool {
+        self.pools.is_empty() && self.leaves.is_empty() && self.spends.is_empty()
+    }
+}
+
+/// Reduce `tx` to its compact form
+///
+/// Returns None for failed transactions and ones that touched no pool or
+/// asset tree.
+pub fn compact_transaction(
+    program_id: &Pubkey,
+    tx: &LedgerTransaction,
+    detail: NoteDetail,
+) -> Option<CompactTransaction> {
+    if !tx.success {
+        return None;
+    }
+
+    let mut compact = CompactTransaction {
+        slot: tx.slot,
+        signature: tx.signature.clone(),
+        pools: Vec::new(),
+        leaves: Vec::new(),
+        spends: Vec::new(),
+    };
+    let pool_leaf = |account: Pubkey, index: u64, commitment: [u8; 32]| CompactLeaf {
+        account,
+        asset_id: [0u8; 32],
+        index,
+        commitment,
+        note: None,
+    };
+
+    for event in parse_events(program_id, &tx.logs).events {
+        match event {
+            PrivacyEvent::PoolInitialized { pool, tree_depth, .. } => {
+                compact.pools.push(CompactPool { pool, tree_depth });
+            }
+            PrivacyEvent::Deposit { pool, leaf, .. } | PrivacyEvent::PrivateTransfer { pool, leaf, .. } => {
+                compact.leaves.push(pool_leaf(pool, leaf.index, leaf.commitment));
+            }
+            PrivacyEvent::Withdraw {
+                pool,
+                nullifier,
+                change,
+                ..
+            } => {
+                compact.spends.push(CompactSpend { account: pool, nullifier });
+                if let Some(leaf) = change {
+                    compact.leaves.push(pool_leaf(pool, leaf.index, leaf.commitment));
+                }
+            }
+            PrivacyEvent::AssetTransferred { asset, nullifier, .. } => {
+                compact.spends.push(CompactSpend { account: asset, nullifier });
+            }
+            PrivacyEvent::NoteCreated {
+                account,
+                leaf,
+                asset_id,
+                encrypted_value,
+                encrypted_memo,
+                ..
+            } => {
+                let note = CompactNote {
+                    encrypted_value,
+                    encrypted_memo: match detail {
+                        NoteDetail::Full => encrypted_memo,
+                        NoteDetail::Compact => Vec::new(),
+                    },
+                };
+                // Pool notes follow the event that appended their leaf; asset
+                // notes are the only record of theirs
+                let existing = compact
+                    .leaves
+                    .iter_mut()
+                    .rev()
+                    .find(|existing| existing.account == account && existing.index == leaf.index);
+                match existing {
+                    Some(existing) => existing.note = Some(note),
+                    None => compact.leaves.push(CompactLeaf {
+                        account,
+                        asset_id,
+                        index: leaf.index,
+                        commitment: leaf.commitment,
+                        note: Some(note),
+                    }),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    (!compact.is_empty()).then_some(compact)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{ledger_transaction, note_created};
+    use shadow_privacy::TreeLeaf;
+
+    #[test]
+    fn test_notes_attach_to_their_leaves() {
+        let program_id = Pubkey::new_unique();
+        let pool = Pubkey::new_unique();
+        let asset = Pubkey::new_unique();
+        let leaf = TreeLeaf { index: 4, commitment: [1u8; 32] };
+        let asset_leaf = TreeLeaf { index: 0, commitment: [2u8; 32] };
+
+        let events = [
+            PrivacyEvent::Deposit {
+                pool,
+                depositor: Pubkey::new_unique(),
+                leaf,
+                root: [0u8; 32],
+                amount: 1,
+            },
+            note_created(pool, leaf, [0u8; 32], vec![5u8; 121], vec![6u8; 20]),
+            PrivacyEvent::AssetTransferred {
+                asset,
+                nullifier: [3u8; 32],
+                commitment: asset_leaf.commitment,
+                note_count: 1,
+            },
+            note_created(asset, asset_leaf, [4u8; 32], vec![7u8; 121], Vec::new()),
+        ];
+        let tx = ledger_transaction(&program_id, 9, true, &events);
+
+        let full = compact_transaction(&program_id, &tx, NoteDetail::Full).unwrap();
+        assert_eq!(full.slot, 9);
+        assert_eq!(full.leaves.len(), 2);
+        assert!(full.leaves[0].is_pool_leaf());
+        assert_eq!(full.leaves[0].note.as_ref().unwrap().ciphertext(), [vec![5u8; 121], vec![6u8; 20]].concat());
+        assert!(!full.leaves[1].is_pool_leaf());
+        assert_eq!(full.leaves[1].asset_id, [4u8; 32]);
+        assert_eq!(full.spends, vec![CompactSpend { account: asset, nullifier: [3u8; 32] }]);
+
+        // Compact mode keeps everything but memos
+        let compact = compact_transaction(&program_id, &tx, NoteDetail::Compact).unwrap();
+        assert!(compact.leaves[0].note.as_ref().unwrap().encrypted_memo.is_empty());
+        assert_eq!(compact.leaves[1], full.leaves[1]);
+
+        let bytes = compact.try_to_vec().unwrap();
+        assert_eq!(CompactTransaction::try_from_slice(&bytes).unwrap(), compact);
+    }
+
+    #[test]
+    fn test_failed_and_unrelated_transactions_skipped() {
+        let program_id = Pubkey::new_unique();
+        let deposit = PrivacyEvent::Deposit {
+            pool: Pubkey::new_unique(),
+            depositor: Pubkey::new_unique(),
+            leaf: TreeLeaf { index: 0, commitment: [1u8; 32] },
+            root: [0u8; 32],
+            amount: 1,
+        };
+        let heartbeat = PrivacyEvent::RelayerHeartbeat { relayer: Pubkey::new_unique(), timestamp: 1 };
+
+        let failed = ledger_transaction(&program_id, 1, false, &[deposit]);
+        assert!(compact_transaction(&program_id, &failed, NoteDetail::Full).is_none());
+        let unrelated = ledger_transaction(&program_id, 2, true, &[heartbeat]);
+        assert!(compact_transaction(&program_id, &unrelated, NoteDetail::Full).is_none());
+    }
+}
@@ -0,0 +1,25 @@
+use shadow_privacy_indexer::IndexerError;
+use shadow_privacy_merkle::MerkleError;
+use solana_program::pubkey::Pubkey;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ScanError {
+    #[error("Ledger source error: {0}")]
+    Source(#[from] IndexerError),
+
+    #[error("Merkle tree error: {0}")]
+    Merkle(#[from] MerkleError),
+
+    #[error("{account}: expected leaf {expected}, got {got} (missing transactions?)")]
+    LeafGap { account: Pubkey, expected: u64, got: u64 },
+
+    #[error("{account}: leaf {index} differs from the one already synced")]
+    LeafMismatch { account: Pubkey, index: u64 },
+
+    #[error("{0} has no commitment tree (asset notes have no Merkle path)")]
+    NoTree(Pubkey),
+
+    #[error("Invalid checkpoint: {0}")]
+    Checkpoint(String),
+}
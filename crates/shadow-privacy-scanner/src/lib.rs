@@ -0,0 +1,27 @@
+// Shadow Privacy Scanner
+//
+// Recovers a wallet's notes from chain data and its keys alone:
+// - Trial decryption of every `NoteCreated` note with the viewing key,
+//   accepting only notes that open their commitment under the wallet's key
+// - Poseidon commitment tree per pool, for Merkle paths to owned notes
+// - Spend detection by recomputing each owned note's nullifier
+// - Borsh checkpoints for incremental sync
+// - Compact transactions (leaves, notes and nullifiers only, optionally
+//   without memos) that a server can hand to light wallets
+//
+// Full transactions come from any indexer `LedgerSource`.
+
+pub mod checkpoint;
+pub mod compact;
+pub mod error;
+pub mod scanner;
+
+#[cfg(test)]
+mod test_utils;
+
+pub use checkpoint::{Checkpoint, TreeCheckpoint, CHECKPOINT_VERSION};
+pub use compact::{
+    compact_transaction, CompactLeaf, CompactNote, CompactPool, CompactSpend, CompactTransaction, NoteDetail,
+};
+pub use error::ScanError;
+pub use scanner::{OwnedNote, ScanStats, Scanner, TxRef, WalletKeys};
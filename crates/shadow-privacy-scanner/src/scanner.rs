@@ -0,0 +1,577 @@
+//! Trial-decrypting wallet scanner
+//!
+//! The scanner replays pool and asset activity and keeps:
+//! - a Poseidon commitment tree per pool, so owned pool notes have Merkle paths
+//! - every note that decrypts with the wallet's viewing key and opens its
+//!   commitment under the wallet's public key
+//! - each owned note's nullifier, to notice when the note is spent
+//!
+//! Ring-signature transfers publish a key image rather than a nullifier, so
+//! they do not mark notes spent.
+
+use crate::{
+    checkpoint::{Checkpoint, TreeCheckpoint, CHECKPOINT_VERSION},
+    compact::{compact_transaction, CompactLeaf, CompactTransaction, NoteDetail},
+    error::ScanError,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use shadow_privacy_indexer::{LedgerSource, LedgerTransaction};
+use shadow_privacy_merkle::{
+    commitment, is_canonical, nullifier, public_key, tree_leaf, FieldBytes, MerklePath, SparseMerkleTree, TREE_DEPTH,
+};
+use shadow_privacy_note::{decrypt, ViewingKey};
+use solana_program::pubkey::Pubkey;
+use std::collections::{btree_map::Entry, BTreeMap, HashMap};
+
+/// The keys a wallet scans with
+#[derive(Debug, Clone)]
+pub struct WalletKeys {
+    /// Decrypts notes sent to the wallet
+    pub viewing_key: ViewingKey,
+    /// Circuit private key owning the wallet's commitments; recognises notes
+    /// and derives their nullifiers
+    pub spending_key: FieldBytes,
+}
+
+/// A transaction by slot and signature
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TxRef {
+    pub slot: u64,
+    pub signature: String,
+}
+
+/// A note the wallet owns
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct OwnedNote {
+    /// Pool, or asset state for asset notes
+    pub account: Pubkey,
+    /// Zero for pool notes
+    pub asset_id: [u8; 32],
+    /// Position in the pool tree (or among the asset's notes)
+    pub leaf_index: u64,
+    pub commitment: FieldBytes,
+    pub amount: u64,
+    pub nonce: FieldBytes,
+    /// Empty when the note has no memo or it was scanned in compact mode
+    pub memo: Vec<u8>,
+    pub nullifier: FieldBytes,
+    pub created: TxRef,
+    pub spent: Option<TxRef>,
+}
+
+impl OwnedNote {
+    pub fn is_spent(&self) -> bool {
+        self.spent.is_some()
+    }
+}
+
+/// Counters from one sync
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ScanStats {
+    pub transactions: usize,
+    pub leaves: usize,
+    pub notes_found: usize,
+    pub spends_found: usize,
+    /// Notes that decrypted but did not open their commitment (forged or mis-sent)
+    pub invalid_notes: usize,
+    /// Commitments at or above the field modulus, kept as empty leaves
+    pub non_canonical_leaves: usize,
+}
+
+/// Wallet state rebuilt from chain data and a pair of keys
+pub struct Scanner {
+    program_id: Pubkey,
+    keys: WalletKeys,
+    public_key: FieldBytes,
+    trees: BTreeMap<Pubkey, SparseMerkleTree>,
+    notes: Vec<OwnedNote>,
+    /// Nullifier -> position in `notes`
+    nullifiers: HashMap<FieldBytes, usize>,
+    last_synced: Option<TxRef>,
+}
+
+impl Scanner {
+    pub fn new(program_id: Pubkey, keys: WalletKeys) -> Result<Self, ScanError> {
+        Ok(Scanner {
+            program_id,
+            public_key: public_key(&keys.spending_key)?,
+            keys,
+            trees: BTreeMap::new(),
+            notes: Vec::new(),
+            nullifiers: HashMap::new(),
+            last_synced: None,
+        })
+    }
+
+    /// Resume from a checkpoint taken with the same keys
+    pub fn restore(keys: WalletKeys, checkpoint: Checkpoint) -> Result<Self, ScanError> {
+        if checkpoint.version != CHECKPOINT_VERSION {
+            return Err(ScanError::Checkpoint(format!("unsupported version {}", checkpoint.version)));
+        }
+
+        let mut scanner = Scanner::new(checkpoint.program_id, keys)?;
+        for tree in checkpoint.trees {
+            let mut rebuilt = SparseMerkleTree::new(tree.depth)?;
+            for leaf in tree.leaves {
+                rebuilt.insert(leaf)?;
+            }
+            scanner.trees.insert(tree.pool, rebuilt);
+        }
+        for note in checkpoint.notes {
+            if note.nullifier != nullifier(&note.commitment, &scanner.keys.spending_key)? {
+                return Err(ScanError::Checkpoint("taken with another spending key".to_string()));
+            }
+            scanner.nullifiers.insert(note.nullifier, scanner.notes.len());
+            scanner.notes.push(note);
+        }
+        scanner.last_synced = checkpoint.last_synced;
+        Ok(scanner)
+    }
+
+    /// Everything needed to resume scanning; holds no keys, but does hold the
+    /// wallet's notes
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            version: CHECKPOINT_VERSION,
+            program_id: self.program_id,
+            last_synced: self.last_synced.clone(),
+            trees: self
+                .trees
+                .iter()
+                .map(|(pool, tree)| TreeCheckpoint {
+                    pool: *pool,
+                    depth: tree.depth(),
+                    leaves: (0..tree.len()).map(|index| tree.leaf(index)).collect(),
+                })
+                .collect(),
+            notes: self.notes.clone(),
+        }
+    }
+
+    pub fn program_id(&self) -> &Pubkey {
+        &self.program_id
+    }
+
+    /// Last transaction applied; resume the ledger source from its slot
+    ///
+    /// Replaying transactions already applied is harmless.
+    pub fn last_synced(&self) -> Option<&TxRef> {
+        self.last_synced.as_ref()
+    }
+
+    pub fn notes(&self) -> &[OwnedNote] {
+        &self.notes
+    }
+
+    pub fn unspent_notes(&self) -> impl Iterator<Item = &OwnedNote> {
+        self.notes.iter().filter(|note| !note.is_spent())
+    }
+
+    /// Unspent total in a pool or asset
+    pub fn balance(&self, account: &Pubkey) -> u64 {
+        self.unspent_notes()
+            .filter(|note| note.account == *account)
+            .map(|note| note.amount)
+            .sum()
+    }
+
+    /// Root of the pool's commitment tree as synced so far
+    pub fn root(&self, pool: &Pubkey) -> Option<FieldBytes> {
+        self.trees.get(pool).map(SparseMerkleTree::root)
+    }
+
+    /// Path from a pool note to the current root, as the Transfer circuit takes it
+    pub fn merkle_path(&self, note: &OwnedNote) -> Result<MerklePath, ScanError> {
+        let tree = self.trees.get(&note.account).ok_or(ScanError::NoTree(note.account))?;
+        Ok(tree.path(note.leaf_index)?)
+    }
+
+    /// Drain `source`, trial-decrypting every note in it
+    pub fn sync<S: LedgerSource + ?Sized>(&mut self, source: &mut S) -> Result<ScanStats, ScanError> {
+        let mut stats = ScanStats::default();
+        while let Some(tx) = source.next_transaction()? {
+            self.apply_transaction(&tx, &mut stats)?;
+        }
+        Ok(stats)
+    }
+
+    /// Apply one full transaction
+    pub fn scan_transaction(&mut self, tx: &LedgerTransaction) -> Result<ScanStats, ScanError> {
+        let mut stats = ScanStats::default();
+        self.apply_transaction(tx, &mut stats)?;
+        Ok(stats)
+    }
+
+    /// Apply compact transactions from a server, oldest first
+    pub fn sync_compact<'a, I>(&mut self, transactions: I) -> Result<ScanStats, ScanError>
+    where
+        I: IntoIterator<Item = &'a CompactTransaction>,
+    {
+        let mut stats = ScanStats::default();
+        for tx in transactions {
+            stats.transactions += 1;
+            self.apply(tx, &mut stats)?;
+        }
+        Ok(stats)
+    }
+
+    fn apply_transaction(&mut self, tx: &LedgerTransaction, stats: &mut ScanStats) -> Result<(), ScanError> {
+        stats.transactions += 1;
+        match compact_transaction(&self.program_id, tx, NoteDetail::Full) {
+            Some(compact) => self.apply(&compact, stats),
+            None => {
+                self.last_synced = Some(TxRef { slot: tx.slot, signature: tx.signature.clone() });
+                Ok(())
+            }
+        }
+    }
+
+    fn apply(&mut self, tx: &CompactTransaction, stats: &mut ScanStats) -> Result<(), ScanError> {
+        let created = TxRef { slot: tx.slot, signature: tx.signature.clone() };
+
+        for pool in &tx.pools {
+            if let Entry::Vacant(entry) = self.trees.entry(pool.pool) {
+                entry.insert(SparseMerkleTree::new(pool.tree_depth)?);
+            }
+        }
+
+        for leaf in &tx.leaves {
+            if leaf.is_pool_leaf() && !self.append_leaf(leaf)? {
+                continue;
+            }
+            stats.leaves += 1;
+            if !is_canonical(&leaf.commitment) {
+                stats.non_canonical_leaves += 1;
+                continue;
+            }
+
+            let owned = self
+                .notes
+                .iter()
+                .any(|note| note.account == leaf.account && note.leaf_index == leaf.index);
+            if !owned {
+                self.trial_decrypt(leaf, &created, stats)?;
+            }
+        }
+
+        for spend in &tx.spends {
+            let Some(&position) = self.nullifiers.get(&spend.nullifier) else {
+                continue;
+            };
+            let note = &mut self.notes[position];
+            if note.account == spend.account && note.spent.is_none() {
+                note.spent = Some(created.clone());
+                stats.spends_found += 1;
+            }
+        }
+
+        self.last_synced = Some(created);
+        Ok(())
+    }
+
+    /// Append a pool leaf; false if it was already synced
+    fn append_leaf(&mut self, leaf: &CompactLeaf) -> Result<bool, ScanError> {
+        let tree = match self.trees.entry(leaf.account) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            // Pool created before PoolInitialized was emitted
+            Entry::Vacant(entry) => entry.insert(SparseMerkleTree::new(TREE_DEPTH as u8)?),
+        };
+
+        let value = tree_leaf(&leaf.commitment);
+        if leaf.index < tree.len() {
+            return match tree.leaf(leaf.index) == value {
+                true => Ok(false),
+                false => Err(ScanError::LeafMismatch { account: leaf.account, index: leaf.index }),
+            };
+        }
+        if leaf.index != tree.len() {
+            return Err(ScanError::LeafGap {
+                account: leaf.account,
+                expected: tree.len(),
+                got: leaf.index,
+            });
+        }
+        tree.insert(value)?;
+        Ok(true)
+    }
+
+    fn trial_decrypt(&mut self, leaf: &CompactLeaf, created: &TxRef, stats: &mut ScanStats) -> Result<(), ScanError> {
+        let Some(note) = &leaf.note else {
+            return Ok(());
+        };
+        // Most notes belong to someone else
+        let Ok(plaintext) = decrypt(&self.keys.viewing_key, &note.ciphertext()) else {
+            return Ok(());
+        };
+
+        // A note only counts if it opens the commitment under our key
+        let opens = commitment(&self.public_key, plaintext.amount, &plaintext.nonce).ok() == Some(leaf.commitment);
+        if !opens || plaintext.asset_id != leaf.asset_id {
+            stats.invalid_notes += 1;
+            return Ok(());
+        }
+
+        let nullifier = nullifier(&leaf.commitment, &self.keys.spending_key)?;
+        self.nullifiers.insert(nullifier, self.notes.len());
+        self.notes.push(OwnedNote {
+            account: leaf.account,
+            asset_id: leaf.asset_id,
+            leaf_index: leaf.index,
+            commitment: leaf.commitment,
+            amount: plaintext.amount,
+            nonce: plaintext.nonce,
+            memo: plaintext.memo,
+            nullifier,
+            created: created.clone(),
+            spent: None,
+        });
+        stats.notes_found += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use shadow_privacy::{PrivacyEvent, TreeLeaf};
+    use shadow_privacy_merkle::field_from_u64;
+    use std::collections::VecDeque;
+
+    const AMOUNT: u64 = 1_000_000_000;
+
+    struct Chain {
+        program_id: Pubkey,
+        pool: Pubkey,
+        transactions: VecDeque<LedgerTransaction>,
+        slot: u64,
+        leaves: Vec<FieldBytes>,
+    }
+
+    impl Chain {
+        fn new() -> Self {
+            let program_id = Pubkey::new_unique();
+            let pool = Pubkey::new_unique();
+            let init = PrivacyEvent::PoolInitialized {
+                pool,
+                authority: Pubkey::new_unique(),
+                vault: Pubkey::new_unique(),
+                tree_depth: 20,
+                denomination: AMOUNT,
+            };
+            Chain {
+                program_id,
+                pool,
+                transactions: VecDeque::from([ledger_transaction(&program_id, 1, true, &[init])]),
+                slot: 1,
+                leaves: Vec::new(),
+            }
+        }
+
+        fn push(&mut self, events: &[PrivacyEvent]) {
+            self.slot += 1;
+            self.transactions.push_back(ledger_transaction(&self.program_id, self.slot, true, events));
+        }
+
+        fn next_leaf(&mut self, commitment: FieldBytes) -> TreeLeaf {
+            self.leaves.push(commitment);
+            TreeLeaf { index: self.leaves.len() as u64 - 1, commitment }
+        }
+
+        /// Deposit owned by `owner` with its note encrypted to `recipient`
+        fn deposit(&mut self, owner: &FieldBytes, nonce: u64, recipient: &ViewingKey, memo: &[u8]) -> TreeLeaf {
+            let leaf = self.next_leaf(commitment(owner, AMOUNT, &field_from_u64(nonce)).unwrap());
+            let (value, memo) = encrypted_note(recipient, AMOUNT, field_from_u64(nonce), [0u8; 32], memo);
+            let deposit = PrivacyEvent::Deposit {
+                pool: self.pool,
+                depositor: Pubkey::new_unique(),
+                leaf,
+                root: [0u8; 32],
+                amount: AMOUNT,
+            };
+            self.push(&[deposit, note_created(self.pool, leaf, [0u8; 32], value, memo)]);
+            leaf
+        }
+
+        fn withdraw(&mut self, nullifier: FieldBytes, change: Option<FieldBytes>) {
+            let change = change.map(|commitment| self.next_leaf(commitment));
+            let withdraw = PrivacyEvent::Withdraw {
+                pool: self.pool,
+                nullifier,
+                recipient: Pubkey::new_unique(),
+                amount: AMOUNT,
+                fee: 0,
+                record_rent: 0,
+                change,
+                root: [0u8; 32],
+                delayed: false,
+            };
+            self.push(&[withdraw]);
+        }
+
+        fn tree(&self) -> SparseMerkleTree {
+            let mut tree = SparseMerkleTree::new(20).unwrap();
+            for leaf in &self.leaves {
+                tree.insert(*leaf).unwrap();
+            }
+            tree
+        }
+    }
+
+    #[test]
+    fn test_finds_own_notes_with_paths() {
+        let (keys, public_key) = wallet(1);
+        let (other, other_public_key) = wallet(2);
+        let mut chain = Chain::new();
+        chain.deposit(&other_public_key, 10, &other.viewing_key, b"");
+        let ours = chain.deposit(&public_key, 11, &keys.viewing_key, b"salary");
+        chain.withdraw([9u8; 32], Some(field_from_u64(77)));
+
+        let mut scanner = Scanner::new(chain.program_id, keys).unwrap();
+        let stats = scanner.sync(&mut chain.transactions.clone()).unwrap();
+        assert_eq!(stats.transactions, 4);
+        assert_eq!(stats.leaves, 3);
+        assert_eq!(stats.notes_found, 1);
+        assert_eq!(stats.spends_found, 0);
+
+        let note = &scanner.notes()[0];
+        assert_eq!(note.account, chain.pool);
+        assert_eq!(note.leaf_index, ours.index);
+        assert_eq!(note.amount, AMOUNT);
+        assert_eq!(note.memo, b"salary");
+        assert_eq!(note.created.slot, 3);
+        assert_eq!(scanner.balance(&chain.pool), AMOUNT);
+
+        let tree = chain.tree();
+        assert_eq!(scanner.root(&chain.pool), Some(tree.root()));
+        let path = scanner.merkle_path(note).unwrap();
+        assert!(path.verify(&note.commitment, &tree.root()).unwrap());
+    }
+
+    #[test]
+    fn test_detects_spends() {
+        let (keys, public_key) = wallet(1);
+        let spending_key = keys.spending_key;
+        let mut chain = Chain::new();
+        let leaf = chain.deposit(&public_key, 11, &keys.viewing_key, b"");
+        chain.withdraw([9u8; 32], None);
+
+        let mut scanner = Scanner::new(chain.program_id, keys).unwrap();
+        scanner.sync(&mut chain.transactions).unwrap();
+        assert_eq!(scanner.unspent_notes().count(), 1);
+
+        chain.withdraw(nullifier(&leaf.commitment, &spending_key).unwrap(), None);
+        let stats = scanner.sync(&mut chain.transactions).unwrap();
+        assert_eq!(stats.spends_found, 1);
+        assert_eq!(scanner.notes()[0].spent.as_ref().unwrap().slot, 4);
+        assert_eq!(scanner.balance(&chain.pool), 0);
+    }
+
+    #[test]
+    fn test_forged_note_rejected() {
+        let (keys, public_key) = wallet(1);
+        let mut chain = Chain::new();
+
+        // Decrypts with our key, but claims more than the commitment holds
+        let leaf = chain.next_leaf(commitment(&public_key, 1, &field_from_u64(5)).unwrap());
+        let (value, memo) = encrypted_note(&keys.viewing_key, AMOUNT, field_from_u64(5), [0u8; 32], b"");
+        let deposit = PrivacyEvent::Deposit {
+            pool: chain.pool,
+            depositor: Pubkey::new_unique(),
+            leaf,
+            root: [0u8; 32],
+            amount: AMOUNT,
+        };
+        chain.push(&[deposit, note_created(chain.pool, leaf, [0u8; 32], value, memo)]);
+
+        let mut scanner = Scanner::new(chain.program_id, keys).unwrap();
+        let stats = scanner.sync(&mut chain.transactions).unwrap();
+        assert_eq!(stats.invalid_notes, 1);
+        assert!(scanner.notes().is_empty());
+    }
+
+    #[test]
+    fn test_non_canonical_leaf_keeps_its_place() {
+        let (keys, public_key) = wallet(1);
+        let mut chain = Chain::new();
+        let bad = chain.next_leaf([0xff; 32]);
+        let deposit = PrivacyEvent::Deposit {
+            pool: chain.pool,
+            depositor: Pubkey::new_unique(),
+            leaf: bad,
+            root: [0u8; 32],
+            amount: AMOUNT,
+        };
+        chain.push(&[deposit]);
+        let ours = chain.deposit(&public_key, 11, &keys.viewing_key, b"");
+        let all: Vec<_> = chain.transactions.iter().cloned().collect();
+
+        let mut scanner = Scanner::new(chain.program_id, keys).unwrap();
+        let stats = scanner.sync(&mut VecDeque::from(all.clone())).unwrap();
+        assert_eq!(stats.leaves, 2);
+        assert_eq!(stats.non_canonical_leaves, 1);
+        assert_eq!(scanner.notes()[0].leaf_index, ours.index);
+        assert_eq!(scanner.last_synced().unwrap().slot, 3);
+
+        let mut tree = SparseMerkleTree::new(20).unwrap();
+        tree.insert([0u8; 32]).unwrap();
+        tree.insert(ours.commitment).unwrap();
+        assert_eq!(scanner.root(&chain.pool), Some(tree.root()));
+
+        // Replaying it is harmless
+        let stats = scanner.sync(&mut VecDeque::from(all)).unwrap();
+        assert_eq!(stats.leaves, 0);
+        assert_eq!(scanner.notes().len(), 1);
+    }
+
+    #[test]
+    fn test_checkpoint_resume_matches_full_sync() {
+        let (keys, public_key) = wallet(1);
+        let mut chain = Chain::new();
+        let first = chain.deposit(&public_key, 1, &keys.viewing_key, b"");
+        chain.deposit(&public_key, 2, &keys.viewing_key, b"");
+        chain.withdraw(nullifier(&first.commitment, &keys.spending_key).unwrap(), Some(field_from_u64(3)));
+        chain.deposit(&public_key, 4, &keys.viewing_key, b"");
+        let all: Vec<_> = chain.transactions.iter().cloned().collect();
+
+        let mut full = Scanner::new(chain.program_id, keys.clone()).unwrap();
+        full.sync(&mut VecDeque::from(all.clone())).unwrap();
+
+        let mut partial = Scanner::new(chain.program_id, keys.clone()).unwrap();
+        partial.sync(&mut VecDeque::from(all[..3].to_vec())).unwrap();
+        let bytes = partial.checkpoint().to_bytes();
+        assert_eq!(partial.last_synced().unwrap().slot, 3);
+
+        // Resuming from the checkpoint's slot replays it harmlessly
+        let mut resumed = Scanner::restore(keys, Checkpoint::from_bytes(&bytes).unwrap()).unwrap();
+        let stats = resumed.sync(&mut VecDeque::from(all[2..].to_vec())).unwrap();
+        assert_eq!(stats.notes_found, 1);
+        assert_eq!(resumed.notes(), full.notes());
+        assert_eq!(resumed.root(&chain.pool), full.root(&chain.pool));
+        assert_eq!(resumed.balance(&chain.pool), 2 * AMOUNT);
+
+        // A missing transaction shows up as a gap
+        let mut gapped = Scanner::new(chain.program_id, wallet(1).0).unwrap();
+        let result = gapped.sync(&mut VecDeque::from(vec![all[0].clone(), all[2].clone()]));
+        assert!(matches!(result, Err(ScanError::LeafGap { expected: 0, got: 1, .. })));
+    }
+
+    #[test]
+    fn test_compact_sync_finds_the_same_notes() {
+        let (keys, public_key) = wallet(1);
+        let mut chain = Chain::new();
+        chain.deposit(&public_key, 1, &keys.viewing_key, b"memo");
+        chain.deposit(&public_key, 2, &wallet(2).0.viewing_key, b"");
+
+        let compact: Vec<_> = chain
+            .transactions
+            .iter()
+            .filter_map(|tx| compact_transaction(&chain.program_id, tx, NoteDetail::Compact))
+            .collect();
+        let mut scanner = Scanner::new(chain.program_id, keys).unwrap();
+        let stats = scanner.sync_compact(&compact).unwrap();
+        assert_eq!(stats.notes_found, 1);
+        assert!(scanner.notes()[0].memo.is_empty());
+        assert_eq!(scanner.root(&chain.pool), Some(chain.tree().root()));
+    }
+}
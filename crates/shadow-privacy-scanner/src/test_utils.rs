@@ -0,0 +1,72 @@
+//! Ledger fixtures shared by the unit tests
+
+use crate::scanner::WalletKeys;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use borsh::BorshSerialize;
+use rand_core::OsRng;
+use shadow_privacy::{PrivacyEvent, TreeLeaf};
+use shadow_privacy_indexer::LedgerTransaction;
+use shadow_privacy_merkle::{field_from_u64, public_key, FieldBytes};
+use shadow_privacy_note::{encrypt, NotePlaintext, ViewingKey};
+use solana_program::pubkey::Pubkey;
+
+/// A transaction in which `program_id` emitted `events`
+pub fn ledger_transaction(program_id: &Pubkey, slot: u64, success: bool, events: &[PrivacyEvent]) -> LedgerTransaction {
+    let mut logs = vec![format!("Program {} invoke [1]", program_id)];
+    for event in events {
+        logs.push(format!("Program data: {}", STANDARD.encode(event.try_to_vec().unwrap())));
+    }
+    logs.push(format!("Program {} success", program_id));
+    LedgerTransaction {
+        slot,
+        signature: format!("sig{}", slot),
+        success,
+        logs,
+    }
+}
+
+pub fn note_created(
+    account: Pubkey,
+    leaf: TreeLeaf,
+    asset_id: [u8; 32],
+    encrypted_value: Vec<u8>,
+    encrypted_memo: Vec<u8>,
+) -> PrivacyEvent {
+    PrivacyEvent::NoteCreated {
+        account,
+        leaf,
+        asset_id,
+        tx_public_key: encrypted_value.get(1..33).map_or([0u8; 32], |key| key.try_into().unwrap()),
+        encrypted_value,
+        encrypted_memo,
+    }
+}
+
+/// Keys derived from `seed`, with the wallet's circuit public key
+pub fn wallet(seed: u64) -> (WalletKeys, FieldBytes) {
+    let spending_key = field_from_u64(seed);
+    let keys = WalletKeys {
+        viewing_key: ViewingKey::from_bytes([seed as u8; 32]),
+        spending_key,
+    };
+    (keys, public_key(&spending_key).unwrap())
+}
+
+/// Value and memo parts of a note encrypted to `recipient`
+pub fn encrypted_note(
+    recipient: &ViewingKey,
+    amount: u64,
+    nonce: FieldBytes,
+    asset_id: [u8; 32],
+    memo: &[u8],
+) -> (Vec<u8>, Vec<u8>) {
+    let note = NotePlaintext {
+        amount,
+        nonce,
+        asset_id,
+        memo: memo.to_vec(),
+    };
+    let mut data = encrypt(&recipient.encryption_key(), &note, &mut OsRng).unwrap();
+    let memo = data.split_off(shadow_privacy::note::VALUE_CIPHERTEXT_LEN);
+    (data, memo)
+}
@@ -8,7 +8,6 @@ import {
   Keypair,
 } from '@solana/web3.js';
 import { buildPoseidon } from 'circomlibjs';
-import { sha256 } from '@noble/hashes/sha256';
 import { randomBytes } from '@noble/hashes/utils';
 import * as snarkjs from 'snarkjs';
 import {
@@ -21,6 +20,7 @@ import {
   WithdrawParams,
 } from './types';
 import { SHADOW_PROGRAM_ID, MERKLE_TREE_DEPTH, CIRCUITS } from './constants';
+import { encryptNote, encryptionKey } from './note';
 
 export class ShadowClient {
   private connection: Connection;
@@ -30,9 +30,12 @@ export class ShadowClient {
   private circuitsPath: string;
   private privateKey: Uint8Array;
   private publicKey: Uint8Array;
+  /** x25519 secret that deposit notes are encrypted to */
+  private viewingKey: Uint8Array;
   private commitments: Map<string, Commitment>;
   private nullifiers: Set<string>;
   public currentPoolAddress: PublicKey | null = null;
+  private poolAuthority: PublicKey | null = null;
   private monitorUrl: string | null = null;
   private relayerUrl: string | null = null;
 
@@ -44,10 +47,12 @@ export class ShadowClient {
     this.monitorUrl = config.monitorUrl || null;
     this.relayerUrl = config.relayerUrl || null;
     this.relayerUrl = config.relayerUrl || null;
+    this.poolAuthority = config.poolAuthority || null;
     this.commitments = new Map();
     this.nullifiers = new Set();
     this.privateKey = randomBytes(32);
     this.publicKey = new Uint8Array(32);
+    this.viewingKey = randomBytes(32);
 
     console.log('🏗️ ShadowClient Config:', {
       programId: this.programId.toString(),
@@ -265,10 +270,15 @@ export class ShadowClient {
   /**
    * Get deterministic pool keypair for a denomination
    */
-  getPoolKeypair(amount: bigint): Keypair {
-    const seedContent = `pool-${amount.toString()}`;
-    const hash = sha256(new TextEncoder().encode(seedContent));
-    return Keypair.fromSeed(hash.slice(0, 32));
+  getPoolAddress(amount: bigint): PublicKey {
+    const authority = this.poolAuthority || this.wallet.publicKey;
+    const denomination = Buffer.alloc(8);
+    denomination.writeBigUInt64LE(amount);
+    const [poolAddress] = PublicKey.findProgramAddressSync(
+      [Buffer.from('pool'), authority.toBuffer(), denomination],
+      this.programId
+    );
+    return poolAddress;
   }
 
   /**
@@ -288,14 +298,13 @@ export class ShadowClient {
     this.commitments.set(commitmentKey, commitment);
 
     // Get deterministic pool account
-    const poolKeypair = this.getPoolKeypair(params.amount);
-    const poolAddress = poolKeypair.publicKey;
+    const poolAddress = this.getPoolAddress(params.amount);
 
     // Check if pool exists
     const accountInfo = await this.connection.getAccountInfo(poolAddress);
     if (!accountInfo) {
       console.log('⚠️ Pool not initialized. Initializing now...');
-      await this.initializePool(poolKeypair, params.amount);
+      await this.initializePool(params.amount);
       console.log('✅ Pool auto-initialized');
       // Wait for RPC to catch up
       console.log('⏳ Waiting for block propagation...');
@@ -317,7 +326,12 @@ export class ShadowClient {
         { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
       ],
       programId: this.programId,
-      data: this.encodeDepositInstruction(commitment.value, params.amount),
+      data: this.encodeDepositInstruction(
+        commitment.value,
+        params.amount,
+        // Note for our own viewing key, so the deposit can be found again by scanning
+        encryptNote(encryptionKey(this.viewingKey), { amount: params.amount, nonce: commitment.nonce })
+      ),
     });
 
     // Send transaction
@@ -331,36 +345,25 @@ export class ShadowClient {
   /**
    * Initialize pool on-chain
    */
-  async initializePool(poolAccount: Keypair, denomination: bigint): Promise<string> {
+  async initializePool(denomination: bigint): Promise<string> {
     console.log('🏗️  Initializing privacy pool on-chain...');
-    this.currentPoolAddress = poolAccount.publicKey;
+    if (this.poolAuthority && !this.poolAuthority.equals(this.wallet.publicKey)) {
+      throw new Error('Only the pool authority can initialize its pools');
+    }
+    const poolAddress = this.getPoolAddress(denomination);
+    this.currentPoolAddress = poolAddress;
 
     const [vaultAddress] = PublicKey.findProgramAddressSync(
-      [Buffer.from('vault'), poolAccount.publicKey.toBuffer()],
+      [Buffer.from('vault'), poolAddress.toBuffer()],
       this.programId
     );
 
-    const space = 8192; // PoolState size + buffer
-    const rent = await this.connection.getMinimumBalanceForRentExemption(space);
-
+    // The program creates and funds the pool PDA and its vault
     const transaction = new Transaction();
-
-    // 1. Create pool account
-    transaction.add(
-      SystemProgram.createAccount({
-        fromPubkey: this.wallet.publicKey,
-        newAccountPubkey: poolAccount.publicKey,
-        lamports: rent,
-        space,
-        programId: this.programId,
-      })
-    );
-
-    // 2. Initialize pool
     transaction.add(
       new TransactionInstruction({
         keys: [
-          { pubkey: poolAccount.publicKey, isSigner: true, isWritable: true },
+          { pubkey: poolAddress, isSigner: false, isWritable: true },
           { pubkey: this.wallet.publicKey, isSigner: true, isWritable: true },
           { pubkey: vaultAddress, isSigner: false, isWritable: true },
           { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
@@ -370,7 +373,7 @@ export class ShadowClient {
       })
     );
 
-    const signature = await this.sendAndConfirm(transaction, [poolAccount]);
+    const signature = await this.sendAndConfirm(transaction);
 
     console.log('✅ Pool initialized');
     return signature;
@@ -418,8 +421,7 @@ export class ShadowClient {
     });
 
     // Get deterministic pool account
-    const poolKeypair = this.getPoolKeypair(params.amount);
-    const poolAddress = poolKeypair.publicKey;
+    const poolAddress = this.getPoolAddress(params.amount);
 
     const [vaultAddress] = PublicKey.findProgramAddressSync(
       [Buffer.from('vault'), poolAddress.toBuffer()],
@@ -431,6 +433,13 @@ export class ShadowClient {
       this.programId
     );
 
+    const [configAddress] = PublicKey.findProgramAddressSync([Buffer.from('config')], this.programId);
+
+    const [nullifierRecordAddress] = PublicKey.findProgramAddressSync(
+      [Buffer.from('nullifier'), poolAddress.toBuffer(), nullifier.value],
+      this.programId
+    );
+
     const recipientPubkey = new PublicKey(params.recipient);
 
     // Build instruction
@@ -441,6 +450,8 @@ export class ShadowClient {
         { pubkey: recipientPubkey, isSigner: false, isWritable: true },
         { pubkey: vkAddress, isSigner: false, isWritable: false }, // VK Account
         { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: configAddress, isSigner: false, isWritable: false },
+        { pubkey: nullifierRecordAddress, isSigner: false, isWritable: true }, // Created by the program
       ],
       programId: this.programId,
       data: this.encodeWithdrawInstruction(
@@ -602,13 +613,35 @@ export class ShadowClient {
     return new Uint8Array(Buffer.from(proofStr));
   }
 
-  private encodeDepositInstruction(commitment: Uint8Array, amount: bigint): Buffer {
-    // Rust: Deposit { commitment: [u8; 32], amount: u64 }
+  private encodeDepositInstruction(commitment: Uint8Array, amount: bigint, encryptedNote: Uint8Array): Buffer {
+    // Rust layout:
+    // Deposit {
+    //   commitment: [u8; 32],
+    //   amount: u64,
+    //   encrypted_note: Vec<u8>,
+    //   auditor_note: Option<AuditorNote>,
+    // }
     // Discriminant: 1
-    const buffer = Buffer.alloc(1 + 32 + 8);
-    buffer.writeUInt8(1, 0);
-    buffer.set(commitment, 1);
-    buffer.writeBigUInt64LE(amount, 33);
+    const buffer = Buffer.alloc(1 + 32 + 8 + 4 + encryptedNote.length + 1);
+    let offset = 0;
+
+    buffer.writeUInt8(1, offset);
+    offset += 1;
+
+    buffer.set(commitment, offset);
+    offset += 32;
+
+    buffer.writeBigUInt64LE(amount, offset);
+    offset += 8;
+
+    buffer.writeUInt32LE(encryptedNote.length, offset);
+    offset += 4;
+    buffer.set(encryptedNote, offset);
+    offset += encryptedNote.length;
+
+    // Option<AuditorNote> (None = 0; pools with an auditor need a proven note)
+    buffer.writeUInt8(0, offset);
+
     return buffer;
   }
 
@@ -627,10 +660,12 @@ export class ShadowClient {
     //   new_commitment: Option<[u8; 32]>,
     //   recipient: Pubkey,
     //   amount: u64,
+    //   encrypted_note: Option<Vec<u8>>,
+    //   auditor_note: Option<AuditorNote>,
     // }
     // Discriminant: 2
 
-    const buffer = Buffer.alloc(1 + 4 + proof.length + 32 + 32 + 1 + 32 + 8);
+    const buffer = Buffer.alloc(1 + 4 + proof.length + 32 + 32 + 1 + 32 + 8 + 1 + 1);
     let offset = 0;
 
     buffer.writeUInt8(2, offset); // Discriminant
@@ -655,6 +690,14 @@ export class ShadowClient {
     offset += 32;
 
     buffer.writeBigUInt64LE(amount, offset);
+    offset += 8;
+
+    // Option<Vec<u8>> for encrypted_note (None = 0; only change carries one)
+    buffer.writeUInt8(0, offset);
+    offset += 1;
+
+    // Option<AuditorNote> (None = 0; only change notes carry one)
+    buffer.writeUInt8(0, offset);
 
     return buffer;
   }
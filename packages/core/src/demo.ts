@@ -63,10 +63,9 @@ async function main() {
 
     // 1. Initialize Privacy Pool
     console.log('\n🏗️  Step 1: Initializing Privacy Pool...');
-    const poolAccount = Keypair.generate();
     const denomination = 100_000_000n; // 0.1 SOL
-    await client.initializePool(poolAccount, denomination);
-    console.log('   Pool Address:', poolAccount.publicKey.toString());
+    await client.initializePool(denomination);
+    console.log('   Pool Address:', client.getPoolAddress(denomination).toString());
 
     // 2. Alice Deposits into Pool
     console.log('\n� Step 2: Alice depositing 0.1 SOL into Privacy Pool...');
@@ -2,3 +2,4 @@
 export { ShadowClient, type ShadowClientConfig } from './client';
 export * from './types';
 export * from './constants';
+export { encryptNote, encryptionKey, type NotePlaintext } from './note';
@@ -0,0 +1,74 @@
+import { createCipheriv } from 'crypto';
+import { x25519 } from '@noble/curves/ed25519';
+import { hkdf } from '@noble/hashes/hkdf';
+import { sha256 } from '@noble/hashes/sha256';
+
+/**
+ * Encrypted notes, version 1 (layout in the program's `note` module,
+ * encryption as in the `shadow-privacy-note` crate):
+ *
+ *   version(1) || ephemeral x25519 key(32) || ChaCha20-Poly1305(amount || nonce || asset_id)
+ *   [|| ChaCha20-Poly1305(memo)]
+ *
+ * key = HKDF-SHA256(X25519(ephemeral, recipient), salt = ephemeral || recipient,
+ *                   info = "shadow-privacy/note/v1")
+ */
+export const NOTE_CIPHERTEXT_VERSION = 1;
+export const MAX_MEMO_LEN = 240;
+
+const KDF_INFO = Buffer.from('shadow-privacy/note/v1');
+const VALUE_NONCE = Buffer.alloc(12);
+const MEMO_NONCE = Buffer.from([1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+export interface NotePlaintext {
+  amount: bigint;
+  /** Commitment nonce, 32 bytes */
+  nonce: Uint8Array;
+  /** Confidential asset id, all zeroes for pool notes */
+  assetId?: Uint8Array;
+  memo?: Uint8Array;
+}
+
+/**
+ * Public key to encrypt notes to for a viewing key (an x25519 secret)
+ */
+export function encryptionKey(viewingKey: Uint8Array): Uint8Array {
+  return x25519.getPublicKey(viewingKey);
+}
+
+/**
+ * Encrypt a note to `recipient`, returning the bytes Deposit and
+ * PrivateTransfer carry
+ */
+export function encryptNote(recipient: Uint8Array, note: NotePlaintext): Buffer {
+  const memo = note.memo || new Uint8Array(0);
+  if (memo.length > MAX_MEMO_LEN) {
+    throw new Error(`Memo too long: ${memo.length} bytes, at most ${MAX_MEMO_LEN}`);
+  }
+
+  const ephemeral = x25519.utils.randomPrivateKey();
+  const ephemeralPublic = x25519.getPublicKey(ephemeral);
+  const shared = x25519.getSharedSecret(ephemeral, recipient);
+  if (shared.every(byte => byte === 0)) {
+    throw new Error('Recipient key has low order');
+  }
+  const key = hkdf(sha256, shared, Buffer.concat([ephemeralPublic, recipient]), KDF_INFO, 32);
+
+  const header = Buffer.concat([Buffer.from([NOTE_CIPHERTEXT_VERSION]), ephemeralPublic]);
+  const value = Buffer.alloc(8 + 32 + 32);
+  value.writeBigUInt64LE(note.amount, 0);
+  value.set(note.nonce, 8);
+  value.set(note.assetId || new Uint8Array(32), 40);
+
+  const parts = [header, seal(key, VALUE_NONCE, value, header)];
+  if (memo.length > 0) {
+    parts.push(seal(key, MEMO_NONCE, memo, header));
+  }
+  return Buffer.concat(parts);
+}
+
+function seal(key: Uint8Array, nonce: Buffer, message: Uint8Array, aad: Buffer): Buffer {
+  const cipher = createCipheriv('chacha20-poly1305', key, nonce, { authTagLength: 16 });
+  cipher.setAAD(aad, { plaintextLength: message.length });
+  return Buffer.concat([cipher.update(message), cipher.final(), cipher.getAuthTag()]);
+}
@@ -7,6 +7,8 @@ export interface ShadowClientConfig {
   circuitsPath?: string;
   monitorUrl?: string;
   relayerUrl?: string;
+  /** Authority of the shared pools (seeds of the pool PDA); defaults to the wallet */
+  poolAuthority?: PublicKey;
 }
 
 export interface Commitment {
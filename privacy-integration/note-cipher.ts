@@ -0,0 +1,95 @@
+/**
+ * Encrypted notes for Deposit and PrivateTransfer (version 1)
+ *
+ * Byte layout is defined in the program's `note` module and encryption in
+ * the `shadow-privacy-note` crate:
+ *
+ *   version(1) || ephemeral x25519 key(32) || ChaCha20-Poly1305(amount || nonce || asset_id)
+ *   [|| ChaCha20-Poly1305(memo)]
+ *
+ * key = HKDF-SHA256(X25519(ephemeral, recipient), salt = ephemeral || recipient,
+ *                   info = "shadow-privacy/note/v1")
+ */
+
+import * as crypto from 'crypto';
+
+export const NOTE_CIPHERTEXT_VERSION = 1;
+export const MAX_MEMO_LEN = 240;
+
+const KDF_INFO = Buffer.from('shadow-privacy/note/v1');
+const VALUE_NONCE = Buffer.alloc(12);
+const MEMO_NONCE = Buffer.from([1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+// DER prefixes wrapping raw 32-byte x25519 keys
+const X25519_SPKI_PREFIX = Buffer.from('302a300506032b656e032100', 'hex');
+const X25519_PKCS8_PREFIX = Buffer.from('302e020100300506032b656e04220420', 'hex');
+
+export interface NotePlaintext {
+  amount: bigint;
+  /** Commitment nonce, 32 bytes */
+  nonce: Uint8Array;
+  /** Confidential asset id, all zeroes for pool notes */
+  assetId?: Uint8Array;
+  memo?: Uint8Array;
+}
+
+/**
+ * Public key to encrypt notes to for a viewing key (an x25519 secret)
+ */
+export function encryptionKey(viewingKey: Uint8Array): Buffer {
+  const secret = crypto.createPrivateKey({
+    key: Buffer.concat([X25519_PKCS8_PREFIX, viewingKey]),
+    format: 'der',
+    type: 'pkcs8',
+  });
+  return rawPublicKey(crypto.createPublicKey(secret));
+}
+
+/**
+ * Encrypt a note to `recipient`, returning the encoded ciphertext
+ */
+export function encryptNote(recipient: Uint8Array, note: NotePlaintext): Buffer {
+  const memo = note.memo || new Uint8Array(0);
+  if (memo.length > MAX_MEMO_LEN) {
+    throw new Error(`Memo too long: ${memo.length} bytes, at most ${MAX_MEMO_LEN}`);
+  }
+
+  const ephemeral = crypto.generateKeyPairSync('x25519');
+  const ephemeralPublic = rawPublicKey(ephemeral.publicKey);
+  const shared = crypto.diffieHellman({
+    privateKey: ephemeral.privateKey,
+    publicKey: crypto.createPublicKey({
+      key: Buffer.concat([X25519_SPKI_PREFIX, recipient]),
+      format: 'der',
+      type: 'spki',
+    }),
+  });
+  if (shared.every(byte => byte === 0)) {
+    throw new Error('Recipient key has low order');
+  }
+  const key = Buffer.from(
+    crypto.hkdfSync('sha256', shared, Buffer.concat([ephemeralPublic, recipient]), KDF_INFO, 32)
+  );
+
+  const header = Buffer.concat([Buffer.from([NOTE_CIPHERTEXT_VERSION]), ephemeralPublic]);
+  const value = Buffer.alloc(8 + 32 + 32);
+  value.writeBigUInt64LE(note.amount, 0);
+  value.set(note.nonce, 8);
+  value.set(note.assetId || new Uint8Array(32), 40);
+
+  const parts = [header, seal(key, VALUE_NONCE, value, header)];
+  if (memo.length > 0) {
+    parts.push(seal(key, MEMO_NONCE, memo, header));
+  }
+  return Buffer.concat(parts);
+}
+
+function seal(key: Buffer, nonce: Buffer, message: Uint8Array, aad: Buffer): Buffer {
+  const cipher = crypto.createCipheriv('chacha20-poly1305', key, nonce, { authTagLength: 16 });
+  cipher.setAAD(aad, { plaintextLength: message.length });
+  return Buffer.concat([cipher.update(message), cipher.final(), cipher.getAuthTag()]);
+}
+
+function rawPublicKey(key: crypto.KeyObject): Buffer {
+  return key.export({ format: 'der', type: 'spki' }).subarray(X25519_SPKI_PREFIX.length);
+}
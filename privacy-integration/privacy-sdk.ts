@@ -8,6 +8,7 @@ import { ZKProofGenerator } from './zkproof';
 import { MerkleTree } from './merkletree';
 import { SolanaPrivacyClient } from './solana-client';
 import { NoteManager, ShieldedNote } from './note-manager';
+import { encryptNote } from './note-cipher';
 import * as fs from 'fs';
 import * as path from 'path';
 import config from '../config/production.config';
@@ -220,7 +221,7 @@ export class ShadowPrivacySDK {
     // Get ring members from Merkle tree (other commitments for anonymity set)
     const ringMembers = this.getRingMembers(fromNote.commitment, 3); // Get 3 other commitments
 
-    // Encrypt amount (simple encryption for demo)
+    // Encrypt the new note (the note store keeps it, so it goes to our own key)
     const encryptedAmount = this.encryptAmount(amountLamports, newNote.secret);
 
     // Submit real on-chain private transfer transaction
@@ -513,23 +514,13 @@ export class ShadowPrivacySDK {
   }
 
   /**
-   * Encrypt amount for private transfer
+   * Encrypt a private transfer's note in the program's note format
    */
   private encryptAmount(amount: number, secret: string): Uint8Array {
-    const crypto = require('crypto');
-    const cipher = crypto.createCipheriv(
-      'aes-256-cbc',
-      Buffer.from(secret.substring(0, 64), 'hex'),
-      Buffer.alloc(16, 0) // IV
-    );
-
-    const amountBuffer = Buffer.alloc(8);
-    amountBuffer.writeBigUInt64LE(BigInt(amount));
-
-    let encrypted = cipher.update(amountBuffer);
-    encrypted = Buffer.concat([encrypted, cipher.final()]);
-
-    return new Uint8Array(encrypted);
+    return encryptNote(this.solanaClient.getEncryptionKey(), {
+      amount: BigInt(amount),
+      nonce: Buffer.from(secret.substring(0, 64).padStart(64, '0'), 'hex'),
+    });
   }
 
   /**
@@ -23,16 +23,48 @@ export interface RelayerInfo {
   registeredAt: number;
   endpoint: string;
   bump: number;
+  lastReportAt: number;
 
   // Computed fields
-  reputationScore: number;
+  /** Time-decayed score, or null until enough recent reports exist */
+  reputationScore: number | null;
   isOnline: boolean;
 }
 
+// Reputation parameters (mirror RelayerAccount constants in state.rs)
+const REPUTATION_EPOCH_SECONDS = 86_400;
+const REPUTATION_BUCKETS = 8;
+const MIN_REPUTATION_SAMPLES = 10;
+
+// RelayerAccount::DISCRIMINATOR (sha256("account:RelayerAccount")[..8]) and VERSION
+const RELAYER_DISCRIMINATOR = Buffer.from([94, 235, 98, 227, 126, 208, 77, 139]);
+const RELAYER_ACCOUNT_VERSION = 2;
+
+// RelayerAccount::MAX_ENDPOINT_LEN and RelayerAccount::LEN (fixed zero-copy layout)
+const MAX_ENDPOINT_LEN = 128;
+const RELAYER_ACCOUNT_LEN = 8 + 1 + 1 + 1 + 1 + 4 + 32 + 8 * 6 + 16 * REPUTATION_BUCKETS + MAX_ENDPOINT_LEN;
+
+class ReputationBucketSchema {
+  epoch: bigint;
+  successes: number;
+  failures: number;
+
+  constructor(fields: any) {
+    this.epoch = fields.epoch;
+    this.successes = fields.successes;
+    this.failures = fields.failures;
+  }
+}
+
 /**
  * Borsh schema for RelayerAccount deserialization
+ *
+ * The account is a fixed `#[repr(C)]` layout; the flag byte and the
+ * fixed-size endpoint buffer are decoded into `is_active` and `endpoint`.
  */
 class RelayerAccountSchema {
+  discriminator: Uint8Array;
+  version: number;
   relayer: Uint8Array;
   stake: bigint;
   successful_relays: bigint;
@@ -42,17 +74,23 @@ class RelayerAccountSchema {
   registered_at: bigint;
   endpoint: string;
   bump: number;
+  last_report_at: bigint;
+  reputation_buckets: ReputationBucketSchema[];
 
   constructor(fields: any) {
+    this.discriminator = fields.discriminator;
+    this.version = fields.version;
     this.relayer = fields.relayer;
     this.stake = fields.stake;
     this.successful_relays = fields.successful_relays;
     this.failed_relays = fields.failed_relays;
     this.last_heartbeat = fields.last_heartbeat;
-    this.is_active = fields.is_active;
+    this.is_active = fields.active !== 0;
     this.registered_at = fields.registered_at;
-    this.endpoint = fields.endpoint;
+    this.endpoint = Buffer.from(fields.endpoint).subarray(0, fields.endpoint_len).toString('utf8');
     this.bump = fields.bump;
+    this.last_report_at = fields.last_report_at;
+    this.reputation_buckets = fields.reputation_buckets;
   }
 }
 
@@ -62,20 +100,76 @@ const RelayerAccountBorshSchema = new Map([
     {
       kind: 'struct',
       fields: [
+        ['discriminator', [8]],
+        ['version', 'u8'],
+        ['active', 'u8'],
+        ['bump', 'u8'],
+        ['endpoint_len', 'u8'],
+        ['_padding', [4]],
         ['relayer', [32]],
         ['stake', 'u64'],
         ['successful_relays', 'u64'],
         ['failed_relays', 'u64'],
         ['last_heartbeat', 'i64'],
-        ['is_active', 'u8'],
         ['registered_at', 'i64'],
-        ['endpoint', 'string'],
-        ['bump', 'u8'],
+        ['last_report_at', 'i64'],
+        ['reputation_buckets', [ReputationBucketSchema, REPUTATION_BUCKETS]],
+        ['endpoint', [MAX_ENDPOINT_LEN]],
+      ],
+    },
+  ],
+  [
+    ReputationBucketSchema,
+    {
+      kind: 'struct',
+      fields: [
+        ['epoch', 'u64'],
+        ['successes', 'u32'],
+        ['failures', 'u32'],
       ],
     },
   ],
 ]);
 
+/**
+ * Time-decayed reputation score (mirrors RelayerAccount::reputation_score)
+ *
+ * Each epoch weighs half as much as the one after it. Returns null while fewer
+ * than MIN_REPUTATION_SAMPLES reports fall inside the window.
+ */
+function reputationScore(data: RelayerAccountSchema, currentTime: number): number | null {
+  const epochOf = (t: number) => Math.floor(Math.max(t, 0) / REPUTATION_EPOCH_SECONDS);
+  const currentEpoch = epochOf(currentTime);
+  const lastActivity = Math.max(Number(data.last_heartbeat), Number(data.last_report_at));
+  if (currentEpoch - epochOf(lastActivity) >= REPUTATION_BUCKETS) {
+    return null;
+  }
+
+  let samples = 0;
+  let weightedSuccesses = 0;
+  let weightedTotal = 0;
+  for (const bucket of data.reputation_buckets) {
+    const age = currentEpoch - Number(bucket.epoch);
+    if (age < 0 || age >= REPUTATION_BUCKETS) {
+      continue;
+    }
+    const weight = 2 ** (REPUTATION_BUCKETS - 1 - age);
+    const total = bucket.successes + bucket.failures;
+    samples += total;
+    weightedSuccesses += bucket.successes * weight;
+    weightedTotal += total * weight;
+  }
+
+  if (samples < MIN_REPUTATION_SAMPLES || weightedTotal === 0) {
+    return null;
+  }
+  return Math.floor((weightedSuccesses * 100) / weightedTotal);
+}
+
+function formatReputation(score: number | null): string {
+  return score === null ? 'unscored' : `${score}/100`;
+}
+
 /**
  * Relayer Registry - manages relayer discovery and selection
  */
@@ -115,8 +209,14 @@ export class RelayerRegistry {
     const accounts = await this.connection.getProgramAccounts(PROGRAM_ID, {
       filters: [
         {
-          // Filter for relayer accounts by checking account size
-          dataSize: 32 + 8 + 8 + 8 + 8 + 1 + 8 + 4 + 128 + 1, // RelayerAccount::LEN
+          memcmp: {
+            offset: 0,
+            bytes: RELAYER_DISCRIMINATOR.toString('base64'),
+            encoding: 'base64',
+          },
+        },
+        {
+          dataSize: RELAYER_ACCOUNT_LEN,
         },
       ],
     });
@@ -133,11 +233,9 @@ export class RelayerRegistry {
           account.data
         ) as RelayerAccountSchema;
 
-        // Calculate reputation score
-        const total = Number(data.successful_relays) + Number(data.failed_relays);
-        let reputationScore = 50; // Default for new relayers
-        if (total > 0) {
-          reputationScore = Math.min(100, Math.floor((Number(data.successful_relays) * 100) / total));
+        // Accounts awaiting Migrate use an older layout
+        if (data.version !== RELAYER_ACCOUNT_VERSION) {
+          continue;
         }
 
         // Check if online (heartbeat within last 5 minutes)
@@ -154,7 +252,8 @@ export class RelayerRegistry {
           registeredAt: Number(data.registered_at),
           endpoint: data.endpoint,
           bump: data.bump,
-          reputationScore,
+          lastReportAt: Number(data.last_report_at),
+          reputationScore: reputationScore(data, currentTime),
           isOnline,
         });
       } catch (error) {
@@ -187,14 +286,15 @@ export class RelayerRegistry {
     // Sort by reputation score (descending), then by stake (descending)
     availableRelayers.sort((a, b) => {
       if (a.reputationScore !== b.reputationScore) {
-        return b.reputationScore - a.reputationScore;
+        // Unscored relayers rank below any scored one
+        return (b.reputationScore ?? -1) - (a.reputationScore ?? -1);
       }
       return b.stake - a.stake;
     });
 
     const best = availableRelayers[0];
     console.log(`✅ Selected relayer: ${best.relayer.toString()}`);
-    console.log(`   Reputation: ${best.reputationScore}/100`);
+    console.log(`   Reputation: ${formatReputation(best.reputationScore)}`);
     console.log(`   Endpoint: ${best.endpoint}`);
     console.log(`   Stake: ${best.stake / 1e9} SOL`);
 
@@ -219,10 +319,9 @@ export class RelayerRegistry {
         accountInfo.data
       ) as RelayerAccountSchema;
 
-      const total = Number(data.successful_relays) + Number(data.failed_relays);
-      let reputationScore = 50;
-      if (total > 0) {
-        reputationScore = Math.min(100, Math.floor((Number(data.successful_relays) * 100) / total));
+      if (data.version !== RELAYER_ACCOUNT_VERSION) {
+        console.warn(`Relayer ${relayerWallet.toBase58()} needs migration`);
+        return null;
       }
 
       const currentTime = Math.floor(Date.now() / 1000);
@@ -239,7 +338,8 @@ export class RelayerRegistry {
         registeredAt: Number(data.registered_at),
         endpoint: data.endpoint,
         bump: data.bump,
-        reputationScore,
+        lastReportAt: Number(data.last_report_at),
+        reputationScore: reputationScore(data, currentTime),
         isOnline,
       };
     } catch (error) {
@@ -256,7 +356,7 @@ export class RelayerRegistry {
 
     return relayers
       .filter(r => r.isActive && r.isOnline)
-      .sort((a, b) => b.reputationScore - a.reputationScore);
+      .sort((a, b) => (b.reputationScore ?? -1) - (a.reputationScore ?? -1));
   }
 
   /**
@@ -272,8 +372,9 @@ export class RelayerRegistry {
     const online = relayers.filter(r => r.isActive && r.isOnline);
 
     const totalStake = relayers.reduce((sum, r) => sum + r.stake, 0);
-    const avgReputation = relayers.length > 0
-      ? relayers.reduce((sum, r) => sum + r.reputationScore, 0) / relayers.length
+    const scored = relayers.filter(r => r.reputationScore !== null);
+    const avgReputation = scored.length > 0
+      ? scored.reduce((sum, r) => sum + (r.reputationScore ?? 0), 0) / scored.length
       : 0;
 
     return {
@@ -306,7 +407,7 @@ async function main() {
   for (const relayer of onlineRelayers) {
     console.log(`\n   Relayer: ${relayer.relayer.toString()}`);
     console.log(`   Endpoint: ${relayer.endpoint}`);
-    console.log(`   Reputation: ${relayer.reputationScore}/100`);
+    console.log(`   Reputation: ${formatReputation(relayer.reputationScore)}`);
     console.log(`   Stake: ${relayer.stake / 1e9} SOL`);
     console.log(`   Relays: ${relayer.successfulRelays} success, ${relayer.failedRelays} failed`);
   }
@@ -317,7 +418,7 @@ async function main() {
   if (best) {
     console.log(`   Address: ${best.relayer.toString()}`);
     console.log(`   Endpoint: ${best.endpoint}`);
-    console.log(`   Reputation: ${best.reputationScore}/100`);
+    console.log(`   Reputation: ${formatReputation(best.reputationScore)}`);
   }
 }
 
@@ -6,7 +6,7 @@
  */
 
 import express from 'express';
-import { Keypair, Connection, PublicKey, Transaction, TransactionInstruction, sendAndConfirmTransaction, LAMPORTS_PER_SOL } from '@solana/web3.js';
+import { Keypair, Connection, PublicKey, SystemProgram, Transaction, TransactionInstruction, sendAndConfirmTransaction, LAMPORTS_PER_SOL } from '@solana/web3.js';
 import * as fs from 'fs';
 import * as path from 'path';
 import * as os from 'os';
@@ -138,19 +138,35 @@ app.post('/relay-withdraw', async (req, res) => {
     let poolPubkey: PublicKey;
     let recipientPubkey: PublicKey;
     let vaultPubkey: PublicKey;
+    let vkPubkey: PublicKey;
+    let configPubkey: PublicKey;
+    let nullifierRecordPubkey: PublicKey;
 
     try {
       poolPubkey = new PublicKey(poolAddress);
       recipientPubkey = new PublicKey(recipient);
+      const nullifierBytes = Buffer.from(nullifier, 'hex');
+      if (nullifierBytes.length !== 32) {
+        throw new Error('nullifier must be 32 bytes of hex');
+      }
 
-      // Derive vault PDA
+      // Derive the PDAs Withdraw takes
       [vaultPubkey] = PublicKey.findProgramAddressSync(
         [Buffer.from('vault'), poolPubkey.toBuffer()],
         PROGRAM_ID
       );
+      [vkPubkey] = PublicKey.findProgramAddressSync(
+        [Buffer.from('vk_transfer'), poolPubkey.toBuffer()],
+        PROGRAM_ID
+      );
+      [configPubkey] = PublicKey.findProgramAddressSync([Buffer.from('config')], PROGRAM_ID);
+      [nullifierRecordPubkey] = PublicKey.findProgramAddressSync(
+        [Buffer.from('nullifier'), poolPubkey.toBuffer(), nullifierBytes],
+        PROGRAM_ID
+      );
     } catch (error) {
       return res.status(400).json({
-        error: 'Invalid address format',
+        error: 'Invalid address or nullifier format',
       });
     }
 
@@ -172,7 +188,10 @@ app.post('/relay-withdraw', async (req, res) => {
         { pubkey: poolPubkey, isSigner: false, isWritable: true },
         { pubkey: vaultPubkey, isSigner: false, isWritable: true },
         { pubkey: recipientPubkey, isSigner: false, isWritable: true },
-        { pubkey: PublicKey.default, isSigner: false, isWritable: false }, // System program placeholder
+        { pubkey: vkPubkey, isSigner: false, isWritable: false }, // Transfer verification key
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: configPubkey, isSigner: false, isWritable: false }, // Protocol config
+        { pubkey: nullifierRecordPubkey, isSigner: false, isWritable: true }, // Nullifier record (created)
       ],
       programId: PROGRAM_ID,
       data: Buffer.from(instructionData, 'base64'),
@@ -14,8 +14,10 @@ import {
   sendAndConfirmTransaction,
 } from '@solana/web3.js';
 import * as borsh from 'borsh';
+import * as crypto from 'crypto';
 import { ZKProofGenerator } from './zkproof';
 import { MerkleTree } from './merkletree';
+import { encryptNote, encryptionKey } from './note-cipher';
 
 // Program ID from deployment
 const PROGRAM_ID = new PublicKey('3wiFPaYTQZZD71rd4pohPRr8JaFaGN3XaNWLoGSk31Ck');
@@ -46,10 +48,13 @@ class DepositData {
   instruction = PrivacyInstruction.Deposit;
   commitment: Uint8Array;
   amount: bigint;
+  encrypted_note: Uint8Array;
+  auditor_note: null = null;
 
-  constructor(props: { commitment: Uint8Array; amount: bigint }) {
+  constructor(props: { commitment: Uint8Array; amount: bigint; encrypted_note: Uint8Array }) {
     this.commitment = props.commitment;
     this.amount = props.amount;
+    this.encrypted_note = props.encrypted_note;
   }
 }
 
@@ -60,6 +65,8 @@ class WithdrawData {
   nullifier: Uint8Array;
   new_commitment: Uint8Array;
   recipient: Uint8Array;
+  amount: bigint;
+  auditor_note: null = null;
 
   constructor(props: {
     proof: Uint8Array;
@@ -67,12 +74,14 @@ class WithdrawData {
     nullifier: Uint8Array;
     new_commitment: Uint8Array;
     recipient: Uint8Array;
+    amount: bigint;
   }) {
     this.proof = props.proof;
     this.root = props.root;
     this.nullifier = props.nullifier;
     this.new_commitment = props.new_commitment;
     this.recipient = props.recipient;
+    this.amount = props.amount;
   }
 }
 
@@ -81,15 +90,18 @@ export class SolanaPrivacyClient {
   private programId: PublicKey;
   private zkGenerator: ZKProofGenerator;
   private merkleTree: MerkleTree;
+  private viewingKey: Uint8Array;
 
   constructor(
     rpcUrl: string = 'https://api.devnet.solana.com',
-    programId: string = PROGRAM_ID.toString()
+    programId: string = PROGRAM_ID.toString(),
+    viewingKey: Uint8Array = crypto.randomBytes(32)
   ) {
     this.connection = new Connection(rpcUrl, 'confirmed');
     this.programId = new PublicKey(programId);
     this.zkGenerator = new ZKProofGenerator();
     this.merkleTree = MerkleTree.load('./data/merkle_tree.json');
+    this.viewingKey = viewingKey;
   }
 
   /**
@@ -99,6 +111,13 @@ export class SolanaPrivacyClient {
     return this.connection;
   }
 
+  /**
+   * Public key deposit notes are encrypted to (x25519, from the viewing key)
+   */
+  getEncryptionKey(): Buffer {
+    return encryptionKey(this.viewingKey);
+  }
+
   /**
    * Initialize a new shielded pool
    */
@@ -109,25 +128,16 @@ export class SolanaPrivacyClient {
   ): Promise<string> {
     console.log('\n=== Initializing Shielded Pool ===');
 
-    // Generate pool account
-    const poolAccount = Keypair.generate();
-
-    // Calculate account size for PoolState
-    // Pubkey(32) + merkle_root[32] + tree_depth(1) + commitment_count(8) +
-    // denomination(8) + tvl(8) + Vec<nullifiers>(4+space) + Vec<key_images>(4+space) + vault(32)
-    // Start with 1KB to allow for dynamic vectors
-    const poolAccountSize = 1024;
-
-    // Get minimum rent-exempt balance
-    const rentExemptBalance = await this.connection.getMinimumBalanceForRentExemption(
-      poolAccountSize
+    // Derive pool and vault PDAs (same seeds as Rust program); the program
+    // creates both, funded by the payer as pool authority
+    const denominationSeed = Buffer.alloc(8);
+    denominationSeed.writeBigUInt64LE(BigInt(denomination));
+    const [poolPubkey] = PublicKey.findProgramAddressSync(
+      [Buffer.from('pool'), payer.publicKey.toBuffer(), denominationSeed],
+      this.programId
     );
-
-    console.log(`Creating pool account (${poolAccountSize} bytes, ${rentExemptBalance / LAMPORTS_PER_SOL} SOL rent)...`);
-
-    // Derive vault PDA (same seeds as Rust program)
     const [vaultPubkey] = PublicKey.findProgramAddressSync(
-      [Buffer.from('vault'), poolAccount.publicKey.toBuffer()],
+      [Buffer.from('vault'), poolPubkey.toBuffer()],
       this.programId
     );
 
@@ -140,42 +150,31 @@ export class SolanaPrivacyClient {
     instructionBuffer.writeUInt8(treeDepth, 1); // tree_depth
     instructionBuffer.writeBigUInt64LE(BigInt(denomination), 2); // denomination (little-endian)
 
-    // Build transaction with account creation + initialization
-    const transaction = new Transaction()
-      .add(
-        SystemProgram.createAccount({
-          fromPubkey: payer.publicKey,
-          newAccountPubkey: poolAccount.publicKey,
-          lamports: rentExemptBalance,
-          space: poolAccountSize,
-          programId: this.programId,
-        })
-      )
-      .add(
-        new TransactionInstruction({
-          keys: [
-            { pubkey: poolAccount.publicKey, isSigner: false, isWritable: true },
-            { pubkey: payer.publicKey, isSigner: true, isWritable: true },
-            { pubkey: vaultPubkey, isSigner: false, isWritable: true }, // Add vault PDA
-            { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
-          ],
-          programId: this.programId,
-          data: instructionBuffer,
-        })
-      );
+    const transaction = new Transaction().add(
+      new TransactionInstruction({
+        keys: [
+          { pubkey: poolPubkey, isSigner: false, isWritable: true },
+          { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+          { pubkey: vaultPubkey, isSigner: false, isWritable: true },
+          { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        ],
+        programId: this.programId,
+        data: instructionBuffer,
+      })
+    );
 
     // Send transaction
     const signature = await sendAndConfirmTransaction(
       this.connection,
       transaction,
-      [payer, poolAccount],
+      [payer],
       { commitment: 'confirmed' }
     );
 
-    console.log(`Pool initialized: ${poolAccount.publicKey.toString()}`);
+    console.log(`Pool initialized: ${poolPubkey.toString()}`);
     console.log(`Transaction: ${signature}`);
 
-    return poolAccount.publicKey.toString();
+    return poolPubkey.toString();
   }
 
   /**
@@ -211,12 +210,28 @@ export class SolanaPrivacyClient {
 
     console.log(`Pool vault: ${vaultPubkey.toString()}`);
 
+    // Note for our own viewing key, so the deposit can be found again by scanning
+    const encryptedNote = encryptNote(this.getEncryptionKey(), {
+      amount: BigInt(amount),
+      nonce: this.hexToBytes(secret),
+    });
+
     // Create deposit instruction data (Borsh serialized)
-    // Borsh uses u8 for enum discriminants (1 byte)
-    const instructionBuffer = Buffer.alloc(1 + 32 + 8); // discriminant(u8) + commitment + amount
-    instructionBuffer.writeUInt8(PrivacyInstruction.Deposit, 0); // enum discriminant (u8)
-    this.hexToBytes(commitment).copy(instructionBuffer, 1); // commitment (32 bytes)
-    instructionBuffer.writeBigUInt64LE(BigInt(amount), 33); // amount (8 bytes)
+    // Format: discriminant(u8) + commitment([u8;32]) + amount(u64) +
+    //         encrypted_note(Vec<u8>) + auditor_note(Option<AuditorNote>)
+    const instructionBuffer = Buffer.alloc(1 + 32 + 8 + 4 + encryptedNote.length + 1);
+    let offset = 0;
+    instructionBuffer.writeUInt8(PrivacyInstruction.Deposit, offset); // enum discriminant (u8)
+    offset += 1;
+    this.hexToBytes(commitment).copy(instructionBuffer, offset); // commitment (32 bytes)
+    offset += 32;
+    instructionBuffer.writeBigUInt64LE(BigInt(amount), offset); // amount (8 bytes)
+    offset += 8;
+    instructionBuffer.writeUInt32LE(encryptedNote.length, offset); // encrypted_note (u32 length + data)
+    offset += 4;
+    encryptedNote.copy(instructionBuffer, offset);
+    offset += encryptedNote.length;
+    instructionBuffer.writeUInt8(0, offset); // auditor_note: None (pools without an auditor)
 
     // Build instruction with correct accounts order
     const instruction = new TransactionInstruction({
@@ -304,11 +319,30 @@ export class SolanaPrivacyClient {
 
     console.log(`Pool vault: ${vaultPubkey.toString()}`);
 
+    const [vkPubkey] = PublicKey.findProgramAddressSync(
+      [Buffer.from('vk_transfer'), poolPubkey.toBuffer()],
+      this.programId
+    );
+    const [configPubkey] = PublicKey.findProgramAddressSync([Buffer.from('config')], this.programId);
+    const [nullifierRecordPubkey] = PublicKey.findProgramAddressSync(
+      [Buffer.from('nullifier'), poolPubkey.toBuffer(), this.hexToBytes(nullifier)],
+      this.programId
+    );
+
+    // Note for the change commitment, so it can be found again by scanning
+    const changeNote = encryptNote(this.getEncryptionKey(), {
+      amount: 0n,
+      nonce: this.hexToBytes(secret),
+    });
+
     // Build Borsh-serialized instruction data
     // Format: discriminant(u8) + proof(Vec) + root([u8;32]) + nullifier([u8;32]) +
-    //         new_commitment(Option<[u8;32]>) + recipient(Pubkey) + amount(u64)
+    //         new_commitment(Option<[u8;32]>) + recipient(Pubkey) + amount(u64) +
+    //         encrypted_note(Option<Vec<u8>>) + auditor_note(Option<AuditorNote>)
 
-    const instructionData = Buffer.alloc(1 + 4 + proofBytes.length + 32 + 32 + 1 + 32 + 32 + 8);
+    const instructionData = Buffer.alloc(
+      1 + 4 + proofBytes.length + 32 + 32 + 1 + 32 + 32 + 8 + 1 + 4 + changeNote.length + 1
+    );
     let offset = 0;
 
     // Discriminant (u8)
@@ -341,16 +375,32 @@ export class SolanaPrivacyClient {
 
     // Amount (u64)
     instructionData.writeBigUInt64LE(BigInt(amount), offset);
+    offset += 8;
+
+    // Encrypted change note (Option<Vec<u8>>: 1 byte for Some/None + u32 length + data)
+    instructionData.writeUInt8(1, offset); // 1 = Some
+    offset += 1;
+    instructionData.writeUInt32LE(changeNote.length, offset);
+    offset += 4;
+    changeNote.copy(instructionData, offset);
+    offset += changeNote.length;
+
+    // Auditor note (Option<AuditorNote>): None, pools without an auditor
+    instructionData.writeUInt8(0, offset);
+    offset += 1;
 
     const instruction = new TransactionInstruction({
       keys: [
         { pubkey: poolPubkey, isSigner: false, isWritable: true },       // Pool state
         { pubkey: vaultPubkey, isSigner: false, isWritable: true },      // Pool vault
         { pubkey: recipient, isSigner: false, isWritable: true },         // Recipient
+        { pubkey: vkPubkey, isSigner: false, isWritable: false },         // Transfer verification key
         { pubkey: SystemProgram.programId, isSigner: false, isWritable: false }, // System program
+        { pubkey: configPubkey, isSigner: false, isWritable: false },     // Protocol config
+        { pubkey: nullifierRecordPubkey, isSigner: false, isWritable: true }, // Nullifier record (created)
       ],
       programId: this.programId,
-      data: instructionData.slice(0, offset + 8), // Trim to actual size
+      data: instructionData.slice(0, offset), // Trim to actual size
     });
 
     const transaction = new Transaction().add(instruction);
@@ -401,13 +451,21 @@ export class SolanaPrivacyClient {
 
     const poolPubkey = new PublicKey(poolAddress);
 
-    // Dummy accounts for sender/recipient commitments (not used in current implementation)
-    const dummyAccount1 = Keypair.generate().publicKey;
-    const dummyAccount2 = Keypair.generate().publicKey;
+    // Record of the spent key image; it outlives the pool's key image cache
+    const [keyImageRecord] = PublicKey.findProgramAddressSync(
+      [Buffer.from('key_image'), poolPubkey.toBuffer(), this.hexToBytes(keyImage)],
+      this.programId
+    );
+
+    // Dummy account for the recipient commitment (not used in current implementation)
+    const dummyAccount = Keypair.generate().publicKey;
+
+    const [configPubkey] = PublicKey.findProgramAddressSync([Buffer.from('config')], this.programId);
 
     // Build Borsh-serialized instruction data
     // Format: discriminant(u8) + ring_signature(Vec) + key_image([u8;32]) +
-    //         ring_members(Vec<[u8;32]>) + new_commitment([u8;32]) + encrypted_amount(Vec)
+    //         ring_members(Vec<[u8;32]>) + new_commitment([u8;32]) + encrypted_amount(Vec) +
+    //         auditor_note(Option<AuditorNote>)
 
     let offset = 0;
     const ringMemberBytes = ringMembers.length * 32;
@@ -417,7 +475,8 @@ export class SolanaPrivacyClient {
       32 + // key_image
       4 + ringMemberBytes + // ring_members Vec
       32 + // new_commitment
-      4 + encryptedAmount.length // encrypted_amount Vec
+      4 + encryptedAmount.length + // encrypted_amount Vec
+      1 // auditor_note Option
     );
 
     // Discriminant (u8) - PrivateTransfer = 3
@@ -452,11 +511,18 @@ export class SolanaPrivacyClient {
     Buffer.from(encryptedAmount).copy(instructionData, offset);
     offset += encryptedAmount.length;
 
+    // Auditor note (Option<AuditorNote>): None, pools without an auditor
+    instructionData.writeUInt8(0, offset);
+    offset += 1;
+
     const instruction = new TransactionInstruction({
       keys: [
         { pubkey: poolPubkey, isSigner: false, isWritable: true },        // Pool state
-        { pubkey: dummyAccount1, isSigner: false, isWritable: false },    // Sender commitment (unused)
-        { pubkey: dummyAccount2, isSigner: false, isWritable: false },    // Recipient commitment (unused)
+        { pubkey: keyImageRecord, isSigner: false, isWritable: true },    // Key image record (created)
+        { pubkey: dummyAccount, isSigner: false, isWritable: true },      // Recipient commitment (unused)
+        { pubkey: configPubkey, isSigner: false, isWritable: false },     // Protocol config
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },    // Payer (record rent)
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false }, // System program
       ],
       programId: this.programId,
       data: instructionData,
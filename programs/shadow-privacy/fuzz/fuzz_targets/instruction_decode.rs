@@ -0,0 +1,20 @@
+//! Instruction decoding: whatever decodes must re-encode to the same bytes,
+//! and the processor must reject (not panic on) it without accounts
+
+#![no_main]
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use libfuzzer_sys::fuzz_target;
+use shadow_privacy::{instruction::PrivacyInstruction, Processor};
+use shadow_privacy_fuzz::runtime;
+
+fuzz_target!(|data: &[u8]| {
+    runtime::install();
+
+    if let Ok(instruction) = PrivacyInstruction::try_from_slice(data) {
+        let encoded = instruction.try_to_vec().expect("decoded instruction re-encodes");
+        assert_eq!(encoded, data, "{:?} is not canonically encoded", instruction);
+    }
+
+    let _ = Processor::process(&shadow_privacy::id(), &[], data);
+});
@@ -0,0 +1,10 @@
+//! Stateful pool harness: see `shadow_privacy_fuzz::pool`
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use shadow_privacy_fuzz::pool::{self, Scenario};
+
+fuzz_target!(|scenario: Scenario| {
+    pool::run(&scenario);
+});
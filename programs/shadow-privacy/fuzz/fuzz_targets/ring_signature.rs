@@ -0,0 +1,33 @@
+//! `verify_ring_signature` on arbitrary rings: size errors exactly where
+//! expected, no panics anywhere
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use shadow_privacy::{verifier::verify_ring_signature, PrivacyError};
+use shadow_privacy_fuzz::runtime;
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    signature: Vec<u8>,
+    key_image: [u8; 32],
+    ring_members: Vec<[u8; 32]>,
+    max_ring_size: u8,
+}
+
+fuzz_target!(|input: Input| {
+    runtime::install();
+
+    let max_ring_size = input.max_ring_size as usize;
+    let result = verify_ring_signature(&input.signature, &input.key_image, &input.ring_members, max_ring_size);
+
+    let ring_size = input.ring_members.len();
+    if ring_size == 0 || ring_size > max_ring_size {
+        assert_eq!(result, Err(PrivacyError::InvalidRingSize.into()));
+    } else if input.signature.len() != 32 * (ring_size + 1) {
+        assert_eq!(result, Err(PrivacyError::InvalidSignature.into()));
+    } else {
+        assert!(result.is_ok(), "well-sized ring rejected: {:?}", result);
+    }
+});
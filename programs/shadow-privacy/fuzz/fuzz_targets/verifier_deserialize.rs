@@ -0,0 +1,27 @@
+//! VK account, proof and public input deserialization on arbitrary bytes
+
+#![no_main]
+
+use ark_bn254::Fr;
+use ark_serialize::CanonicalSerialize;
+use libfuzzer_sys::fuzz_target;
+use shadow_privacy::verifier::{deserialize_field_elements, deserialize_proof, load_verification_key_from_account};
+use shadow_privacy_fuzz::runtime;
+
+fuzz_target!(|data: &[u8]| {
+    runtime::install();
+
+    let _ = load_verification_key_from_account(data);
+    let _ = deserialize_proof(data);
+
+    // Public inputs: only canonical 32-byte encodings may decode
+    let inputs: Vec<Vec<u8>> = data.chunks(32).map(<[u8]>::to_vec).collect();
+    if let Ok(elements) = deserialize_field_elements(&inputs) {
+        assert_eq!(elements.len(), inputs.len());
+        for (element, input) in elements.iter().zip(&inputs) {
+            let mut encoded = Vec::with_capacity(32);
+            Fr::serialize_compressed(element, &mut encoded).unwrap();
+            assert_eq!(&encoded, input, "non-canonical public input accepted");
+        }
+    }
+});
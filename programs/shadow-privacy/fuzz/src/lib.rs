@@ -0,0 +1,10 @@
+//! Shared pieces of the shadow-privacy fuzz targets
+//!
+//! `runtime` stands in for the Solana runtime natively; `pool` drives a pool
+//! through `Processor::process` and checks its accounting invariants.
+//!
+//! Run a target with cargo-fuzz from `programs/shadow-privacy`:
+//! `cargo +nightly fuzz run processor`.
+
+pub mod pool;
+pub mod runtime;
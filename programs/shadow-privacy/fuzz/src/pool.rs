@@ -0,0 +1,406 @@
+//! Stateful pool harness
+//!
+//! A fuzzer-chosen sequence of deposits, withdrawals, claims and admin
+//! operations runs against one pool in a [`Bank`]. After every instruction the
+//! harness checks the pool's accounting against its own model:
+//!
+//! - the vault holds at least `tvl` plus its own rent
+//! - `tvl` is the sum of accepted deposits less accepted withdrawals
+//! - no nullifier is accepted twice, whether or not it is still in the cache
+
+use crate::runtime::{self, Bank, SimAccount};
+use arbitrary::Arbitrary;
+use shadow_privacy::{
+    note::{NOTE_CIPHERTEXT_VERSION, VALUE_CIPHERTEXT_LEN},
+    state::{
+        CircuitType, ConfigParams, DelayedWithdrawal, NullifierAccount, PoolLimits, PoolState, ProgramAccount,
+        ProtocolConfig, ZeroCopyAccount,
+    },
+};
+use shadow_privacy_client::{instruction as ix, pda};
+use solana_program::{
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    instruction::Instruction, program_error::ProgramError, pubkey::Pubkey, rent::Rent,
+    system_program,
+};
+use std::collections::BTreeSet;
+
+pub const DENOMINATION: u64 = 1_000_000_000;
+
+/// Distinct nullifiers (and delayed withdrawal PDAs) operations can use
+pub const NULLIFIERS: u8 = 8;
+
+/// Withdrawal recipients
+pub const RECIPIENTS: u8 = 3;
+
+/// Operations run per scenario
+pub const MAX_OPS: usize = 64;
+
+const START_TIME: i64 = 1_700_000_000;
+
+#[derive(Arbitrary, Debug)]
+pub struct Scenario {
+    pub denomination: Amount,
+    /// Taken modulo MAX_CACHE_ENTRIES + 1, so runs go past the cache
+    pub nullifier_cache_size: u8,
+    pub ops: Vec<Op>,
+}
+
+#[derive(Arbitrary, Clone, Copy, Debug)]
+pub enum Amount {
+    Denomination,
+    Raw(u64),
+}
+
+#[derive(Arbitrary, Clone, Debug)]
+pub enum Op {
+    Deposit {
+        commitment: u8,
+        amount: Amount,
+    },
+    Withdraw {
+        nullifier: u8,
+        recipient: u8,
+        amount: Amount,
+        change: Option<u8>,
+        /// Pass the delayed withdrawal PDA so over-limit withdrawals queue
+        delayed: bool,
+    },
+    Claim {
+        nullifier: u8,
+        recipient: u8,
+    },
+    SetLimits {
+        tvl_cap: u64,
+        epoch_outflow_limit: u64,
+        window_outflow_limit: u64,
+        window_seconds: u16,
+        withdrawal_delay: u16,
+    },
+    Pause {
+        operations: u8,
+        expires_in: Option<u16>,
+    },
+    Unpause {
+        operations: u8,
+    },
+    AdvanceClock {
+        seconds: u32,
+    },
+}
+
+/// One pool and the accounts operations touch
+pub struct PoolHarness {
+    pub bank: Bank,
+    pub denomination: u64,
+    admin: Pubkey,
+    authority: Pubkey,
+    depositor: Pubkey,
+    pub pool: Pubkey,
+    pub vault: Pubkey,
+    recipients: Vec<Pubkey>,
+    /// Deposits less withdrawals the program accepted
+    tvl: u64,
+    /// Nullifiers the program accepted
+    spent: BTreeSet<[u8; 32]>,
+}
+
+impl PoolHarness {
+    /// Config and pool initialized through their instructions
+    pub fn new(denomination: u64, nullifier_cache_size: u16) -> Self {
+        runtime::install();
+        runtime::set_unix_timestamp(START_TIME);
+
+        let program_id = shadow_privacy::id();
+        let admin = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let depositor = Pubkey::new_unique();
+        let pool = pda::pool_address(&program_id, &authority, denomination);
+        let vault = pda::vault_address(&program_id, &pool);
+        let recipients: Vec<Pubkey> = (0..RECIPIENTS).map(|_| Pubkey::new_unique()).collect();
+
+        let mut bank = Bank::default();
+        let wallet = |lamports| SimAccount::new(system_program::id(), lamports, vec![]);
+        // Only its address is checked
+        bank.add(system_program::id(), SimAccount::new(Pubkey::default(), 1, vec![]));
+        bank.add(admin, wallet(100 * DENOMINATION));
+        bank.add(authority, wallet(100 * DENOMINATION));
+        bank.add(depositor, wallet(u64::MAX / 4));
+        for recipient in &recipients {
+            bank.add(*recipient, wallet(0));
+        }
+        bank.add(pda::config_address(&program_id), SimAccount::unallocated(ProtocolConfig::LEN));
+        let program_data = UpgradeableLoaderState::ProgramData {
+            slot: 0,
+            upgrade_authority_address: Some(admin),
+        };
+        bank.add(
+            pda::program_data_address(&program_id),
+            SimAccount::new(bpf_loader_upgradeable::id(), 1, bincode::serialize(&program_data).unwrap()),
+        );
+        bank.add(pool, SimAccount::unallocated(PoolState::LEN));
+        bank.add(vault, SimAccount::unallocated(0));
+        // Demo builds skip proof verification, so the VKs are never stored.
+        // The pool has no auditor; its AuditorNote VK is passed all the same.
+        for circuit_type in [CircuitType::Transfer, CircuitType::AuditorNote] {
+            bank.add(pda::verification_key_address(&program_id, &pool, circuit_type), SimAccount::unallocated(0));
+        }
+        for nullifier in (0..NULLIFIERS).map(nullifier) {
+            bank.add(
+                pda::nullifier_address(&program_id, &pool, &nullifier),
+                SimAccount::unallocated(NullifierAccount::LEN),
+            );
+            bank.add(
+                pda::delayed_withdrawal_address(&program_id, &pool, &nullifier),
+                SimAccount::unallocated(DelayedWithdrawal::LEN),
+            );
+        }
+
+        let mut harness = PoolHarness {
+            bank,
+            denomination,
+            admin,
+            authority,
+            depositor,
+            pool,
+            vault,
+            recipients,
+            tvl: 0,
+            spent: BTreeSet::new(),
+        };
+
+        let params = ConfigParams {
+            nullifier_cache_size,
+            ..ConfigParams::default()
+        };
+        harness
+            .process(&ix::initialize_config(&program_id, &harness.admin, params))
+            .expect("InitializeConfig");
+        harness
+            .process(&ix::initialize_pool(&program_id, &harness.authority, 20, denomination))
+            .expect("InitializePool");
+        harness.check_invariants();
+        harness
+    }
+
+    /// Run `op`, update the model from its result and check the invariants
+    pub fn apply(&mut self, op: &Op) -> Result<(), ProgramError> {
+        let program_id = shadow_privacy::id();
+        let result = match *op {
+            Op::Deposit { commitment, amount } => {
+                let amount = self.amount(amount);
+                let deposit = ix::deposit(&program_id, &self.pool, &self.depositor, [commitment; 32], amount, note(), None);
+                let result = self.process(&deposit);
+                if result.is_ok() {
+                    self.tvl = self.tvl.checked_add(amount).expect("deposits overflow the model");
+                }
+                result
+            }
+            Op::Withdraw {
+                nullifier: index,
+                recipient,
+                amount,
+                change,
+                delayed,
+            } => {
+                let nullifier = nullifier(index % NULLIFIERS);
+                let amount = self.amount(amount);
+                let mut withdraw = ix::withdraw(
+                    &program_id,
+                    &self.pool,
+                    vec![0u8; 256],
+                    [0u8; 32],
+                    nullifier,
+                    change.map(|change| [change; 32]),
+                    &self.recipient(recipient),
+                    amount,
+                    change.map(|_| note()),
+                    None,
+                );
+                if !delayed {
+                    // Drop the delayed withdrawal PDA and the auditor VK after it
+                    withdraw.accounts.truncate(7);
+                }
+                let result = self.process(&withdraw);
+                if result.is_ok() {
+                    assert!(self.spent.insert(nullifier), "nullifier {:?} accepted twice", nullifier);
+                    self.tvl = self.tvl.checked_sub(amount).expect("withdrew more than was deposited");
+                }
+                result
+            }
+            Op::Claim { nullifier: index, recipient } => {
+                let nullifier = nullifier(index % NULLIFIERS);
+                let claim = ix::claim_delayed_withdrawal(&program_id, &self.pool, &nullifier, &self.recipient(recipient));
+                self.process(&claim)
+            }
+            Op::SetLimits {
+                tvl_cap,
+                epoch_outflow_limit,
+                window_outflow_limit,
+                window_seconds,
+                withdrawal_delay,
+            } => {
+                let limits = PoolLimits {
+                    tvl_cap,
+                    epoch_outflow_limit,
+                    window_outflow_limit,
+                    window_seconds: window_seconds as i64,
+                    withdrawal_delay: withdrawal_delay as i64,
+                };
+                self.process(&ix::set_pool_limits(&program_id, &self.pool, &self.authority, limits))
+            }
+            Op::Pause { operations, expires_in } => {
+                let expires_at = expires_in.map(|seconds| runtime::unix_timestamp() + seconds as i64);
+                self.process(&ix::pause(&program_id, &self.pool, &self.authority, operations, expires_at))
+            }
+            Op::Unpause { operations } => {
+                self.process(&ix::unpause(&program_id, &self.pool, &self.authority, operations))
+            }
+            Op::AdvanceClock { seconds } => {
+                runtime::set_unix_timestamp(runtime::unix_timestamp() + seconds as i64);
+                Ok(())
+            }
+        };
+        self.check_invariants();
+        result
+    }
+
+    pub fn pool_state(&self) -> PoolState {
+        *PoolState::from_bytes(&self.bank.get(&self.pool).data).expect("pool state")
+    }
+
+    /// Panic unless the pool's accounting matches the model
+    pub fn check_invariants(&self) {
+        let pool_state = self.pool_state();
+        let vault_rent = Rent::default().minimum_balance(0);
+        assert_eq!(pool_state.tvl, self.tvl, "pool tvl diverged from accepted deposits and withdrawals");
+        assert!(
+            self.bank.lamports(&self.vault) >= pool_state.tvl.saturating_add(vault_rent),
+            "vault holds {} lamports for a tvl of {}",
+            self.bank.lamports(&self.vault),
+            pool_state.tvl
+        );
+        assert_eq!(pool_state.nullifier_count, self.spent.len() as u64, "nullifier count diverged");
+        for nullifier in &self.spent {
+            let record = pda::nullifier_address(&shadow_privacy::id(), &self.pool, nullifier);
+            let record = NullifierAccount::load(&self.bank.get(&record).data).expect("spent nullifier has a record");
+            assert_eq!(&record.nullifier, nullifier);
+        }
+    }
+
+    fn process(&mut self, instruction: &Instruction) -> Result<(), ProgramError> {
+        self.bank.process(instruction)
+    }
+
+    fn amount(&self, amount: Amount) -> u64 {
+        match amount {
+            Amount::Denomination => self.denomination,
+            Amount::Raw(amount) => amount,
+        }
+    }
+
+    fn recipient(&self, index: u8) -> Pubkey {
+        self.recipients[(index % RECIPIENTS) as usize]
+    }
+}
+
+fn nullifier(index: u8) -> [u8; 32] {
+    [index + 1; 32]
+}
+
+/// A well-formed note ciphertext without a memo
+fn note() -> Vec<u8> {
+    let mut data = vec![NOTE_CIPHERTEXT_VERSION];
+    data.resize(VALUE_CIPHERTEXT_LEN, 1);
+    data
+}
+
+/// Run a scenario, panicking on any broken invariant
+pub fn run(scenario: &Scenario) {
+    let denomination = match scenario.denomination {
+        Amount::Denomination => DENOMINATION,
+        Amount::Raw(0) => return,
+        Amount::Raw(amount) => amount,
+    };
+    let cache_size = scenario.nullifier_cache_size as u16 % (PoolState::MAX_CACHE_ENTRIES as u16 + 1);
+    let mut harness = PoolHarness::new(denomination, cache_size);
+    for op in scenario.ops.iter().take(MAX_OPS) {
+        let _ = harness.apply(op);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shadow_privacy::PrivacyError;
+
+    #[test]
+    fn test_nullifier_past_cache_rejected() {
+        let mut harness = PoolHarness::new(DENOMINATION, 1);
+        for commitment in 0..3 {
+            let deposit = Op::Deposit { commitment, amount: Amount::Denomination };
+            harness.apply(&deposit).unwrap();
+        }
+        let withdraw = |nullifier| Op::Withdraw {
+            nullifier,
+            recipient: 0,
+            amount: Amount::Denomination,
+            change: None,
+            delayed: true,
+        };
+
+        harness.apply(&withdraw(0)).unwrap();
+        harness.apply(&withdraw(1)).unwrap();
+        assert_eq!(harness.pool_state().nullifier_cache_len, 1);
+        // Only the record PDA remembers the second nullifier
+        assert_eq!(harness.apply(&withdraw(1)), Err(PrivacyError::NullifierAlreadyUsed.into()));
+        assert_eq!(harness.pool_state().tvl, DENOMINATION);
+    }
+
+    #[test]
+    fn test_delayed_withdrawal_claimed() {
+        let mut harness = PoolHarness::new(DENOMINATION, 4);
+        harness.apply(&Op::Deposit { commitment: 1, amount: Amount::Denomination }).unwrap();
+        let limits = Op::SetLimits {
+            tvl_cap: 0,
+            epoch_outflow_limit: 1,
+            window_outflow_limit: 0,
+            window_seconds: 0,
+            withdrawal_delay: 60,
+        };
+        harness.apply(&limits).unwrap();
+        let withdraw = Op::Withdraw {
+            nullifier: 0,
+            recipient: 2,
+            amount: Amount::Denomination,
+            change: Some(9),
+            delayed: true,
+        };
+        harness.apply(&withdraw).unwrap();
+        assert_eq!(harness.pool_state().tvl, 0);
+
+        let claim = Op::Claim { nullifier: 0, recipient: 2 };
+        assert_eq!(harness.apply(&claim), Err(PrivacyError::WithdrawalNotReleased.into()));
+        harness.apply(&Op::AdvanceClock { seconds: 60 }).unwrap();
+        harness.apply(&claim).unwrap();
+        assert_eq!(harness.apply(&withdraw), Err(PrivacyError::NullifierAlreadyUsed.into()));
+    }
+
+    #[test]
+    fn test_other_amounts_rejected() {
+        let mut harness = PoolHarness::new(DENOMINATION, 4);
+        harness.apply(&Op::Deposit { commitment: 1, amount: Amount::Denomination }).unwrap();
+        for amount in [DENOMINATION - 1, DENOMINATION + 1, u64::MAX] {
+            let deposit = Op::Deposit { commitment: 2, amount: Amount::Raw(amount) };
+            assert_eq!(harness.apply(&deposit), Err(PrivacyError::InvalidAmount.into()));
+            let withdraw = Op::Withdraw {
+                nullifier: 0,
+                recipient: 0,
+                amount: Amount::Raw(amount),
+                change: None,
+                delayed: false,
+            };
+            assert_eq!(harness.apply(&withdraw), Err(PrivacyError::InvalidAmount.into()));
+        }
+    }
+}
@@ -0,0 +1,272 @@
+//! Native stand-in for the parts of the Solana runtime the processor uses
+//!
+//! Syscall stubs serve Clock and Rent and run System program CPIs against the
+//! AccountInfos they are passed, checking signers and PDA seeds the way the
+//! runtime does. Native AccountInfos cannot grow, so accounts the program
+//! creates are given their final size up front and Allocate only checks it.
+
+use solana_program::{
+    account_info::AccountInfo,
+    clock::{Clock, Epoch},
+    entrypoint::{ProgramResult, SUCCESS},
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction::SystemInstruction,
+    system_program,
+};
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Once,
+    },
+};
+
+/// Seconds per epoch in the simulated clock
+pub const EPOCH_SECONDS: i64 = 2 * 24 * 60 * 60;
+
+static UNIX_TIMESTAMP: AtomicI64 = AtomicI64::new(1_700_000_000);
+static INSTALL: Once = Once::new();
+
+/// Install the stubs (once per process)
+pub fn install() {
+    INSTALL.call_once(|| {
+        set_syscall_stubs(Box::new(Stubs));
+    });
+}
+
+pub fn unix_timestamp() -> i64 {
+    UNIX_TIMESTAMP.load(Ordering::Relaxed)
+}
+
+pub fn set_unix_timestamp(unix_timestamp: i64) {
+    UNIX_TIMESTAMP.store(unix_timestamp, Ordering::Relaxed);
+}
+
+struct Stubs;
+
+impl SyscallStubs for Stubs {
+    fn sol_log(&self, _message: &str) {}
+
+    fn sol_log_data(&self, _fields: &[&[u8]]) {}
+
+    fn sol_log_compute_units(&self) {}
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let unix_timestamp = unix_timestamp();
+        let clock = Clock {
+            slot: (unix_timestamp * 2) as u64,
+            epoch_start_timestamp: unix_timestamp - unix_timestamp.rem_euclid(EPOCH_SECONDS),
+            epoch: unix_timestamp.div_euclid(EPOCH_SECONDS) as u64,
+            leader_schedule_epoch: 0,
+            unix_timestamp,
+        };
+        // Sysvar::get passes a pointer to a Clock
+        unsafe { *(var_addr as *mut Clock) = clock };
+        SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        SUCCESS
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        if instruction.program_id != system_program::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let signers = signers_seeds
+            .iter()
+            .map(|seeds| Pubkey::create_program_address(seeds, &shadow_privacy::id()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| ProgramError::InvalidSeeds)?;
+        let account = |index: usize| -> Result<&AccountInfo, ProgramError> {
+            let meta = instruction.accounts.get(index).ok_or(ProgramError::NotEnoughAccountKeys)?;
+            let info = account_infos
+                .iter()
+                .find(|info| info.key == &meta.pubkey)
+                .ok_or(ProgramError::NotEnoughAccountKeys)?;
+            if meta.is_signer && !info.is_signer && !signers.contains(info.key) {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            if meta.is_writable && !info.is_writable {
+                return Err(ProgramError::InvalidArgument);
+            }
+            Ok(info)
+        };
+
+        let system_instruction: SystemInstruction =
+            bincode::deserialize(&instruction.data).map_err(|_| ProgramError::InvalidInstructionData)?;
+
+        match system_instruction {
+            SystemInstruction::Transfer { lamports } => {
+                let from = account(0)?;
+                let to = account(1)?;
+                if from.owner != &system_program::id() {
+                    return Err(ProgramError::InvalidArgument);
+                }
+                move_lamports(from, to, lamports)
+            }
+            SystemInstruction::CreateAccount { lamports, space, owner } => {
+                let from = account(0)?;
+                let to = account(1)?;
+                if to.lamports() > 0 || to.owner != &system_program::id() {
+                    return Err(ProgramError::AccountAlreadyInitialized);
+                }
+                allocate(to, space)?;
+                move_lamports(from, to, lamports)?;
+                to.assign(&owner);
+                Ok(())
+            }
+            SystemInstruction::Allocate { space } => {
+                let to = account(0)?;
+                if to.owner != &system_program::id() {
+                    return Err(ProgramError::AccountAlreadyInitialized);
+                }
+                allocate(to, space)
+            }
+            SystemInstruction::Assign { owner } => {
+                let to = account(0)?;
+                if to.owner != &system_program::id() {
+                    return Err(ProgramError::IllegalOwner);
+                }
+                to.assign(&owner);
+                Ok(())
+            }
+            _ => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+}
+
+fn allocate(account: &AccountInfo, space: u64) -> ProgramResult {
+    // Sized by the harness; a mismatch is a harness bug, not a program bug
+    assert_eq!(account.data_len() as u64, space, "{} pre-sized to the wrong length", account.key);
+    Ok(())
+}
+
+fn move_lamports(from: &AccountInfo, to: &AccountInfo, lamports: u64) -> ProgramResult {
+    let from_lamports = from.lamports().checked_sub(lamports).ok_or(ProgramError::InsufficientFunds)?;
+    let to_lamports = to.lamports().checked_add(lamports).ok_or(ProgramError::ArithmeticOverflow)?;
+    **from.try_borrow_mut_lamports()? = from_lamports;
+    **to.try_borrow_mut_lamports()? = to_lamports;
+    Ok(())
+}
+
+/// Owned backing storage for an AccountInfo
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimAccount {
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+}
+
+impl SimAccount {
+    pub fn new(owner: Pubkey, lamports: u64, data: Vec<u8>) -> Self {
+        SimAccount { owner, lamports, data }
+    }
+
+    /// A system account with no lamports, sized for what will be created there
+    pub fn unallocated(space: usize) -> Self {
+        Self::new(system_program::id(), 0, vec![0; space])
+    }
+}
+
+/// Accounts by address, with transaction-style execution
+#[derive(Clone, Debug, Default)]
+pub struct Bank {
+    pub accounts: BTreeMap<Pubkey, SimAccount>,
+}
+
+impl Bank {
+    pub fn add(&mut self, key: Pubkey, account: SimAccount) {
+        self.accounts.insert(key, account);
+    }
+
+    pub fn get(&self, key: &Pubkey) -> &SimAccount {
+        &self.accounts[key]
+    }
+
+    pub fn lamports(&self, key: &Pubkey) -> u64 {
+        self.accounts.get(key).map_or(0, |account| account.lamports)
+    }
+
+    /// Run one instruction, rolling every account back if it fails
+    ///
+    /// Panics if the program breaks a rule the runtime enforces: lamports
+    /// must balance and read-only accounts must not change.
+    pub fn process(&mut self, instruction: &Instruction) -> ProgramResult {
+        install();
+        let snapshot = self.accounts.clone();
+        let result = self.execute(&instruction.program_id, &instruction.accounts, &instruction.data);
+        if result.is_err() {
+            self.accounts = snapshot;
+            return result;
+        }
+
+        let total = |accounts: &BTreeMap<Pubkey, SimAccount>| -> u128 {
+            accounts.values().map(|account| account.lamports as u128).sum()
+        };
+        assert_eq!(total(&snapshot), total(&self.accounts), "lamports created or destroyed");
+        for meta in instruction.accounts.iter().filter(|meta| !meta.is_writable) {
+            assert_eq!(snapshot[&meta.pubkey], self.accounts[&meta.pubkey], "read-only {} modified", meta.pubkey);
+        }
+
+        // Accounts left without lamports are garbage-collected
+        for account in self.accounts.values_mut().filter(|account| account.lamports == 0) {
+            account.owner = system_program::id();
+            account.data.fill(0);
+        }
+        result
+    }
+
+    fn execute(&mut self, program_id: &Pubkey, metas: &[AccountMeta], data: &[u8]) -> ProgramResult {
+        let mut by_key: BTreeMap<Pubkey, (&mut SimAccount, Pubkey)> = self
+            .accounts
+            .iter_mut()
+            .map(|(key, account)| (*key, (account, *key)))
+            .collect();
+        let mut keys = Vec::with_capacity(metas.len());
+        let mut owned = Vec::with_capacity(metas.len());
+        for meta in metas {
+            // The harness never passes an account twice
+            let (account, key) = by_key.remove(&meta.pubkey).expect("unknown or duplicate account");
+            keys.push(key);
+            owned.push((account, meta.is_signer, meta.is_writable));
+        }
+        let infos: Vec<AccountInfo> = owned
+            .iter_mut()
+            .zip(&keys)
+            .map(|((account, is_signer, is_writable), key)| {
+                AccountInfo::new(
+                    key,
+                    *is_signer,
+                    *is_writable,
+                    &mut account.lamports,
+                    &mut account.data,
+                    &account.owner,
+                    false,
+                    Epoch::default(),
+                )
+            })
+            .collect();
+
+        let result = shadow_privacy::Processor::process(program_id, &infos, data);
+
+        // assign() writes through the owner reference; copy it back
+        let owners: Vec<Pubkey> = infos.iter().map(|info| *info.owner).collect();
+        drop(infos);
+        for ((account, _, _), owner) in owned.iter_mut().zip(owners) {
+            account.owner = owner;
+        }
+        result
+    }
+}
@@ -0,0 +1,196 @@
+//! Auditor notes
+//!
+//! A pool authority can register an auditor's Baby Jubjub public key with
+//! SetAuditor. From then on, every commitment Deposit, Withdraw (change) and
+//! PrivateTransfer append must come with an [`AuditorNote`]:
+//!
+//! ```text
+//! ephemeral_key = r · B8
+//! shared        = r · auditor_key
+//! ciphertext[i] = plaintext[i] + Poseidon(shared.x, shared.y, i)
+//! plaintext     = [owner public key, amount, nonce]
+//! ```
+//!
+//! and a Groth16 proof against the pool's AuditorNote verification key
+//! (`circuits/auditor_note.circom`) that the plaintext opens the commitment:
+//! `commitment = Poseidon(owner public key, amount, nonce)`. Public inputs
+//! are `[auditor_key.x, auditor_key.y, commitment, ephemeral_key.x,
+//! ephemeral_key.y, ciphertext[0..3]]`, 32-byte little-endian field elements.
+//!
+//! Baby Jubjub is the twisted Edwards curve `a·x² + y² = 1 + d·x²·y²` over
+//! the BN254 scalar field with circomlib's parameters. The program only
+//! validates keys; the auditor decrypts with its secret scalar off-chain
+//! (`shadow-privacy-note`).
+
+use crate::{
+    events::{PrivacyEvent, TreeLeaf},
+    state::AuditorNote,
+};
+use ark_bn254::Fr;
+use ark_ff::{Field, MontFp, One, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use solana_program::pubkey::Pubkey;
+
+/// Curve coefficient a
+pub const BABYJUBJUB_A: Fr = MontFp!("168700");
+
+/// Curve coefficient d
+pub const BABYJUBJUB_D: Fr = MontFp!("168696");
+
+/// circomlib's `Base8`, generator of the prime-order subgroup
+pub const BASE8: Point = Point {
+    x: MontFp!("5299619240641551281634865583518297030282874472190772894086521144482721001553"),
+    y: MontFp!("16950150798460657717958625567821834550301663161624707787222815936182638968203"),
+};
+
+/// Order of the subgroup generated by [`BASE8`], little-endian
+pub const SUBGROUP_ORDER: [u8; 32] = [
+    0xf1, 0x26, 0x21, 0x39, 0xdc, 0x97, 0x72, 0x67, 0x0a, 0xee, 0x20, 0x39, 0xb8, 0xed, 0x3e, 0xab,
+    0x0b, 0x2b, 0x30, 0xd0, 0xb6, 0x08, 0x0a, 0x37, 0x05, 0x34, 0x26, 0x5c, 0xce, 0x89, 0x0c, 0x06,
+];
+
+/// Number of public inputs of the AuditorNote circuit
+pub const AUDITOR_PUBLIC_INPUTS: usize = 8;
+
+/// Affine Baby Jubjub point
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Point {
+    pub x: Fr,
+    pub y: Fr,
+}
+
+impl Point {
+    pub const IDENTITY: Point = Point { x: MontFp!("0"), y: MontFp!("1") };
+
+    /// Point from little-endian (x, y), if both are canonical and on the curve
+    pub fn from_bytes(bytes: &[[u8; 32]; 2]) -> Option<Point> {
+        let x = Fr::deserialize_compressed(&bytes[0][..]).ok()?;
+        let y = Fr::deserialize_compressed(&bytes[1][..]).ok()?;
+        let point = Point { x, y };
+        point.is_on_curve().then_some(point)
+    }
+
+    /// Little-endian (x, y), the encoding of keys and public inputs
+    pub fn to_bytes(&self) -> [[u8; 32]; 2] {
+        let mut bytes = [[0u8; 32]; 2];
+        self.x.serialize_compressed(&mut bytes[0][..]).expect("32-byte field element");
+        self.y.serialize_compressed(&mut bytes[1][..]).expect("32-byte field element");
+        bytes
+    }
+
+    pub fn is_on_curve(&self) -> bool {
+        let x2 = self.x.square();
+        let y2 = self.y.square();
+        BABYJUBJUB_A * x2 + y2 == Fr::one() + BABYJUBJUB_D * x2 * y2
+    }
+
+    /// Twisted Edwards addition (complete on Baby Jubjub)
+    pub fn add(&self, other: &Point) -> Point {
+        let xy = self.x * other.x * self.y * other.y;
+        let x_denominator = (Fr::one() + BABYJUBJUB_D * xy).inverse().expect("complete addition");
+        let y_denominator = (Fr::one() - BABYJUBJUB_D * xy).inverse().expect("complete addition");
+        Point {
+            x: (self.x * other.y + self.y * other.x) * x_denominator,
+            y: (self.y * other.y - BABYJUBJUB_A * self.x * other.x) * y_denominator,
+        }
+    }
+
+    /// `scalar · self`, with `scalar` little-endian
+    pub fn mul(&self, scalar: &[u8; 32]) -> Point {
+        let mut result = Point::IDENTITY;
+        for byte in scalar.iter().rev() {
+            for bit in (0..8).rev() {
+                result = result.add(&result);
+                if (byte >> bit) & 1 == 1 {
+                    result = result.add(self);
+                }
+            }
+        }
+        result
+    }
+
+    /// Whether `8 · self` is the identity, i.e. the point has no
+    /// prime-order component
+    pub fn is_small_order(&self) -> bool {
+        let mut point = *self;
+        for _ in 0..3 {
+            point = point.add(&point);
+        }
+        point.x.is_zero()
+    }
+}
+
+/// Whether `key` can be registered as an auditor key
+///
+/// It must be a curve point outside the small-order torsion; otherwise the
+/// shared secret would take only a handful of values.
+pub fn is_valid_auditor_key(key: &[[u8; 32]; 2]) -> bool {
+    match Point::from_bytes(key) {
+        Some(point) => !point.is_small_order(),
+        None => false,
+    }
+}
+
+impl AuditorNote {
+    /// AuditorNote circuit public inputs for the note of `commitment`
+    pub fn public_inputs(&self, auditor_key: &[[u8; 32]; 2], commitment: &[u8; 32]) -> Vec<Vec<u8>> {
+        let mut inputs = Vec::with_capacity(AUDITOR_PUBLIC_INPUTS);
+        inputs.extend(auditor_key.iter().map(|element| element.to_vec()));
+        inputs.push(commitment.to_vec());
+        inputs.extend(self.ephemeral_key.iter().map(|element| element.to_vec()));
+        inputs.extend(self.ciphertext.iter().map(|element| element.to_vec()));
+        inputs
+    }
+
+    /// `AuditorNoteCreated` event for the note of `leaf` in `pool`
+    pub fn created_event(&self, pool: Pubkey, leaf: TreeLeaf) -> PrivacyEvent {
+        PrivacyEvent::AuditorNoteCreated {
+            pool,
+            leaf,
+            ephemeral_key: self.ephemeral_key,
+            ciphertext: self.ciphertext,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base8_generates_the_subgroup() {
+        assert!(BASE8.is_on_curve());
+        assert!(!BASE8.is_small_order());
+        assert_eq!(BASE8.mul(&SUBGROUP_ORDER), Point::IDENTITY);
+        assert_eq!(Point::from_bytes(&BASE8.to_bytes()), Some(BASE8));
+    }
+
+    #[test]
+    fn test_auditor_key_validation() {
+        let key = BASE8.mul(&[7u8; 32]);
+        assert!(is_valid_auditor_key(&key.to_bytes()));
+
+        // Unset, off-curve, small-order and non-canonical keys
+        let mut off_curve = key.to_bytes();
+        off_curve[1][0] ^= 1;
+        let two_torsion = Point { x: Fr::zero(), y: -Fr::one() };
+        assert!(two_torsion.is_on_curve());
+        for invalid in [[[0u8; 32]; 2], off_curve, Point::IDENTITY.to_bytes(), two_torsion.to_bytes()] {
+            assert!(!is_valid_auditor_key(&invalid));
+        }
+        assert!(!is_valid_auditor_key(&[[0xff; 32]; 2]));
+    }
+
+    #[test]
+    fn test_public_input_order() {
+        let note = AuditorNote {
+            ephemeral_key: [[1u8; 32], [2u8; 32]],
+            ciphertext: [[3u8; 32], [4u8; 32], [5u8; 32]],
+            proof: vec![],
+        };
+        let inputs = note.public_inputs(&[[6u8; 32], [7u8; 32]], &[8u8; 32]);
+        let firsts: Vec<u8> = inputs.iter().map(|input| input[0]).collect();
+        assert_eq!(firsts, [6, 7, 8, 1, 2, 3, 4, 5]);
+        assert_eq!(inputs.len(), AUDITOR_PUBLIC_INPUTS);
+    }
+}
@@ -1,7 +1,8 @@
+use num_derive::FromPrimitive;
 use solana_program::program_error::ProgramError;
 use thiserror::Error;
 
-#[derive(Error, Debug, Copy, Clone)]
+#[derive(Error, Debug, Copy, Clone, FromPrimitive)]
 pub enum PrivacyError {
     #[error("Invalid instruction")]
     InvalidInstruction,
@@ -59,6 +60,69 @@ pub enum PrivacyError {
 
     #[error("Invalid ring size")]
     InvalidRingSize,
+
+    #[error("Protocol config not initialized")]
+    ConfigNotInitialized,
+
+    #[error("Invalid protocol config")]
+    InvalidConfig,
+
+    #[error("No pending authority transfer")]
+    NoPendingAuthority,
+
+    #[error("Invalid multisig")]
+    InvalidMultisig,
+
+    #[error("Proposal does not have enough approvals")]
+    ProposalNotApproved,
+
+    #[error("Proposal already executed")]
+    ProposalAlreadyExecuted,
+
+    #[error("Pool operation paused")]
+    PoolPaused,
+
+    #[error("Deposit would exceed the pool TVL cap")]
+    TvlCapExceeded,
+
+    #[error("Withdrawal exceeds the pool outflow limit")]
+    WithdrawalLimitExceeded,
+
+    #[error("Delayed withdrawal not yet claimable")]
+    WithdrawalNotReleased,
+
+    #[error("Account uses an old layout and must be migrated")]
+    AccountNeedsMigration,
+
+    #[error("Account is not owned by the expected program")]
+    InvalidAccountOwner,
+
+    #[error("Account address does not match the expected PDA")]
+    InvalidPda,
+
+    #[error("Account must be writable")]
+    AccountNotWritable,
+
+    #[error("Same account passed more than once")]
+    DuplicateAccount,
+
+    #[error("Malformed encrypted note")]
+    InvalidNoteCiphertext,
+
+    #[error("Auditor key is not a prime-order Baby Jubjub point")]
+    InvalidAuditorKey,
+
+    #[error("Pool has an auditor; new notes need an auditor note")]
+    AuditorNoteRequired,
+
+    #[error("Auditor note without a pool auditor or a new note")]
+    UnexpectedAuditorNote,
+
+    #[error("Tree depth must be between 1 and 32")]
+    InvalidTreeDepth,
+
+    #[error("Commitment tree is full")]
+    MerkleTreeFull,
 }
 
 impl From<PrivacyError> for ProgramError {
@@ -0,0 +1,318 @@
+use crate::state::{AccountType, CircuitType, ConfigParams, PoolLimits};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{entrypoint::ProgramResult, log::sol_log_data, pubkey::Pubkey};
+
+/// A commitment appended to a pool's Merkle tree
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
+pub struct TreeLeaf {
+    /// Position of the leaf (the pool's commitment count before the append)
+    pub index: u64,
+    pub commitment: [u8; 32],
+}
+
+/// Events emitted by the Shadow Privacy program
+///
+/// Each event is Borsh-encoded and written with `sol_log_data`, so it shows up
+/// base64-encoded in the transaction logs as `Program data: ...`. New variants
+/// are only ever appended, so existing discriminants stay stable for decoders.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub enum PrivacyEvent {
+    /// Protocol config account created
+    ConfigInitialized {
+        /// Config admin
+        admin: Pubkey,
+        /// Initial limits
+        params: ConfigParams,
+    },
+
+    /// Protocol config changed by the admin
+    ConfigUpdated {
+        /// Admin that signed the update
+        updated_by: Pubkey,
+        /// Admin after the update
+        admin: Pubkey,
+        /// Limits after the update
+        params: ConfigParams,
+    },
+
+    /// Pool authority transfer proposed
+    AuthorityProposed {
+        pool: Pubkey,
+        authority: Pubkey,
+        pending_authority: Pubkey,
+    },
+
+    /// Pending pool authority accepted the transfer
+    AuthorityAccepted {
+        pool: Pubkey,
+        previous_authority: Pubkey,
+        authority: Pubkey,
+    },
+
+    /// Pool Merkle root replaced by the authority
+    RootUpdated {
+        pool: Pubkey,
+        root: [u8; 32],
+    },
+
+    /// Pool guardian set or cleared by the authority
+    GuardianSet {
+        pool: Pubkey,
+        guardian: Option<Pubkey>,
+    },
+
+    /// Pool operations paused by the guardian or authority
+    PoolPaused {
+        pool: Pubkey,
+        paused_by: Pubkey,
+        /// PAUSE_* flags now in effect
+        paused: u8,
+        /// 0 = until unpaused
+        expires_at: i64,
+    },
+
+    /// Pool operations unpaused by the authority
+    PoolUnpaused {
+        pool: Pubkey,
+        /// PAUSE_* flags still in effect
+        paused: u8,
+    },
+
+    /// Pool deposit cap / withdrawal limits changed by the authority
+    LimitsUpdated {
+        pool: Pubkey,
+        limits: PoolLimits,
+    },
+
+    /// Over-limit withdrawal moved into a delayed withdrawal escrow
+    WithdrawalQueued {
+        pool: Pubkey,
+        delayed_withdrawal: Pubkey,
+        recipient: Pubkey,
+        amount: u64,
+        release_at: i64,
+    },
+
+    /// Delayed withdrawal paid out
+    DelayedWithdrawalClaimed {
+        pool: Pubkey,
+        delayed_withdrawal: Pubkey,
+        recipient: Pubkey,
+        amount: u64,
+    },
+
+    /// Multisig executed an approved proposal
+    ProposalExecuted {
+        multisig: Pubkey,
+        proposal: Pubkey,
+        index: u64,
+    },
+
+    /// Pool state and vault PDAs created
+    PoolInitialized {
+        pool: Pubkey,
+        authority: Pubkey,
+        vault: Pubkey,
+        tree_depth: u8,
+        denomination: u64,
+    },
+
+    /// Deposit moved into the vault and its commitment appended to the tree
+    Deposit {
+        pool: Pubkey,
+        depositor: Pubkey,
+        leaf: TreeLeaf,
+        /// Pool root after the append
+        root: [u8; 32],
+        amount: u64,
+    },
+
+    /// Nullifier spent and funds released (or queued, see `WithdrawalQueued`)
+    Withdraw {
+        pool: Pubkey,
+        nullifier: [u8; 32],
+        recipient: Pubkey,
+        /// Amount leaving the vault, including `fee` and `record_rent`
+        ///
+        /// The recipient is paid `amount - fee - record_rent`.
+        amount: u64,
+        /// Part of `amount` paid to a relayer (not charged yet, always 0)
+        fee: u64,
+        /// Part of `amount` kept as rent for the nullifier record
+        record_rent: u64,
+        /// Change commitment appended to the tree, if any
+        change: Option<TreeLeaf>,
+        /// Pool root after the withdrawal
+        root: [u8; 32],
+        /// Payout moved into a delayed withdrawal escrow instead of sent
+        delayed: bool,
+    },
+
+    /// Key image spent and the recipient's commitment appended to the tree
+    PrivateTransfer {
+        pool: Pubkey,
+        key_image: [u8; 32],
+        ring_size: u32,
+        leaf: TreeLeaf,
+        /// Pool root after the append
+        root: [u8; 32],
+    },
+
+    /// Private asset issued
+    AssetIssued {
+        asset: Pubkey,
+        asset_id: [u8; 32],
+        issuer: Pubkey,
+        decimals: u8,
+        total_supply: u64,
+    },
+
+    /// Asset note spent and a new note commitment created
+    AssetTransferred {
+        asset: Pubkey,
+        nullifier: [u8; 32],
+        commitment: [u8; 32],
+        /// Asset note count after the transfer
+        note_count: u64,
+    },
+
+    /// Verification key created or replaced
+    VerificationKeyStored {
+        pool: Pubkey,
+        circuit_type: CircuitType,
+        vk_account: Pubkey,
+        authority: Pubkey,
+        vk_len: u32,
+        stored_at: i64,
+    },
+
+    /// Relayer PDA created
+    RelayerRegistered {
+        relayer: Pubkey,
+        relayer_account: Pubkey,
+        stake: u64,
+        endpoint: String,
+        registered_at: i64,
+    },
+
+    /// Relayer liveness heartbeat
+    RelayerHeartbeat {
+        relayer: Pubkey,
+        timestamp: i64,
+    },
+
+    /// Relay outcome recorded against a relayer
+    RelayReported {
+        relayer: Pubkey,
+        reported_by: Pubkey,
+        success: bool,
+        successful_relays: u64,
+        failed_relays: u64,
+        /// Decayed score after the report, None while unscored
+        reputation: Option<u8>,
+    },
+
+    /// Legacy account rewritten in the current layout
+    AccountMigrated {
+        account: Pubkey,
+        account_type: AccountType,
+        size: u64,
+    },
+
+    /// Multisig PDA created
+    MultisigCreated {
+        multisig: Pubkey,
+        threshold: u8,
+        signers: Vec<Pubkey>,
+    },
+
+    /// Multisig proposal created (counts as the proposer's approval)
+    ProposalCreated {
+        multisig: Pubkey,
+        proposal: Pubkey,
+        index: u64,
+        proposer: Pubkey,
+    },
+
+    /// Multisig member approved a proposal
+    ProposalApproved {
+        multisig: Pubkey,
+        proposal: Pubkey,
+        member: Pubkey,
+        approvals: u8,
+    },
+
+    /// Encrypted note for a commitment created by Deposit, PrivateTransfer or
+    /// TransferAsset, emitted right after that instruction's own event
+    ///
+    /// Carries the fields a `ShieldedNote` would hold, so wallets can find
+    /// their notes by trial-decrypting these events alone.
+    NoteCreated {
+        /// Pool, or asset state for asset notes
+        account: Pubkey,
+        /// The new commitment (for asset notes, `index` counts the asset's notes)
+        leaf: TreeLeaf,
+        /// Asset ID, zero for pool notes
+        asset_id: [u8; 32],
+        /// Sender's ephemeral x25519 key
+        tx_public_key: [u8; 32],
+        /// Value part of the note ciphertext (see [`crate::note`])
+        encrypted_value: Vec<u8>,
+        /// Memo part, empty when the note has none
+        encrypted_memo: Vec<u8>,
+    },
+
+    /// Pool auditor key set or cleared by the authority
+    AuditorSet {
+        pool: Pubkey,
+        /// Baby Jubjub key (x, y), None when the auditor was removed
+        auditor_key: Option<[[u8; 32]; 2]>,
+    },
+
+    /// Auditor copy of a note in a pool with an auditor, emitted after the
+    /// instruction's own event (and its `NoteCreated`)
+    AuditorNoteCreated {
+        pool: Pubkey,
+        /// The new commitment
+        leaf: TreeLeaf,
+        /// Sender's ephemeral Baby Jubjub key (x, y)
+        ephemeral_key: [[u8; 32]; 2],
+        /// Masked owner public key, amount and nonce (see [`crate::auditor`])
+        ciphertext: [[u8; 32]; 3],
+    },
+}
+
+impl PrivacyEvent {
+    /// Serialize and log the event
+    pub fn emit(&self) -> ProgramResult {
+        let data = self.try_to_vec()?;
+        sol_log_data(&[&data]);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_encoding_is_stable() {
+        let pool = Pubkey::new_unique();
+        let deposit = PrivacyEvent::Deposit {
+            pool,
+            depositor: Pubkey::new_unique(),
+            leaf: TreeLeaf { index: 7, commitment: [1u8; 32] },
+            root: [2u8; 32],
+            amount: 1_000_000,
+        };
+        let data = deposit.try_to_vec().unwrap();
+        assert_eq!(PrivacyEvent::try_from_slice(&data).unwrap(), deposit);
+
+        // Appended variants keep the discriminants decoders already know
+        let root_updated = PrivacyEvent::RootUpdated { pool, root: [0u8; 32] };
+        assert_eq!(root_updated.try_to_vec().unwrap()[0], 4);
+        assert_eq!(data[0], 13);
+        assert_eq!(&data[1..33], pool.as_ref());
+        assert_eq!(u64::from_le_bytes(data[65..73].try_into().unwrap()), 7);
+    }
+}
@@ -1,45 +1,50 @@
+use crate::state::{AccountType, AuditorNote, ConfigParams, PoolLimits, ProposalAccount};
 use borsh::{BorshDeserialize, BorshSerialize};
+use shank::ShankInstruction;
 use solana_program::pubkey::Pubkey;
 
 /// Instructions supported by the Shadow Privacy program
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, ShankInstruction)]
 pub enum PrivacyInstruction {
     /// Initialize privacy pool
-    ///
-    /// Accounts:
-    /// 0. `[writable]` Pool state account
-    /// 1. `[signer, writable]` Pool authority (pays for vault creation)
-    /// 2. `[writable]` Pool vault PDA (will be created)
-    /// 3. `[]` System program
+    #[account(0, writable, name = "pool", desc = "Pool state PDA (will be created; seeds: \"pool\", authority, denomination)")]
+    #[account(1, writable, signer, name = "authority", desc = "Pool authority (pays for pool and vault creation)")]
+    #[account(2, writable, name = "vault", desc = "Pool vault PDA (will be created)")]
+    #[account(3, name = "system_program", desc = "System program")]
     InitializePool {
-        /// Maximum tree depth for commitments
+        /// Depth of the commitment tree (1 to 32)
         tree_depth: u8,
         /// Denomination for pool (e.g., 0.1 SOL, 1 SOL, 10 SOL)
         denomination: u64,
     },
 
     /// Deposit into privacy pool
-    ///
-    /// Accounts:
-    /// 0. `[writable]` Pool state
-    /// 1. `[writable, signer]` Depositor
-    /// 2. `[writable]` Pool vault
-    /// 3. `[]` System program
+    #[account(0, writable, name = "pool", desc = "Pool state")]
+    #[account(1, writable, signer, name = "depositor", desc = "Depositor")]
+    #[account(2, writable, name = "vault", desc = "Pool vault")]
+    #[account(3, name = "system_program", desc = "System program")]
+    #[account(4, optional, name = "auditor_verification_key", desc = "Verification key account (PDA for AuditorNote circuit; required when the pool has an auditor)")]
     Deposit {
         /// Commitment to deposited amount
         commitment: [u8; 32],
         /// Amount to deposit (must match denomination)
         amount: u64,
+        /// Encrypted note for the depositor's own viewing key (format in the `note` module)
+        encrypted_note: Vec<u8>,
+        /// Auditor copy of the note (required exactly when the pool has an auditor)
+        auditor_note: Option<AuditorNote>,
     },
 
     /// Withdraw from privacy pool using ZK proof
-    ///
-    /// Accounts:
-    /// 0. `[writable]` Pool state
-    /// 1. `[writable]` Pool vault
-    /// 2. `[writable]` Recipient
-    /// 3. `[]` Verification key account (PDA for Transfer circuit)
-    /// 4. `[]` System program
+    #[account(0, writable, name = "pool", desc = "Pool state")]
+    #[account(1, writable, name = "vault", desc = "Pool vault")]
+    #[account(2, writable, name = "recipient", desc = "Recipient (must equal `recipient`)")]
+    #[account(3, name = "verification_key", desc = "Verification key account (PDA for Transfer circuit)")]
+    #[account(4, name = "system_program", desc = "System program")]
+    #[account(5, name = "config", desc = "Protocol config (PDA)")]
+    #[account(6, writable, name = "nullifier_record", desc = "Nullifier record PDA (created here; its rent comes out of `amount`)")]
+    #[account(7, writable, optional, name = "delayed_withdrawal", desc = "Delayed withdrawal (PDA; required when the withdrawal exceeds the pool limits and the pool queues over-limit withdrawals)")]
+    #[account(8, optional, name = "auditor_verification_key", desc = "Verification key account (PDA for AuditorNote circuit; required for change when the pool has an auditor)")]
     Withdraw {
         /// ZK proof of ownership
         proof: Vec<u8>,
@@ -53,14 +58,22 @@ pub enum PrivacyInstruction {
         recipient: Pubkey,
         /// Amount to withdraw
         amount: u64,
+        /// Encrypted change note for the owner's viewing key (format in the
+        /// `note` module; required exactly when there is change)
+        encrypted_note: Option<Vec<u8>>,
+        /// Auditor copy of the change note (required exactly when there is
+        /// change and the pool has an auditor)
+        auditor_note: Option<AuditorNote>,
     },
 
     /// Private transfer using ring signature
-    ///
-    /// Accounts:
-    /// 0. `[writable]` Pool state
-    /// 1. `[writable]` Sender's commitment
-    /// 2. `[writable]` Recipient's commitment
+    #[account(0, writable, name = "pool", desc = "Pool state")]
+    #[account(1, writable, name = "key_image_record", desc = "Key image record PDA (created here)")]
+    #[account(2, writable, name = "recipient_commitment", desc = "Recipient's commitment")]
+    #[account(3, name = "config", desc = "Protocol config (PDA)")]
+    #[account(4, writable, signer, name = "payer", desc = "Payer (key image record rent)")]
+    #[account(5, name = "system_program", desc = "System program")]
+    #[account(6, optional, name = "auditor_verification_key", desc = "Verification key account (PDA for AuditorNote circuit; required when the pool has an auditor)")]
     PrivateTransfer {
         /// Ring signature proof
         ring_signature: Vec<u8>,
@@ -70,16 +83,16 @@ pub enum PrivacyInstruction {
         ring_members: Vec<[u8; 32]>,
         /// New commitment for recipient
         new_commitment: [u8; 32],
-        /// Encrypted amount (for recipient only)
+        /// Encrypted note for the recipient (format in the `note` module)
         encrypted_amount: Vec<u8>,
+        /// Auditor copy of the note (required exactly when the pool has an auditor)
+        auditor_note: Option<AuditorNote>,
     },
 
     /// Verify balance proof
-    ///
-    /// Accounts:
-    /// 0. `[]` Pool state
-    /// 1. `[]` User account
-    /// 2. `[]` Verification key account (PDA for Balance circuit)
+    #[account(0, name = "pool", desc = "Pool state")]
+    #[account(1, name = "user", desc = "User account")]
+    #[account(2, name = "verification_key", desc = "Verification key account (PDA for Balance circuit)")]
     VerifyBalance {
         /// ZK proof of minimum balance
         proof: Vec<u8>,
@@ -90,11 +103,9 @@ pub enum PrivacyInstruction {
     },
 
     /// Issue private asset (Zcash ZSA style)
-    ///
-    /// Accounts:
-    /// 0. `[writable, signer]` Asset issuer
-    /// 1. `[writable]` Asset state
-    /// 2. `[]` System program
+    #[account(0, writable, signer, name = "issuer", desc = "Asset issuer")]
+    #[account(1, writable, name = "asset", desc = "Asset state PDA (will be created; seeds: \"asset\", asset_id)")]
+    #[account(2, name = "system_program", desc = "System program")]
     IssueAsset {
         /// Asset metadata
         name: String,
@@ -107,12 +118,10 @@ pub enum PrivacyInstruction {
     },
 
     /// Transfer shielded asset
-    ///
-    /// Accounts:
-    /// 0. `[writable]` Asset state
-    /// 1. `[writable]` Sender shielded note
-    /// 2. `[writable]` Recipient shielded note
-    /// 3. `[]` Verification key account (PDA for Transfer circuit)
+    #[account(0, writable, name = "asset", desc = "Asset state")]
+    #[account(1, writable, name = "sender_note", desc = "Sender shielded note (unused; notes are published in `NoteCreated` events)")]
+    #[account(2, writable, name = "recipient_note", desc = "Recipient shielded note (unused; notes are published in `NoteCreated` events)")]
+    #[account(3, name = "verification_key", desc = "Verification key account (Transfer circuit PDA of any pool)")]
     TransferAsset {
         /// ZK proof of asset transfer
         proof: Vec<u8>,
@@ -122,30 +131,28 @@ pub enum PrivacyInstruction {
         nullifier: [u8; 32],
         /// New note commitment for recipient
         new_commitment: [u8; 32],
-        /// Encrypted amount and memo
+        /// Encrypted note (amount, nonce, asset ID, memo) for the recipient (format in the `note` module)
         encrypted_data: Vec<u8>,
     },
 
     /// Store verification key for a circuit type
-    ///
-    /// Accounts:
-    /// 0. `[writable]` Verification key account (PDA)
-    /// 1. `[writable]` Pool state
-    /// 2. `[signer]` Pool authority
-    /// 3. `[]` System program
+    #[account(0, writable, name = "verification_key", desc = "Verification key account (PDA)")]
+    #[account(1, name = "pool", desc = "Pool state (read only)")]
+    #[account(2, signer, name = "authority", desc = "Pool authority (also writable when it pays, i.e. without a payer)")]
+    #[account(3, name = "system_program", desc = "System program")]
+    #[account(4, writable, signer, optional, name = "payer", desc = "Payer (defaults to the authority; required when the authority is a multisig)")]
     StoreVerificationKey {
-        /// Circuit type (Transfer, Balance, or RingSignature)
+        /// Circuit type (Transfer, Balance, RingSignature or AuditorNote)
         circuit_type: u8,
         /// Serialized verification key (ark-groth16 VerifyingKey<Bn254>)
         vk_data: Vec<u8>,
     },
 
     /// Register a new relayer in the network
-    ///
-    /// Accounts:
-    /// 0. `[writable]` Relayer account (PDA)
-    /// 1. `[signer, writable]` Relayer wallet
-    /// 2. `[]` System program
+    #[account(0, writable, name = "relayer", desc = "Relayer account (PDA)")]
+    #[account(1, writable, signer, name = "wallet", desc = "Relayer wallet")]
+    #[account(2, name = "system_program", desc = "System program")]
+    #[account(3, name = "config", desc = "Protocol config (PDA)")]
     RegisterRelayer {
         /// Service endpoint (URL or IP)
         endpoint: String,
@@ -154,20 +161,181 @@ pub enum PrivacyInstruction {
     },
 
     /// Update relayer heartbeat
-    ///
-    /// Accounts:
-    /// 0. `[writable]` Relayer account (PDA)
-    /// 1. `[signer]` Relayer wallet
+    #[account(0, writable, name = "relayer", desc = "Relayer account (PDA)")]
+    #[account(1, signer, name = "wallet", desc = "Relayer wallet")]
     UpdateHeartbeat,
 
     /// Report relay success/failure (updates reputation)
     ///
-    /// Accounts:
-    /// 0. `[writable]` Relayer account (PDA)
-    /// 1. `[]` Pool state (for verification)
-    /// 2. `[signer]` Pool authority or relayer
+    /// Only the protocol config admin reports on relayers; a relayer may
+    /// also report its own failures.
+    #[account(0, writable, name = "relayer", desc = "Relayer account (PDA)")]
+    #[account(1, name = "config", desc = "Protocol config PDA")]
+    #[account(2, signer, name = "reporter", desc = "Config admin, or the relayer itself when reporting a failure")]
     ReportRelay {
         /// Was relay successful?
         success: bool,
     },
+
+    /// Migrate a relayer account created before reputation buckets existed
+    ///
+    /// Same as `Migrate { account_type: AccountType::Relayer }`.
+    #[account(0, writable, name = "relayer", desc = "Relayer account (PDA)")]
+    #[account(1, writable, signer, name = "wallet", desc = "Relayer wallet (pays the extra rent)")]
+    #[account(2, name = "system_program", desc = "System program")]
+    MigrateRelayer,
+
+    /// Create the program-wide config account
+    ///
+    /// The signer must be the program's upgrade authority and becomes the
+    /// config admin.
+    #[account(0, writable, name = "config", desc = "Protocol config (PDA)")]
+    #[account(1, writable, signer, name = "admin", desc = "Upgrade authority (pays for account creation)")]
+    #[account(2, name = "system_program", desc = "System program")]
+    #[account(3, name = "program_data", desc = "Program data account (upgradeable loader)")]
+    InitializeConfig {
+        /// Initial protocol limits
+        params: ConfigParams,
+    },
+
+    /// Update protocol limits and optionally hand over the admin key
+    #[account(0, writable, name = "config", desc = "Protocol config (PDA)")]
+    #[account(1, signer, name = "admin", desc = "Current admin")]
+    UpdateConfig {
+        /// New protocol limits
+        params: ConfigParams,
+        /// New admin (None keeps the current admin)
+        new_admin: Option<Pubkey>,
+    },
+
+    /// Replace the pool's Merkle root after an off-chain tree update
+    ///
+    /// Only needed by pools migrated with commitments from before the
+    /// on-chain tree; other pools recompute their root at each commitment.
+    #[account(0, writable, name = "pool", desc = "Pool state")]
+    #[account(1, signer, name = "authority", desc = "Pool authority")]
+    UpdateRoot {
+        /// New Merkle root
+        new_root: [u8; 32],
+    },
+
+    /// Propose a new pool authority (first step of a two-step transfer)
+    #[account(0, writable, name = "pool", desc = "Pool state")]
+    #[account(1, signer, name = "authority", desc = "Current pool authority")]
+    ProposeAuthority {
+        /// Authority that must accept the transfer
+        new_authority: Pubkey,
+    },
+
+    /// Accept a pending pool authority transfer
+    #[account(0, writable, name = "pool", desc = "Pool state")]
+    #[account(1, signer, name = "pending_authority", desc = "Pending authority")]
+    AcceptAuthority,
+
+    /// Create an M-of-N multisig PDA that can act as a pool authority
+    #[account(0, writable, name = "multisig", desc = "Multisig (PDA)")]
+    #[account(1, writable, signer, name = "payer", desc = "Payer")]
+    #[account(2, name = "system_program", desc = "System program")]
+    CreateMultisig {
+        /// Unique key for PDA derivation
+        create_key: Pubkey,
+        /// Approvals required to execute a proposal
+        threshold: u8,
+        /// Multisig members
+        signers: Vec<Pubkey>,
+    },
+
+    /// Propose an instruction for the multisig to sign (counts as the proposer's approval)
+    #[account(0, writable, name = "multisig", desc = "Multisig (PDA)")]
+    #[account(1, writable, name = "proposal", desc = "Proposal (PDA, index = multisig.proposal_count)")]
+    #[account(2, writable, signer, name = "proposer", desc = "Proposer (member, pays for the proposal account)")]
+    #[account(3, name = "system_program", desc = "System program")]
+    CreateProposal {
+        /// Borsh-encoded PrivacyInstruction
+        instruction_data: Vec<u8>,
+        /// Accounts for the instruction, in order
+        accounts: Vec<ProposalAccount>,
+    },
+
+    /// Approve a multisig proposal
+    #[account(0, name = "multisig", desc = "Multisig (PDA)")]
+    #[account(1, writable, name = "proposal", desc = "Proposal (PDA)")]
+    #[account(2, signer, name = "member", desc = "Member")]
+    ApproveProposal,
+
+    /// Execute an approved proposal, signing as the multisig
+    ///
+    /// Followed by the proposal's accounts, in order.
+    #[account(0, name = "multisig", desc = "Multisig (PDA)")]
+    #[account(1, writable, name = "proposal", desc = "Proposal (PDA)")]
+    #[account(2, name = "program", desc = "This program")]
+    ExecuteProposal,
+
+    /// Set or clear the pool's emergency guardian
+    #[account(0, writable, name = "pool", desc = "Pool state")]
+    #[account(1, signer, name = "authority", desc = "Pool authority")]
+    SetGuardian {
+        /// New guardian (None removes it)
+        guardian: Option<Pubkey>,
+    },
+
+    /// Pause pool operations
+    #[account(0, writable, name = "pool", desc = "Pool state")]
+    #[account(1, signer, name = "guardian", desc = "Pool guardian or authority")]
+    Pause {
+        /// Operations to pause (PoolState::PAUSE_* flags)
+        operations: u8,
+        /// Unix timestamp when the pause lapses (None = until unpaused)
+        expires_at: Option<i64>,
+    },
+
+    /// Unpause pool operations
+    #[account(0, writable, name = "pool", desc = "Pool state")]
+    #[account(1, signer, name = "authority", desc = "Pool authority")]
+    Unpause {
+        /// Operations to unpause (PoolState::PAUSE_* flags)
+        operations: u8,
+    },
+
+    /// Set the pool's TVL cap and withdrawal rate limits
+    #[account(0, writable, name = "pool", desc = "Pool state")]
+    #[account(1, signer, name = "authority", desc = "Pool authority")]
+    SetPoolLimits {
+        /// New limits (0 disables a limit)
+        limits: PoolLimits,
+    },
+
+    /// Pay out a delayed withdrawal after its release time
+    ///
+    /// The payout counts toward the pool's outflow limits at this point.
+    #[account(0, writable, name = "pool", desc = "Pool state")]
+    #[account(1, writable, name = "delayed_withdrawal", desc = "Delayed withdrawal (PDA, closed)")]
+    #[account(2, writable, name = "recipient", desc = "Recipient")]
+    ClaimDelayedWithdrawal,
+
+    /// Rewrite an account created before account headers existed in the current layout
+    ///
+    /// Reallocates the account to the current LEN (topping up rent from the payer).
+    /// Accounts already in the current layout are left untouched. Headerless
+    /// verification keys and relayers are only accepted at the PDA their own
+    /// fields derive; legacy pools and assets were created at keypair
+    /// addresses, so a pool's stored vault must derive from its address.
+    #[account(0, writable, name = "account", desc = "Account to migrate")]
+    #[account(1, writable, signer, name = "payer", desc = "Payer (extra rent)")]
+    #[account(2, name = "system_program", desc = "System program")]
+    Migrate {
+        /// Type the account holds (Pool, Asset, VerificationKey or Relayer)
+        account_type: AccountType,
+    },
+
+    /// Set or clear the pool's auditor encryption key
+    ///
+    /// Notes created afterwards carry an auditor copy; earlier notes stay
+    /// unreadable to the auditor.
+    #[account(0, writable, name = "pool", desc = "Pool state")]
+    #[account(1, signer, name = "authority", desc = "Pool authority")]
+    SetAuditor {
+        /// Baby Jubjub public key (x, y) (None removes the auditor)
+        auditor_key: Option<[[u8; 32]; 2]>,
+    },
 }
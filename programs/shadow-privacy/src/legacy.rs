@@ -0,0 +1,325 @@
+//! Superseded account layouts
+//!
+//! Version 0 accounts were written before account headers existed; version 1
+//! pools and relayers were Borsh-encoded before the zero-copy layouts,
+//! version 2 pools predate the auditor key and version 3 pools the on-chain
+//! commitment tree. These are only read by the
+//! Migrate instruction, which converts them into the current layouts in
+//! `state`.
+
+use crate::{
+    error::PrivacyError,
+    state::{
+        AssetState, CircuitType, OutflowTracker, PoolLimits, PoolState, ProgramAccount,
+        RelayerAccount, ReputationBucket, VerificationKeyAccount,
+    },
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use bytemuck::Zeroable;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+/// PoolState before headers, authority transfer, pause and limits
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct PoolStateV0 {
+    pub authority: Pubkey,
+    pub merkle_root: [u8; 32],
+    pub tree_depth: u8,
+    pub commitment_count: u64,
+    pub denomination: u64,
+    pub tvl: u64,
+    pub used_nullifiers: Vec<[u8; 32]>,
+    pub used_key_images: Vec<[u8; 32]>,
+    pub nullifier_count: u64,
+    pub key_image_count: u64,
+    pub vault: Pubkey,
+    pub is_initialized: bool,
+}
+
+impl From<PoolStateV0> for PoolStateV1 {
+    /// New fields start out disabled: no pending authority, no guardian,
+    /// not paused and no limits.
+    fn from(old: PoolStateV0) -> Self {
+        PoolStateV1 {
+            discriminator: PoolState::DISCRIMINATOR,
+            version: PoolStateV1::VERSION,
+            authority: old.authority,
+            merkle_root: old.merkle_root,
+            tree_depth: old.tree_depth,
+            commitment_count: old.commitment_count,
+            denomination: old.denomination,
+            tvl: old.tvl,
+            used_nullifiers: old.used_nullifiers,
+            used_key_images: old.used_key_images,
+            nullifier_count: old.nullifier_count,
+            key_image_count: old.key_image_count,
+            vault: old.vault,
+            is_initialized: old.is_initialized,
+            pending_authority: None,
+            guardian: None,
+            paused: 0,
+            pause_expires_at: 0,
+            limits: PoolLimits::default(),
+            outflow: OutflowTracker::default(),
+        }
+    }
+}
+
+/// PoolState with a header, Borsh-encoded with Vec caches
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct PoolStateV1 {
+    pub discriminator: [u8; 8],
+    pub version: u8,
+    pub authority: Pubkey,
+    pub merkle_root: [u8; 32],
+    pub tree_depth: u8,
+    pub commitment_count: u64,
+    pub denomination: u64,
+    pub tvl: u64,
+    pub used_nullifiers: Vec<[u8; 32]>,
+    pub used_key_images: Vec<[u8; 32]>,
+    pub nullifier_count: u64,
+    pub key_image_count: u64,
+    pub vault: Pubkey,
+    pub is_initialized: bool,
+    pub pending_authority: Option<Pubkey>,
+    pub guardian: Option<Pubkey>,
+    pub paused: u8,
+    pub pause_expires_at: i64,
+    pub limits: PoolLimits,
+    pub outflow: OutflowTracker,
+}
+
+impl PoolStateV1 {
+    pub const VERSION: u8 = 1;
+}
+
+impl From<PoolStateV1> for PoolState {
+    /// Cache entries beyond MAX_CACHE_ENTRIES (never written) are dropped.
+    fn from(old: PoolStateV1) -> Self {
+        let mut pool = PoolState::new(old.authority, old.vault, old.tree_depth, old.denomination);
+        pool.initialized = old.is_initialized as u8;
+        pool.merkle_root = old.merkle_root;
+        pool.commitment_count = old.commitment_count;
+        pool.migrate_tree();
+        pool.tvl = old.tvl;
+        pool.nullifier_count = old.nullifier_count;
+        pool.key_image_count = old.key_image_count;
+        pool.pending_authority = old.pending_authority.unwrap_or_default();
+        pool.guardian = old.guardian.unwrap_or_default();
+        pool.paused = old.paused;
+        pool.pause_expires_at = old.pause_expires_at;
+        pool.limits = old.limits;
+        pool.outflow = old.outflow;
+
+        for (i, nullifier) in old.used_nullifiers.iter().take(PoolState::MAX_CACHE_ENTRIES).enumerate() {
+            pool.used_nullifiers[i] = *nullifier;
+            pool.nullifier_cache_len += 1;
+        }
+        for (i, key_image) in old.used_key_images.iter().take(PoolState::MAX_CACHE_ENTRIES).enumerate() {
+            pool.used_key_images[i] = *key_image;
+            pool.key_image_cache_len += 1;
+        }
+
+        pool
+    }
+}
+
+/// Zero-copy PoolState before the auditor key
+///
+/// The auditor key was appended to the layout, so a version 2 pool is the
+/// current layout cut short; it decodes straight into `PoolState`.
+pub struct PoolStateV2;
+
+impl PoolStateV2 {
+    pub const VERSION: u8 = 2;
+
+    pub const LEN: usize = PoolStateV3::LEN - 64; // auditor_key
+
+    /// Current layout of the version 2 pool in `data`, without an auditor
+    pub fn load(data: &[u8]) -> Result<PoolState, ProgramError> {
+        load_truncated_pool(data, Self::LEN)
+    }
+}
+
+/// Zero-copy PoolState before the on-chain commitment tree
+///
+/// The frontier was appended to the layout and the `onchain_tree` flag took
+/// a padding byte (always zero), so this also decodes straight into
+/// `PoolState`.
+pub struct PoolStateV3;
+
+impl PoolStateV3 {
+    pub const VERSION: u8 = 3;
+
+    pub const LEN: usize = PoolState::LEN - 32 * PoolState::MAX_TREE_DEPTH; // filled_subtrees
+
+    /// Current layout of the version 3 pool in `data`
+    pub fn load(data: &[u8]) -> Result<PoolState, ProgramError> {
+        load_truncated_pool(data, Self::LEN)
+    }
+}
+
+/// Zero-extend the first `len` bytes of `data` into the current layout
+fn load_truncated_pool(data: &[u8], len: usize) -> Result<PoolState, ProgramError> {
+    if data.len() < len {
+        return Err(PrivacyError::InvalidAccountData.into());
+    }
+
+    let mut pool = PoolState::zeroed();
+    bytemuck::bytes_of_mut(&mut pool)[..len].copy_from_slice(&data[..len]);
+    pool.version = PoolState::VERSION;
+    pool.migrate_tree();
+    Ok(pool)
+}
+
+/// AssetState before headers
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct AssetStateV0 {
+    pub asset_id: [u8; 32],
+    pub issuer: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub total_supply: u64,
+    pub circulating_supply: u64,
+    pub note_tree_root: [u8; 32],
+    pub note_count: u64,
+    pub used_nullifiers: Vec<[u8; 32]>,
+    pub is_initialized: bool,
+}
+
+impl From<AssetStateV0> for AssetState {
+    fn from(old: AssetStateV0) -> Self {
+        AssetState {
+            discriminator: AssetState::DISCRIMINATOR,
+            version: AssetState::VERSION,
+            asset_id: old.asset_id,
+            issuer: old.issuer,
+            name: old.name,
+            symbol: old.symbol,
+            decimals: old.decimals,
+            total_supply: old.total_supply,
+            circulating_supply: old.circulating_supply,
+            note_tree_root: old.note_tree_root,
+            note_count: old.note_count,
+            used_nullifiers: old.used_nullifiers,
+            is_initialized: old.is_initialized,
+        }
+    }
+}
+
+/// VerificationKeyAccount before headers
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct VerificationKeyAccountV0 {
+    pub circuit_type: CircuitType,
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub vk_data: Vec<u8>,
+    pub stored_at: i64,
+    pub bump: u8,
+}
+
+impl From<VerificationKeyAccountV0> for VerificationKeyAccount {
+    fn from(old: VerificationKeyAccountV0) -> Self {
+        VerificationKeyAccount {
+            discriminator: VerificationKeyAccount::DISCRIMINATOR,
+            version: VerificationKeyAccount::VERSION,
+            circuit_type: old.circuit_type,
+            pool: old.pool,
+            authority: old.authority,
+            vk_data: old.vk_data,
+            stored_at: old.stored_at,
+            bump: old.bump,
+        }
+    }
+}
+
+/// RelayerAccount before headers and reputation buckets
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct RelayerAccountV0 {
+    pub relayer: Pubkey,
+    pub stake: u64,
+    pub successful_relays: u64,
+    pub failed_relays: u64,
+    pub last_heartbeat: i64,
+    pub is_active: bool,
+    pub registered_at: i64,
+    pub endpoint: String,
+    pub bump: u8,
+}
+
+impl RelayerAccountV0 {
+    /// Relayer accounts were always created at exactly this size
+    pub const LEN: usize = 32 + // relayer
+        8 + // stake
+        8 + // successful_relays
+        8 + // failed_relays
+        8 + // last_heartbeat
+        1 + // is_active
+        8 + // registered_at
+        4 + RelayerAccount::MAX_ENDPOINT_LEN + // endpoint (string)
+        1; // bump
+}
+
+impl From<RelayerAccountV0> for RelayerAccountV1 {
+    /// Lifetime counters are kept, but since old reports carry no timestamps the
+    /// decayed score starts empty and builds up from new reports.
+    fn from(old: RelayerAccountV0) -> Self {
+        RelayerAccountV1 {
+            discriminator: RelayerAccount::DISCRIMINATOR,
+            version: RelayerAccountV1::VERSION,
+            relayer: old.relayer,
+            stake: old.stake,
+            successful_relays: old.successful_relays,
+            failed_relays: old.failed_relays,
+            last_heartbeat: old.last_heartbeat,
+            is_active: old.is_active,
+            registered_at: old.registered_at,
+            endpoint: old.endpoint,
+            bump: old.bump,
+            last_report_at: 0,
+            reputation_buckets: [ReputationBucket::default(); RelayerAccount::REPUTATION_BUCKETS],
+        }
+    }
+}
+
+/// RelayerAccount with a header and reputation buckets, Borsh-encoded
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct RelayerAccountV1 {
+    pub discriminator: [u8; 8],
+    pub version: u8,
+    pub relayer: Pubkey,
+    pub stake: u64,
+    pub successful_relays: u64,
+    pub failed_relays: u64,
+    pub last_heartbeat: i64,
+    pub is_active: bool,
+    pub registered_at: i64,
+    pub endpoint: String,
+    pub bump: u8,
+    pub last_report_at: i64,
+    pub reputation_buckets: [ReputationBucket; RelayerAccount::REPUTATION_BUCKETS],
+}
+
+impl RelayerAccountV1 {
+    pub const VERSION: u8 = 1;
+}
+
+impl From<RelayerAccountV1> for RelayerAccount {
+    fn from(old: RelayerAccountV1) -> Self {
+        let mut relayer = RelayerAccount::new(
+            old.relayer,
+            old.stake,
+            &old.endpoint,
+            old.bump,
+            old.registered_at,
+        );
+        relayer.active = old.is_active as u8;
+        relayer.successful_relays = old.successful_relays;
+        relayer.failed_relays = old.failed_relays;
+        relayer.last_heartbeat = old.last_heartbeat;
+        relayer.last_report_at = old.last_report_at;
+        relayer.reputation_buckets = old.reputation_buckets;
+        relayer
+    }
+}
@@ -6,13 +6,21 @@
 // - Shielded transactions (Zcash ZSA-style)
 // - Private balances
 
+pub mod auditor;
 pub mod error;
+pub mod events;
 pub mod instruction;
+pub mod legacy;
+pub mod note;
 pub mod processor;
+#[cfg(feature = "serde")]
+pub mod serde_helpers;
 pub mod state;
+pub mod validation;
 pub mod verifier;
 
 pub use error::PrivacyError;
+pub use events::{PrivacyEvent, TreeLeaf};
 pub use instruction::PrivacyInstruction;
 pub use processor::Processor;
 
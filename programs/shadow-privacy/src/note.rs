@@ -0,0 +1,169 @@
+//! Encrypted note format
+//!
+//! `PrivateTransfer.encrypted_amount` and `TransferAsset.encrypted_data`
+//! carry one note ciphertext for the recipient (version 1):
+//!
+//! ```text
+//! value part (VALUE_CIPHERTEXT_LEN bytes)
+//!   version          1   NOTE_CIPHERTEXT_VERSION
+//!   ephemeral key   32   sender's one-time x25519 public key
+//!   ciphertext      72   ChaCha20-Poly1305 of amount (u64 LE) || nonce || asset_id
+//!   tag             16
+//! memo part (optional, TAG_LEN..=MAX_MEMO_CIPHERTEXT_LEN bytes)
+//!   ciphertext       n   ChaCha20-Poly1305 of the memo
+//!   tag             16
+//! ```
+//!
+//! The key is derived from the x25519 shared secret; see the
+//! `shadow-privacy-note` crate. The program cannot decrypt, so it only checks
+//! the shape: the value part fits `ShieldedNote::encrypted_value`, the memo
+//! part fits `ShieldedNote::encrypted_memo`, and the ephemeral key becomes
+//! the note's `tx_public_key`. Deposit carries the same format in
+//! `encrypted_note`, addressed to the depositor's own key.
+//!
+//! Every accepted note is published in a `NoteCreated` event next to the
+//! commitment it opens.
+
+use crate::{
+    error::PrivacyError,
+    events::{PrivacyEvent, TreeLeaf},
+    state::ShieldedNote,
+};
+use solana_program::pubkey::Pubkey;
+
+/// Current ciphertext version
+pub const NOTE_CIPHERTEXT_VERSION: u8 = 1;
+
+/// x25519 public key length
+pub const EPHEMERAL_KEY_LEN: usize = 32;
+
+/// Poly1305 tag length
+pub const TAG_LEN: usize = 16;
+
+/// amount (8) + nonce (32) + asset_id (32)
+pub const NOTE_PLAINTEXT_LEN: usize = 8 + 32 + 32;
+
+/// Length of the value part, which holds everything but the memo
+pub const VALUE_CIPHERTEXT_LEN: usize = 1 + EPHEMERAL_KEY_LEN + NOTE_PLAINTEXT_LEN + TAG_LEN;
+
+/// Longest memo part (memo plus tag)
+pub const MAX_MEMO_CIPHERTEXT_LEN: usize = ShieldedNote::MAX_ENCRYPTED_MEMO_LEN;
+
+/// Longest plaintext memo
+pub const MAX_MEMO_LEN: usize = MAX_MEMO_CIPHERTEXT_LEN - TAG_LEN;
+
+/// Longest note ciphertext
+pub const MAX_NOTE_CIPHERTEXT_LEN: usize = VALUE_CIPHERTEXT_LEN + MAX_MEMO_CIPHERTEXT_LEN;
+
+const _: () = assert!(VALUE_CIPHERTEXT_LEN <= ShieldedNote::MAX_ENCRYPTED_VALUE_LEN);
+
+/// A note ciphertext split into the parts a `ShieldedNote` stores
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoteCiphertext<'a> {
+    pub version: u8,
+    /// Sender's ephemeral x25519 public key (the note's `tx_public_key`)
+    pub ephemeral_key: [u8; 32],
+    /// Version, ephemeral key and encrypted amount/nonce/asset id
+    pub value: &'a [u8],
+    /// Encrypted memo, empty when the note has none
+    pub memo: &'a [u8],
+}
+
+impl<'a> NoteCiphertext<'a> {
+    /// Check the version and part sizes of an encoded ciphertext
+    pub fn parse(data: &'a [u8]) -> Result<Self, PrivacyError> {
+        if data.len() < VALUE_CIPHERTEXT_LEN || data.len() > MAX_NOTE_CIPHERTEXT_LEN {
+            return Err(PrivacyError::InvalidNoteCiphertext);
+        }
+        if data[0] != NOTE_CIPHERTEXT_VERSION {
+            return Err(PrivacyError::InvalidNoteCiphertext);
+        }
+
+        let (value, memo) = data.split_at(VALUE_CIPHERTEXT_LEN);
+        if !memo.is_empty() && memo.len() < TAG_LEN {
+            return Err(PrivacyError::InvalidNoteCiphertext);
+        }
+
+        let mut ephemeral_key = [0u8; 32];
+        ephemeral_key.copy_from_slice(&value[1..1 + EPHEMERAL_KEY_LEN]);
+        Ok(NoteCiphertext {
+            version: data[0],
+            ephemeral_key,
+            value,
+            memo,
+        })
+    }
+
+    /// `NoteCreated` event for the note of `leaf` in `account`'s tree
+    pub fn created_event(&self, account: Pubkey, leaf: TreeLeaf, asset_id: [u8; 32]) -> PrivacyEvent {
+        PrivacyEvent::NoteCreated {
+            account,
+            leaf,
+            asset_id,
+            tx_public_key: self.ephemeral_key,
+            encrypted_value: self.value.to_vec(),
+            encrypted_memo: self.memo.to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ciphertext(memo_len: usize) -> Vec<u8> {
+        let mut data = vec![NOTE_CIPHERTEXT_VERSION];
+        data.extend_from_slice(&[7u8; EPHEMERAL_KEY_LEN]);
+        data.resize(VALUE_CIPHERTEXT_LEN + memo_len, 1);
+        data
+    }
+
+    #[test]
+    fn test_parse_splits_parts() {
+        let data = ciphertext(0);
+        let note = NoteCiphertext::parse(&data).unwrap();
+        assert_eq!(note.ephemeral_key, [7u8; 32]);
+        assert_eq!(note.value.len(), VALUE_CIPHERTEXT_LEN);
+        assert!(note.memo.is_empty());
+
+        let data = ciphertext(MAX_MEMO_CIPHERTEXT_LEN);
+        let note = NoteCiphertext::parse(&data).unwrap();
+        assert_eq!(note.memo.len(), MAX_MEMO_CIPHERTEXT_LEN);
+    }
+
+    #[test]
+    fn test_created_event_carries_both_parts() {
+        let data = ciphertext(TAG_LEN + 5);
+        let note = NoteCiphertext::parse(&data).unwrap();
+        let account = Pubkey::new_unique();
+        let leaf = TreeLeaf { index: 3, commitment: [2u8; 32] };
+
+        let expected = PrivacyEvent::NoteCreated {
+            account,
+            leaf,
+            asset_id: [4u8; 32],
+            tx_public_key: [7u8; 32],
+            encrypted_value: data[..VALUE_CIPHERTEXT_LEN].to_vec(),
+            encrypted_memo: data[VALUE_CIPHERTEXT_LEN..].to_vec(),
+        };
+        assert_eq!(note.created_event(account, leaf, [4u8; 32]), expected);
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_shapes() {
+        let mut short = ciphertext(0);
+        short.pop();
+        let mut wrong_version = ciphertext(0);
+        wrong_version[0] = 2;
+
+        for data in [
+            vec![],
+            short,
+            wrong_version,
+            ciphertext(TAG_LEN - 1),
+            ciphertext(MAX_MEMO_CIPHERTEXT_LEN + 1),
+        ] {
+            assert!(matches!(NoteCiphertext::parse(&data), Err(PrivacyError::InvalidNoteCiphertext)));
+        }
+    }
+}
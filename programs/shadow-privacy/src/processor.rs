@@ -1,13 +1,28 @@
 use crate::{
     error::PrivacyError,
+    events::{PrivacyEvent, TreeLeaf},
     instruction::PrivacyInstruction,
-    state::{AssetState, PoolState, VerificationKeyAccount, CircuitType, RelayerAccount},
+    legacy::{
+        AssetStateV0, PoolStateV0, PoolStateV1, PoolStateV2, PoolStateV3, RelayerAccountV0, RelayerAccountV1,
+        VerificationKeyAccountV0,
+    },
+    auditor,
+    note::NoteCiphertext,
+    state::{
+        AssetState, AuditorNote, ConfigParams, DelayedWithdrawal, KeyImageAccount, Multisig, MultisigProposal, NullifierAccount,
+        PoolLimits, PoolState, ProposalAccount, ProtocolConfig, VerificationKeyAccount, CircuitType, RelayerAccount, AccountType, ProgramAccount,
+    },
+    validation::{
+        check_address, check_owner, check_pda, check_signer, check_system_program, check_upgrade_authority,
+        check_unique, check_writable, Account, ZeroCopy, ZeroCopyMut,
+    },
     verifier,
 };
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
-    entrypoint::ProgramResult,
+    entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE},
+    instruction::{AccountMeta, Instruction},
     msg,
     program::{invoke, invoke_signed},
     program_error::ProgramError,
@@ -38,9 +53,18 @@ impl Processor {
             PrivacyInstruction::Deposit {
                 commitment,
                 amount,
+                encrypted_note,
+                auditor_note,
             } => {
                 msg!("Instruction: Deposit");
-                Self::process_deposit(program_id, accounts, commitment, amount)
+                Self::process_deposit(
+                    program_id,
+                    accounts,
+                    commitment,
+                    amount,
+                    encrypted_note,
+                    auditor_note,
+                )
             }
             PrivacyInstruction::Withdraw {
                 proof,
@@ -49,6 +73,8 @@ impl Processor {
                 new_commitment,
                 recipient,
                 amount,
+                encrypted_note,
+                auditor_note,
             } => {
                 msg!("Instruction: Withdraw");
                 Self::process_withdraw(
@@ -60,6 +86,8 @@ impl Processor {
                     new_commitment,
                     recipient,
                     amount,
+                    encrypted_note,
+                    auditor_note,
                 )
             }
             PrivacyInstruction::PrivateTransfer {
@@ -68,6 +96,7 @@ impl Processor {
                 ring_members,
                 new_commitment,
                 encrypted_amount,
+                auditor_note,
             } => {
                 msg!("Instruction: PrivateTransfer");
                 Self::process_private_transfer(
@@ -78,6 +107,7 @@ impl Processor {
                     ring_members,
                     new_commitment,
                     encrypted_amount,
+                    auditor_note,
                 )
             }
             PrivacyInstruction::VerifyBalance {
@@ -158,6 +188,103 @@ impl Processor {
                 msg!("Instruction: ReportRelay");
                 Self::process_report_relay(program_id, accounts, success)
             }
+            PrivacyInstruction::MigrateRelayer => {
+                msg!("Instruction: MigrateRelayer");
+                Self::process_migrate(program_id, accounts, AccountType::Relayer)
+            }
+            PrivacyInstruction::InitializeConfig {
+                params,
+            } => {
+                msg!("Instruction: InitializeConfig");
+                Self::process_initialize_config(program_id, accounts, params)
+            }
+            PrivacyInstruction::UpdateConfig {
+                params,
+                new_admin,
+            } => {
+                msg!("Instruction: UpdateConfig");
+                Self::process_update_config(program_id, accounts, params, new_admin)
+            }
+            PrivacyInstruction::UpdateRoot {
+                new_root,
+            } => {
+                msg!("Instruction: UpdateRoot");
+                Self::process_update_root(program_id, accounts, new_root)
+            }
+            PrivacyInstruction::ProposeAuthority {
+                new_authority,
+            } => {
+                msg!("Instruction: ProposeAuthority");
+                Self::process_propose_authority(program_id, accounts, new_authority)
+            }
+            PrivacyInstruction::AcceptAuthority => {
+                msg!("Instruction: AcceptAuthority");
+                Self::process_accept_authority(program_id, accounts)
+            }
+            PrivacyInstruction::CreateMultisig {
+                create_key,
+                threshold,
+                signers,
+            } => {
+                msg!("Instruction: CreateMultisig");
+                Self::process_create_multisig(program_id, accounts, create_key, threshold, signers)
+            }
+            PrivacyInstruction::CreateProposal {
+                instruction_data,
+                accounts: proposal_accounts,
+            } => {
+                msg!("Instruction: CreateProposal");
+                Self::process_create_proposal(program_id, accounts, instruction_data, proposal_accounts)
+            }
+            PrivacyInstruction::ApproveProposal => {
+                msg!("Instruction: ApproveProposal");
+                Self::process_approve_proposal(program_id, accounts)
+            }
+            PrivacyInstruction::ExecuteProposal => {
+                msg!("Instruction: ExecuteProposal");
+                Self::process_execute_proposal(program_id, accounts)
+            }
+            PrivacyInstruction::SetGuardian {
+                guardian,
+            } => {
+                msg!("Instruction: SetGuardian");
+                Self::process_set_guardian(program_id, accounts, guardian)
+            }
+            PrivacyInstruction::Pause {
+                operations,
+                expires_at,
+            } => {
+                msg!("Instruction: Pause");
+                Self::process_pause(program_id, accounts, operations, expires_at)
+            }
+            PrivacyInstruction::Unpause {
+                operations,
+            } => {
+                msg!("Instruction: Unpause");
+                Self::process_unpause(program_id, accounts, operations)
+            }
+            PrivacyInstruction::SetPoolLimits {
+                limits,
+            } => {
+                msg!("Instruction: SetPoolLimits");
+                Self::process_set_pool_limits(program_id, accounts, limits)
+            }
+            PrivacyInstruction::ClaimDelayedWithdrawal => {
+                msg!("Instruction: ClaimDelayedWithdrawal");
+                Self::process_claim_delayed_withdrawal(program_id, accounts)
+            }
+            PrivacyInstruction::Migrate {
+                account_type,
+            } => {
+                msg!("Instruction: Migrate");
+                Self::process_migrate(program_id, accounts, account_type)
+            }
+            PrivacyInstruction::SetAuditor {
+                auditor_key,
+            } => {
+                msg!("Instruction: SetAuditor");
+                Self::process_set_auditor(program_id, accounts, auditor_key)
+            }
         }
     }
 
@@ -174,56 +301,77 @@ impl Processor {
         let system_program = next_account_info(account_info_iter)?;
 
         // Verify authority is signer
-        if !authority.is_signer {
-            return Err(ProgramError::MissingRequiredSignature);
+        check_signer(authority)?;
+        check_system_program(system_program)?;
+        check_unique(&[pool_account, authority, vault_account])?;
+
+        if !PoolState::is_valid_tree_depth(tree_depth) {
+            msg!("Invalid tree depth: {}", tree_depth);
+            return Err(PrivacyError::InvalidTreeDepth.into());
         }
 
-        // Verify vault PDA
-        let (vault_pubkey, vault_bump) = Pubkey::find_program_address(
-            &[b"vault", pool_account.key.as_ref()],
-            program_id,
-        );
+        // Verify pool and vault PDAs
+        let (pool_pubkey, pool_bump) =
+            PoolState::derive_address(authority.key, denomination, program_id);
+        check_address(pool_account, &pool_pubkey)?;
+        check_writable(pool_account)?;
 
-        if vault_account.key != &vault_pubkey {
-            return Err(ProgramError::InvalidAccountData);
+        let (vault_pubkey, vault_bump) =
+            PoolState::derive_vault_address(pool_account.key, program_id);
+        check_address(vault_account, &vault_pubkey)?;
+
+        // Check if the pool already exists
+        if pool_account.owner == program_id {
+            msg!("Pool already initialized");
+            return Err(ProgramError::AccountAlreadyInitialized);
         }
 
-        // Create vault account (PDA owned by program, stores SOL)
-        let vault_space = 0; // No data space needed, just to hold SOL
-        let vault_rent = Rent::get()?.minimum_balance(vault_space);
+        // Create pool state account (PDA owned by program, rent-exempt at full size)
+        Self::create_pda_account(
+            program_id,
+            authority,
+            pool_account,
+            system_program,
+            PoolState::LEN,
+            0,
+            &[
+                b"pool",
+                authority.key.as_ref(),
+                &denomination.to_le_bytes(),
+                &[pool_bump],
+            ],
+        )?;
 
-        invoke_signed(
-            &system_instruction::create_account(
-                authority.key,
-                &vault_pubkey,
-                vault_rent,
-                vault_space as u64,
-                program_id, // Vault is owned by this program
-            ),
-            &[authority.clone(), vault_account.clone(), system_program.clone()],
-            &[&[b"vault", pool_account.key.as_ref(), &[vault_bump]]],
+        msg!("Pool account created: {}", pool_pubkey);
+
+        // Create vault account (PDA owned by program, stores SOL; no data)
+        Self::create_pda_account(
+            program_id,
+            authority,
+            vault_account,
+            system_program,
+            0,
+            0,
+            &[b"vault", pool_account.key.as_ref(), &[vault_bump]],
         )?;
 
         msg!("Vault account created: {}", vault_pubkey);
 
-        // Initialize pool state
-        let mut pool_state = PoolState {
+        // Initialize pool state in place
+        ZeroCopyMut::init(
+            program_id,
+            pool_account,
+            PoolState::new(*authority.key, vault_pubkey, tree_depth, denomination),
+        )?;
+
+        PrivacyEvent::PoolInitialized {
+            pool: pool_pubkey,
             authority: *authority.key,
-            merkle_root: [0u8; 32],
+            vault: vault_pubkey,
             tree_depth,
-            commitment_count: 0,
             denomination,
-            tvl: 0,
-            used_nullifiers: Vec::new(),
-            used_key_images: Vec::new(),
-            nullifier_count: 0,
-            key_image_count: 0,
-            vault: vault_pubkey,
-            is_initialized: true,
-        };
-
-        // Serialize and save
-        pool_state.serialize(&mut *pool_account.data.borrow_mut())?;
+        }
+        .emit()?;
 
         msg!("Privacy pool initialized");
         msg!("  Tree depth: {}", tree_depth);
@@ -238,19 +386,33 @@ impl Processor {
         accounts: &[AccountInfo],
         commitment: [u8; 32],
         amount: u64,
+        encrypted_note: Vec<u8>,
+        auditor_note: Option<AuditorNote>,
     ) -> ProgramResult {
         let account_info_iter = &mut accounts.iter();
         let pool_account = next_account_info(account_info_iter)?;
         let depositor = next_account_info(account_info_iter)?;
         let vault = next_account_info(account_info_iter)?;
         let system_program = next_account_info(account_info_iter)?;
+        let auditor_vk_account = next_account_info(account_info_iter).ok();
 
-        // Load pool state (use deserialize instead of try_from_slice to handle extra bytes)
-        let mut pool_state = PoolState::deserialize(&mut &pool_account.data.borrow()[..])?;
+        check_signer(depositor)?;
+        check_system_program(system_program)?;
+        let mut unique = vec![pool_account, depositor, vault];
+        unique.extend(auditor_vk_account);
+        check_unique(&unique)?;
 
-        // Verify pool is initialized
-        if !pool_state.is_initialized {
-            return Err(PrivacyError::PoolNotInitialized.into());
+        // The depositor's note must fit a ShieldedNote
+        let note = NoteCiphertext::parse(&encrypted_note)?;
+        Self::check_commitment(&commitment)?;
+
+        // Load pool state
+        let mut pool_state = Self::load_pool(program_id, pool_account)?;
+        Self::check_vault(program_id, &pool_state, vault)?;
+
+        let clock = solana_program::clock::Clock::get()?;
+        if pool_state.is_paused(PoolState::PAUSE_DEPOSITS, clock.unix_timestamp) {
+            return Err(PrivacyError::PoolPaused.into());
         }
 
         // Verify amount matches denomination
@@ -258,6 +420,21 @@ impl Processor {
             return Err(PrivacyError::InvalidAmount.into());
         }
 
+        // Enforce TVL cap
+        if !pool_state.deposit_within_cap(amount) {
+            msg!("TVL cap reached: {} + {} > {}", pool_state.tvl, amount, pool_state.limits.tvl_cap);
+            return Err(PrivacyError::TvlCapExceeded.into());
+        }
+
+        Self::check_auditor_note(
+            program_id,
+            pool_account.key,
+            &pool_state,
+            Some(&commitment),
+            auditor_note.as_ref(),
+            auditor_vk_account,
+        )?;
+
         // Transfer SOL to vault
         invoke(
             &system_instruction::transfer(depositor.key, vault.key, amount),
@@ -265,11 +442,26 @@ impl Processor {
         )?;
 
         // Add commitment to tree
-        pool_state.add_commitment(commitment);
-        pool_state.tvl += amount;
-
-        // Save state
-        pool_state.serialize(&mut *pool_account.data.borrow_mut())?;
+        let leaf_index = pool_state.commitment_count;
+        pool_state.add_commitment(commitment)?;
+        pool_state.tvl = pool_state
+            .tvl
+            .checked_add(amount)
+            .ok_or(PrivacyError::InvalidAmount)?;
+
+        let leaf = TreeLeaf { index: leaf_index, commitment };
+        PrivacyEvent::Deposit {
+            pool: *pool_account.key,
+            depositor: *depositor.key,
+            leaf,
+            root: pool_state.merkle_root,
+            amount,
+        }
+        .emit()?;
+        note.created_event(*pool_account.key, leaf, [0u8; 32]).emit()?;
+        if let Some(auditor_note) = &auditor_note {
+            auditor_note.created_event(*pool_account.key, leaf).emit()?;
+        }
 
         msg!("Deposit successful");
         msg!("  Commitment: {:?}", commitment);
@@ -279,6 +471,7 @@ impl Processor {
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn process_withdraw(
         program_id: &Pubkey,
         accounts: &[AccountInfo],
@@ -288,6 +481,8 @@ impl Processor {
         new_commitment: Option<[u8; 32]>,
         recipient: Pubkey,
         amount: u64,
+        encrypted_note: Option<Vec<u8>>,
+        auditor_note: Option<AuditorNote>,
     ) -> ProgramResult {
         let account_info_iter = &mut accounts.iter();
         let pool_account = next_account_info(account_info_iter)?;
@@ -295,17 +490,70 @@ impl Processor {
         let recipient_account = next_account_info(account_info_iter)?;
         let vk_account = next_account_info(account_info_iter)?;
         let system_program = next_account_info(account_info_iter)?;
+        let config_account = next_account_info(account_info_iter)?;
+        let nullifier_account = next_account_info(account_info_iter)?;
+        let delayed_withdrawal_account = next_account_info(account_info_iter).ok();
+        let auditor_vk_account = next_account_info(account_info_iter).ok();
+
+        check_system_program(system_program)?;
+        let mut unique = vec![
+            pool_account,
+            vault,
+            recipient_account,
+            vk_account,
+            config_account,
+            nullifier_account,
+        ];
+        unique.extend(delayed_withdrawal_account);
+        unique.extend(auditor_vk_account);
+        check_unique(&unique)?;
+
+        // Change gets a note its owner can find, like any other new leaf
+        let note = match (&new_commitment, &encrypted_note) {
+            (Some(commitment), Some(encrypted_note)) => {
+                Self::check_commitment(commitment)?;
+                Some(NoteCiphertext::parse(encrypted_note)?)
+            }
+            (None, None) => None,
+            (Some(_), None) => {
+                msg!("Change commitment without an encrypted note");
+                return Err(PrivacyError::InvalidNoteCiphertext.into());
+            }
+            (None, Some(_)) => {
+                msg!("Encrypted note without a change commitment");
+                return Err(PrivacyError::InvalidNoteCiphertext.into());
+            }
+        };
 
-        // Load pool state (use deserialize instead of try_from_slice to handle extra bytes)
-        let mut pool_state = PoolState::deserialize(&mut &pool_account.data.borrow()[..])?;
+        let config = Self::load_config(program_id, config_account)?;
 
-        // Verify pool is initialized
-        if !pool_state.is_initialized {
-            return Err(PrivacyError::PoolNotInitialized.into());
+        // Load pool state
+        let mut pool_state = Self::load_pool(program_id, pool_account)?;
+        Self::check_vault(program_id, &pool_state, vault)?;
+        Self::check_verification_key(program_id, pool_account.key, CircuitType::Transfer, vk_account)?;
+
+        // Funds may only go to the recipient named in the instruction
+        check_address(recipient_account, &recipient)?;
+        check_writable(recipient_account)?;
+
+        // The proof does not bind the amount; the pool's denomination does
+        if amount != pool_state.denomination {
+            return Err(PrivacyError::InvalidAmount.into());
         }
 
-        // Verify nullifier not used
-        if pool_state.is_nullifier_used(&nullifier) {
+        let (nullifier_pubkey, nullifier_bump) =
+            PoolState::derive_nullifier_pda(pool_account.key, &nullifier, program_id);
+        check_address(nullifier_account, &nullifier_pubkey)?;
+        check_writable(nullifier_account)?;
+
+        let clock = solana_program::clock::Clock::get()?;
+        if pool_state.is_paused(PoolState::PAUSE_WITHDRAWALS, clock.unix_timestamp) {
+            return Err(PrivacyError::PoolPaused.into());
+        }
+
+        // Verify nullifier not used. The cache only holds the first
+        // nullifier_cache_size nullifiers; the record PDA covers all of them.
+        if pool_state.is_nullifier_used(&nullifier) || nullifier_account.owner == program_id {
             return Err(PrivacyError::NullifierAlreadyUsed.into());
         }
 
@@ -337,24 +585,131 @@ impl Processor {
             return Err(PrivacyError::InvalidProof.into());
         }
 
+        Self::check_auditor_note(
+            program_id,
+            pool_account.key,
+            &pool_state,
+            new_commitment.as_ref(),
+            auditor_note.as_ref(),
+            auditor_vk_account,
+        )?;
+
         // Mark nullifier as used
-        pool_state.add_nullifier(nullifier);
+        pool_state.add_nullifier(nullifier, config.params.nullifier_cache_size as usize);
+        let record_rent = Self::record_nullifier(
+            program_id,
+            pool_account,
+            vault,
+            nullifier_account,
+            system_program,
+            nullifier,
+            nullifier_bump,
+            clock.unix_timestamp,
+        )?;
+
+        // The recipient gets the amount less the record's rent
+        let payout = amount.checked_sub(record_rent).ok_or_else(|| {
+            msg!("Withdrawal too small to cover the nullifier record");
+            PrivacyError::InvalidAmount
+        })?;
 
         // If there's a new commitment (change), add it to tree
-        if let Some(commitment) = new_commitment {
-            pool_state.add_commitment(commitment);
+        let change = new_commitment
+            .map(|commitment| {
+                let leaf = TreeLeaf { index: pool_state.commitment_count, commitment };
+                pool_state.add_commitment(commitment).map(|()| leaf)
+            })
+            .transpose()?;
+        let emit_withdraw_events = |root: [u8; 32], delayed: bool| -> ProgramResult {
+            PrivacyEvent::Withdraw {
+                pool: *pool_account.key,
+                nullifier,
+                recipient,
+                amount,
+                fee: 0,
+                record_rent,
+                change,
+                root,
+                delayed,
+            }
+            .emit()?;
+            if let (Some(note), Some(leaf)) = (&note, change) {
+                note.created_event(*pool_account.key, leaf, [0u8; 32]).emit()?;
+            }
+            if let (Some(auditor_note), Some(leaf)) = (&auditor_note, change) {
+                auditor_note.created_event(*pool_account.key, leaf).emit()?;
+            }
+            Ok(())
+        };
+
+        let within_limits =
+            pool_state.outflow_within_limits(amount, clock.epoch, clock.unix_timestamp);
+
+        if !within_limits {
+            if pool_state.limits.withdrawal_delay == 0 {
+                msg!("Withdrawal of {} exceeds the pool outflow limits", amount);
+                return Err(PrivacyError::WithdrawalLimitExceeded.into());
+            }
+
+            let delayed_withdrawal_account =
+                delayed_withdrawal_account.ok_or(ProgramError::NotEnoughAccountKeys)?;
+            let release_at = clock
+                .unix_timestamp
+                .saturating_add(pool_state.limits.withdrawal_delay);
+
+            Self::queue_withdrawal(
+                program_id,
+                pool_account,
+                vault,
+                delayed_withdrawal_account,
+                system_program,
+                DelayedWithdrawal {
+                    discriminator: DelayedWithdrawal::DISCRIMINATOR,
+                    version: DelayedWithdrawal::VERSION,
+                    pool: *pool_account.key,
+                    recipient,
+                    amount: payout,
+                    nullifier,
+                    release_at,
+                    bump: 0,
+                },
+            )?;
+
+            pool_state.tvl = pool_state
+                .tvl
+                .checked_sub(amount)
+                .ok_or(PrivacyError::InsufficientFunds)?;
+
+            emit_withdraw_events(pool_state.merkle_root, true)?;
+
+            msg!("Withdrawal queued");
+            msg!("  Nullifier: {:?}", nullifier);
+            msg!("  Amount: {}", amount);
+            msg!("  Release at: {}", release_at);
+
+            return Ok(());
         }
 
         // Transfer from vault to recipient
         // Since vault is a PDA owned by our program, we can't use system_instruction::transfer
         // Instead, we manually transfer lamports
-        **vault.try_borrow_mut_lamports()? -= amount;
-        **recipient_account.try_borrow_mut_lamports()? += amount;
-
-        pool_state.tvl -= amount;
-
-        // Save state
-        pool_state.serialize(&mut *pool_account.data.borrow_mut())?;
+        let vault_lamports = vault
+            .lamports()
+            .checked_sub(payout)
+            .ok_or(PrivacyError::InsufficientFunds)?;
+        **vault.try_borrow_mut_lamports()? = vault_lamports;
+        **recipient_account.try_borrow_mut_lamports()? = recipient_account
+            .lamports()
+            .checked_add(payout)
+            .ok_or(PrivacyError::InvalidAmount)?;
+
+        pool_state.tvl = pool_state
+            .tvl
+            .checked_sub(amount)
+            .ok_or(PrivacyError::InsufficientFunds)?;
+        pool_state.record_outflow(amount, clock.epoch, clock.unix_timestamp);
+
+        emit_withdraw_events(pool_state.merkle_root, false)?;
 
         msg!("Withdrawal successful");
         msg!("  Nullifier: {:?}", nullifier);
@@ -364,6 +719,7 @@ impl Processor {
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn process_private_transfer(
         program_id: &Pubkey,
         accounts: &[AccountInfo],
@@ -372,33 +728,112 @@ impl Processor {
         ring_members: Vec<[u8; 32]>,
         new_commitment: [u8; 32],
         encrypted_amount: Vec<u8>,
+        auditor_note: Option<AuditorNote>,
     ) -> ProgramResult {
         let account_info_iter = &mut accounts.iter();
         let pool_account = next_account_info(account_info_iter)?;
-        let sender_commitment_account = next_account_info(account_info_iter)?;
+        let key_image_account = next_account_info(account_info_iter)?;
         let recipient_commitment_account = next_account_info(account_info_iter)?;
+        let config_account = next_account_info(account_info_iter)?;
+        let payer = next_account_info(account_info_iter)?;
+        let system_program = next_account_info(account_info_iter)?;
+        let auditor_vk_account = next_account_info(account_info_iter).ok();
+
+        check_signer(payer)?;
+        check_system_program(system_program)?;
+        let mut unique = vec![
+            pool_account,
+            key_image_account,
+            recipient_commitment_account,
+            config_account,
+            payer,
+        ];
+        unique.extend(auditor_vk_account);
+        check_unique(&unique)?;
+
+        // The recipient's note must fit a ShieldedNote
+        let note = NoteCiphertext::parse(&encrypted_amount)?;
+        Self::check_commitment(&new_commitment)?;
+
+        let config = Self::load_config(program_id, config_account)?;
+
+        // Load pool state
+        let mut pool_state = Self::load_pool(program_id, pool_account)?;
+
+        let clock = solana_program::clock::Clock::get()?;
+        if pool_state.is_paused(PoolState::PAUSE_TRANSFERS, clock.unix_timestamp) {
+            return Err(PrivacyError::PoolPaused.into());
+        }
 
-        // Load pool state (use deserialize instead of try_from_slice to handle extra bytes)
-        let mut pool_state = PoolState::deserialize(&mut &pool_account.data.borrow()[..])?;
+        let (key_image_pubkey, key_image_bump) =
+            PoolState::derive_key_image_pda(pool_account.key, &key_image, program_id);
+        check_address(key_image_account, &key_image_pubkey)?;
+        check_writable(key_image_account)?;
 
-        // Verify key image not used
-        if pool_state.is_key_image_used(&key_image) {
+        // Verify key image not used. The record PDA covers every key image;
+        // the cache only holds the first key_image_cache_size of them.
+        if key_image_account.owner == program_id || pool_state.is_key_image_used(&key_image) {
             return Err(PrivacyError::KeyImageAlreadyUsed.into());
         }
 
+        Self::check_auditor_note(
+            program_id,
+            pool_account.key,
+            &pool_state,
+            Some(&new_commitment),
+            auditor_note.as_ref(),
+            auditor_vk_account,
+        )?;
+
         // Verify ring signature
-        if !verifier::verify_ring_signature(&ring_signature, &key_image, &ring_members)? {
+        if !verifier::verify_ring_signature(
+            &ring_signature,
+            &key_image,
+            &ring_members,
+            config.params.max_ring_size as usize,
+        )? {
             return Err(PrivacyError::InvalidRingSignature.into());
         }
 
         // Mark key image as used
-        pool_state.add_key_image(key_image);
+        pool_state.add_key_image(key_image, config.params.key_image_cache_size as usize);
+        Self::create_pda_account(
+            program_id,
+            payer,
+            key_image_account,
+            system_program,
+            KeyImageAccount::LEN,
+            0,
+            &[b"key_image", pool_account.key.as_ref(), &key_image, &[key_image_bump]],
+        )?;
+        KeyImageAccount {
+            discriminator: KeyImageAccount::DISCRIMINATOR,
+            version: KeyImageAccount::VERSION,
+            key_image,
+            pool: *pool_account.key,
+            tx_signature: None,
+            timestamp: clock.unix_timestamp,
+            bump: key_image_bump,
+        }
+        .serialize(&mut &mut key_image_account.data.borrow_mut()[..])?;
 
         // Add new commitment for recipient
-        pool_state.add_commitment(new_commitment);
+        let leaf_index = pool_state.commitment_count;
+        pool_state.add_commitment(new_commitment)?;
 
-        // Save state
-        pool_state.serialize(&mut *pool_account.data.borrow_mut())?;
+        let leaf = TreeLeaf { index: leaf_index, commitment: new_commitment };
+        PrivacyEvent::PrivateTransfer {
+            pool: *pool_account.key,
+            key_image,
+            ring_size: ring_members.len() as u32,
+            leaf,
+            root: pool_state.merkle_root,
+        }
+        .emit()?;
+        note.created_event(*pool_account.key, leaf, [0u8; 32]).emit()?;
+        if let Some(auditor_note) = &auditor_note {
+            auditor_note.created_event(*pool_account.key, leaf).emit()?;
+        }
 
         msg!("Private transfer successful");
         msg!("  Key image: {:?}", key_image);
@@ -416,13 +851,19 @@ impl Processor {
         balance_commitment: [u8; 32],
     ) -> ProgramResult {
         let account_info_iter = &mut accounts.iter();
-        let _pool_account = next_account_info(account_info_iter)?;
+        let pool_account = next_account_info(account_info_iter)?;
         let _user_account = next_account_info(account_info_iter)?;
         let vk_account = next_account_info(account_info_iter)?;
 
-        // Verify balance proof
+        Self::load_pool_ref(program_id, pool_account)?;
+        Self::check_verification_key(program_id, pool_account.key, CircuitType::Balance, vk_account)?;
+
+        // Verify balance proof. Public inputs are 32-byte little-endian field
+        // elements, so min_balance is zero-extended.
+        let mut min_balance_input = [0u8; 32];
+        min_balance_input[..8].copy_from_slice(&min_balance.to_le_bytes());
         let public_inputs = vec![
-            min_balance.to_le_bytes().to_vec(),
+            min_balance_input.to_vec(),
             balance_commitment.to_vec(),
         ];
 
@@ -455,12 +896,42 @@ impl Processor {
         let system_program = next_account_info(account_info_iter)?;
 
         // Verify issuer is signer
-        if !issuer.is_signer {
-            return Err(ProgramError::MissingRequiredSignature);
+        check_signer(issuer)?;
+        check_system_program(system_program)?;
+        check_unique(&[issuer, asset_account])?;
+
+        // Verify asset PDA
+        let (asset_pubkey, asset_bump) = AssetState::derive_address(&asset_id, program_id);
+        check_address(asset_account, &asset_pubkey)?;
+        check_writable(asset_account)?;
+
+        // Check if the asset already exists
+        if asset_account.owner == program_id {
+            msg!("Asset already issued");
+            return Err(ProgramError::AccountAlreadyInitialized);
         }
 
+        // Validate metadata lengths (AssetState::LEN reserves the maximum)
+        if name.len() > AssetState::MAX_NAME_LEN || symbol.len() > AssetState::MAX_SYMBOL_LEN {
+            msg!("Asset name/symbol too long (max {}/{} bytes)", AssetState::MAX_NAME_LEN, AssetState::MAX_SYMBOL_LEN);
+            return Err(PrivacyError::InvalidAccountData.into());
+        }
+
+        // Create asset state account (PDA owned by program, rent-exempt at full size)
+        Self::create_pda_account(
+            program_id,
+            issuer,
+            asset_account,
+            system_program,
+            AssetState::LEN,
+            0,
+            &[b"asset", asset_id.as_ref(), &[asset_bump]],
+        )?;
+
         // Initialize asset state
         let asset_state = AssetState {
+            discriminator: AssetState::DISCRIMINATOR,
+            version: AssetState::VERSION,
             asset_id,
             issuer: *issuer.key,
             name: name.clone(),
@@ -475,7 +946,16 @@ impl Processor {
         };
 
         // Save state
-        asset_state.serialize(&mut *asset_account.data.borrow_mut())?;
+        asset_state.serialize(&mut &mut asset_account.data.borrow_mut()[..])?;
+
+        PrivacyEvent::AssetIssued {
+            asset: asset_pubkey,
+            asset_id,
+            issuer: *issuer.key,
+            decimals,
+            total_supply,
+        }
+        .emit()?;
 
         msg!("Private asset issued");
         msg!("  Name: {}", name);
@@ -502,8 +982,24 @@ impl Processor {
         let recipient_note = next_account_info(account_info_iter)?;
         let vk_account = next_account_info(account_info_iter)?;
 
+        check_unique(&[asset_account, sender_note, recipient_note, vk_account])?;
+
+        // The recipient's note must fit a ShieldedNote
+        let note = NoteCiphertext::parse(&encrypted_data)?;
+
         // Load asset state
-        let mut asset_state = AssetState::try_from_slice(&asset_account.data.borrow())?;
+        let mut asset_state = Account::<AssetState>::load_mut(program_id, asset_account)?;
+
+        // Assets have no pool of their own; the proof is checked against a
+        // pool's Transfer VK, which must be a genuine VK PDA
+        #[cfg(feature = "real-zk-verification")]
+        {
+            let vk_state = Account::<VerificationKeyAccount>::load(program_id, vk_account)?;
+            if vk_state.circuit_type != CircuitType::Transfer {
+                return Err(PrivacyError::InvalidVerificationKey.into());
+            }
+            Self::check_verification_key(program_id, &vk_state.pool, CircuitType::Transfer, vk_account)?;
+        }
 
         // Verify asset ID matches
         if asset_id != asset_state.asset_id {
@@ -515,6 +1011,12 @@ impl Processor {
             return Err(PrivacyError::NullifierAlreadyUsed.into());
         }
 
+        // Nullifiers are never evicted, so a full set cannot accept more transfers
+        if asset_state.used_nullifiers.len() >= AssetState::MAX_NULLIFIERS {
+            msg!("Asset nullifier set full ({} entries)", AssetState::MAX_NULLIFIERS);
+            return Err(PrivacyError::InvalidAccountData.into());
+        }
+
         // Verify ZK proof for asset transfer
         let public_inputs = vec![
             asset_id.to_vec(),
@@ -533,10 +1035,20 @@ impl Processor {
         asset_state.used_nullifiers.push(nullifier);
 
         // Add new commitment
+        let leaf = TreeLeaf { index: asset_state.note_count, commitment: new_commitment };
         asset_state.note_count += 1;
 
         // Save state
-        asset_state.serialize(&mut *asset_account.data.borrow_mut())?;
+        asset_state.save()?;
+
+        PrivacyEvent::AssetTransferred {
+            asset: *asset_account.key,
+            nullifier,
+            commitment: new_commitment,
+            note_count: asset_state.note_count,
+        }
+        .emit()?;
+        note.created_event(*asset_account.key, leaf, asset_id).emit()?;
 
         msg!("Private asset transfer successful");
         msg!("  Asset ID: {:?}", asset_id);
@@ -557,20 +1069,19 @@ impl Processor {
         let pool_account = next_account_info(account_info_iter)?;
         let authority = next_account_info(account_info_iter)?;
         let system_program = next_account_info(account_info_iter)?;
-
-        // Verify authority is signer
-        if !authority.is_signer {
-            return Err(ProgramError::MissingRequiredSignature);
-        }
-
-        // Load pool state to verify authority
-        let pool_state = PoolState::deserialize(&mut &pool_account.data.borrow()[..])?;
-
-        if !pool_state.is_initialized {
-            return Err(PrivacyError::PoolNotInitialized.into());
-        }
-
-        // Verify authority matches pool authority
+        // Optional separate payer (a multisig authority cannot fund accounts)
+        let payer = next_account_info(account_info_iter).unwrap_or(authority);
+
+        // Verify payer is signer
+        check_signer(payer)?;
+        check_system_program(system_program)?;
+        check_writable(vk_account)?;
+        check_unique(&[vk_account, pool_account, authority])?;
+
+        // Only the VK PDA is written, so the pool is loaded read-only and
+        // deposits and withdrawals are not locked out meanwhile
+        check_signer(authority)?;
+        let pool_state = Self::load_pool_ref(program_id, pool_account)?;
         if pool_state.authority != *authority.key {
             return Err(PrivacyError::Unauthorized.into());
         }
@@ -586,6 +1097,7 @@ impl Processor {
             0 => CircuitType::Transfer,
             1 => CircuitType::Balance,
             2 => CircuitType::RingSignature,
+            3 => CircuitType::AuditorNote,
             _ => return Err(PrivacyError::InvalidAccountData.into()),
         };
 
@@ -597,45 +1109,46 @@ impl Processor {
         );
 
         // Verify provided account matches derived PDA
-        if vk_account.key != &vk_pubkey {
-            msg!("VK account mismatch: expected {}, got {}", vk_pubkey, vk_account.key);
-            return Err(ProgramError::InvalidAccountData);
+        check_address(vk_account, &vk_pubkey)?;
+
+        // Get current timestamp
+        let clock = solana_program::clock::Clock::get()?;
+        let stored_at = clock.unix_timestamp;
+
+        if pool_state.is_paused(PoolState::PAUSE_VK_UPDATES, stored_at) {
+            return Err(PrivacyError::PoolPaused.into());
         }
 
-        // Create VK account if it doesn't exist
-        if vk_account.lamports() == 0 {
+        // Create VK account unless it already exists (then it is overwritten)
+        if vk_account.owner != program_id {
             let space = VerificationKeyAccount::LEN;
-            let rent = Rent::get()?.minimum_balance(space);
 
-            msg!("Creating VK account: {} bytes, {} lamports rent", space, rent);
+            msg!("Creating VK account: {} bytes", space);
 
-            invoke_signed(
-                &system_instruction::create_account(
-                    authority.key,
-                    &vk_pubkey,
-                    rent,
-                    space as u64,
-                    program_id,
-                ),
-                &[authority.clone(), vk_account.clone(), system_program.clone()],
-                &[&[
+            Self::create_pda_account(
+                program_id,
+                payer,
+                vk_account,
+                system_program,
+                space,
+                0,
+                &[
                     match circuit_type_enum {
                         CircuitType::Transfer => b"vk_transfer",
                         CircuitType::Balance => b"vk_balance",
                         CircuitType::RingSignature => b"vk_ring_sig",
+                        CircuitType::AuditorNote => b"vk_auditor_note",
                     },
                     pool_account.key.as_ref(),
                     &[bump],
-                ]],
+                ],
             )?;
         }
 
-        // Get current timestamp
-        let clock = solana_program::clock::Clock::get()?;
-        let stored_at = clock.unix_timestamp;
-
         // Initialize VK account
         let vk_account_state = VerificationKeyAccount {
+            discriminator: VerificationKeyAccount::DISCRIMINATOR,
+            version: VerificationKeyAccount::VERSION,
             circuit_type: circuit_type_enum,
             pool: *pool_account.key,
             authority: *authority.key,
@@ -645,7 +1158,17 @@ impl Processor {
         };
 
         // Serialize and save
-        vk_account_state.serialize(&mut *vk_account.data.borrow_mut())?;
+        vk_account_state.serialize(&mut &mut vk_account.data.borrow_mut()[..])?;
+
+        PrivacyEvent::VerificationKeyStored {
+            pool: *pool_account.key,
+            circuit_type: circuit_type_enum,
+            vk_account: vk_pubkey,
+            authority: *authority.key,
+            vk_len: vk_account_state.vk_data.len() as u32,
+            stored_at,
+        }
+        .emit()?;
 
         msg!("Verification key stored successfully");
         msg!("  Circuit type: {:?}", circuit_type_enum);
@@ -665,11 +1188,14 @@ impl Processor {
         let relayer_account = next_account_info(account_info_iter)?;
         let relayer_wallet = next_account_info(account_info_iter)?;
         let system_program = next_account_info(account_info_iter)?;
+        let config_account = next_account_info(account_info_iter)?;
 
         // Verify relayer wallet is signer
-        if !relayer_wallet.is_signer {
-            return Err(ProgramError::MissingRequiredSignature);
-        }
+        check_signer(relayer_wallet)?;
+        check_system_program(system_program)?;
+        check_writable(relayer_account)?;
+
+        let config = Self::load_config(program_id, config_account)?;
 
         // Validate endpoint length
         if endpoint.is_empty() || endpoint.len() > RelayerAccount::MAX_ENDPOINT_LEN {
@@ -677,9 +1203,9 @@ impl Processor {
             return Err(PrivacyError::InvalidAccountData.into());
         }
 
-        // Validate minimum stake (0.1 SOL)
-        if stake < 100_000_000 {
-            msg!("Insufficient stake: {} lamports (minimum 0.1 SOL)", stake);
+        // Validate minimum stake
+        if stake < config.params.min_relayer_stake {
+            msg!("Insufficient stake: {} lamports (minimum {})", stake, config.params.min_relayer_stake);
             return Err(PrivacyError::InvalidAmount.into());
         }
 
@@ -690,59 +1216,53 @@ impl Processor {
         );
 
         // Verify provided account matches derived PDA
-        if relayer_account.key != &relayer_pubkey {
-            msg!("Relayer account mismatch: expected {}, got {}", relayer_pubkey, relayer_account.key);
-            return Err(ProgramError::InvalidAccountData);
-        }
+        check_address(relayer_account, &relayer_pubkey)?;
 
         // Check if relayer already registered
-        if relayer_account.lamports() > 0 {
+        if relayer_account.owner == program_id {
             msg!("Relayer already registered");
             return Err(PrivacyError::InvalidAccountData.into());
         }
 
-        // Create relayer account
+        // Create relayer account, holding the stake on top of rent
         let space = RelayerAccount::LEN;
-        let rent = Rent::get()?.minimum_balance(space);
 
-        msg!("Creating relayer account: {} bytes, {} lamports rent", space, rent);
+        msg!("Creating relayer account: {} bytes", space);
 
-        invoke_signed(
-            &system_instruction::create_account(
-                relayer_wallet.key,
-                &relayer_pubkey,
-                rent + stake, // Account rent + stake
-                space as u64,
-                program_id,
-            ),
-            &[relayer_wallet.clone(), relayer_account.clone(), system_program.clone()],
-            &[&[b"relayer", relayer_wallet.key.as_ref(), &[bump]]],
+        Self::create_pda_account(
+            program_id,
+            relayer_wallet,
+            relayer_account,
+            system_program,
+            space,
+            stake,
+            &[b"relayer", relayer_wallet.key.as_ref(), &[bump]],
         )?;
 
         // Get current timestamp
         let clock = solana_program::clock::Clock::get()?;
         let current_time = clock.unix_timestamp;
 
-        // Initialize relayer account
-        let relayer_state = RelayerAccount {
+        // Initialize relayer account in place
+        let relayer_state = ZeroCopyMut::init(
+            program_id,
+            relayer_account,
+            RelayerAccount::new(*relayer_wallet.key, stake, &endpoint, bump, current_time),
+        )?;
+
+        PrivacyEvent::RelayerRegistered {
             relayer: *relayer_wallet.key,
+            relayer_account: relayer_pubkey,
             stake,
-            successful_relays: 0,
-            failed_relays: 0,
-            last_heartbeat: current_time,
-            is_active: true,
+            endpoint: relayer_state.endpoint().to_string(),
             registered_at: current_time,
-            endpoint,
-            bump,
-        };
-
-        // Serialize and save
-        relayer_state.serialize(&mut *relayer_account.data.borrow_mut())?;
+        }
+        .emit()?;
 
         msg!("Relayer registered successfully");
         msg!("  Relayer: {}", relayer_wallet.key);
         msg!("  Stake: {} SOL", stake as f64 / 1_000_000_000.0);
-        msg!("  Endpoint: {}", relayer_state.endpoint);
+        msg!("  Endpoint: {}", relayer_state.endpoint());
 
         Ok(())
     }
@@ -756,12 +1276,10 @@ impl Processor {
         let relayer_wallet = next_account_info(account_info_iter)?;
 
         // Verify relayer wallet is signer
-        if !relayer_wallet.is_signer {
-            return Err(ProgramError::MissingRequiredSignature);
-        }
+        check_signer(relayer_wallet)?;
 
         // Load relayer state
-        let mut relayer_state = RelayerAccount::deserialize(&mut &relayer_account.data.borrow()[..])?;
+        let mut relayer_state = Self::load_relayer(program_id, relayer_account)?;
 
         // Verify relayer matches
         if relayer_state.relayer != *relayer_wallet.key {
@@ -772,8 +1290,11 @@ impl Processor {
         let clock = solana_program::clock::Clock::get()?;
         relayer_state.last_heartbeat = clock.unix_timestamp;
 
-        // Save state
-        relayer_state.serialize(&mut *relayer_account.data.borrow_mut())?;
+        PrivacyEvent::RelayerHeartbeat {
+            relayer: *relayer_wallet.key,
+            timestamp: clock.unix_timestamp,
+        }
+        .emit()?;
 
         msg!("Heartbeat updated for relayer {}", relayer_wallet.key);
 
@@ -787,35 +1308,1786 @@ impl Processor {
     ) -> ProgramResult {
         let account_info_iter = &mut accounts.iter();
         let relayer_account = next_account_info(account_info_iter)?;
-        let _pool_account = next_account_info(account_info_iter)?;
-        let authority = next_account_info(account_info_iter)?;
+        let config_account = next_account_info(account_info_iter)?;
+        let reporter = next_account_info(account_info_iter)?;
 
-        // Verify authority is signer
-        if !authority.is_signer {
-            return Err(ProgramError::MissingRequiredSignature);
-        }
+        // Verify reporter is signer
+        check_signer(reporter)?;
 
         // Load relayer state
-        let mut relayer_state = RelayerAccount::deserialize(&mut &relayer_account.data.borrow()[..])?;
+        let mut relayer_state = Self::load_relayer(program_id, relayer_account)?;
+        let config = Self::load_config(program_id, config_account)?;
+
+        // Anyone can create a pool, so pool authorities are not trusted
+        // reporters: only the config admin is. A relayer may only report
+        // its own failures.
+        let is_self_report = relayer_state.relayer == *reporter.key;
+        if config.admin != *reporter.key && (!is_self_report || success) {
+            return Err(PrivacyError::Unauthorized.into());
+        }
 
         // Update reputation
-        if success {
-            relayer_state.successful_relays += 1;
-        } else {
-            relayer_state.failed_relays += 1;
-        }
+        let clock = solana_program::clock::Clock::get()?;
+        relayer_state.record_relay(success, clock.unix_timestamp);
 
         // Calculate new reputation
-        let reputation = relayer_state.reputation_score();
-
-        // Save state
-        relayer_state.serialize(&mut *relayer_account.data.borrow_mut())?;
+        let reputation = relayer_state.reputation_score(clock.unix_timestamp);
+
+        PrivacyEvent::RelayReported {
+            relayer: relayer_state.relayer,
+            reported_by: *reporter.key,
+            success,
+            successful_relays: relayer_state.successful_relays,
+            failed_relays: relayer_state.failed_relays,
+            reputation,
+        }
+        .emit()?;
 
         msg!("Relay reported: {}", if success { "SUCCESS" } else { "FAILED" });
         msg!("  Relayer: {}", relayer_state.relayer);
-        msg!("  New reputation: {}/100", reputation);
+        match reputation {
+            Some(score) => msg!("  New reputation: {}/100", score),
+            None => msg!("  New reputation: unscored (< {} recent reports)", RelayerAccount::MIN_REPUTATION_SAMPLES),
+        }
         msg!("  Success: {}, Failed: {}", relayer_state.successful_relays, relayer_state.failed_relays);
 
         Ok(())
     }
+
+    fn process_migrate(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        account_type: AccountType,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let account = next_account_info(account_info_iter)?;
+        let payer = next_account_info(account_info_iter)?;
+        let system_program = next_account_info(account_info_iter)?;
+
+        // Verify payer is signer
+        check_signer(payer)?;
+        check_system_program(system_program)?;
+        check_owner(account, program_id)?;
+        check_writable(account)?;
+        check_unique(&[account, payer])?;
+
+        // Decode the legacy layout and re-encode it in the current one.
+        // Accounts already at the current version are left alone.
+        let migration = {
+            let data = account.data.borrow();
+            match account_type {
+                AccountType::Pool => {
+                    let state = if !PoolState::has_discriminator(&data) {
+                        Self::check_headerless(&data)?;
+                        let old_state = PoolStateV0::deserialize(&mut &data[..])?;
+                        // Legacy pools live at keypair addresses, but their
+                        // vault was always derived from that address
+                        let (vault, _) = PoolState::derive_vault_address(account.key, program_id);
+                        if old_state.vault != vault {
+                            msg!("Legacy pool vault {} is not derived from {}", old_state.vault, account.key);
+                            return Err(PrivacyError::InvalidAccountData.into());
+                        }
+                        Some(PoolState::from(PoolStateV1::from(old_state)))
+                    } else if data[8] == PoolStateV1::VERSION {
+                        Some(PoolState::from(PoolStateV1::deserialize(&mut &data[..])?))
+                    } else if data[8] == PoolStateV2::VERSION {
+                        Some(PoolStateV2::load(&data)?)
+                    } else if data[8] == PoolStateV3::VERSION {
+                        Some(PoolStateV3::load(&data)?)
+                    } else {
+                        None
+                    };
+                    match state {
+                        Some(state) => Some((state.try_to_vec()?, PoolState::LEN, 0)),
+                        None => None,
+                    }
+                }
+                AccountType::Asset => {
+                    if AssetState::has_discriminator(&data) {
+                        None
+                    } else {
+                        Self::check_headerless(&data)?;
+                        // Legacy assets live at whatever address the issuer
+                        // created, so ownership and the missing header are the guard
+                        let old_state = AssetStateV0::deserialize(&mut &data[..])?;
+                        if !old_state.is_initialized {
+                            return Err(PrivacyError::InvalidAccountData.into());
+                        }
+                        let state = AssetState::from(old_state);
+                        Some((state.try_to_vec()?, AssetState::LEN, 0))
+                    }
+                }
+                AccountType::VerificationKey => {
+                    if VerificationKeyAccount::has_discriminator(&data) {
+                        None
+                    } else {
+                        Self::check_headerless(&data)?;
+                        let old_state = VerificationKeyAccountV0::deserialize(&mut &data[..])?;
+                        let (expected, _) = VerificationKeyAccount::derive_address(
+                            &old_state.pool,
+                            old_state.circuit_type,
+                            program_id,
+                        );
+                        check_address(account, &expected)?;
+                        let state = VerificationKeyAccount::from(old_state);
+                        Some((state.try_to_vec()?, VerificationKeyAccount::LEN, 0))
+                    }
+                }
+                AccountType::Relayer => {
+                    let old_state = if !RelayerAccount::has_discriminator(&data) {
+                        Self::check_headerless(&data)?;
+                        if data.len() != RelayerAccountV0::LEN {
+                            msg!("Relayer account is not in the legacy layout ({} bytes)", data.len());
+                            return Err(PrivacyError::InvalidAccountData.into());
+                        }
+                        let old_state = RelayerAccountV0::deserialize(&mut &data[..])?;
+                        check_pda(account, &[b"relayer", old_state.relayer.as_ref()], old_state.bump, program_id)?;
+                        Some(RelayerAccountV1::from(old_state))
+                    } else if data[8] == RelayerAccountV1::VERSION {
+                        Some(RelayerAccountV1::deserialize(&mut &data[..])?)
+                    } else {
+                        None
+                    };
+                    match old_state {
+                        Some(old_state) => {
+                            // Stake is held on top of rent and must stay there
+                            let stake = old_state.stake;
+                            let state = RelayerAccount::from(old_state);
+                            Some((state.try_to_vec()?, RelayerAccount::LEN, stake))
+                        }
+                        None => None,
+                    }
+                }
+                _ => {
+                    msg!("{:?} accounts have no legacy layout", account_type);
+                    return Err(PrivacyError::InvalidInstruction.into());
+                }
+            }
+        };
+
+        // (new data, target LEN, lamports kept on top of rent)
+        let (migrated, target_len, extra_lamports) = match migration {
+            Some(migration) => migration,
+            None => {
+                msg!("Account already uses the current layout");
+                return Ok(());
+            }
+        };
+
+        // Grow to the current LEN, within the per-instruction realloc limit.
+        // Caller-sized accounts that are already larger keep their size.
+        let new_len = target_len
+            .min(account.data_len() + MAX_PERMITTED_DATA_INCREASE)
+            .max(account.data_len());
+        if new_len < migrated.len() {
+            msg!("Account too small for migrated data: {} < {}", new_len, migrated.len());
+            return Err(PrivacyError::InvalidAccountData.into());
+        }
+
+        let required = Rent::get()?
+            .minimum_balance(new_len)
+            .saturating_add(extra_lamports);
+        let lamports_needed = required.saturating_sub(account.lamports());
+        if lamports_needed > 0 {
+            invoke(
+                &system_instruction::transfer(payer.key, account.key, lamports_needed),
+                &[payer.clone(), account.clone(), system_program.clone()],
+            )?;
+        }
+
+        if new_len != account.data_len() {
+            account.realloc(new_len, true)?;
+        }
+
+        let mut data = account.data.borrow_mut();
+        data[..migrated.len()].copy_from_slice(&migrated);
+        data[migrated.len()..].fill(0);
+
+        PrivacyEvent::AccountMigrated {
+            account: *account.key,
+            account_type,
+            size: new_len as u64,
+        }
+        .emit()?;
+
+        msg!("Account migrated");
+        msg!("  Type: {:?}", account_type);
+        msg!("  Size: {} bytes", new_len);
+
+        Ok(())
+    }
+
+    /// Require legacy `data` to carry no account header at all
+    ///
+    /// Version 0 layouts are only recognised by the missing header, so an
+    /// account of another type must not be decoded as one.
+    fn check_headerless(data: &[u8]) -> ProgramResult {
+        if let Some(account_type) = AccountType::from_header(data) {
+            msg!("Account holds a {:?}, not a legacy layout", account_type);
+            return Err(PrivacyError::InvalidAccountData.into());
+        }
+        Ok(())
+    }
+
+    fn process_initialize_config(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        params: ConfigParams,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let config_account = next_account_info(account_info_iter)?;
+        let admin = next_account_info(account_info_iter)?;
+        let system_program = next_account_info(account_info_iter)?;
+        let program_data = next_account_info(account_info_iter)?;
+
+        // Only whoever can upgrade the program may take over its config
+        check_signer(admin)?;
+        check_system_program(system_program)?;
+        check_upgrade_authority(program_data, admin.key, program_id)?;
+
+        if !params.is_valid() {
+            msg!("Invalid config params: {:?}", params);
+            return Err(PrivacyError::InvalidConfig.into());
+        }
+
+        // Derive config PDA
+        let (config_pubkey, bump) = ProtocolConfig::derive_address(program_id);
+        check_address(config_account, &config_pubkey)?;
+
+        // Check if config already exists
+        if config_account.owner == program_id {
+            msg!("Protocol config already initialized");
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        Self::create_pda_account(
+            program_id,
+            admin,
+            config_account,
+            system_program,
+            ProtocolConfig::LEN,
+            0,
+            &[b"config", &[bump]],
+        )?;
+
+        let config = ProtocolConfig {
+            discriminator: ProtocolConfig::DISCRIMINATOR,
+            version: ProtocolConfig::VERSION,
+            admin: *admin.key,
+            params,
+            bump,
+            is_initialized: true,
+        };
+
+        config.serialize(&mut &mut config_account.data.borrow_mut()[..])?;
+
+        PrivacyEvent::ConfigInitialized {
+            admin: config.admin,
+            params,
+        }
+        .emit()?;
+
+        msg!("Protocol config initialized");
+        msg!("  Admin: {}", admin.key);
+
+        Ok(())
+    }
+
+    fn process_update_config(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        params: ConfigParams,
+        new_admin: Option<Pubkey>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let config_account = next_account_info(account_info_iter)?;
+        let admin = next_account_info(account_info_iter)?;
+
+        // Verify admin is signer
+        check_signer(admin)?;
+        check_writable(config_account)?;
+
+        let mut config = Self::load_config(program_id, config_account)?;
+
+        if config.admin != *admin.key {
+            return Err(PrivacyError::Unauthorized.into());
+        }
+
+        if !params.is_valid() {
+            msg!("Invalid config params: {:?}", params);
+            return Err(PrivacyError::InvalidConfig.into());
+        }
+
+        config.params = params;
+        if let Some(new_admin) = new_admin {
+            config.admin = new_admin;
+        }
+
+        config.save()?;
+
+        PrivacyEvent::ConfigUpdated {
+            updated_by: *admin.key,
+            admin: config.admin,
+            params,
+        }
+        .emit()?;
+
+        msg!("Protocol config updated");
+        msg!("  Admin: {}", config.admin);
+
+        Ok(())
+    }
+
+    fn process_update_root(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        new_root: [u8; 32],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let pool_account = next_account_info(account_info_iter)?;
+        let authority = next_account_info(account_info_iter)?;
+
+        let mut pool_state = Self::load_pool_as_authority(program_id, pool_account, authority)?;
+
+        pool_state.update_root(new_root);
+
+        PrivacyEvent::RootUpdated {
+            pool: *pool_account.key,
+            root: new_root,
+        }
+        .emit()?;
+
+        msg!("Merkle root updated");
+        msg!("  Root: {:?}", new_root);
+
+        Ok(())
+    }
+
+    fn process_propose_authority(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        new_authority: Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let pool_account = next_account_info(account_info_iter)?;
+        let authority = next_account_info(account_info_iter)?;
+
+        let mut pool_state = Self::load_pool_as_authority(program_id, pool_account, authority)?;
+
+        // A new proposal replaces any earlier one
+        pool_state.pending_authority = new_authority;
+
+        PrivacyEvent::AuthorityProposed {
+            pool: *pool_account.key,
+            authority: pool_state.authority,
+            pending_authority: new_authority,
+        }
+        .emit()?;
+
+        msg!("Authority transfer proposed");
+        msg!("  Pending authority: {}", new_authority);
+
+        Ok(())
+    }
+
+    fn process_accept_authority(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let pool_account = next_account_info(account_info_iter)?;
+        let new_authority = next_account_info(account_info_iter)?;
+
+        // Verify pending authority is signer
+        check_signer(new_authority)?;
+
+        let mut pool_state = Self::load_pool(program_id, pool_account)?;
+
+        match pool_state.pending_authority() {
+            None => return Err(PrivacyError::NoPendingAuthority.into()),
+            Some(pending) if pending != *new_authority.key => {
+                return Err(PrivacyError::Unauthorized.into());
+            }
+            Some(_) => {}
+        }
+
+        let previous_authority = pool_state.authority;
+        pool_state.authority = *new_authority.key;
+        pool_state.pending_authority = Pubkey::default();
+
+        PrivacyEvent::AuthorityAccepted {
+            pool: *pool_account.key,
+            previous_authority,
+            authority: pool_state.authority,
+        }
+        .emit()?;
+
+        msg!("Authority transferred");
+        msg!("  From: {}", previous_authority);
+        msg!("  To: {}", pool_state.authority);
+
+        Ok(())
+    }
+
+    fn process_create_multisig(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        create_key: Pubkey,
+        threshold: u8,
+        signers: Vec<Pubkey>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let multisig_account = next_account_info(account_info_iter)?;
+        let payer = next_account_info(account_info_iter)?;
+        let system_program = next_account_info(account_info_iter)?;
+
+        // Verify payer is signer
+        check_signer(payer)?;
+        check_system_program(system_program)?;
+
+        // Validate membership and threshold
+        if signers.is_empty() || signers.len() > Multisig::MAX_SIGNERS {
+            msg!("Invalid signer count: {} (max {})", signers.len(), Multisig::MAX_SIGNERS);
+            return Err(PrivacyError::InvalidMultisig.into());
+        }
+
+        if threshold == 0 || threshold as usize > signers.len() {
+            msg!("Invalid threshold: {} of {}", threshold, signers.len());
+            return Err(PrivacyError::InvalidMultisig.into());
+        }
+
+        for (i, signer) in signers.iter().enumerate() {
+            if signers[..i].contains(signer) {
+                msg!("Duplicate multisig signer: {}", signer);
+                return Err(PrivacyError::InvalidMultisig.into());
+            }
+        }
+
+        // Derive multisig PDA
+        let (multisig_pubkey, bump) = Multisig::derive_address(&create_key, program_id);
+
+        check_address(multisig_account, &multisig_pubkey)?;
+
+        if multisig_account.owner == program_id {
+            msg!("Multisig already exists");
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        Self::create_pda_account(
+            program_id,
+            payer,
+            multisig_account,
+            system_program,
+            Multisig::LEN,
+            0,
+            &[b"multisig", create_key.as_ref(), &[bump]],
+        )?;
+
+        let multisig = Multisig {
+            discriminator: Multisig::DISCRIMINATOR,
+            version: Multisig::VERSION,
+            create_key,
+            threshold,
+            signers,
+            proposal_count: 0,
+            bump,
+            is_initialized: true,
+        };
+
+        multisig.serialize(&mut &mut multisig_account.data.borrow_mut()[..])?;
+
+        PrivacyEvent::MultisigCreated {
+            multisig: multisig_pubkey,
+            threshold: multisig.threshold,
+            signers: multisig.signers.clone(),
+        }
+        .emit()?;
+
+        msg!("Multisig created");
+        msg!("  Address: {}", multisig_pubkey);
+        msg!("  Threshold: {} of {}", multisig.threshold, multisig.signers.len());
+
+        Ok(())
+    }
+
+    fn process_create_proposal(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        instruction_data: Vec<u8>,
+        proposal_accounts: Vec<ProposalAccount>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let multisig_account = next_account_info(account_info_iter)?;
+        let proposal_account = next_account_info(account_info_iter)?;
+        let proposer = next_account_info(account_info_iter)?;
+        let system_program = next_account_info(account_info_iter)?;
+
+        // Verify proposer is signer
+        check_signer(proposer)?;
+        check_system_program(system_program)?;
+        check_writable(multisig_account)?;
+
+        let mut multisig = Self::load_multisig(program_id, multisig_account)?;
+
+        if !multisig.is_signer(proposer.key) {
+            return Err(PrivacyError::Unauthorized.into());
+        }
+
+        if instruction_data.len() > MultisigProposal::MAX_INSTRUCTION_DATA
+            || proposal_accounts.len() > MultisigProposal::MAX_ACCOUNTS
+        {
+            msg!("Proposal too large");
+            return Err(PrivacyError::InvalidAccountData.into());
+        }
+
+        // Only well-formed instructions for this program can be proposed
+        if PrivacyInstruction::try_from_slice(&instruction_data).is_err() {
+            msg!("Proposal does not contain a valid instruction");
+            return Err(PrivacyError::InvalidInstruction.into());
+        }
+
+        let index = multisig.proposal_count;
+        let (proposal_pubkey, bump) =
+            MultisigProposal::derive_address(multisig_account.key, index, program_id);
+
+        check_address(proposal_account, &proposal_pubkey)?;
+
+        Self::create_pda_account(
+            program_id,
+            proposer,
+            proposal_account,
+            system_program,
+            MultisigProposal::LEN,
+            0,
+            &[b"proposal", multisig_account.key.as_ref(), &index.to_le_bytes(), &[bump]],
+        )?;
+
+        let proposal = MultisigProposal {
+            discriminator: MultisigProposal::DISCRIMINATOR,
+            version: MultisigProposal::VERSION,
+            multisig: *multisig_account.key,
+            index,
+            proposer: *proposer.key,
+            instruction_data,
+            accounts: proposal_accounts,
+            approvals: vec![*proposer.key],
+            executed: false,
+            bump,
+        };
+
+        proposal.serialize(&mut &mut proposal_account.data.borrow_mut()[..])?;
+
+        multisig.proposal_count += 1;
+        multisig.save()?;
+
+        PrivacyEvent::ProposalCreated {
+            multisig: *multisig_account.key,
+            proposal: proposal_pubkey,
+            index,
+            proposer: *proposer.key,
+        }
+        .emit()?;
+
+        msg!("Proposal created");
+        msg!("  Proposal: {}", proposal_pubkey);
+        msg!("  Index: {}", index);
+
+        Ok(())
+    }
+
+    fn process_approve_proposal(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let multisig_account = next_account_info(account_info_iter)?;
+        let proposal_account = next_account_info(account_info_iter)?;
+        let member = next_account_info(account_info_iter)?;
+
+        // Verify member is signer
+        check_signer(member)?;
+        check_writable(proposal_account)?;
+
+        let multisig = Self::load_multisig(program_id, multisig_account)?;
+        let mut proposal = Self::load_proposal(program_id, multisig_account, proposal_account)?;
+
+        if !multisig.is_signer(member.key) {
+            return Err(PrivacyError::Unauthorized.into());
+        }
+
+        if proposal.executed {
+            return Err(PrivacyError::ProposalAlreadyExecuted.into());
+        }
+
+        if !proposal.approvals.contains(member.key) {
+            proposal.approvals.push(*member.key);
+        }
+
+        proposal.save()?;
+
+        PrivacyEvent::ProposalApproved {
+            multisig: *multisig_account.key,
+            proposal: *proposal_account.key,
+            member: *member.key,
+            approvals: proposal.approvals.len() as u8,
+        }
+        .emit()?;
+
+        msg!("Proposal approved");
+        msg!("  Approvals: {} of {}", proposal.approvals.len(), multisig.threshold);
+
+        Ok(())
+    }
+
+    fn process_execute_proposal(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let multisig_account = next_account_info(account_info_iter)?;
+        let proposal_account = next_account_info(account_info_iter)?;
+        let program_account = next_account_info(account_info_iter)?;
+        let instruction_accounts = account_info_iter.as_slice();
+
+        if program_account.key != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        check_writable(proposal_account)?;
+
+        let multisig = Self::load_multisig(program_id, multisig_account)?;
+        let mut proposal = Self::load_proposal(program_id, multisig_account, proposal_account)?;
+
+        if proposal.executed {
+            return Err(PrivacyError::ProposalAlreadyExecuted.into());
+        }
+
+        if proposal.approvals.len() < multisig.threshold as usize {
+            msg!("Not enough approvals: {} of {}", proposal.approvals.len(), multisig.threshold);
+            return Err(PrivacyError::ProposalNotApproved.into());
+        }
+
+        // Accounts must be passed exactly as proposed
+        if instruction_accounts.len() < proposal.accounts.len() {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+
+        for (meta, info) in proposal.accounts.iter().zip(instruction_accounts) {
+            if meta.pubkey != *info.key {
+                msg!("Proposal account mismatch: expected {}, got {}", meta.pubkey, info.key);
+                return Err(ProgramError::InvalidAccountData);
+            }
+        }
+
+        // Mark executed before the CPI so the proposal cannot be replayed from within it
+        proposal.executed = true;
+        proposal.save()?;
+
+        let instruction = Instruction {
+            program_id: *program_id,
+            accounts: proposal
+                .accounts
+                .iter()
+                .map(|meta| AccountMeta {
+                    pubkey: meta.pubkey,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+                .collect(),
+            data: proposal.instruction_data.clone(),
+        };
+
+        let mut account_infos = instruction_accounts[..proposal.accounts.len()].to_vec();
+        account_infos.push(program_account.clone());
+
+        invoke_signed(
+            &instruction,
+            &account_infos,
+            &[&[b"multisig", multisig.create_key.as_ref(), &[multisig.bump]]],
+        )?;
+
+        PrivacyEvent::ProposalExecuted {
+            multisig: *multisig_account.key,
+            proposal: *proposal_account.key,
+            index: proposal.index,
+        }
+        .emit()?;
+
+        msg!("Proposal executed");
+        msg!("  Proposal: {}", proposal_account.key);
+
+        Ok(())
+    }
+
+    fn process_set_guardian(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        guardian: Option<Pubkey>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let pool_account = next_account_info(account_info_iter)?;
+        let authority = next_account_info(account_info_iter)?;
+
+        let mut pool_state = Self::load_pool_as_authority(program_id, pool_account, authority)?;
+
+        // The guardian must be a separate key from the authority
+        if guardian == Some(pool_state.authority) {
+            msg!("Guardian must differ from the pool authority");
+            return Err(PrivacyError::InvalidAccountData.into());
+        }
+
+        pool_state.guardian = guardian.unwrap_or_default();
+
+        PrivacyEvent::GuardianSet {
+            pool: *pool_account.key,
+            guardian,
+        }
+        .emit()?;
+
+        msg!("Guardian updated");
+        msg!("  Guardian: {:?}", guardian);
+
+        Ok(())
+    }
+
+    fn process_set_auditor(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        auditor_key: Option<[[u8; 32]; 2]>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let pool_account = next_account_info(account_info_iter)?;
+        let authority = next_account_info(account_info_iter)?;
+
+        let mut pool_state = Self::load_pool_as_authority(program_id, pool_account, authority)?;
+
+        if let Some(key) = &auditor_key {
+            if !auditor::is_valid_auditor_key(key) {
+                msg!("Auditor key must be a Baby Jubjub point outside the small-order subgroup");
+                return Err(PrivacyError::InvalidAuditorKey.into());
+            }
+        }
+
+        pool_state.auditor_key = auditor_key.unwrap_or_default();
+
+        PrivacyEvent::AuditorSet {
+            pool: *pool_account.key,
+            auditor_key,
+        }
+        .emit()?;
+
+        msg!("Auditor updated");
+        msg!("  Auditor key: {:?}", auditor_key);
+
+        Ok(())
+    }
+
+    fn process_pause(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        operations: u8,
+        expires_at: Option<i64>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let pool_account = next_account_info(account_info_iter)?;
+        let signer = next_account_info(account_info_iter)?;
+
+        // Verify guardian or authority is signer
+        check_signer(signer)?;
+
+        let mut pool_state = Self::load_pool(program_id, pool_account)?;
+
+        if pool_state.guardian() != Some(*signer.key) && pool_state.authority != *signer.key {
+            return Err(PrivacyError::Unauthorized.into());
+        }
+
+        let clock = solana_program::clock::Clock::get()?;
+
+        if operations == 0 || operations & !PoolState::PAUSE_ALL != 0 {
+            msg!("Invalid pause flags: {:#04x}", operations);
+            return Err(PrivacyError::InvalidInstruction.into());
+        }
+
+        if matches!(expires_at, Some(t) if t <= clock.unix_timestamp) {
+            msg!("Pause expiry must be in the future");
+            return Err(PrivacyError::InvalidInstruction.into());
+        }
+
+        pool_state.pause(operations, expires_at, clock.unix_timestamp);
+
+        PrivacyEvent::PoolPaused {
+            pool: *pool_account.key,
+            paused_by: *signer.key,
+            paused: pool_state.paused,
+            expires_at: pool_state.pause_expires_at,
+        }
+        .emit()?;
+
+        msg!("Pool paused");
+        msg!("  Flags: {:#04x}", pool_state.paused);
+        msg!("  Expires at: {}", pool_state.pause_expires_at);
+
+        Ok(())
+    }
+
+    fn process_unpause(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        operations: u8,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let pool_account = next_account_info(account_info_iter)?;
+        let authority = next_account_info(account_info_iter)?;
+
+        let mut pool_state = Self::load_pool_as_authority(program_id, pool_account, authority)?;
+
+        pool_state.unpause(operations);
+
+        PrivacyEvent::PoolUnpaused {
+            pool: *pool_account.key,
+            paused: pool_state.paused,
+        }
+        .emit()?;
+
+        msg!("Pool unpaused");
+        msg!("  Flags still paused: {:#04x}", pool_state.paused);
+
+        Ok(())
+    }
+
+    fn process_set_pool_limits(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        limits: PoolLimits,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let pool_account = next_account_info(account_info_iter)?;
+        let authority = next_account_info(account_info_iter)?;
+
+        let mut pool_state = Self::load_pool_as_authority(program_id, pool_account, authority)?;
+
+        if !limits.is_valid() {
+            msg!("Invalid pool limits: {:?}", limits);
+            return Err(PrivacyError::InvalidInstruction.into());
+        }
+
+        pool_state.limits = limits;
+
+        PrivacyEvent::LimitsUpdated {
+            pool: *pool_account.key,
+            limits,
+        }
+        .emit()?;
+
+        msg!("Pool limits updated");
+        msg!("  TVL cap: {}", limits.tvl_cap);
+        msg!("  Epoch outflow limit: {}", limits.epoch_outflow_limit);
+        msg!("  Window outflow limit: {} per {}s", limits.window_outflow_limit, limits.window_seconds);
+        msg!("  Withdrawal delay: {}s", limits.withdrawal_delay);
+
+        Ok(())
+    }
+
+    fn process_claim_delayed_withdrawal(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let pool_account = next_account_info(account_info_iter)?;
+        let delayed_withdrawal_account = next_account_info(account_info_iter)?;
+        let recipient_account = next_account_info(account_info_iter)?;
+
+        check_unique(&[pool_account, delayed_withdrawal_account, recipient_account])?;
+        check_writable(recipient_account)?;
+
+        let mut pool_state = Self::load_pool(program_id, pool_account)?;
+        let withdrawal =
+            Account::<DelayedWithdrawal>::load_mut(program_id, delayed_withdrawal_account)?;
+
+        if withdrawal.pool != *pool_account.key {
+            return Err(PrivacyError::InvalidAccountData.into());
+        }
+        check_pda(
+            delayed_withdrawal_account,
+            &[b"withdrawal", pool_account.key.as_ref(), &withdrawal.nullifier],
+            withdrawal.bump,
+            program_id,
+        )?;
+
+        if withdrawal.recipient != *recipient_account.key {
+            return Err(PrivacyError::Unauthorized.into());
+        }
+
+        // Queued withdrawals stay subject to pauses, which is what the delay is for
+        let clock = solana_program::clock::Clock::get()?;
+        if pool_state.is_paused(PoolState::PAUSE_WITHDRAWALS, clock.unix_timestamp) {
+            return Err(PrivacyError::PoolPaused.into());
+        }
+
+        if clock.unix_timestamp < withdrawal.release_at {
+            msg!("Delayed withdrawal releases at {}", withdrawal.release_at);
+            return Err(PrivacyError::WithdrawalNotReleased.into());
+        }
+
+        // Close the escrow: all lamports (amount + rent) go to the recipient
+        let lamports = delayed_withdrawal_account.lamports();
+        **delayed_withdrawal_account.try_borrow_mut_lamports()? = 0;
+        **recipient_account.try_borrow_mut_lamports()? = recipient_account
+            .lamports()
+            .checked_add(lamports)
+            .ok_or(PrivacyError::InvalidAmount)?;
+        delayed_withdrawal_account.data.borrow_mut().fill(0);
+
+        // The payout counts against the limits when it leaves the pool, not
+        // when it is queued
+        pool_state.record_outflow(withdrawal.amount, clock.epoch, clock.unix_timestamp);
+
+        PrivacyEvent::DelayedWithdrawalClaimed {
+            pool: *pool_account.key,
+            delayed_withdrawal: *delayed_withdrawal_account.key,
+            recipient: withdrawal.recipient,
+            amount: lamports,
+        }
+        .emit()?;
+
+        msg!("Delayed withdrawal claimed");
+        msg!("  Recipient: {}", withdrawal.recipient);
+        msg!("  Amount: {}", lamports);
+
+        Ok(())
+    }
+
+    /// Create the PDA `account` with `space` bytes, owned by the program
+    ///
+    /// `payer` tops the account up to rent plus `extra_lamports`. Lamports
+    /// already sent to the address count toward that, so pre-funding a PDA
+    /// cannot block its creation the way it blocks `create_account`.
+    fn create_pda_account<'a>(
+        program_id: &Pubkey,
+        payer: &AccountInfo<'a>,
+        account: &AccountInfo<'a>,
+        system_program: &AccountInfo<'a>,
+        space: usize,
+        extra_lamports: u64,
+        seeds: &[&[u8]],
+    ) -> ProgramResult {
+        let required = Rent::get()?
+            .minimum_balance(space)
+            .saturating_add(extra_lamports);
+        let lamports_needed = required.saturating_sub(account.lamports());
+        if lamports_needed > 0 {
+            invoke(
+                &system_instruction::transfer(payer.key, account.key, lamports_needed),
+                &[payer.clone(), account.clone(), system_program.clone()],
+            )?;
+        }
+
+        invoke_signed(
+            &system_instruction::allocate(account.key, space as u64),
+            &[account.clone(), system_program.clone()],
+            &[seeds],
+        )?;
+
+        invoke_signed(
+            &system_instruction::assign(account.key, program_id),
+            &[account.clone(), system_program.clone()],
+            &[seeds],
+        )
+    }
+
+    /// Create the nullifier record PDA, funding its rent from the vault
+    ///
+    /// Returns the lamports taken from the vault. Lamports already sent to the
+    /// address count toward the rent, so pre-funding it cannot block the note.
+    #[allow(clippy::too_many_arguments)]
+    fn record_nullifier<'a>(
+        program_id: &Pubkey,
+        pool_account: &AccountInfo<'a>,
+        vault: &AccountInfo<'a>,
+        nullifier_account: &AccountInfo<'a>,
+        system_program: &AccountInfo<'a>,
+        nullifier: [u8; 32],
+        bump: u8,
+        timestamp: i64,
+    ) -> Result<u64, ProgramError> {
+        let space = NullifierAccount::LEN;
+        let lamports = Rent::get()?
+            .minimum_balance(space)
+            .saturating_sub(nullifier_account.lamports());
+
+        // Allocate before moving lamports: a CPI must not see the vault,
+        // which it is not passed, out of balance with the record
+        let seeds: &[&[u8]] = &[b"nullifier", pool_account.key.as_ref(), &nullifier, &[bump]];
+
+        invoke_signed(
+            &system_instruction::allocate(nullifier_account.key, space as u64),
+            &[nullifier_account.clone(), system_program.clone()],
+            &[seeds],
+        )?;
+
+        invoke_signed(
+            &system_instruction::assign(nullifier_account.key, program_id),
+            &[nullifier_account.clone(), system_program.clone()],
+            &[seeds],
+        )?;
+
+        let vault_lamports = vault
+            .lamports()
+            .checked_sub(lamports)
+            .ok_or(PrivacyError::InsufficientFunds)?;
+        **vault.try_borrow_mut_lamports()? = vault_lamports;
+        **nullifier_account.try_borrow_mut_lamports()? = nullifier_account
+            .lamports()
+            .checked_add(lamports)
+            .ok_or(PrivacyError::InvalidAmount)?;
+
+        NullifierAccount {
+            discriminator: NullifierAccount::DISCRIMINATOR,
+            version: NullifierAccount::VERSION,
+            nullifier,
+            pool: *pool_account.key,
+            tx_signature: None,
+            timestamp,
+            bump,
+        }
+        .serialize(&mut &mut nullifier_account.data.borrow_mut()[..])?;
+
+        Ok(lamports)
+    }
+
+    /// Move `withdrawal.amount` from the vault into a new delayed withdrawal PDA
+    ///
+    /// The escrow is funded from the withdrawn amount itself, so the amount must
+    /// cover its rent; the recipient gets everything back when claiming.
+    fn queue_withdrawal<'a>(
+        program_id: &Pubkey,
+        pool_account: &AccountInfo<'a>,
+        vault: &AccountInfo<'a>,
+        delayed_withdrawal_account: &AccountInfo<'a>,
+        system_program: &AccountInfo<'a>,
+        mut withdrawal: DelayedWithdrawal,
+    ) -> ProgramResult {
+        let (delayed_pubkey, bump) =
+            DelayedWithdrawal::derive_address(pool_account.key, &withdrawal.nullifier, program_id);
+
+        check_address(delayed_withdrawal_account, &delayed_pubkey)?;
+        check_writable(delayed_withdrawal_account)?;
+
+        if delayed_withdrawal_account.owner == program_id {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        let space = DelayedWithdrawal::LEN;
+        if withdrawal.amount < Rent::get()?.minimum_balance(space) {
+            msg!("Withdrawal too small to queue");
+            return Err(PrivacyError::InvalidAmount.into());
+        }
+
+        // Allocate before moving lamports (see record_nullifier)
+        let seeds: &[&[u8]] = &[
+            b"withdrawal",
+            pool_account.key.as_ref(),
+            &withdrawal.nullifier,
+            &[bump],
+        ];
+
+        invoke_signed(
+            &system_instruction::allocate(&delayed_pubkey, space as u64),
+            &[delayed_withdrawal_account.clone(), system_program.clone()],
+            &[seeds],
+        )?;
+
+        invoke_signed(
+            &system_instruction::assign(&delayed_pubkey, program_id),
+            &[delayed_withdrawal_account.clone(), system_program.clone()],
+            &[seeds],
+        )?;
+
+        let vault_lamports = vault
+            .lamports()
+            .checked_sub(withdrawal.amount)
+            .ok_or(PrivacyError::InsufficientFunds)?;
+        **vault.try_borrow_mut_lamports()? = vault_lamports;
+        **delayed_withdrawal_account.try_borrow_mut_lamports()? = delayed_withdrawal_account
+            .lamports()
+            .checked_add(withdrawal.amount)
+            .ok_or(PrivacyError::InvalidAmount)?;
+
+        withdrawal.bump = bump;
+        withdrawal.serialize(&mut &mut delayed_withdrawal_account.data.borrow_mut()[..])?;
+
+        PrivacyEvent::WithdrawalQueued {
+            pool: *pool_account.key,
+            delayed_withdrawal: delayed_pubkey,
+            recipient: withdrawal.recipient,
+            amount: withdrawal.amount,
+            release_at: withdrawal.release_at,
+        }
+        .emit()?;
+
+        Ok(())
+    }
+
+    /// Load an initialized pool the instruction will write back
+    fn load_pool<'a>(
+        program_id: &Pubkey,
+        pool_account: &'a AccountInfo,
+    ) -> Result<ZeroCopyMut<'a, PoolState>, ProgramError> {
+        let pool_state = ZeroCopyMut::<PoolState>::load_mut(program_id, pool_account)?;
+
+        if !pool_state.is_initialized() {
+            return Err(PrivacyError::PoolNotInitialized.into());
+        }
+
+        Ok(pool_state)
+    }
+
+    /// Load pool state without write access, for instructions that only read it
+    fn load_pool_ref<'a>(
+        program_id: &Pubkey,
+        pool_account: &'a AccountInfo,
+    ) -> Result<ZeroCopy<'a, PoolState>, ProgramError> {
+        let pool_state = ZeroCopy::<PoolState>::load(program_id, pool_account)?;
+
+        if !pool_state.is_initialized() {
+            return Err(PrivacyError::PoolNotInitialized.into());
+        }
+
+        Ok(pool_state)
+    }
+
+    /// Load pool state and check that `authority` is its signing authority
+    ///
+    /// A multisig authority satisfies this check only inside ExecuteProposal.
+    fn load_pool_as_authority<'a>(
+        program_id: &Pubkey,
+        pool_account: &'a AccountInfo,
+        authority: &AccountInfo,
+    ) -> Result<ZeroCopyMut<'a, PoolState>, ProgramError> {
+        check_signer(authority)?;
+
+        let pool_state = Self::load_pool(program_id, pool_account)?;
+
+        if pool_state.authority != *authority.key {
+            return Err(PrivacyError::Unauthorized.into());
+        }
+
+        Ok(pool_state)
+    }
+
+    /// Check that a new commitment is a canonical field element
+    ///
+    /// Anything else could never be hashed into the off-chain tree or opened by
+    /// a proof, so it would only take up a leaf index.
+    fn check_commitment(commitment: &[u8; 32]) -> ProgramResult {
+        if !verifier::is_canonical_field(commitment) {
+            msg!("Commitment is not a canonical field element");
+            return Err(PrivacyError::InvalidCommitment.into());
+        }
+        Ok(())
+    }
+
+    /// Check that `vault` is the writable vault recorded in the pool
+    fn check_vault(program_id: &Pubkey, pool_state: &PoolState, vault: &AccountInfo) -> ProgramResult {
+        check_address(vault, &pool_state.vault)?;
+        check_owner(vault, program_id)?;
+        check_writable(vault)
+    }
+
+    /// Check that `vk_account` is the `circuit_type` VK PDA of `pool`
+    fn check_verification_key(
+        program_id: &Pubkey,
+        pool: &Pubkey,
+        circuit_type: CircuitType,
+        vk_account: &AccountInfo,
+    ) -> ProgramResult {
+        let (vk_pubkey, _) = VerificationKeyAccount::derive_address(pool, circuit_type, program_id);
+        check_address(vk_account, &vk_pubkey)?;
+
+        // Demo builds skip proof verification, so the VK may not be stored yet
+        #[cfg(feature = "real-zk-verification")]
+        check_owner(vk_account, program_id)?;
+
+        Ok(())
+    }
+
+    /// Check the auditor note for a commitment the pool is about to append
+    ///
+    /// Pools without an auditor take no auditor notes. Pools with one take
+    /// exactly one per new commitment, proven against their AuditorNote VK.
+    fn check_auditor_note(
+        program_id: &Pubkey,
+        pool: &Pubkey,
+        pool_state: &PoolState,
+        commitment: Option<&[u8; 32]>,
+        auditor_note: Option<&AuditorNote>,
+        vk_account: Option<&AccountInfo>,
+    ) -> ProgramResult {
+        let (auditor_key, commitment) = match (pool_state.auditor_key(), commitment) {
+            (Some(auditor_key), Some(commitment)) => (auditor_key, commitment),
+            _ if auditor_note.is_some() => {
+                return Err(PrivacyError::UnexpectedAuditorNote.into());
+            }
+            _ => return Ok(()),
+        };
+
+        let auditor_note = auditor_note.ok_or(PrivacyError::AuditorNoteRequired)?;
+        let vk_account = vk_account.ok_or(ProgramError::NotEnoughAccountKeys)?;
+        Self::check_verification_key(program_id, pool, CircuitType::AuditorNote, vk_account)?;
+
+        let public_inputs = auditor_note.public_inputs(&auditor_key, commitment);
+        let vk_account_data = &vk_account.data.borrow();
+
+        if !verifier::verify_auditor_note_proof(&auditor_note.proof, &public_inputs, vk_account_data)? {
+            return Err(PrivacyError::InvalidProof.into());
+        }
+
+        Ok(())
+    }
+
+    /// Load a relayer PDA the instruction will write back
+    fn load_relayer<'a>(
+        program_id: &Pubkey,
+        relayer_account: &'a AccountInfo,
+    ) -> Result<ZeroCopyMut<'a, RelayerAccount>, ProgramError> {
+        let relayer_state = ZeroCopyMut::<RelayerAccount>::load_mut(program_id, relayer_account)?;
+
+        check_pda(
+            relayer_account,
+            &[b"relayer", relayer_state.relayer.as_ref()],
+            relayer_state.bump,
+            program_id,
+        )?;
+
+        Ok(relayer_state)
+    }
+
+    /// Load a multisig PDA, verifying its owner and address
+    fn load_multisig<'a, 'info>(
+        program_id: &Pubkey,
+        multisig_account: &'a AccountInfo<'info>,
+    ) -> Result<Account<'a, 'info, Multisig>, ProgramError> {
+        let multisig = Account::<Multisig>::load(program_id, multisig_account)?;
+
+        if !multisig.is_initialized {
+            return Err(PrivacyError::InvalidMultisig.into());
+        }
+
+        check_pda(
+            multisig_account,
+            &[b"multisig", multisig.create_key.as_ref()],
+            multisig.bump,
+            program_id,
+        )?;
+
+        Ok(multisig)
+    }
+
+    /// Load a proposal PDA, verifying it belongs to `multisig_account`
+    fn load_proposal<'a, 'info>(
+        program_id: &Pubkey,
+        multisig_account: &AccountInfo,
+        proposal_account: &'a AccountInfo<'info>,
+    ) -> Result<Account<'a, 'info, MultisigProposal>, ProgramError> {
+        let proposal = Account::<MultisigProposal>::load(program_id, proposal_account)?;
+
+        if proposal.multisig != *multisig_account.key {
+            return Err(PrivacyError::InvalidMultisig.into());
+        }
+
+        check_pda(
+            proposal_account,
+            &[b"proposal", multisig_account.key.as_ref(), &proposal.index.to_le_bytes()],
+            proposal.bump,
+            program_id,
+        )?;
+
+        Ok(proposal)
+    }
+
+    /// Load the protocol config PDA, verifying its address and owner
+    fn load_config<'a, 'info>(
+        program_id: &Pubkey,
+        config_account: &'a AccountInfo<'info>,
+    ) -> Result<Account<'a, 'info, ProtocolConfig>, ProgramError> {
+        let (config_pubkey, _) = ProtocolConfig::derive_address(program_id);
+        check_address(config_account, &config_pubkey)?;
+
+        // The PDA stays system-owned until InitializeConfig runs
+        if config_account.owner != program_id {
+            return Err(PrivacyError::ConfigNotInitialized.into());
+        }
+
+        let config = Account::<ProtocolConfig>::load(program_id, config_account)?;
+
+        if !config.is_initialized {
+            return Err(PrivacyError::ConfigNotInitialized.into());
+        }
+
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::{clock::Epoch, system_program};
+
+    /// Owned backing storage for an AccountInfo
+    struct TestAccount {
+        key: Pubkey,
+        owner: Pubkey,
+        lamports: u64,
+        data: Vec<u8>,
+        is_signer: bool,
+        is_writable: bool,
+    }
+
+    impl TestAccount {
+        fn new(key: Pubkey, owner: Pubkey, data: Vec<u8>) -> Self {
+            TestAccount {
+                key,
+                owner,
+                lamports: 1_000_000_000,
+                data,
+                is_signer: false,
+                is_writable: true,
+            }
+        }
+
+        fn program<T: ProgramAccount>(key: Pubkey, program_id: &Pubkey, state: &T, len: usize) -> Self {
+            let mut data = state.try_to_vec().unwrap();
+            data.resize(len, 0);
+            Self::new(key, *program_id, data)
+        }
+
+        fn wallet(key: Pubkey) -> Self {
+            let mut account = Self::new(key, system_program::id(), Vec::new());
+            account.is_signer = true;
+            account
+        }
+
+        fn system_program() -> Self {
+            let mut account = Self::new(system_program::id(), Pubkey::default(), Vec::new());
+            account.is_writable = false;
+            account
+        }
+
+        fn info(&mut self) -> AccountInfo<'_> {
+            AccountInfo::new(
+                &self.key,
+                self.is_signer,
+                self.is_writable,
+                &mut self.lamports,
+                &mut self.data,
+                &self.owner,
+                false,
+                Epoch::default(),
+            )
+        }
+    }
+
+    fn run(program_id: &Pubkey, accounts: &mut [TestAccount], instruction: PrivacyInstruction) -> ProgramResult {
+        let infos: Vec<AccountInfo> = accounts.iter_mut().map(|a| a.info()).collect();
+        Processor::process(program_id, &infos, &instruction.try_to_vec().unwrap())
+    }
+
+    fn err(e: PrivacyError) -> Result<(), ProgramError> {
+        Err(e.into())
+    }
+
+    /// Handlers reach the clock only after all account checks pass, and the
+    /// clock sysvar is unavailable off-chain
+    const PASSED_VALIDATION: Result<(), ProgramError> = Err(ProgramError::UnsupportedSysvar);
+
+    fn pool_state(program_id: &Pubkey, pool: &Pubkey, authority: Pubkey) -> PoolState {
+        let vault = PoolState::derive_vault_address(pool, program_id).0;
+        PoolState::new(authority, vault, 20, 1_000_000)
+    }
+
+    fn config_account(program_id: &Pubkey) -> TestAccount {
+        config_account_with_admin(program_id, Pubkey::new_unique())
+    }
+
+    fn config_account_with_admin(program_id: &Pubkey, admin: Pubkey) -> TestAccount {
+        let (key, bump) = ProtocolConfig::derive_address(program_id);
+        let config = ProtocolConfig {
+            discriminator: ProtocolConfig::DISCRIMINATOR,
+            version: ProtocolConfig::VERSION,
+            admin,
+            params: ConfigParams::default(),
+            bump,
+            is_initialized: true,
+        };
+        TestAccount::program(key, program_id, &config, ProtocolConfig::LEN)
+    }
+
+    fn relayer_account(program_id: &Pubkey, wallet: Pubkey) -> TestAccount {
+        let (key, bump) = RelayerAccount::derive_address(&wallet, program_id);
+        let relayer = RelayerAccount::new(wallet, 0, "https://relayer.example", bump, 0);
+        TestAccount::program(key, program_id, &relayer, RelayerAccount::LEN)
+    }
+
+    /// [pool, depositor, vault, system program]
+    fn deposit_accounts(program_id: &Pubkey) -> Vec<TestAccount> {
+        let pool = Pubkey::new_unique();
+        let state = pool_state(program_id, &pool, Pubkey::new_unique());
+        let vault = state.vault;
+        vec![
+            TestAccount::program(pool, program_id, &state, PoolState::LEN),
+            TestAccount::wallet(Pubkey::new_unique()),
+            TestAccount::new(vault, *program_id, Vec::new()),
+            TestAccount::system_program(),
+        ]
+    }
+
+    fn deposit() -> PrivacyInstruction {
+        let mut encrypted_note = vec![crate::note::NOTE_CIPHERTEXT_VERSION];
+        encrypted_note.resize(crate::note::VALUE_CIPHERTEXT_LEN, 0);
+        PrivacyInstruction::Deposit {
+            commitment: [1u8; 32],
+            amount: 1_000_000,
+            encrypted_note,
+            auditor_note: None,
+        }
+    }
+
+    #[test]
+    fn test_deposit_account_validation() {
+        let program_id = Pubkey::new_unique();
+
+        let mut accounts = deposit_accounts(&program_id);
+        assert_eq!(run(&program_id, &mut accounts, deposit()), PASSED_VALIDATION);
+
+        // Spoofed vault
+        let mut accounts = deposit_accounts(&program_id);
+        accounts[2].key = Pubkey::new_unique();
+        assert_eq!(run(&program_id, &mut accounts, deposit()), err(PrivacyError::InvalidPda));
+
+        // Vault passed read-only
+        let mut accounts = deposit_accounts(&program_id);
+        accounts[2].is_writable = false;
+        assert_eq!(run(&program_id, &mut accounts, deposit()), err(PrivacyError::AccountNotWritable));
+
+        // Pool data copied into an account owned by another program
+        let mut accounts = deposit_accounts(&program_id);
+        accounts[0].owner = Pubkey::new_unique();
+        assert_eq!(run(&program_id, &mut accounts, deposit()), err(PrivacyError::InvalidAccountOwner));
+
+        // Another account type passed as the pool
+        let mut accounts = deposit_accounts(&program_id);
+        accounts[0] = relayer_account(&program_id, Pubkey::new_unique());
+        assert_eq!(run(&program_id, &mut accounts, deposit()), err(PrivacyError::InvalidAccountData));
+
+        // Pool passed as its own vault
+        let mut accounts = deposit_accounts(&program_id);
+        accounts[2].key = accounts[0].key;
+        assert_eq!(run(&program_id, &mut accounts, deposit()), err(PrivacyError::DuplicateAccount));
+
+        // Fake system program
+        let mut accounts = deposit_accounts(&program_id);
+        accounts[3].key = Pubkey::new_unique();
+        assert_eq!(run(&program_id, &mut accounts, deposit()), Err(ProgramError::IncorrectProgramId));
+    }
+
+    /// [pool, vault, recipient, vk, system program, config, nullifier record]
+    fn withdraw_accounts(program_id: &Pubkey) -> Vec<TestAccount> {
+        let mut accounts = deposit_accounts(program_id);
+        let pool = accounts.remove(0);
+        let vault = accounts.remove(1);
+        let (vk, _) = VerificationKeyAccount::derive_address(&pool.key, CircuitType::Transfer, program_id);
+        let (record, _) = PoolState::derive_nullifier_pda(&pool.key, &[2u8; 32], program_id);
+        let mut record = TestAccount::new(record, system_program::id(), Vec::new());
+        record.lamports = 0;
+        vec![
+            pool,
+            vault,
+            TestAccount::new(Pubkey::new_unique(), system_program::id(), Vec::new()),
+            TestAccount::new(vk, *program_id, Vec::new()),
+            TestAccount::system_program(),
+            config_account(program_id),
+            record,
+        ]
+    }
+
+    fn withdraw(recipient: Pubkey) -> PrivacyInstruction {
+        PrivacyInstruction::Withdraw {
+            proof: vec![0u8; 256],
+            root: [0u8; 32],
+            nullifier: [2u8; 32],
+            new_commitment: None,
+            recipient,
+            amount: 1_000_000,
+            encrypted_note: None,
+            auditor_note: None,
+        }
+    }
+
+    #[test]
+    fn test_withdraw_account_validation() {
+        let program_id = Pubkey::new_unique();
+
+        let mut accounts = withdraw_accounts(&program_id);
+        let recipient = accounts[2].key;
+        assert_eq!(run(&program_id, &mut accounts, withdraw(recipient)), PASSED_VALIDATION);
+
+        // Spoofed vault
+        let mut accounts = withdraw_accounts(&program_id);
+        accounts[1].key = Pubkey::new_unique();
+        assert_eq!(run(&program_id, &mut accounts, withdraw(recipient)), err(PrivacyError::InvalidPda));
+
+        // Funds redirected to an account other than the named recipient
+        let mut accounts = withdraw_accounts(&program_id);
+        assert_eq!(
+            run(&program_id, &mut accounts, withdraw(Pubkey::new_unique())),
+            err(PrivacyError::InvalidPda)
+        );
+
+        // VK for another circuit, and VK of another pool
+        let mut accounts = withdraw_accounts(&program_id);
+        accounts[3].key =
+            VerificationKeyAccount::derive_address(&accounts[0].key, CircuitType::Balance, &program_id).0;
+        assert_eq!(run(&program_id, &mut accounts, withdraw(recipient)), err(PrivacyError::InvalidPda));
+
+        let mut accounts = withdraw_accounts(&program_id);
+        accounts[3].key =
+            VerificationKeyAccount::derive_address(&Pubkey::new_unique(), CircuitType::Transfer, &program_id).0;
+        assert_eq!(run(&program_id, &mut accounts, withdraw(recipient)), err(PrivacyError::InvalidPda));
+
+        // Config that is not the config PDA
+        let mut accounts = withdraw_accounts(&program_id);
+        accounts[5].key = Pubkey::new_unique();
+        assert_eq!(run(&program_id, &mut accounts, withdraw(recipient)), err(PrivacyError::InvalidPda));
+
+        // Nullifier record for another nullifier
+        let mut accounts = withdraw_accounts(&program_id);
+        accounts[6].key = PoolState::derive_nullifier_pda(&accounts[0].key, &[3u8; 32], &program_id).0;
+        assert_eq!(run(&program_id, &mut accounts, withdraw(recipient)), err(PrivacyError::InvalidPda));
+
+        // Pool passed read-only
+        let mut accounts = withdraw_accounts(&program_id);
+        accounts[0].is_writable = false;
+        assert_eq!(run(&program_id, &mut accounts, withdraw(recipient)), err(PrivacyError::AccountNotWritable));
+
+        // Vault passed as the recipient
+        let mut accounts = withdraw_accounts(&program_id);
+        accounts[2].key = accounts[1].key;
+        let vault = accounts[1].key;
+        assert_eq!(run(&program_id, &mut accounts, withdraw(vault)), err(PrivacyError::DuplicateAccount));
+    }
+
+    #[test]
+    fn test_initialize_pool_creates_pda() {
+        let program_id = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let denomination = 1_000_000;
+        let (pool, _) = PoolState::derive_address(&authority, denomination, &program_id);
+        let (vault, _) = PoolState::derive_vault_address(&pool, &program_id);
+        let init = || PrivacyInstruction::InitializePool { tree_depth: 20, denomination };
+        let accounts = |pool: Pubkey| {
+            let mut pool = TestAccount::new(pool, system_program::id(), Vec::new());
+            pool.lamports = 0;
+            let mut vault = TestAccount::new(vault, system_program::id(), Vec::new());
+            vault.lamports = 0;
+            vec![pool, TestAccount::wallet(authority), vault, TestAccount::system_program()]
+        };
+
+        assert_eq!(run(&program_id, &mut accounts(pool), init()), PASSED_VALIDATION);
+
+        // Any address other than the PDA for (authority, denomination)
+        assert_eq!(run(&program_id, &mut accounts(Pubkey::new_unique()), init()), err(PrivacyError::InvalidPda));
+        let (other, _) = PoolState::derive_address(&authority, denomination + 1, &program_id);
+        assert_eq!(run(&program_id, &mut accounts(other), init()), err(PrivacyError::InvalidPda));
+
+        // An existing pool is never overwritten
+        let mut existing = accounts(pool);
+        existing[0] = TestAccount::program(pool, &program_id, &pool_state(&program_id, &pool, authority), PoolState::LEN);
+        assert_eq!(run(&program_id, &mut existing, init()), Err(ProgramError::AccountAlreadyInitialized));
+    }
+
+    #[test]
+    fn test_issue_asset_creates_pda() {
+        let program_id = Pubkey::new_unique();
+        let issuer = Pubkey::new_unique();
+        let asset_id = [7u8; 32];
+        let (asset, _) = AssetState::derive_address(&asset_id, &program_id);
+        let issue = || PrivacyInstruction::IssueAsset {
+            name: "Shadow".to_string(),
+            symbol: "SHD".to_string(),
+            decimals: 9,
+            total_supply: 1_000,
+            asset_id,
+        };
+        let accounts = |asset: Pubkey, owner: Pubkey, lamports: u64| {
+            let mut asset = TestAccount::new(asset, owner, Vec::new());
+            asset.lamports = lamports;
+            vec![TestAccount::wallet(issuer), asset, TestAccount::system_program()]
+        };
+        let system = system_program::id();
+
+        assert_eq!(run(&program_id, &mut accounts(asset, system, 0), issue()), PASSED_VALIDATION);
+        assert_eq!(
+            run(&program_id, &mut accounts(Pubkey::new_unique(), system, 0), issue()),
+            err(PrivacyError::InvalidPda)
+        );
+        // Lamports sent to the address do not make it an existing asset
+        assert_eq!(run(&program_id, &mut accounts(asset, system, 1), issue()), PASSED_VALIDATION);
+        assert_eq!(
+            run(&program_id, &mut accounts(asset, program_id, 1), issue()),
+            Err(ProgramError::AccountAlreadyInitialized)
+        );
+    }
+
+    #[test]
+    fn test_store_verification_key_validation() {
+        let program_id = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let pool = Pubkey::new_unique();
+        let state = pool_state(&program_id, &pool, authority);
+        let (vk, _) = VerificationKeyAccount::derive_address(&pool, CircuitType::Transfer, &program_id);
+        let store = || PrivacyInstruction::StoreVerificationKey {
+            circuit_type: 0,
+            vk_data: vec![1u8; 64],
+        };
+        let accounts = |vk: Pubkey, authority: Pubkey| {
+            let mut pool = TestAccount::program(pool, &program_id, &state, PoolState::LEN);
+            pool.is_writable = false;
+            vec![
+                TestAccount::new(vk, system_program::id(), Vec::new()),
+                pool,
+                TestAccount::wallet(authority),
+                TestAccount::system_program(),
+            ]
+        };
+
+        // The pool is only read
+        assert_eq!(run(&program_id, &mut accounts(vk, authority), store()), PASSED_VALIDATION);
+        assert_eq!(
+            run(&program_id, &mut accounts(vk, Pubkey::new_unique()), store()),
+            err(PrivacyError::Unauthorized)
+        );
+
+        // VK account that is not this pool's Transfer VK PDA
+        assert_eq!(
+            run(&program_id, &mut accounts(Pubkey::new_unique(), authority), store()),
+            err(PrivacyError::InvalidPda)
+        );
+        let (balance_vk, _) = VerificationKeyAccount::derive_address(&pool, CircuitType::Balance, &program_id);
+        assert_eq!(
+            run(&program_id, &mut accounts(balance_vk, authority), store()),
+            err(PrivacyError::InvalidPda)
+        );
+    }
+
+    #[test]
+    fn test_relayer_account_validation() {
+        let program_id = Pubkey::new_unique();
+        let wallet = Pubkey::new_unique();
+
+        // Genuine relayer data at an address that is not its PDA
+        let mut accounts = vec![relayer_account(&program_id, wallet), TestAccount::wallet(wallet)];
+        accounts[0].key = Pubkey::new_unique();
+        assert_eq!(
+            run(&program_id, &mut accounts, PrivacyInstruction::UpdateHeartbeat),
+            err(PrivacyError::InvalidPda)
+        );
+
+        // Relayer account forged by another program
+        let mut accounts = vec![relayer_account(&program_id, wallet), TestAccount::wallet(wallet)];
+        accounts[0].owner = Pubkey::new_unique();
+        assert_eq!(
+            run(&program_id, &mut accounts, PrivacyInstruction::UpdateHeartbeat),
+            err(PrivacyError::InvalidAccountOwner)
+        );
+
+        let mut accounts = vec![relayer_account(&program_id, wallet), TestAccount::wallet(wallet)];
+        assert_eq!(run(&program_id, &mut accounts, PrivacyInstruction::UpdateHeartbeat), PASSED_VALIDATION);
+    }
+
+    #[test]
+    fn test_report_relay_authorization() {
+        let program_id = Pubkey::new_unique();
+        let wallet = Pubkey::new_unique();
+        let admin = Pubkey::new_unique();
+        let accounts = |signer: Pubkey| {
+            vec![
+                relayer_account(&program_id, wallet),
+                config_account_with_admin(&program_id, admin),
+                TestAccount::wallet(signer),
+            ]
+        };
+        let report = |success| PrivacyInstruction::ReportRelay { success };
+
+        assert_eq!(run(&program_id, &mut accounts(admin), report(true)), PASSED_VALIDATION);
+        assert_eq!(run(&program_id, &mut accounts(wallet), report(false)), PASSED_VALIDATION);
+        assert_eq!(
+            run(&program_id, &mut accounts(wallet), report(true)),
+            err(PrivacyError::Unauthorized)
+        );
+        assert_eq!(
+            run(&program_id, &mut accounts(Pubkey::new_unique()), report(false)),
+            err(PrivacyError::Unauthorized)
+        );
+
+        // A config account at another address
+        let mut spoofed = accounts(admin);
+        spoofed[1].key = Pubkey::new_unique();
+        assert_eq!(run(&program_id, &mut spoofed, report(true)), err(PrivacyError::InvalidPda));
+    }
+
+    #[test]
+    fn test_multisig_pda_validation() {
+        let program_id = Pubkey::new_unique();
+        let member = Pubkey::new_unique();
+        let create_key = Pubkey::new_unique();
+        let (multisig_key, bump) = Multisig::derive_address(&create_key, &program_id);
+        let multisig = Multisig {
+            discriminator: Multisig::DISCRIMINATOR,
+            version: Multisig::VERSION,
+            create_key,
+            threshold: 1,
+            signers: vec![member],
+            proposal_count: 1,
+            bump,
+            is_initialized: true,
+        };
+        let (proposal_key, proposal_bump) = MultisigProposal::derive_address(&multisig_key, 0, &program_id);
+        let proposal = MultisigProposal {
+            discriminator: MultisigProposal::DISCRIMINATOR,
+            version: MultisigProposal::VERSION,
+            multisig: multisig_key,
+            index: 0,
+            proposer: member,
+            instruction_data: Vec::new(),
+            accounts: Vec::new(),
+            approvals: vec![member],
+            executed: false,
+            bump: proposal_bump,
+        };
+        let accounts = || {
+            vec![
+                TestAccount::program(multisig_key, &program_id, &multisig, Multisig::LEN),
+                TestAccount::program(proposal_key, &program_id, &proposal, MultisigProposal::LEN),
+                TestAccount::wallet(member),
+            ]
+        };
+
+        let mut genuine = accounts();
+        assert_eq!(run(&program_id, &mut genuine, PrivacyInstruction::ApproveProposal), Ok(()));
+
+        // Proposal data copied to an address that is not its PDA
+        let mut spoofed = accounts();
+        spoofed[1].key = Pubkey::new_unique();
+        assert_eq!(
+            run(&program_id, &mut spoofed, PrivacyInstruction::ApproveProposal),
+            err(PrivacyError::InvalidPda)
+        );
+
+        // Multisig data copied to an address that is not its PDA
+        let mut spoofed = accounts();
+        spoofed[0].key = Pubkey::new_unique();
+        assert_eq!(
+            run(&program_id, &mut spoofed, PrivacyInstruction::ApproveProposal),
+            err(PrivacyError::InvalidPda)
+        );
+    }
 }
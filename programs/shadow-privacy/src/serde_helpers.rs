@@ -0,0 +1,52 @@
+//! `serialize_with` helpers for the `serde` feature
+//!
+//! Pubkeys serialize as base58 and byte strings (hashes, commitments,
+//! discriminators) as lowercase hex, so JSON dumps read like explorer output.
+
+use serde::Serializer;
+use solana_program::pubkey::Pubkey;
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub fn pubkey<S: Serializer>(key: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(key)
+}
+
+pub fn pubkeys<S: Serializer>(keys: &[Pubkey], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(keys.iter().map(Pubkey::to_string))
+}
+
+pub fn hex<S: Serializer, T: AsRef<[u8]>>(bytes: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&to_hex(bytes.as_ref()))
+}
+
+pub fn hex_option<S: Serializer, T: AsRef<[u8]>>(bytes: &Option<T>, serializer: S) -> Result<S::Ok, S::Error> {
+    match bytes {
+        Some(bytes) => serializer.serialize_some(&to_hex(bytes.as_ref())),
+        None => serializer.serialize_none(),
+    }
+}
+
+pub fn hex_seq<S: Serializer, T: AsRef<[[u8; 32]]>>(items: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(items.as_ref().iter().map(|item| to_hex(item)))
+}
+
+/// Fixed-size caches: only the occupied (non-zero) slots
+pub fn hex_cache<S: Serializer, T: AsRef<[[u8; 32]]>>(items: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(
+        items
+            .as_ref()
+            .iter()
+            .filter(|item| **item != [0u8; 32])
+            .map(|item| to_hex(item)),
+    )
+}
+
+/// Zero-padded UTF-8 buffers
+pub fn padded_str<S: Serializer, T: AsRef<[u8]>>(bytes: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    let bytes = bytes.as_ref();
+    let len = bytes.iter().position(|&byte| byte == 0).unwrap_or(bytes.len());
+    serializer.serialize_str(&String::from_utf8_lossy(&bytes[..len]))
+}
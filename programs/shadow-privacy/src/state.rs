@@ -1,17 +1,193 @@
+use crate::error::PrivacyError;
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::pubkey::Pubkey;
+use bytemuck::{Pod, Zeroable};
+use solana_program::{
+    bpf_loader_upgradeable, entrypoint::MAX_PERMITTED_DATA_INCREASE, program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use shadow_privacy_merkle::{insert_into_frontier, MerkleError, ZERO_HASHES};
+use std::mem::size_of;
+
+/// Program account types, as stored in account headers
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum AccountType {
+    Pool,
+    Asset,
+    ShieldedNote,
+    Nullifier,
+    KeyImage,
+    VerificationKey,
+    Relayer,
+    ProtocolConfig,
+    Multisig,
+    MultisigProposal,
+    DelayedWithdrawal,
+}
+
+impl AccountType {
+    /// Type whose header `data` starts with, if any
+    pub fn from_header(data: &[u8]) -> Option<Self> {
+        if data.len() < 8 {
+            return None;
+        }
+        [
+            (PoolState::DISCRIMINATOR, AccountType::Pool),
+            (AssetState::DISCRIMINATOR, AccountType::Asset),
+            (ShieldedNote::DISCRIMINATOR, AccountType::ShieldedNote),
+            (NullifierAccount::DISCRIMINATOR, AccountType::Nullifier),
+            (KeyImageAccount::DISCRIMINATOR, AccountType::KeyImage),
+            (VerificationKeyAccount::DISCRIMINATOR, AccountType::VerificationKey),
+            (RelayerAccount::DISCRIMINATOR, AccountType::Relayer),
+            (ProtocolConfig::DISCRIMINATOR, AccountType::ProtocolConfig),
+            (Multisig::DISCRIMINATOR, AccountType::Multisig),
+            (MultisigProposal::DISCRIMINATOR, AccountType::MultisigProposal),
+            (DelayedWithdrawal::DISCRIMINATOR, AccountType::DelayedWithdrawal),
+        ]
+        .into_iter()
+        .find(|(discriminator, _)| data[..8] == *discriminator)
+        .map(|(_, account_type)| account_type)
+    }
+}
+
+/// Header shared by every account owned by the program
+///
+/// Each account starts with an 8-byte discriminator (first 8 bytes of
+/// sha256("account:<TypeName>")) followed by a layout version byte. Accounts
+/// written before headers existed are version 0 and must go through Migrate.
+pub trait ProgramAccount: BorshSerialize + BorshDeserialize {
+    const ACCOUNT_TYPE: AccountType;
+    const DISCRIMINATOR: [u8; 8];
+    const VERSION: u8;
+
+    /// Size of the discriminator + version header
+    const HEADER_LEN: usize = 8 + 1;
+
+    /// Check whether `data` starts with this type's header (any version)
+    fn has_discriminator(data: &[u8]) -> bool {
+        data.len() >= Self::HEADER_LEN && data[..8] == Self::DISCRIMINATOR
+    }
+
+    /// Deserialize account data, checking discriminator and version
+    ///
+    /// Trailing bytes are allowed, since accounts are allocated at maximum size.
+    fn load(data: &[u8]) -> Result<Self, ProgramError> {
+        if !Self::has_discriminator(data) {
+            return Err(PrivacyError::InvalidAccountData.into());
+        }
+
+        if data[8] != Self::VERSION {
+            return Err(PrivacyError::AccountNeedsMigration.into());
+        }
+
+        Self::deserialize(&mut &data[..]).map_err(|_| PrivacyError::InvalidAccountData.into())
+    }
+}
+
+/// Program accounts stored as their `#[repr(C)]` in-memory layout
+///
+/// These are read and mutated in place instead of being Borsh-decoded and
+/// re-encoded. The layout has no padding, uses little-endian integers and
+/// fixed-size arrays only, so its bytes are exactly the Borsh encoding of the
+/// same struct: `ProgramAccount::load` keeps working as the off-chain view.
+pub trait ZeroCopyAccount: ProgramAccount + Pod {
+    /// Borrow the account in `data` (header checked like `ProgramAccount::load`)
+    fn from_bytes(data: &[u8]) -> Result<&Self, ProgramError> {
+        Self::check_header(data)?;
+        bytemuck::try_from_bytes(&data[..size_of::<Self>()])
+            .map_err(|_| PrivacyError::InvalidAccountData.into())
+    }
+
+    /// Mutably borrow the account in `data`
+    fn from_bytes_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        Self::check_header(data)?;
+        bytemuck::try_from_bytes_mut(&mut data[..size_of::<Self>()])
+            .map_err(|_| PrivacyError::InvalidAccountData.into())
+    }
+
+    /// Check the header and that `data` holds a whole account
+    fn check_header(data: &[u8]) -> Result<(), ProgramError> {
+        if !Self::has_discriminator(data) {
+            return Err(PrivacyError::InvalidAccountData.into());
+        }
+
+        // Older layouts may be shorter, so the version comes before the size
+        if data[8] != Self::VERSION {
+            return Err(PrivacyError::AccountNeedsMigration.into());
+        }
+
+        if data.len() < size_of::<Self>() {
+            return Err(PrivacyError::InvalidAccountData.into());
+        }
+
+        Ok(())
+    }
+}
+
+/// `None` for the unset key in zero-copy layouts
+fn optional_key(key: Pubkey) -> Option<Pubkey> {
+    if key == Pubkey::default() {
+        None
+    } else {
+        Some(key)
+    }
+}
 
 /// Privacy pool state
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+///
+/// Stored zero-copy (see [`ZeroCopyAccount`]): fields are ordered so the
+/// `#[repr(C)]` layout has no padding, flags are `u8`, unset keys are
+/// `Pubkey::default()`, and the caches are fixed arrays with a length.
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PoolState {
+    /// Account discriminator (ProgramAccount::DISCRIMINATOR)
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::hex"))]
+    pub discriminator: [u8; 8],
+
+    /// Account layout version (ProgramAccount::VERSION)
+    pub version: u8,
+
+    /// Tree depth
+    pub tree_depth: u8,
+
+    /// Is pool initialized (0 or 1)
+    pub initialized: u8,
+
+    /// Paused operations (PAUSE_* bit flags)
+    pub paused: u8,
+
+    /// Whether `merkle_root` is computed on-chain from `filled_subtrees`
+    /// (0 or 1). Pools migrated with commitments from before the on-chain
+    /// tree keep an authority-maintained root instead.
+    pub onchain_tree: u8,
+
+    /// Keeps the following fields 8-byte aligned
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub _padding: [u8; 3],
+
     /// Pool authority
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::pubkey"))]
     pub authority: Pubkey,
 
     /// Current merkle root
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::hex"))]
     pub merkle_root: [u8; 32],
 
-    /// Tree depth
-    pub tree_depth: u8,
+    /// Pool vault address
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::pubkey"))]
+    pub vault: Pubkey,
+
+    /// Authority proposed by ProposeAuthority, waiting for AcceptAuthority
+    /// (`Pubkey::default()` when none)
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::pubkey"))]
+    pub pending_authority: Pubkey,
+
+    /// Emergency guardian allowed to pause (but not unpause) the pool
+    /// (`Pubkey::default()` when none)
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::pubkey"))]
+    pub guardian: Pubkey,
 
     /// Number of commitments in tree
     pub commitment_count: u64,
@@ -22,6 +198,27 @@ pub struct PoolState {
     /// Total value locked in pool
     pub tvl: u64,
 
+    /// Nullifier account counter (for migration to PDA storage)
+    pub nullifier_count: u64,
+
+    /// Key image account counter
+    pub key_image_count: u64,
+
+    /// Entries of `used_nullifiers` in use
+    pub nullifier_cache_len: u64,
+
+    /// Entries of `used_key_images` in use
+    pub key_image_cache_len: u64,
+
+    /// When the current pause lapses (0 = until unpaused)
+    pub pause_expires_at: i64,
+
+    /// Deposit cap and withdrawal rate limits (set by the authority)
+    pub limits: PoolLimits,
+
+    /// Withdrawal volume counted against `limits`
+    pub outflow: OutflowTracker,
+
     /// Nullifier set (used nullifiers to prevent double-spending)
     /// OPTIMIZATION: In production, nullifiers should be stored in separate PDA accounts
     /// derived from the nullifier hash itself. This provides:
@@ -35,72 +232,263 @@ pub struct PoolState {
     /// - If account doesn't exist, nullifier is unused
     ///
     /// For backwards compatibility, we keep a small cache here:
-    pub used_nullifiers: Vec<[u8; 32]>,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::hex_cache"))]
+    pub used_nullifiers: [[u8; 32]; PoolState::MAX_CACHE_ENTRIES],
 
     /// Key images (for ring signatures)
     /// Same optimization applies - should use PDA accounts
-    pub used_key_images: Vec<[u8; 32]>,
-
-    /// Nullifier account counter (for migration to PDA storage)
-    pub nullifier_count: u64,
-
-    /// Key image account counter
-    pub key_image_count: u64,
-
-    /// Pool vault address
-    pub vault: Pubkey,
-
-    /// Is pool initialized
-    pub is_initialized: bool,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::hex_cache"))]
+    pub used_key_images: [[u8; 32]; PoolState::MAX_CACHE_ENTRIES],
+
+    /// Auditor's Baby Jubjub encryption key (x, y), all zero when the pool has
+    /// no auditor (see [`crate::auditor`])
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::hex_seq"))]
+    pub auditor_key: [[u8; 32]; 2],
+
+    /// Frontier of the commitment tree: the last left child at each level,
+    /// leaf level first (only the first `tree_depth` entries are used)
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::hex_seq"))]
+    pub filled_subtrees: [[u8; 32]; PoolState::MAX_TREE_DEPTH],
 }
 
 impl PoolState {
-    pub const LEN: usize = 32 + // authority
-        32 + // merkle_root
+    /// Entries allocated for each of the nullifier and key image caches
+    pub const MAX_CACHE_ENTRIES: usize = 100;
+
+    /// Deepest commitment tree a pool can have (`filled_subtrees` entries)
+    pub const MAX_TREE_DEPTH: usize = 32;
+
+    pub const LEN: usize = 8 + // discriminator
+        1 + // version
         1 + // tree_depth
+        1 + // initialized
+        1 + // paused
+        1 + // onchain_tree
+        3 + // _padding
+        32 + // authority
+        32 + // merkle_root
+        32 + // vault
+        32 + // pending_authority
+        32 + // guardian
         8 + // commitment_count
         8 + // denomination
         8 + // tvl
-        4 + (32 * 100) + // used_nullifiers (reduced to 100 for cache only)
-        4 + (32 * 100) + // used_key_images (reduced to 100 for cache only)
         8 + // nullifier_count
         8 + // key_image_count
-        32 + // vault
-        1; // is_initialized
+        8 + // nullifier_cache_len
+        8 + // key_image_cache_len
+        8 + // pause_expires_at
+        PoolLimits::LEN + // limits
+        OutflowTracker::LEN + // outflow
+        32 * Self::MAX_CACHE_ENTRIES + // used_nullifiers (cache only)
+        32 * Self::MAX_CACHE_ENTRIES + // used_key_images (cache only)
+        64 + // auditor_key
+        32 * Self::MAX_TREE_DEPTH; // filled_subtrees
+
+    /// A fresh, initialized pool with an empty commitment tree
+    pub fn new(authority: Pubkey, vault: Pubkey, tree_depth: u8, denomination: u64) -> Self {
+        let mut pool = PoolState {
+            discriminator: Self::DISCRIMINATOR,
+            version: Self::VERSION,
+            tree_depth,
+            initialized: 1,
+            authority,
+            vault,
+            denomination,
+            ..Zeroable::zeroed()
+        };
+        pool.start_tree();
+        pool
+    }
+
+    /// Check that `tree_depth` is one the commitment tree supports
+    pub fn is_valid_tree_depth(tree_depth: u8) -> bool {
+        tree_depth != 0 && tree_depth as usize <= Self::MAX_TREE_DEPTH
+    }
+
+    /// Reset the commitment tree to the empty tree of `tree_depth`
+    fn start_tree(&mut self) {
+        let depth = (self.tree_depth as usize).min(Self::MAX_TREE_DEPTH);
+        self.filled_subtrees = [[0u8; 32]; Self::MAX_TREE_DEPTH];
+        self.filled_subtrees[..depth].copy_from_slice(&ZERO_HASHES[..depth]);
+        self.merkle_root = ZERO_HASHES[depth];
+        self.onchain_tree = 1;
+    }
+
+    /// Set up the commitment tree of a pool migrated from a layout without one
+    ///
+    /// Empty pools start the on-chain tree. The commitments of other pools
+    /// were never hashed into a frontier, so they keep their current root
+    /// and rely on UpdateRoot.
+    pub fn migrate_tree(&mut self) {
+        if self.commitment_count == 0 && Self::is_valid_tree_depth(self.tree_depth) {
+            self.start_tree();
+        } else {
+            self.filled_subtrees = [[0u8; 32]; Self::MAX_TREE_DEPTH];
+            self.onchain_tree = 0;
+        }
+    }
+
+    pub fn is_initialized(&self) -> bool {
+        self.initialized != 0
+    }
+
+    /// Pending authority, if a transfer has been proposed
+    pub fn pending_authority(&self) -> Option<Pubkey> {
+        optional_key(self.pending_authority)
+    }
+
+    /// Guardian, if one is set
+    pub fn guardian(&self) -> Option<Pubkey> {
+        optional_key(self.guardian)
+    }
+
+    /// Auditor encryption key, if one is set
+    pub fn auditor_key(&self) -> Option<[[u8; 32]; 2]> {
+        if self.auditor_key == [[0u8; 32]; 2] {
+            None
+        } else {
+            Some(self.auditor_key)
+        }
+    }
+
+    pub const PAUSE_DEPOSITS: u8 = 1 << 0;
+    pub const PAUSE_WITHDRAWALS: u8 = 1 << 1;
+    pub const PAUSE_TRANSFERS: u8 = 1 << 2;
+    pub const PAUSE_VK_UPDATES: u8 = 1 << 3;
+    pub const PAUSE_ALL: u8 = Self::PAUSE_DEPOSITS
+        | Self::PAUSE_WITHDRAWALS
+        | Self::PAUSE_TRANSFERS
+        | Self::PAUSE_VK_UPDATES;
+
+    /// Check if an operation is currently paused (expired pauses are ignored)
+    pub fn is_paused(&self, operation: u8, current_time: i64) -> bool {
+        self.paused & operation != 0
+            && (self.pause_expires_at == 0 || current_time < self.pause_expires_at)
+    }
+
+    /// Pause operations, optionally until `expires_at`
+    ///
+    /// While a pause is active it can only be extended: adding flags never
+    /// shortens the expiry, and an indefinite pause stays indefinite.
+    pub fn pause(&mut self, operations: u8, expires_at: Option<i64>, current_time: i64) {
+        let active = self.is_paused(Self::PAUSE_ALL, current_time);
+        let requested = expires_at.unwrap_or(0);
+
+        self.pause_expires_at = if !active {
+            requested
+        } else if self.pause_expires_at == 0 || requested == 0 {
+            0
+        } else {
+            self.pause_expires_at.max(requested)
+        };
+
+        // Flags from a lapsed pause do not carry over
+        self.paused = if active { self.paused | operations } else { operations };
+    }
+
+    /// Unpause operations
+    pub fn unpause(&mut self, operations: u8) {
+        self.paused &= !operations;
+        if self.paused == 0 {
+            self.pause_expires_at = 0;
+        }
+    }
+
+    /// Check whether a deposit of `amount` stays under the TVL cap
+    pub fn deposit_within_cap(&self, amount: u64) -> bool {
+        match self.tvl.checked_add(amount) {
+            Some(tvl) => self.limits.tvl_cap == 0 || tvl <= self.limits.tvl_cap,
+            None => false,
+        }
+    }
+
+    /// Roll the outflow counters forward and check whether `amount` fits
+    /// under both the per-epoch and the rolling-window limit
+    pub fn outflow_within_limits(&mut self, amount: u64, epoch: u64, current_time: i64) -> bool {
+        self.outflow.roll(epoch, current_time, self.limits.window_seconds);
+
+        let epoch_ok = self.limits.epoch_outflow_limit == 0
+            || self.outflow.epoch_outflow.saturating_add(amount) <= self.limits.epoch_outflow_limit;
+
+        let window_ok = self.limits.window_outflow_limit == 0
+            || self.limits.window_seconds <= 0
+            || self
+                .outflow
+                .window_estimate(current_time, self.limits.window_seconds)
+                .saturating_add(amount)
+                <= self.limits.window_outflow_limit;
+
+        epoch_ok && window_ok
+    }
+
+    /// Count a completed withdrawal against the limits
+    ///
+    /// Rolls the counters first, since a delayed withdrawal can be paid out
+    /// in a later epoch or window than the one it was checked in.
+    pub fn record_outflow(&mut self, amount: u64, epoch: u64, current_time: i64) {
+        self.outflow.roll(epoch, current_time, self.limits.window_seconds);
+        self.outflow.epoch_outflow = self.outflow.epoch_outflow.saturating_add(amount);
+        self.outflow.window_outflow = self.outflow.window_outflow.saturating_add(amount);
+    }
 
     /// Check if nullifier has been used (cache check only)
-    /// For production, use check_nullifier_pda() for O(1) lookup
+    /// Withdraw also rejects nullifiers whose record PDA exists
     pub fn is_nullifier_used(&self, nullifier: &[u8; 32]) -> bool {
-        // Check cache (O(n) but small n=100)
-        self.used_nullifiers.contains(nullifier)
+        // Check cache (O(n) but small n <= MAX_CACHE_ENTRIES)
+        self.used_nullifiers[..self.nullifier_cache_len as usize].contains(nullifier)
     }
 
     /// Mark nullifier as used in cache
-    /// For production, create nullifier PDA account instead
-    pub fn add_nullifier(&mut self, nullifier: [u8; 32]) {
+    /// Withdraw also creates the nullifier's record PDA, which outlives the cache
+    ///
+    /// `cache_size` comes from ProtocolConfig and is at most MAX_CACHE_ENTRIES.
+    pub fn add_nullifier(&mut self, nullifier: [u8; 32], cache_size: usize) {
         // Only add if cache not full
-        if self.used_nullifiers.len() < 100 {
-            self.used_nullifiers.push(nullifier);
+        let len = self.nullifier_cache_len as usize;
+        if len < cache_size.min(Self::MAX_CACHE_ENTRIES) {
+            self.used_nullifiers[len] = nullifier;
+            self.nullifier_cache_len += 1;
         }
-        // Note: In production, always create PDA account regardless of cache
         self.nullifier_count += 1;
     }
 
     /// Check if key image has been used (cache check only)
+    /// PrivateTransfer also rejects key images whose record PDA exists
     pub fn is_key_image_used(&self, key_image: &[u8; 32]) -> bool {
         // Check cache
-        self.used_key_images.contains(key_image)
+        self.used_key_images[..self.key_image_cache_len as usize].contains(key_image)
     }
 
     /// Mark key image as used in cache
-    pub fn add_key_image(&mut self, key_image: [u8; 32]) {
+    /// PrivateTransfer also creates the key image's record PDA, which outlives the cache
+    pub fn add_key_image(&mut self, key_image: [u8; 32], cache_size: usize) {
         // Only add if cache not full
-        if self.used_key_images.len() < 100 {
-            self.used_key_images.push(key_image);
+        let len = self.key_image_cache_len as usize;
+        if len < cache_size.min(Self::MAX_CACHE_ENTRIES) {
+            self.used_key_images[len] = key_image;
+            self.key_image_cache_len += 1;
         }
         self.key_image_count += 1;
     }
 
+    /// Derive pool PDA address (one pool per authority and denomination)
+    pub fn derive_address(
+        authority: &Pubkey,
+        denomination: u64,
+        program_id: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[b"pool", authority.as_ref(), &denomination.to_le_bytes()],
+            program_id,
+        )
+    }
+
+    /// Derive vault PDA address
+    pub fn derive_vault_address(pool: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"vault", pool.as_ref()], program_id)
+    }
+
     /// Derive nullifier PDA address
     pub fn derive_nullifier_pda(
         pool: &Pubkey,
@@ -125,46 +513,198 @@ impl PoolState {
         )
     }
 
-    /// Add commitment to tree
-    ///
-    /// ARCHITECTURE NOTE: Full Merkle tree with Poseidon hashing is maintained OFF-CHAIN
-    /// by the client (see privacy-integration/merkletree.ts). This is the standard architecture
-    /// for ZK privacy protocols (Tornado Cash, Zcash) because:
-    /// 1. Poseidon hashing on-chain is computationally expensive on Solana
-    /// 2. Full tree storage would exceed account size limits
-    /// 3. ZK-SNARK proofs already verify the Merkle path, so on-chain verification is sufficient
+    /// Append a commitment to the tree
     ///
-    /// On-chain, we only track:
-    /// - Current commitment count (for indexing)
-    /// - Current Merkle root (for proof verification)
-    ///
-    /// Clients must:
-    /// 1. Maintain local Merkle tree with Poseidon hashing
-    /// 2. Generate proofs using the local tree
-    /// 3. Submit proofs that reference the on-chain root
-    pub fn add_commitment(&mut self, commitment: [u8; 32]) {
+    /// The root is recomputed with Poseidon (the sol_poseidon syscall) from
+    /// the frontier, exactly as `shadow_privacy_merkle::SparseMerkleTree`
+    /// and the circuits build the tree off-chain. Pools without an on-chain
+    /// tree only count the commitment.
+    pub fn add_commitment(&mut self, commitment: [u8; 32]) -> Result<(), ProgramError> {
+        if self.onchain_tree != 0 {
+            let depth = (self.tree_depth as usize).min(Self::MAX_TREE_DEPTH);
+            self.merkle_root = insert_into_frontier(
+                &mut self.filled_subtrees[..depth],
+                self.commitment_count,
+                commitment,
+            )
+            .map_err(|e| match e {
+                MerkleError::TreeFull(_) => PrivacyError::MerkleTreeFull,
+                MerkleError::InvalidTreeDepth(_) => PrivacyError::InvalidTreeDepth,
+                _ => PrivacyError::InvalidCommitment,
+            })?;
+        }
         self.commitment_count += 1;
-
-        // Note: The merkle_root is NOT updated here. Instead, it should be updated
-        // by a privileged authority (relayer or DAO) after batching commitments off-chain.
-        // This prevents MEV attacks and ensures atomic batch updates.
-        //
-        // For development/testing, you can update it here:
-        // (In production, remove this and use a separate UpdateRoot instruction)
-        use solana_program::keccak;
-        let mut hasher_input = Vec::new();
-        hasher_input.extend_from_slice(&self.merkle_root);
-        hasher_input.extend_from_slice(&commitment);
-        let new_hash = keccak::hash(&hasher_input);
-        self.merkle_root.copy_from_slice(&new_hash.to_bytes());
+        Ok(())
     }
 
     /// Update Merkle root (called by relayer after off-chain tree update)
+    ///
+    /// Pools with an on-chain tree recompute the root from their frontier
+    /// at the next commitment.
     pub fn update_root(&mut self, new_root: [u8; 32]) {
         self.merkle_root = new_root;
     }
 }
 
+/// Per-pool deposit cap and withdrawal rate limits (0 disables a limit)
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, Default, PartialEq, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PoolLimits {
+    /// Maximum TVL in lamports
+    pub tvl_cap: u64,
+
+    /// Maximum withdrawn lamports per Solana epoch
+    pub epoch_outflow_limit: u64,
+
+    /// Maximum withdrawn lamports per rolling window
+    pub window_outflow_limit: u64,
+
+    /// Rolling window length in seconds
+    pub window_seconds: i64,
+
+    /// Over-limit withdrawals are queued for this many seconds instead of failing
+    pub withdrawal_delay: i64,
+}
+
+impl PoolLimits {
+    pub const LEN: usize = 8 + // tvl_cap
+        8 + // epoch_outflow_limit
+        8 + // window_outflow_limit
+        8 + // window_seconds
+        8; // withdrawal_delay
+
+    /// Check that the limits are consistent
+    pub fn is_valid(&self) -> bool {
+        self.window_seconds >= 0
+            && self.withdrawal_delay >= 0
+            && (self.window_outflow_limit == 0 || self.window_seconds > 0)
+    }
+}
+
+/// Withdrawal volume tracking for PoolLimits
+///
+/// The rolling window uses a sliding-window counter: volume in the previous
+/// fixed window is weighted by how much of it still overlaps the rolling window.
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, Default, PartialEq, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct OutflowTracker {
+    /// Solana epoch `epoch_outflow` belongs to
+    pub epoch: u64,
+
+    /// Lamports withdrawn in `epoch`
+    pub epoch_outflow: u64,
+
+    /// Start of the current fixed window (multiple of window_seconds)
+    pub window_start: i64,
+
+    /// Lamports withdrawn in the current fixed window
+    pub window_outflow: u64,
+
+    /// Lamports withdrawn in the fixed window before it
+    pub previous_window_outflow: u64,
+}
+
+impl OutflowTracker {
+    pub const LEN: usize = 8 + // epoch
+        8 + // epoch_outflow
+        8 + // window_start
+        8 + // window_outflow
+        8; // previous_window_outflow
+
+    /// Reset counters that no longer cover `epoch` / `current_time`
+    pub fn roll(&mut self, epoch: u64, current_time: i64, window_seconds: i64) {
+        if self.epoch != epoch {
+            self.epoch = epoch;
+            self.epoch_outflow = 0;
+        }
+
+        if window_seconds <= 0 {
+            return;
+        }
+
+        let window_start = current_time - current_time.rem_euclid(window_seconds);
+        if window_start == self.window_start {
+            return;
+        }
+
+        self.previous_window_outflow = if window_start - self.window_start == window_seconds {
+            self.window_outflow
+        } else {
+            0
+        };
+        self.window_outflow = 0;
+        self.window_start = window_start;
+    }
+
+    /// Estimated volume over the last `window_seconds` (call after `roll`)
+    pub fn window_estimate(&self, current_time: i64, window_seconds: i64) -> u64 {
+        let elapsed = (current_time - self.window_start).clamp(0, window_seconds);
+        let previous_weight = (window_seconds - elapsed) as u128;
+        let previous = self.previous_window_outflow as u128 * previous_weight / window_seconds as u128;
+        (previous as u64).saturating_add(self.window_outflow)
+    }
+}
+
+/// Withdrawal held back by PoolLimits, claimable after `release_at`
+///
+/// The PDA holds the withdrawn lamports until claimed.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DelayedWithdrawal {
+    /// Account discriminator (ProgramAccount::DISCRIMINATOR)
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::hex"))]
+    pub discriminator: [u8; 8],
+
+    /// Account layout version (ProgramAccount::VERSION)
+    pub version: u8,
+
+    /// Pool the withdrawal came from
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::pubkey"))]
+    pub pool: Pubkey,
+
+    /// Recipient of the funds
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::pubkey"))]
+    pub recipient: Pubkey,
+
+    /// Amount in lamports
+    pub amount: u64,
+
+    /// Nullifier spent by the withdrawal
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::hex"))]
+    pub nullifier: [u8; 32],
+
+    /// Earliest claim time
+    pub release_at: i64,
+
+    /// Bump seed for PDA derivation
+    pub bump: u8,
+}
+
+impl DelayedWithdrawal {
+    pub const LEN: usize = 8 + // discriminator
+        1 + // version
+        32 + // pool
+        32 + // recipient
+        8 + // amount
+        32 + // nullifier
+        8 + // release_at
+        1; // bump
+
+    /// Derive delayed withdrawal PDA address
+    pub fn derive_address(
+        pool: &Pubkey,
+        nullifier: &[u8; 32],
+        program_id: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[b"withdrawal", pool.as_ref(), nullifier],
+            program_id,
+        )
+    }
+}
+
 /// Commitment data
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
 pub struct Commitment {
@@ -190,11 +730,21 @@ impl Commitment {
 
 /// Shielded asset state
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct AssetState {
+    /// Account discriminator (ProgramAccount::DISCRIMINATOR)
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::hex"))]
+    pub discriminator: [u8; 8],
+
+    /// Account layout version (ProgramAccount::VERSION)
+    pub version: u8,
+
     /// Asset ID
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::hex"))]
     pub asset_id: [u8; 32],
 
     /// Issuer
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::pubkey"))]
     pub issuer: Pubkey,
 
     /// Asset metadata
@@ -209,12 +759,14 @@ pub struct AssetState {
     pub circulating_supply: u64,
 
     /// Note commitment tree root
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::hex"))]
     pub note_tree_root: [u8; 32],
 
     /// Number of notes
     pub note_count: u64,
 
     /// Used nullifiers for this asset
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::hex_seq"))]
     pub used_nullifiers: Vec<[u8; 32]>,
 
     /// Is initialized
@@ -222,35 +774,65 @@ pub struct AssetState {
 }
 
 impl AssetState {
-    pub const LEN: usize = 32 + // asset_id
+    pub const MAX_NAME_LEN: usize = 64;
+    pub const MAX_SYMBOL_LEN: usize = 16;
+    /// Sized so the account can be created in one CPI (see `LEN` below)
+    pub const MAX_NULLIFIERS: usize = 300;
+
+    pub const LEN: usize = 8 + // discriminator
+        1 + // version
+        32 + // asset_id
         32 + // issuer
-        4 + 64 + // name (max 64 chars)
-        4 + 16 + // symbol (max 16 chars)
+        4 + Self::MAX_NAME_LEN + // name (bytes)
+        4 + Self::MAX_SYMBOL_LEN + // symbol (bytes)
         1 + // decimals
         8 + // total_supply
         8 + // circulating_supply
         32 + // note_tree_root
         8 + // note_count
-        4 + (32 * 1000) + // used_nullifiers
+        4 + (32 * Self::MAX_NULLIFIERS) + // used_nullifiers
         1; // is_initialized
+
+    /// Derive asset PDA address
+    pub fn derive_address(asset_id: &[u8; 32], program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"asset", asset_id.as_ref()], program_id)
+    }
 }
 
+// Program-created accounts are allocated by a single system program CPI,
+// which may grow an account by at most MAX_PERMITTED_DATA_INCREASE
+const _: () = assert!(PoolState::LEN <= MAX_PERMITTED_DATA_INCREASE);
+const _: () = assert!(AssetState::LEN <= MAX_PERMITTED_DATA_INCREASE);
+
 /// Shielded note
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ShieldedNote {
+    /// Account discriminator (ProgramAccount::DISCRIMINATOR)
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::hex"))]
+    pub discriminator: [u8; 8],
+
+    /// Account layout version (ProgramAccount::VERSION)
+    pub version: u8,
+
     /// Note commitment
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::hex"))]
     pub commitment: [u8; 32],
 
     /// Asset ID
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::hex"))]
     pub asset_id: [u8; 32],
 
     /// Encrypted value (only recipient can decrypt)
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::hex"))]
     pub encrypted_value: Vec<u8>,
 
     /// Encrypted memo (optional)
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::hex"))]
     pub encrypted_memo: Vec<u8>,
 
     /// Transaction public key (for stealth address)
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::hex"))]
     pub tx_public_key: [u8; 32],
 
     /// Is spent
@@ -258,10 +840,18 @@ pub struct ShieldedNote {
 }
 
 impl ShieldedNote {
-    pub const LEN: usize = 32 + // commitment
+    /// Largest `encrypted_value` (the note ciphertext's value part, see [`crate::note`])
+    pub const MAX_ENCRYPTED_VALUE_LEN: usize = 256;
+
+    /// Largest `encrypted_memo` (the note ciphertext's memo part)
+    pub const MAX_ENCRYPTED_MEMO_LEN: usize = 256;
+
+    pub const LEN: usize = 8 + // discriminator
+        1 + // version
+        32 + // commitment
         32 + // asset_id
-        4 + 256 + // encrypted_value (max 256 bytes)
-        4 + 256 + // encrypted_memo (max 256 bytes)
+        4 + Self::MAX_ENCRYPTED_VALUE_LEN + // encrypted_value
+        4 + Self::MAX_ENCRYPTED_MEMO_LEN + // encrypted_memo
         32 + // tx_public_key
         1; // is_spent
 }
@@ -270,14 +860,25 @@ impl ShieldedNote {
 /// Each nullifier gets its own PDA account. If account exists, nullifier is used.
 /// This provides unlimited scalability compared to storing in Vec.
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct NullifierAccount {
+    /// Account discriminator (ProgramAccount::DISCRIMINATOR)
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::hex"))]
+    pub discriminator: [u8; 8],
+
+    /// Account layout version (ProgramAccount::VERSION)
+    pub version: u8,
+
     /// The nullifier value
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::hex"))]
     pub nullifier: [u8; 32],
 
     /// Pool this nullifier belongs to
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::pubkey"))]
     pub pool: Pubkey,
 
     /// Transaction signature that used this nullifier
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::hex_option"))]
     pub tx_signature: Option<[u8; 64]>,
 
     /// Timestamp when nullifier was used
@@ -288,7 +889,9 @@ pub struct NullifierAccount {
 }
 
 impl NullifierAccount {
-    pub const LEN: usize = 32 + // nullifier
+    pub const LEN: usize = 8 + // discriminator
+        1 + // version
+        32 + // nullifier
         32 + // pool
         1 + 64 + // tx_signature (option)
         8 + // timestamp
@@ -297,14 +900,25 @@ impl NullifierAccount {
 
 /// Key image account (for ring signature double-spend prevention)
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct KeyImageAccount {
+    /// Account discriminator (ProgramAccount::DISCRIMINATOR)
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::hex"))]
+    pub discriminator: [u8; 8],
+
+    /// Account layout version (ProgramAccount::VERSION)
+    pub version: u8,
+
     /// The key image value
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::hex"))]
     pub key_image: [u8; 32],
 
     /// Pool this key image belongs to
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::pubkey"))]
     pub pool: Pubkey,
 
     /// Transaction signature
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::hex_option"))]
     pub tx_signature: Option<[u8; 64]>,
 
     /// Timestamp
@@ -315,7 +929,9 @@ pub struct KeyImageAccount {
 }
 
 impl KeyImageAccount {
-    pub const LEN: usize = 32 + // key_image
+    pub const LEN: usize = 8 + // discriminator
+        1 + // version
+        32 + // key_image
         32 + // pool
         1 + 64 + // tx_signature
         8 + // timestamp
@@ -325,18 +941,29 @@ impl KeyImageAccount {
 /// Verification Key Account (stores Groth16 verification keys)
 /// Each circuit type gets its own VK account for on-chain verification
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct VerificationKeyAccount {
+    /// Account discriminator (ProgramAccount::DISCRIMINATOR)
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::hex"))]
+    pub discriminator: [u8; 8],
+
+    /// Account layout version (ProgramAccount::VERSION)
+    pub version: u8,
+
     /// Circuit type identifier
     pub circuit_type: CircuitType,
 
     /// Pool this VK belongs to
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::pubkey"))]
     pub pool: Pubkey,
 
     /// Authority that can update this VK
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::pubkey"))]
     pub authority: Pubkey,
 
     /// Serialized verification key (ark-groth16 format)
     /// This is the compressed serialized VerifyingKey<Bn254>
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::hex"))]
     pub vk_data: Vec<u8>,
 
     /// Timestamp when VK was stored
@@ -348,16 +975,20 @@ pub struct VerificationKeyAccount {
 
 /// Circuit types for verification
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum CircuitType {
     Transfer,
     Balance,
     RingSignature,
+    AuditorNote,
 }
 
 impl VerificationKeyAccount {
     pub const MAX_VK_SIZE: usize = 2048; // ~2KB should be sufficient for BN254 VK
 
-    pub const LEN: usize = 1 + // circuit_type (enum)
+    pub const LEN: usize = 8 + // discriminator
+        1 + // version
+        1 + // circuit_type (enum)
         32 + // pool
         32 + // authority
         4 + Self::MAX_VK_SIZE + // vk_data (vec)
@@ -374,6 +1005,7 @@ impl VerificationKeyAccount {
             CircuitType::Transfer => b"vk_transfer",
             CircuitType::Balance => b"vk_balance",
             CircuitType::RingSignature => b"vk_ring_sig",
+            CircuitType::AuditorNote => b"vk_auditor_note",
         };
 
         Pubkey::find_program_address(
@@ -383,71 +1015,387 @@ impl VerificationKeyAccount {
     }
 }
 
+/// M-of-N multisig that can act as a pool authority
+///
+/// The multisig PDA signs privileged instructions through ExecuteProposal, which
+/// invokes this program with the PDA seeds once a proposal has enough approvals.
+/// Membership is fixed at creation.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Multisig {
+    /// Account discriminator (ProgramAccount::DISCRIMINATOR)
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::hex"))]
+    pub discriminator: [u8; 8],
+
+    /// Account layout version (ProgramAccount::VERSION)
+    pub version: u8,
+
+    /// Unique key used to derive the PDA
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::pubkey"))]
+    pub create_key: Pubkey,
+
+    /// Approvals required to execute a proposal
+    pub threshold: u8,
+
+    /// Members allowed to propose and approve
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::pubkeys"))]
+    pub signers: Vec<Pubkey>,
+
+    /// Number of proposals created (next proposal index)
+    pub proposal_count: u64,
+
+    /// Bump seed for PDA derivation
+    pub bump: u8,
+
+    /// Is initialized
+    pub is_initialized: bool,
+}
+
+impl Multisig {
+    pub const MAX_SIGNERS: usize = 10;
+
+    pub const LEN: usize = 8 + // discriminator
+        1 + // version
+        32 + // create_key
+        1 + // threshold
+        4 + (32 * Self::MAX_SIGNERS) + // signers
+        8 + // proposal_count
+        1 + // bump
+        1; // is_initialized
+
+    /// Check if a key is a member of this multisig
+    pub fn is_signer(&self, key: &Pubkey) -> bool {
+        self.signers.contains(key)
+    }
+
+    /// Derive multisig PDA address
+    pub fn derive_address(create_key: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[b"multisig", create_key.as_ref()],
+            program_id,
+        )
+    }
+}
+
+/// Account meta stored in a multisig proposal
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ProposalAccount {
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::pubkey"))]
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+impl ProposalAccount {
+    pub const LEN: usize = 32 + // pubkey
+        1 + // is_signer
+        1; // is_writable
+}
+
+/// Auditor copy of a new note, for pools with an auditor key
+///
+/// Encryption and public inputs are described in [`crate::auditor`].
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AuditorNote {
+    /// Sender's ephemeral Baby Jubjub key (x, y)
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::hex_seq"))]
+    pub ephemeral_key: [[u8; 32]; 2],
+    /// Owner public key, amount and nonce, each masked with a Poseidon key stream
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::hex_seq"))]
+    pub ciphertext: [[u8; 32]; 3],
+    /// Groth16 proof (AuditorNote circuit) that the ciphertext opens the commitment
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::hex"))]
+    pub proof: Vec<u8>,
+}
+
+/// Pending privileged instruction for a multisig
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MultisigProposal {
+    /// Account discriminator (ProgramAccount::DISCRIMINATOR)
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::hex"))]
+    pub discriminator: [u8; 8],
+
+    /// Account layout version (ProgramAccount::VERSION)
+    pub version: u8,
+
+    /// Multisig this proposal belongs to
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::pubkey"))]
+    pub multisig: Pubkey,
+
+    /// Proposal index within the multisig
+    pub index: u64,
+
+    /// Member that created the proposal
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::pubkey"))]
+    pub proposer: Pubkey,
+
+    /// Borsh-encoded PrivacyInstruction to execute
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::hex"))]
+    pub instruction_data: Vec<u8>,
+
+    /// Accounts for the instruction, in order
+    pub accounts: Vec<ProposalAccount>,
+
+    /// Members that approved
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::pubkeys"))]
+    pub approvals: Vec<Pubkey>,
+
+    /// Has been executed
+    pub executed: bool,
+
+    /// Bump seed for PDA derivation
+    pub bump: u8,
+}
+
+impl MultisigProposal {
+    /// Enough for a StoreVerificationKey with a full-size VK
+    pub const MAX_INSTRUCTION_DATA: usize = VerificationKeyAccount::MAX_VK_SIZE + 16;
+    pub const MAX_ACCOUNTS: usize = 8;
+
+    pub const LEN: usize = 8 + // discriminator
+        1 + // version
+        32 + // multisig
+        8 + // index
+        32 + // proposer
+        4 + Self::MAX_INSTRUCTION_DATA + // instruction_data
+        4 + (ProposalAccount::LEN * Self::MAX_ACCOUNTS) + // accounts
+        4 + (32 * Multisig::MAX_SIGNERS) + // approvals
+        1 + // executed
+        1; // bump
+
+    /// Derive proposal PDA address
+    pub fn derive_address(multisig: &Pubkey, index: u64, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[b"proposal", multisig.as_ref(), &index.to_le_bytes()],
+            program_id,
+        )
+    }
+}
+
+/// Relay outcomes reported during one reputation epoch
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, Default, PartialEq, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ReputationBucket {
+    /// Epoch index (unix timestamp / REPUTATION_EPOCH_SECONDS)
+    pub epoch: u64,
+
+    /// Successful relays reported in this epoch
+    pub successes: u32,
+
+    /// Failed relays reported in this epoch
+    pub failures: u32,
+}
+
+impl ReputationBucket {
+    pub const LEN: usize = 8 + // epoch
+        4 + // successes
+        4; // failures
+}
+
 /// Relayer account for decentralized relay network
 /// Each relayer registers with stake and builds reputation over time
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+///
+/// Stored zero-copy (see [`ZeroCopyAccount`]); the endpoint is a fixed
+/// buffer holding `endpoint_len` bytes of UTF-8.
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct RelayerAccount {
+    /// Account discriminator (ProgramAccount::DISCRIMINATOR)
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::hex"))]
+    pub discriminator: [u8; 8],
+
+    /// Account layout version (ProgramAccount::VERSION)
+    pub version: u8,
+
+    /// Is active, i.e. not slashed/banned (0 or 1)
+    pub active: u8,
+
+    /// Bump seed for PDA
+    pub bump: u8,
+
+    /// Bytes of `endpoint` in use
+    pub endpoint_len: u8,
+
+    /// Keeps the following fields 8-byte aligned
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub _padding: [u8; 4],
+
     /// Relayer wallet address
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::pubkey"))]
     pub relayer: Pubkey,
 
     /// Stake amount (for slashing if misbehaves)
     pub stake: u64,
 
-    /// Total successful relays
+    /// Total successful relays (lifetime)
     pub successful_relays: u64,
 
-    /// Total failed relays
+    /// Total failed relays (lifetime)
     pub failed_relays: u64,
 
     /// Last heartbeat timestamp
     pub last_heartbeat: i64,
 
-    /// Is active (not slashed/banned)
-    pub is_active: bool,
-
     /// Registration timestamp
     pub registered_at: i64,
 
-    /// Service endpoint (URL or IP)
-    pub endpoint: String,
+    /// Timestamp of the most recent relay report
+    pub last_report_at: i64,
 
-    /// Bump seed for PDA
-    pub bump: u8,
+    /// Ring buffer of per-epoch relay outcomes, indexed by epoch % REPUTATION_BUCKETS
+    pub reputation_buckets: [ReputationBucket; RelayerAccount::REPUTATION_BUCKETS],
+
+    /// Service endpoint (URL or IP)
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::padded_str"))]
+    pub endpoint: [u8; RelayerAccount::MAX_ENDPOINT_LEN],
 }
 
 impl RelayerAccount {
     pub const MAX_ENDPOINT_LEN: usize = 128;
 
-    pub const LEN: usize = 32 + // relayer
+    /// Length of one reputation epoch (1 day)
+    pub const REPUTATION_EPOCH_SECONDS: i64 = 86_400;
+
+    /// Number of epochs kept for scoring; older reports no longer count
+    pub const REPUTATION_BUCKETS: usize = 8;
+
+    /// Reports required inside the window before a score is produced
+    pub const MIN_REPUTATION_SAMPLES: u64 = 10;
+
+    pub const LEN: usize = 8 + // discriminator
+        1 + // version
+        1 + // active
+        1 + // bump
+        1 + // endpoint_len
+        4 + // _padding
+        32 + // relayer
         8 + // stake
         8 + // successful_relays
         8 + // failed_relays
         8 + // last_heartbeat
-        1 + // is_active
         8 + // registered_at
-        4 + Self::MAX_ENDPOINT_LEN + // endpoint (string)
-        1; // bump
+        8 + // last_report_at
+        ReputationBucket::LEN * Self::REPUTATION_BUCKETS + // reputation_buckets
+        Self::MAX_ENDPOINT_LEN; // endpoint
+
+    /// A newly registered, active relayer
+    ///
+    /// `endpoint` must be at most MAX_ENDPOINT_LEN bytes.
+    pub fn new(relayer: Pubkey, stake: u64, endpoint: &str, bump: u8, current_time: i64) -> Self {
+        let mut account = RelayerAccount {
+            discriminator: Self::DISCRIMINATOR,
+            version: Self::VERSION,
+            active: 1,
+            bump,
+            relayer,
+            stake,
+            last_heartbeat: current_time,
+            registered_at: current_time,
+            ..Zeroable::zeroed()
+        };
+        account.set_endpoint(endpoint);
+        account
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active != 0
+    }
+
+    /// Service endpoint as a string (empty if not valid UTF-8)
+    pub fn endpoint(&self) -> &str {
+        std::str::from_utf8(&self.endpoint[..self.endpoint_len as usize]).unwrap_or("")
+    }
+
+    /// Replace the endpoint, truncating to MAX_ENDPOINT_LEN bytes
+    pub fn set_endpoint(&mut self, endpoint: &str) {
+        let bytes = &endpoint.as_bytes()[..endpoint.len().min(Self::MAX_ENDPOINT_LEN)];
+        self.endpoint = [0u8; Self::MAX_ENDPOINT_LEN];
+        self.endpoint[..bytes.len()].copy_from_slice(bytes);
+        self.endpoint_len = bytes.len() as u8;
+    }
+
+    /// Reputation epoch containing `timestamp`
+    pub fn reputation_epoch(timestamp: i64) -> u64 {
+        (timestamp.max(0) / Self::REPUTATION_EPOCH_SECONDS) as u64
+    }
 
-    /// Calculate reputation score (0-100)
-    pub fn reputation_score(&self) -> u8 {
-        if self.successful_relays == 0 && self.failed_relays == 0 {
-            return 50; // Neutral score for new relayers
+    /// Record a relay outcome in the lifetime counters and the current epoch bucket
+    pub fn record_relay(&mut self, success: bool, current_time: i64) {
+        if success {
+            self.successful_relays = self.successful_relays.saturating_add(1);
+        } else {
+            self.failed_relays = self.failed_relays.saturating_add(1);
         }
 
-        let total = self.successful_relays + self.failed_relays;
-        let success_rate = (self.successful_relays * 100) / total;
+        let epoch = Self::reputation_epoch(current_time);
+        let bucket = &mut self.reputation_buckets[(epoch % Self::REPUTATION_BUCKETS as u64) as usize];
 
-        // Cap at 100
-        if success_rate > 100 {
-            100
+        // Slot still holds an older epoch: recycle it
+        if bucket.epoch != epoch {
+            *bucket = ReputationBucket {
+                epoch,
+                ..ReputationBucket::default()
+            };
+        }
+
+        if success {
+            bucket.successes = bucket.successes.saturating_add(1);
         } else {
-            success_rate as u8
+            bucket.failures = bucket.failures.saturating_add(1);
         }
+
+        self.last_report_at = current_time;
     }
 
-    /// Check if relayer is online (heartbeat within last 5 minutes)
-    pub fn is_online(&self, current_time: i64) -> bool {
-        self.is_active && (current_time - self.last_heartbeat) < 300 // 5 minutes
+    /// Calculate time-decayed reputation score (0-100)
+    ///
+    /// Each epoch's outcomes are weighted by half the weight of the epoch after it,
+    /// so recent behaviour dominates. Returns `None` while the relayer has fewer than
+    /// MIN_REPUTATION_SAMPLES reports in the window, or when it has neither reported
+    /// nor sent a heartbeat for the whole window.
+    pub fn reputation_score(&self, current_time: i64) -> Option<u8> {
+        let window = Self::REPUTATION_BUCKETS as u64;
+        let current_epoch = Self::reputation_epoch(current_time);
+
+        let last_activity = self.last_heartbeat.max(self.last_report_at);
+        if current_epoch.saturating_sub(Self::reputation_epoch(last_activity)) >= window {
+            return None;
+        }
+
+        let mut samples: u64 = 0;
+        let mut weighted_successes: u64 = 0;
+        let mut weighted_total: u64 = 0;
+
+        for bucket in self.reputation_buckets.iter() {
+            // Skip empty slots, stale epochs and buckets from the future (clock skew)
+            if bucket.epoch > current_epoch || current_epoch - bucket.epoch >= window {
+                continue;
+            }
+
+            let weight = 1u64 << (window - 1 - (current_epoch - bucket.epoch));
+            let successes = bucket.successes as u64;
+            let total = successes + bucket.failures as u64;
+
+            samples += total;
+            weighted_successes += successes * weight;
+            weighted_total += total * weight;
+        }
+
+        if samples < Self::MIN_REPUTATION_SAMPLES || weighted_total == 0 {
+            return None;
+        }
+
+        Some(((weighted_successes * 100) / weighted_total) as u8)
+    }
+
+    /// Check if relayer is online (heartbeat within ProtocolConfig::relayer_online_window)
+    pub fn is_online(&self, current_time: i64, online_window: i64) -> bool {
+        self.is_active() && (current_time - self.last_heartbeat) < online_window
     }
 
     /// Derive relayer PDA address
@@ -461,3 +1409,651 @@ impl RelayerAccount {
         )
     }
 }
+
+/// Tunable protocol limits, stored in ProtocolConfig
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ConfigParams {
+    /// Minimum relayer stake in lamports
+    pub min_relayer_stake: u64,
+
+    /// Seconds since last heartbeat for a relayer to count as online
+    pub relayer_online_window: i64,
+
+    /// Maximum number of ring members in a private transfer
+    pub max_ring_size: u8,
+
+    /// Nullifiers kept in each PoolState cache (<= PoolState::MAX_CACHE_ENTRIES)
+    pub nullifier_cache_size: u16,
+
+    /// Key images kept in each PoolState cache (<= PoolState::MAX_CACHE_ENTRIES)
+    pub key_image_cache_size: u16,
+}
+
+impl ConfigParams {
+    pub const LEN: usize = 8 + // min_relayer_stake
+        8 + // relayer_online_window
+        1 + // max_ring_size
+        2 + // nullifier_cache_size
+        2; // key_image_cache_size
+
+    /// Hard upper bound on max_ring_size (verification cost grows linearly)
+    pub const MAX_RING_SIZE_LIMIT: u8 = 32;
+
+    /// Check that the parameters are usable by the processor
+    pub fn is_valid(&self) -> bool {
+        self.min_relayer_stake > 0
+            && self.relayer_online_window > 0
+            && self.max_ring_size > 0
+            && self.max_ring_size <= Self::MAX_RING_SIZE_LIMIT
+            && self.nullifier_cache_size as usize <= PoolState::MAX_CACHE_ENTRIES
+            && self.key_image_cache_size as usize <= PoolState::MAX_CACHE_ENTRIES
+    }
+}
+
+impl Default for ConfigParams {
+    fn default() -> Self {
+        ConfigParams {
+            min_relayer_stake: 100_000_000, // 0.1 SOL
+            relayer_online_window: 300, // 5 minutes
+            max_ring_size: 16,
+            nullifier_cache_size: PoolState::MAX_CACHE_ENTRIES as u16,
+            key_image_cache_size: PoolState::MAX_CACHE_ENTRIES as u16,
+        }
+    }
+}
+
+/// Program-wide configuration (singleton PDA)
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ProtocolConfig {
+    /// Account discriminator (ProgramAccount::DISCRIMINATOR)
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::hex"))]
+    pub discriminator: [u8; 8],
+
+    /// Account layout version (ProgramAccount::VERSION)
+    pub version: u8,
+
+    /// Admin allowed to update the configuration
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+
+    /// Operational limits read by the processor
+    pub params: ConfigParams,
+
+    /// Bump seed for PDA derivation
+    pub bump: u8,
+
+    /// Is initialized
+    pub is_initialized: bool,
+}
+
+impl ProtocolConfig {
+    pub const LEN: usize = 8 + // discriminator
+        1 + // version
+        32 + // admin
+        ConfigParams::LEN + // params
+        1 + // bump
+        1; // is_initialized
+
+    /// Derive config PDA address
+    pub fn derive_address(program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"config"], program_id)
+    }
+
+    /// Derive the ProgramData address of `program_id`, whose upgrade
+    /// authority initializes the config
+    pub fn derive_program_data_address(program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id())
+    }
+}
+
+impl ProgramAccount for PoolState {
+    const ACCOUNT_TYPE: AccountType = AccountType::Pool;
+    const DISCRIMINATOR: [u8; 8] = [247, 237, 227, 245, 215, 195, 222, 70];
+    /// Version 2: zero-copy layout
+    /// Version 3: auditor key appended
+    /// Version 4: on-chain Poseidon commitment tree
+    const VERSION: u8 = 4;
+}
+
+impl ZeroCopyAccount for PoolState {}
+
+// The zero-copy layout must match LEN exactly (no implicit padding)
+const _: () = assert!(size_of::<PoolState>() == PoolState::LEN);
+// The frontier holds a tree of any depth the merkle crate supports
+const _: () = assert!(PoolState::MAX_TREE_DEPTH == shadow_privacy_merkle::MAX_TREE_DEPTH);
+
+impl ProgramAccount for AssetState {
+    const ACCOUNT_TYPE: AccountType = AccountType::Asset;
+    const DISCRIMINATOR: [u8; 8] = [233, 229, 132, 170, 169, 225, 98, 171];
+    const VERSION: u8 = 1;
+}
+
+impl ProgramAccount for ShieldedNote {
+    const ACCOUNT_TYPE: AccountType = AccountType::ShieldedNote;
+    const DISCRIMINATOR: [u8; 8] = [100, 241, 124, 234, 166, 77, 196, 56];
+    const VERSION: u8 = 1;
+}
+
+impl ProgramAccount for NullifierAccount {
+    const ACCOUNT_TYPE: AccountType = AccountType::Nullifier;
+    const DISCRIMINATOR: [u8; 8] = [250, 31, 238, 177, 213, 98, 48, 172];
+    const VERSION: u8 = 1;
+}
+
+impl ProgramAccount for KeyImageAccount {
+    const ACCOUNT_TYPE: AccountType = AccountType::KeyImage;
+    const DISCRIMINATOR: [u8; 8] = [151, 189, 92, 92, 20, 20, 206, 228];
+    const VERSION: u8 = 1;
+}
+
+impl ProgramAccount for VerificationKeyAccount {
+    const ACCOUNT_TYPE: AccountType = AccountType::VerificationKey;
+    const DISCRIMINATOR: [u8; 8] = [67, 6, 141, 237, 93, 54, 220, 214];
+    const VERSION: u8 = 1;
+}
+
+impl ProgramAccount for RelayerAccount {
+    const ACCOUNT_TYPE: AccountType = AccountType::Relayer;
+    const DISCRIMINATOR: [u8; 8] = [94, 235, 98, 227, 126, 208, 77, 139];
+    /// Version 2: zero-copy layout
+    const VERSION: u8 = 2;
+}
+
+impl ZeroCopyAccount for RelayerAccount {}
+
+// The zero-copy layout must match LEN exactly (no implicit padding)
+const _: () = assert!(size_of::<RelayerAccount>() == RelayerAccount::LEN);
+
+impl ProgramAccount for ProtocolConfig {
+    const ACCOUNT_TYPE: AccountType = AccountType::ProtocolConfig;
+    const DISCRIMINATOR: [u8; 8] = [207, 91, 250, 28, 152, 179, 215, 209];
+    const VERSION: u8 = 1;
+}
+
+impl ProgramAccount for Multisig {
+    const ACCOUNT_TYPE: AccountType = AccountType::Multisig;
+    const DISCRIMINATOR: [u8; 8] = [224, 116, 121, 186, 68, 161, 79, 236];
+    const VERSION: u8 = 1;
+}
+
+impl ProgramAccount for MultisigProposal {
+    const ACCOUNT_TYPE: AccountType = AccountType::MultisigProposal;
+    const DISCRIMINATOR: [u8; 8] = [13, 15, 144, 55, 252, 164, 83, 208];
+    const VERSION: u8 = 1;
+}
+
+impl ProgramAccount for DelayedWithdrawal {
+    const ACCOUNT_TYPE: AccountType = AccountType::DelayedWithdrawal;
+    const DISCRIMINATOR: [u8; 8] = [144, 167, 121, 15, 45, 9, 91, 68];
+    const VERSION: u8 = 1;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::legacy::{RelayerAccountV0, RelayerAccountV1};
+
+    const DAY: i64 = RelayerAccount::REPUTATION_EPOCH_SECONDS;
+
+    fn new_relayer(now: i64) -> RelayerAccount {
+        RelayerAccount::new(Pubkey::new_unique(), 100_000_000, "https://relayer.example", 255, now)
+    }
+
+    #[test]
+    fn test_reputation_requires_min_samples() {
+        let now = 1_700_000_000;
+        let mut relayer = new_relayer(now);
+        assert_eq!(relayer.reputation_score(now), None);
+
+        for _ in 0..RelayerAccount::MIN_REPUTATION_SAMPLES - 1 {
+            relayer.record_relay(true, now);
+        }
+        assert_eq!(relayer.reputation_score(now), None);
+
+        relayer.record_relay(true, now);
+        assert_eq!(relayer.reputation_score(now), Some(100));
+    }
+
+    #[test]
+    fn test_reputation_recent_failures_dominate() {
+        let start = 1_700_000_000;
+        let mut relayer = new_relayer(start);
+
+        // A good week followed by one bad day
+        for day in 0..7 {
+            for _ in 0..20 {
+                relayer.record_relay(true, start + day * DAY);
+            }
+        }
+        let bad_day = start + 7 * DAY;
+        for _ in 0..20 {
+            relayer.record_relay(false, bad_day);
+        }
+
+        assert_eq!(relayer.successful_relays, 140);
+        assert_eq!(relayer.failed_relays, 20);
+        // Lifetime ratio would be 87; the decayed score is roughly halved
+        let score = relayer.reputation_score(bad_day).unwrap();
+        assert!(score < 55, "score {} should be dominated by recent failures", score);
+    }
+
+    #[test]
+    fn test_reputation_expires_after_window() {
+        let start = 1_700_000_000;
+        let mut relayer = new_relayer(start);
+        for _ in 0..20 {
+            relayer.record_relay(true, start);
+        }
+
+        let window = RelayerAccount::REPUTATION_BUCKETS as i64 * DAY;
+        assert_eq!(relayer.reputation_score(start + window - DAY), Some(100));
+        assert_eq!(relayer.reputation_score(start + window), None);
+    }
+
+    #[test]
+    fn test_relayer_layout_sizes() {
+        let mut relayer = new_relayer(0);
+        let endpoint = "x".repeat(RelayerAccount::MAX_ENDPOINT_LEN);
+        relayer.set_endpoint(&endpoint);
+        assert_eq!(relayer.endpoint(), endpoint);
+        assert_eq!(relayer.try_to_vec().unwrap().len(), RelayerAccount::LEN);
+
+        let old = RelayerAccountV0 {
+            relayer: relayer.relayer,
+            stake: relayer.stake,
+            successful_relays: 7,
+            failed_relays: 3,
+            last_heartbeat: 42,
+            is_active: true,
+            registered_at: 1,
+            endpoint: endpoint.clone(),
+            bump: relayer.bump,
+        };
+        assert_eq!(old.try_to_vec().unwrap().len(), RelayerAccountV0::LEN);
+
+        let migrated = RelayerAccount::from(RelayerAccountV1::from(old));
+        assert_eq!(migrated.endpoint(), endpoint);
+        assert!(migrated.is_active());
+        assert_eq!(migrated.successful_relays, 7);
+        assert_eq!(migrated.failed_relays, 3);
+        assert_eq!(migrated.reputation_score(42), None);
+    }
+
+    #[test]
+    fn test_config_defaults_and_limits() {
+        let params = ConfigParams::default();
+        assert!(params.is_valid());
+
+        let config = ProtocolConfig {
+            discriminator: ProtocolConfig::DISCRIMINATOR,
+            version: ProtocolConfig::VERSION,
+            admin: Pubkey::new_unique(),
+            params,
+            bump: 255,
+            is_initialized: true,
+        };
+        assert_eq!(config.try_to_vec().unwrap().len(), ProtocolConfig::LEN);
+
+        let oversized_cache = ConfigParams {
+            nullifier_cache_size: PoolState::MAX_CACHE_ENTRIES as u16 + 1,
+            ..params
+        };
+        assert!(!oversized_cache.is_valid());
+
+        let empty_ring = ConfigParams {
+            max_ring_size: 0,
+            ..params
+        };
+        assert!(!empty_ring.is_valid());
+    }
+
+    #[test]
+    fn test_multisig_layout_sizes() {
+        let signers: Vec<Pubkey> = (0..Multisig::MAX_SIGNERS).map(|_| Pubkey::new_unique()).collect();
+        let multisig = Multisig {
+            discriminator: Multisig::DISCRIMINATOR,
+            version: Multisig::VERSION,
+            create_key: Pubkey::new_unique(),
+            threshold: 2,
+            signers: signers.clone(),
+            proposal_count: 0,
+            bump: 255,
+            is_initialized: true,
+        };
+        assert_eq!(multisig.try_to_vec().unwrap().len(), Multisig::LEN);
+        assert!(multisig.is_signer(&signers[3]));
+        assert!(!multisig.is_signer(&Pubkey::new_unique()));
+
+        let proposal = MultisigProposal {
+            discriminator: MultisigProposal::DISCRIMINATOR,
+            version: MultisigProposal::VERSION,
+            multisig: Pubkey::new_unique(),
+            index: 0,
+            proposer: signers[0],
+            instruction_data: vec![0u8; MultisigProposal::MAX_INSTRUCTION_DATA],
+            accounts: vec![
+                ProposalAccount {
+                    pubkey: Pubkey::new_unique(),
+                    is_signer: false,
+                    is_writable: true,
+                };
+                MultisigProposal::MAX_ACCOUNTS
+            ],
+            approvals: signers,
+            executed: false,
+            bump: 255,
+        };
+        assert_eq!(proposal.try_to_vec().unwrap().len(), MultisigProposal::LEN);
+    }
+
+    #[test]
+    fn test_pause_expiry_and_extension() {
+        let mut pool = PoolState::new(Pubkey::new_unique(), Pubkey::new_unique(), 20, 1_000_000_000);
+        pool.guardian = Pubkey::new_unique();
+        let now = 1_000;
+
+        pool.pause(PoolState::PAUSE_DEPOSITS, Some(now + 100), now);
+        assert!(pool.is_paused(PoolState::PAUSE_DEPOSITS, now));
+        assert!(!pool.is_paused(PoolState::PAUSE_WITHDRAWALS, now));
+        assert!(!pool.is_paused(PoolState::PAUSE_DEPOSITS, now + 100));
+
+        // Adding a flag with a shorter expiry keeps the longer one
+        pool.pause(PoolState::PAUSE_WITHDRAWALS, Some(now + 10), now);
+        assert!(pool.is_paused(PoolState::PAUSE_WITHDRAWALS, now + 50));
+
+        // An indefinite pause cannot be shortened
+        pool.pause(PoolState::PAUSE_TRANSFERS, None, now);
+        pool.pause(PoolState::PAUSE_TRANSFERS, Some(now + 1), now);
+        assert!(pool.is_paused(PoolState::PAUSE_DEPOSITS, now + 1_000_000));
+
+        pool.unpause(PoolState::PAUSE_ALL);
+        assert_eq!(pool.paused, 0);
+        assert!(!pool.is_paused(PoolState::PAUSE_ALL, now));
+    }
+
+    #[test]
+    fn test_outflow_limits() {
+        let mut tracker = OutflowTracker::default();
+        let window = 3_600;
+
+        tracker.roll(1, 7_200, window);
+        tracker.window_outflow = 100;
+
+        // Half way into the next window, half of the previous volume still counts
+        tracker.roll(1, 7_200 + window + window / 2, window);
+        assert_eq!(tracker.previous_window_outflow, 100);
+        assert_eq!(tracker.window_estimate(7_200 + window + window / 2, window), 50);
+
+        // Skipping a whole window forgets it
+        tracker.roll(2, 7_200 + 4 * window, window);
+        assert_eq!(tracker.window_estimate(7_200 + 4 * window, window), 0);
+        assert_eq!(tracker.epoch_outflow, 0);
+
+        let limits = PoolLimits {
+            tvl_cap: 0,
+            epoch_outflow_limit: 1_000,
+            window_outflow_limit: 300,
+            window_seconds: window,
+            withdrawal_delay: 0,
+        };
+        assert!(limits.is_valid());
+        assert!(!PoolLimits { window_seconds: 0, ..limits }.is_valid());
+    }
+
+    #[test]
+    fn test_commitment_tree_matches_sparse_tree() {
+        use shadow_privacy_merkle::{field_from_u64, SparseMerkleTree};
+
+        let key = Pubkey::new_unique();
+        let mut pool = PoolState::new(key, key, 3, 1);
+        let mut tree = SparseMerkleTree::new(3).unwrap();
+        assert_eq!(pool.merkle_root, tree.root());
+
+        for n in 1..=8 {
+            pool.add_commitment(field_from_u64(n)).unwrap();
+            tree.insert(field_from_u64(n)).unwrap();
+            assert_eq!(pool.merkle_root, tree.root());
+        }
+        assert_eq!(pool.commitment_count, 8);
+        assert_eq!(
+            pool.add_commitment(field_from_u64(9)),
+            Err(PrivacyError::MerkleTreeFull.into())
+        );
+
+        let mut pool = PoolState::new(key, key, 3, 1);
+        assert_eq!(pool.add_commitment([0xff; 32]), Err(PrivacyError::InvalidCommitment.into()));
+        assert_eq!(pool.commitment_count, 0);
+    }
+
+    #[test]
+    fn test_migrate_tree() {
+        let key = Pubkey::new_unique();
+
+        // An empty legacy pool starts the on-chain tree
+        let mut pool = PoolState::new(key, key, 20, 1);
+        pool.onchain_tree = 0;
+        pool.merkle_root = [5; 32];
+        pool.migrate_tree();
+        assert_eq!(bytemuck::bytes_of(&pool), bytemuck::bytes_of(&PoolState::new(key, key, 20, 1)));
+
+        // A non-empty one keeps its root, which UpdateRoot maintains
+        pool.merkle_root = [5; 32];
+        pool.commitment_count = 2;
+        pool.migrate_tree();
+        assert_eq!(pool.onchain_tree, 0);
+        pool.add_commitment([6; 32]).unwrap();
+        assert_eq!(pool.merkle_root, [5; 32]);
+        assert_eq!(pool.commitment_count, 3);
+    }
+
+    fn assert_max_len<T: ProgramAccount>(account: &T, len: usize) {
+        let data = account.try_to_vec().unwrap();
+        assert_eq!(data.len(), len, "{:?} LEN does not match max size", T::ACCOUNT_TYPE);
+        assert!(T::load(&data).is_ok());
+    }
+
+    #[test]
+    fn test_account_lens_match_max_contents() {
+        let key = Pubkey::new_unique();
+
+        let mut pool = PoolState::new(key, key, 20, u64::MAX);
+        pool.merkle_root = [1u8; 32];
+        pool.commitment_count = u64::MAX;
+        pool.tvl = u64::MAX;
+        pool.used_nullifiers = [[2u8; 32]; PoolState::MAX_CACHE_ENTRIES];
+        pool.used_key_images = [[3u8; 32]; PoolState::MAX_CACHE_ENTRIES];
+        pool.nullifier_cache_len = PoolState::MAX_CACHE_ENTRIES as u64;
+        pool.key_image_cache_len = PoolState::MAX_CACHE_ENTRIES as u64;
+        pool.nullifier_count = u64::MAX;
+        pool.key_image_count = u64::MAX;
+        pool.pending_authority = key;
+        pool.guardian = key;
+        pool.paused = PoolState::PAUSE_ALL;
+        pool.pause_expires_at = i64::MAX;
+        pool.auditor_key = [[14u8; 32]; 2];
+        pool.filled_subtrees = [[15u8; 32]; PoolState::MAX_TREE_DEPTH];
+        assert_max_len(&pool, PoolState::LEN);
+
+        let asset = AssetState {
+            discriminator: AssetState::DISCRIMINATOR,
+            version: AssetState::VERSION,
+            asset_id: [4u8; 32],
+            issuer: key,
+            name: "n".repeat(AssetState::MAX_NAME_LEN),
+            symbol: "s".repeat(AssetState::MAX_SYMBOL_LEN),
+            decimals: 9,
+            total_supply: u64::MAX,
+            circulating_supply: u64::MAX,
+            note_tree_root: [5u8; 32],
+            note_count: u64::MAX,
+            used_nullifiers: vec![[6u8; 32]; AssetState::MAX_NULLIFIERS],
+            is_initialized: true,
+        };
+        assert_max_len(&asset, AssetState::LEN);
+
+        let note = ShieldedNote {
+            discriminator: ShieldedNote::DISCRIMINATOR,
+            version: ShieldedNote::VERSION,
+            commitment: [7u8; 32],
+            asset_id: [8u8; 32],
+            encrypted_value: vec![0u8; ShieldedNote::MAX_ENCRYPTED_VALUE_LEN],
+            encrypted_memo: vec![0u8; ShieldedNote::MAX_ENCRYPTED_MEMO_LEN],
+            tx_public_key: [9u8; 32],
+            is_spent: true,
+        };
+        assert_max_len(&note, ShieldedNote::LEN);
+
+        let nullifier = NullifierAccount {
+            discriminator: NullifierAccount::DISCRIMINATOR,
+            version: NullifierAccount::VERSION,
+            nullifier: [10u8; 32],
+            pool: key,
+            tx_signature: Some([11u8; 64]),
+            timestamp: i64::MAX,
+            bump: 255,
+        };
+        assert_max_len(&nullifier, NullifierAccount::LEN);
+
+        let key_image = KeyImageAccount {
+            discriminator: KeyImageAccount::DISCRIMINATOR,
+            version: KeyImageAccount::VERSION,
+            key_image: [12u8; 32],
+            pool: key,
+            tx_signature: Some([13u8; 64]),
+            timestamp: i64::MAX,
+            bump: 255,
+        };
+        assert_max_len(&key_image, KeyImageAccount::LEN);
+
+        let vk = VerificationKeyAccount {
+            discriminator: VerificationKeyAccount::DISCRIMINATOR,
+            version: VerificationKeyAccount::VERSION,
+            circuit_type: CircuitType::RingSignature,
+            pool: key,
+            authority: key,
+            vk_data: vec![0u8; VerificationKeyAccount::MAX_VK_SIZE],
+            stored_at: i64::MAX,
+            bump: 255,
+        };
+        assert_max_len(&vk, VerificationKeyAccount::LEN);
+
+        let mut relayer = new_relayer(0);
+        relayer.set_endpoint(&"x".repeat(RelayerAccount::MAX_ENDPOINT_LEN));
+        assert_max_len(&relayer, RelayerAccount::LEN);
+
+        let withdrawal = DelayedWithdrawal {
+            discriminator: DelayedWithdrawal::DISCRIMINATOR,
+            version: DelayedWithdrawal::VERSION,
+            pool: key,
+            recipient: key,
+            amount: u64::MAX,
+            nullifier: [14u8; 32],
+            release_at: i64::MAX,
+            bump: 255,
+        };
+        assert_max_len(&withdrawal, DelayedWithdrawal::LEN);
+    }
+
+    #[test]
+    fn test_zero_copy_matches_borsh() {
+        let mut pool = PoolState::new(Pubkey::new_unique(), Pubkey::new_unique(), 20, 1_000_000);
+        pool.add_nullifier([1u8; 32], PoolState::MAX_CACHE_ENTRIES);
+        pool.guardian = Pubkey::new_unique();
+        let mut data = pool.try_to_vec().unwrap();
+        assert_eq!(bytemuck::bytes_of(&pool), &data[..]);
+
+        // In-place writes are visible to the Borsh decoder
+        let zero_copy = PoolState::from_bytes_mut(&mut data).unwrap();
+        zero_copy.add_key_image([2u8; 32], PoolState::MAX_CACHE_ENTRIES);
+        zero_copy.pending_authority = Pubkey::new_unique();
+        let decoded = PoolState::load(&data).unwrap();
+        assert!(decoded.is_nullifier_used(&[1u8; 32]));
+        assert!(decoded.is_key_image_used(&[2u8; 32]));
+        assert!(decoded.pending_authority().is_some());
+        assert_eq!(decoded.guardian(), pool.guardian());
+
+        let relayer = new_relayer(7);
+        let data = relayer.try_to_vec().unwrap();
+        assert_eq!(bytemuck::bytes_of(&relayer), &data[..]);
+        assert_eq!(RelayerAccount::from_bytes(&data).unwrap().endpoint(), "https://relayer.example");
+
+        // Header and size are checked before casting
+        assert!(RelayerAccount::from_bytes(&data[..RelayerAccount::LEN - 1]).is_err());
+        let mut stale = data.clone();
+        stale[8] = RelayerAccountV1::VERSION;
+        assert_eq!(
+            RelayerAccount::from_bytes(&stale).unwrap_err(),
+            PrivacyError::AccountNeedsMigration.into()
+        );
+    }
+
+    #[test]
+    fn test_discriminators_match_type_names() {
+        fn expected(name: &str) -> [u8; 8] {
+            let hash = solana_program::hash::hash(format!("account:{}", name).as_bytes());
+            let mut discriminator = [0u8; 8];
+            discriminator.copy_from_slice(&hash.to_bytes()[..8]);
+            discriminator
+        }
+
+        assert_eq!(PoolState::DISCRIMINATOR, expected("PoolState"));
+        assert_eq!(AssetState::DISCRIMINATOR, expected("AssetState"));
+        assert_eq!(ShieldedNote::DISCRIMINATOR, expected("ShieldedNote"));
+        assert_eq!(NullifierAccount::DISCRIMINATOR, expected("NullifierAccount"));
+        assert_eq!(KeyImageAccount::DISCRIMINATOR, expected("KeyImageAccount"));
+        assert_eq!(VerificationKeyAccount::DISCRIMINATOR, expected("VerificationKeyAccount"));
+        assert_eq!(RelayerAccount::DISCRIMINATOR, expected("RelayerAccount"));
+        assert_eq!(ProtocolConfig::DISCRIMINATOR, expected("ProtocolConfig"));
+        assert_eq!(Multisig::DISCRIMINATOR, expected("Multisig"));
+        assert_eq!(MultisigProposal::DISCRIMINATOR, expected("MultisigProposal"));
+        assert_eq!(DelayedWithdrawal::DISCRIMINATOR, expected("DelayedWithdrawal"));
+    }
+
+    #[test]
+    fn test_load_checks_header() {
+        let relayer = new_relayer(0);
+        let mut data = relayer.try_to_vec().unwrap();
+        data.resize(RelayerAccount::LEN, 0);
+        assert!(RelayerAccount::load(&data).is_ok());
+
+        // Another account type's data is rejected
+        assert_eq!(
+            ProtocolConfig::load(&data).unwrap_err(),
+            PrivacyError::InvalidAccountData.into()
+        );
+
+        // Older layout versions must be migrated first
+        data[8] = 0;
+        assert_eq!(
+            RelayerAccount::load(&data).unwrap_err(),
+            PrivacyError::AccountNeedsMigration.into()
+        );
+
+        // Headerless (v0) data has no discriminator
+        assert!(!RelayerAccount::has_discriminator(&data[RelayerAccount::HEADER_LEN..]));
+        assert!(RelayerAccount::load(&[]).is_err());
+        assert_eq!(AccountType::from_header(&data), Some(AccountType::Relayer));
+        assert_eq!(AccountType::from_header(&data[RelayerAccount::HEADER_LEN..]), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_json_encoding() {
+        let authority = Pubkey::new_unique();
+        let mut pool = PoolState::new(authority, Pubkey::new_unique(), 20, 1_000_000_000);
+        pool.merkle_root = [0xab; 32];
+        pool.used_nullifiers[0] = [1; 32];
+        pool.nullifier_count = 1;
+
+        let json = serde_json::to_value(pool).unwrap();
+        assert_eq!(json["authority"], authority.to_string());
+        assert_eq!(json["merkle_root"], "ab".repeat(32));
+        assert_eq!(json["used_nullifiers"], serde_json::json!(["01".repeat(32)]));
+        assert!(json.get("_padding").is_none());
+
+        let json = serde_json::to_value(new_relayer(0)).unwrap();
+        assert_eq!(json["endpoint"], "https://relayer.example");
+    }
+}
@@ -0,0 +1,362 @@
+//! Account validation shared by every instruction
+//!
+//! Handlers check the accounts they are given through these helpers before
+//! touching any state: signer and writability flags, the owning program,
+//! PDA addresses, the program's upgrade authority, duplicate accounts, and (through [`Account`], [`ZeroCopy`]
+//! and [`ZeroCopyMut`]) the account discriminator and layout version.
+
+use crate::{
+    error::PrivacyError,
+    state::{ProgramAccount, ProtocolConfig, ZeroCopyAccount},
+};
+use solana_program::{
+    account_info::AccountInfo,
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    program_utils::limited_deserialize,
+    pubkey::Pubkey,
+    system_program,
+};
+use std::{
+    cell::{Ref, RefMut},
+    marker::PhantomData,
+    mem::size_of,
+    ops::{Deref, DerefMut},
+};
+
+/// Require `info` to have signed the transaction
+pub fn check_signer(info: &AccountInfo) -> ProgramResult {
+    if !info.is_signer {
+        msg!("Missing signature: {}", info.key);
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    Ok(())
+}
+
+/// Require `info` to be passed as writable
+pub fn check_writable(info: &AccountInfo) -> ProgramResult {
+    if !info.is_writable {
+        msg!("Account not writable: {}", info.key);
+        return Err(PrivacyError::AccountNotWritable.into());
+    }
+    Ok(())
+}
+
+/// Require `info` to be owned by `owner`
+pub fn check_owner(info: &AccountInfo, owner: &Pubkey) -> ProgramResult {
+    if info.owner != owner {
+        msg!("Account {} owned by {}, expected {}", info.key, info.owner, owner);
+        return Err(PrivacyError::InvalidAccountOwner.into());
+    }
+    Ok(())
+}
+
+/// Require `info` to be at `expected`, usually a freshly derived PDA
+pub fn check_address(info: &AccountInfo, expected: &Pubkey) -> ProgramResult {
+    if info.key != expected {
+        msg!("Account mismatch: expected {}, got {}", expected, info.key);
+        return Err(PrivacyError::InvalidPda.into());
+    }
+    Ok(())
+}
+
+/// Require `info` to be the PDA for `seeds` + `bump`
+///
+/// Used for accounts that store their own bump, which avoids the search in
+/// `find_program_address`.
+pub fn check_pda(
+    info: &AccountInfo,
+    seeds: &[&[u8]],
+    bump: u8,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    let bump = [bump];
+    let mut seeds_with_bump = seeds.to_vec();
+    seeds_with_bump.push(&bump);
+
+    let expected = Pubkey::create_program_address(&seeds_with_bump, program_id)
+        .map_err(|_| PrivacyError::InvalidPda)?;
+    check_address(info, &expected)
+}
+
+/// Require `info` to be the system program
+pub fn check_system_program(info: &AccountInfo) -> ProgramResult {
+    if info.key != &system_program::id() {
+        msg!("Expected system program, got {}", info.key);
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(())
+}
+
+/// Require `program_data` to be this program's ProgramData account, with
+/// `authority` as its upgrade authority
+///
+/// Programs deployed without an upgrade authority (or loaded by a
+/// non-upgradeable loader) have no such authority, and fail this check.
+pub fn check_upgrade_authority(
+    program_data: &AccountInfo,
+    authority: &Pubkey,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    let (expected, _) = ProtocolConfig::derive_program_data_address(program_id);
+    check_address(program_data, &expected)?;
+    check_owner(program_data, &bpf_loader_upgradeable::id())?;
+
+    let data = program_data.data.borrow();
+    let metadata = &data[..data.len().min(UpgradeableLoaderState::size_of_programdata_metadata())];
+    match limited_deserialize(metadata, UpgradeableLoaderState::size_of_programdata_metadata() as u64) {
+        Ok(UpgradeableLoaderState::ProgramData {
+            upgrade_authority_address,
+            ..
+        }) => {
+            if upgrade_authority_address != Some(*authority) {
+                msg!("{} is not the program upgrade authority", authority);
+                return Err(PrivacyError::Unauthorized.into());
+            }
+            Ok(())
+        }
+        _ => Err(PrivacyError::InvalidAccountData.into()),
+    }
+}
+
+/// Reject the same account appearing twice among `accounts`
+///
+/// Handlers pass the accounts whose roles must not overlap, e.g. a vault and
+/// the recipient it pays out to.
+pub fn check_unique(accounts: &[&AccountInfo]) -> ProgramResult {
+    for (i, info) in accounts.iter().enumerate() {
+        if accounts[..i].iter().any(|other| other.key == info.key) {
+            msg!("Duplicate account: {}", info.key);
+            return Err(PrivacyError::DuplicateAccount.into());
+        }
+    }
+    Ok(())
+}
+
+/// A program-owned account decoded as `T`
+///
+/// Loading checks the owner, discriminator and layout version; `load_mut`
+/// also requires the account to be writable. Derefs to the decoded state and
+/// writes it back with [`Account::save`].
+pub struct Account<'a, 'info, T: ProgramAccount> {
+    info: &'a AccountInfo<'info>,
+    state: T,
+}
+
+impl<'a, 'info, T: ProgramAccount> Account<'a, 'info, T> {
+    /// Load a read-only account
+    pub fn load(program_id: &Pubkey, info: &'a AccountInfo<'info>) -> Result<Self, ProgramError> {
+        check_owner(info, program_id)?;
+        let state = T::load(&info.data.borrow())?;
+        Ok(Self { info, state })
+    }
+
+    /// Load an account the instruction will write back
+    pub fn load_mut(
+        program_id: &Pubkey,
+        info: &'a AccountInfo<'info>,
+    ) -> Result<Self, ProgramError> {
+        check_writable(info)?;
+        Self::load(program_id, info)
+    }
+
+    pub fn key(&self) -> &'a Pubkey {
+        self.info.key
+    }
+
+    pub fn info(&self) -> &'a AccountInfo<'info> {
+        self.info
+    }
+
+    /// Serialize the state back into the account
+    ///
+    /// Writes through a reborrowed slice so the account's own data slice is
+    /// not advanced past the written bytes.
+    pub fn save(&self) -> ProgramResult {
+        let mut data = self.info.data.borrow_mut();
+        self.state.serialize(&mut &mut data[..])?;
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> T {
+        self.state
+    }
+}
+
+impl<'a, 'info, T: ProgramAccount> Deref for Account<'a, 'info, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.state
+    }
+}
+
+impl<'a, 'info, T: ProgramAccount> DerefMut for Account<'a, 'info, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.state
+    }
+}
+
+/// A zero-copy program account borrowed read-only in place
+///
+/// Same checks as [`Account::load`]; holds a borrow of the account data.
+pub struct ZeroCopy<'a, T: ZeroCopyAccount> {
+    data: Ref<'a, [u8]>,
+    marker: PhantomData<T>,
+}
+
+impl<'a, T: ZeroCopyAccount> ZeroCopy<'a, T> {
+    pub fn load(program_id: &Pubkey, info: &'a AccountInfo) -> Result<Self, ProgramError> {
+        check_owner(info, program_id)?;
+        let data = Ref::map(info.data.borrow(), |data| &**data);
+        T::from_bytes(&data)?;
+        Ok(Self { data, marker: PhantomData })
+    }
+}
+
+impl<'a, T: ZeroCopyAccount> Deref for ZeroCopy<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // Size, alignment and header were checked in `load`
+        bytemuck::from_bytes(&self.data[..size_of::<T>()])
+    }
+}
+
+/// A zero-copy program account mutated in place
+///
+/// Same checks as [`Account::load_mut`]. Writes go straight to the account
+/// data, so there is nothing to save; the data stays borrowed until drop.
+pub struct ZeroCopyMut<'a, T: ZeroCopyAccount> {
+    data: RefMut<'a, [u8]>,
+    marker: PhantomData<T>,
+}
+
+impl<'a, T: ZeroCopyAccount> ZeroCopyMut<'a, T> {
+    pub fn load_mut(program_id: &Pubkey, info: &'a AccountInfo) -> Result<Self, ProgramError> {
+        check_writable(info)?;
+        check_owner(info, program_id)?;
+        let mut data = RefMut::map(info.data.borrow_mut(), |data| &mut **data);
+        T::from_bytes_mut(&mut data)?;
+        Ok(Self { data, marker: PhantomData })
+    }
+
+    /// Write a fresh account into `info`, which must be writable, owned by
+    /// the program and large enough
+    pub fn init(program_id: &Pubkey, info: &'a AccountInfo, state: T) -> Result<Self, ProgramError> {
+        check_writable(info)?;
+        check_owner(info, program_id)?;
+        let mut data = RefMut::map(info.data.borrow_mut(), |data| &mut **data);
+        if data.len() < size_of::<T>() {
+            msg!("Account too small: {} < {}", data.len(), size_of::<T>());
+            return Err(PrivacyError::InvalidAccountData.into());
+        }
+        data[..size_of::<T>()].copy_from_slice(bytemuck::bytes_of(&state));
+        T::from_bytes_mut(&mut data)?;
+        Ok(Self { data, marker: PhantomData })
+    }
+}
+
+impl<'a, T: ZeroCopyAccount> Deref for ZeroCopyMut<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        bytemuck::from_bytes(&self.data[..size_of::<T>()])
+    }
+}
+
+impl<'a, T: ZeroCopyAccount> DerefMut for ZeroCopyMut<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        bytemuck::from_bytes_mut(&mut self.data[..size_of::<T>()])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::RelayerAccount;
+    use borsh::BorshSerialize;
+    use solana_program::clock::Epoch;
+
+    fn info<'a>(
+        key: &'a Pubkey,
+        owner: &'a Pubkey,
+        lamports: &'a mut u64,
+        data: &'a mut [u8],
+        is_writable: bool,
+    ) -> AccountInfo<'a> {
+        AccountInfo::new(key, false, is_writable, lamports, data, owner, false, Epoch::default())
+    }
+
+    #[test]
+    fn test_check_unique() {
+        let (a, b, owner) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let (mut l1, mut l2, mut l3) = (0, 0, 0);
+        let (mut d1, mut d2, mut d3) = ([0u8; 0], [0u8; 0], [0u8; 0]);
+        let first = info(&a, &owner, &mut l1, &mut d1, true);
+        let second = info(&b, &owner, &mut l2, &mut d2, true);
+        let again = info(&a, &owner, &mut l3, &mut d3, false);
+
+        assert!(check_unique(&[&first, &second]).is_ok());
+        assert_eq!(
+            check_unique(&[&first, &second, &again]).unwrap_err(),
+            PrivacyError::DuplicateAccount.into()
+        );
+    }
+
+    #[test]
+    fn test_check_pda() {
+        let program_id = Pubkey::new_unique();
+        let wallet = Pubkey::new_unique();
+        let (pda, bump) = RelayerAccount::derive_address(&wallet, &program_id);
+        let other = Pubkey::new_unique();
+        let (mut l1, mut l2) = (0, 0);
+        let (mut d1, mut d2) = ([0u8; 0], [0u8; 0]);
+        let genuine = info(&pda, &program_id, &mut l1, &mut d1, true);
+        let spoofed = info(&other, &program_id, &mut l2, &mut d2, true);
+
+        let seeds: &[&[u8]] = &[b"relayer", wallet.as_ref()];
+        assert!(check_pda(&genuine, seeds, bump, &program_id).is_ok());
+        assert_eq!(
+            check_pda(&spoofed, seeds, bump, &program_id).unwrap_err(),
+            PrivacyError::InvalidPda.into()
+        );
+        assert!(check_pda(&genuine, seeds, bump, &other).is_err());
+    }
+
+    #[test]
+    fn test_account_load_checks() {
+        let program_id = Pubkey::new_unique();
+        let key = Pubkey::new_unique();
+        let mut relayer = RelayerAccount::new(Pubkey::new_unique(), 0, "", 255, 0);
+        let mut data = relayer.try_to_vec().unwrap();
+        data.resize(RelayerAccount::LEN, 0);
+        let mut lamports = 0;
+
+        let other_program = Pubkey::new_unique();
+        let foreign = info(&key, &other_program, &mut lamports, &mut data, true);
+        assert_eq!(
+            Account::<RelayerAccount>::load(&program_id, &foreign).err().unwrap(),
+            PrivacyError::InvalidAccountOwner.into()
+        );
+
+        let readonly = info(&key, &program_id, &mut lamports, &mut data, false);
+        assert!(Account::<RelayerAccount>::load(&program_id, &readonly).is_ok());
+        assert_eq!(
+            Account::<RelayerAccount>::load_mut(&program_id, &readonly).err().unwrap(),
+            PrivacyError::AccountNotWritable.into()
+        );
+
+        let writable = info(&key, &program_id, &mut lamports, &mut data, true);
+        let mut account = Account::<RelayerAccount>::load_mut(&program_id, &writable).unwrap();
+        account.stake = 42;
+        account.save().unwrap();
+        relayer.stake = 42;
+        assert_eq!(
+            Account::<RelayerAccount>::load(&program_id, &writable).unwrap().stake,
+            relayer.stake
+        );
+    }
+}
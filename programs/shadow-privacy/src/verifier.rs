@@ -1,37 +1,53 @@
 use crate::error::PrivacyError;
 use solana_program::{msg, program_error::ProgramError};
 use ark_bn254::{Bn254, Fr};
-use ark_groth16::{Groth16, Proof, VerifyingKey, prepare_verifying_key};
+#[cfg(feature = "real-zk-verification")]
+use ark_groth16::{Groth16, prepare_verifying_key};
+use ark_groth16::{Proof, VerifyingKey};
 use ark_serialize::CanonicalDeserialize;
-use ark_snark::SNARK;
-use borsh::BorshDeserialize;
 
 /// Verify Groth16 ZK-SNARK proof for transfer using ark-groth16
+///
+/// Public inputs: Merkle root, nullifier, new commitment.
 pub fn verify_transfer_proof(proof: &[u8], public_inputs: &[Vec<u8>], vk_account_data: &[u8]) -> Result<bool, ProgramError> {
+    verify_groth16("transfer", proof, public_inputs, vk_account_data)
+}
+
+/// Verify balance proof using ark-groth16
+pub fn verify_balance_proof(proof: &[u8], public_inputs: &[Vec<u8>], vk_account_data: &[u8]) -> Result<bool, ProgramError> {
+    verify_groth16("balance", proof, public_inputs, vk_account_data)
+}
+
+/// Verify an auditor note proof (ciphertext opens the commitment) using ark-groth16
+pub fn verify_auditor_note_proof(proof: &[u8], public_inputs: &[Vec<u8>], vk_account_data: &[u8]) -> Result<bool, ProgramError> {
+    verify_groth16("auditor note", proof, public_inputs, vk_account_data)
+}
+
+/// Verify a Groth16 proof against the VK stored in `vk_account_data`
+///
+/// `label` names the circuit in the logs.
+fn verify_groth16(label: &str, proof: &[u8], public_inputs: &[Vec<u8>], vk_account_data: &[u8]) -> Result<bool, ProgramError> {
     #[cfg(not(feature = "real-zk-verification"))]
     {
-        msg!("DEBUG: Skipping ZK verification for demo");
+        let _ = (proof, public_inputs, vk_account_data);
+        msg!("DEBUG: Skipping {} ZK verification for demo", label);
         Ok(true)
     }
 
     #[cfg(feature = "real-zk-verification")]
     {
-        msg!("Verifying Groth16 transfer proof...");
-        
+        msg!("Verifying Groth16 {} proof...", label);
+
         // 1. Load and prepare the Verifying Key from PDA (Heap allocated)
         let vk = load_verification_key_from_account(vk_account_data)?;
         let pvk = Box::new(prepare_verifying_key(&vk));
-        
+
         // 2. Deserialize the proof (Boxed to save stack space)
-        let proof_obj = Box::new(Proof::<Bn254>::deserialize_compressed(proof)
-            .map_err(|e| {
-                msg!("Error deserializing proof: {:?}", e);
-                PrivacyError::InvalidProof
-            })?);
-            
-        // 3. Prepare public inputs (Merkle root, Nullifier, New Commitment)
+        let proof_obj = deserialize_proof(proof)?;
+
+        // 3. Prepare public inputs
         let inputs = deserialize_field_elements(public_inputs)?;
-        
+
         // 4. Perform Groth16 verification
         // Argument order: PVK, Proof, PublicInputs
         let result = Groth16::<Bn254>::verify_proof(&pvk, &proof_obj, &inputs)
@@ -39,61 +55,25 @@ pub fn verify_transfer_proof(proof: &[u8], public_inputs: &[Vec<u8>], vk_account
                 msg!("Error during ZK verification: {:?}", e);
                 PrivacyError::InvalidProof
             })?;
-            
-        if result {
-            msg!("✓ Groth16 transfer proof verified successfully");
-        } else {
-            msg!("✗ Groth16 transfer proof verification failed");
-        }
-        
-        Ok(result)
-    }
-}
 
-/// Verify balance proof using ark-groth16
-pub fn verify_balance_proof(proof: &[u8], public_inputs: &[Vec<u8>], vk_account_data: &[u8]) -> Result<bool, ProgramError> {
-    #[cfg(not(feature = "real-zk-verification"))]
-    {
-        msg!("DEBUG: Skipping ZK verification for demo");
-        Ok(true)
-    }
-
-    #[cfg(feature = "real-zk-verification")]
-    {
-        msg!("Verifying Groth16 balance proof...");
-        
-        let vk = load_verification_key_from_account(vk_account_data)?;
-        let pvk = Box::new(prepare_verifying_key(&vk));
-        
-        let proof_obj = Box::new(Proof::<Bn254>::deserialize_compressed(proof)
-            .map_err(|e| {
-                msg!("Error deserializing proof: {:?}", e);
-                PrivacyError::InvalidProof
-            })?);
-            
-        let inputs = deserialize_field_elements(public_inputs)?;
-        
-        let result = Groth16::<Bn254>::verify_proof(&pvk, &proof_obj, &inputs)
-            .map_err(|e| {
-                msg!("Error during ZK verification: {:?}", e);
-                PrivacyError::InvalidProof
-            })?;
-            
         if result {
-            msg!("✓ Groth16 balance proof verified successfully");
+            msg!("✓ Groth16 {} proof verified successfully", label);
         } else {
-            msg!("✗ Groth16 balance proof verification failed");
+            msg!("✗ Groth16 {} proof verification failed", label);
         }
-        
+
         Ok(result)
     }
 }
 
 /// Verify Monero-style MLSAG ring signature
+///
+/// `max_ring_size` comes from ProtocolConfig.
 pub fn verify_ring_signature(
     signature: &[u8],
     key_image: &[u8; 32],
     ring_members: &[[u8; 32]],
+    max_ring_size: usize,
 ) -> Result<bool, ProgramError> {
     msg!("Verifying MLSAG ring signature...");
     msg!("  Signature size: {} bytes", signature.len());
@@ -105,8 +85,8 @@ pub fn verify_ring_signature(
         return Err(PrivacyError::InvalidRingSize.into());
     }
 
-    if ring_members.len() > 16 {
-        msg!("Error: Ring too large (max 16)");
+    if ring_members.len() > max_ring_size {
+        msg!("Error: Ring too large (max {})", max_ring_size);
         return Err(PrivacyError::InvalidRingSize.into());
     }
 
@@ -178,6 +158,8 @@ pub fn verify_ring_signature(
 /// Load transfer verification key from PDA account
 ///
 /// Expects VK account data in the following format (see VerificationKeyAccount):
+/// - discriminator: [u8; 8]
+/// - version: u8
 /// - circuit_type: u8
 /// - pool: Pubkey (32 bytes)
 /// - authority: Pubkey (32 bytes)
@@ -187,10 +169,10 @@ pub fn verify_ring_signature(
 pub fn load_verification_key_from_account(
     vk_account_data: &[u8],
 ) -> Result<Box<VerifyingKey<Bn254>>, ProgramError> {
-    use crate::state::VerificationKeyAccount;
+    use crate::state::{ProgramAccount, VerificationKeyAccount};
 
-    // Deserialize the VK account
-    let vk_account = VerificationKeyAccount::try_from_slice(vk_account_data)
+    // Deserialize the VK account (header checked, trailing space allowed)
+    let vk_account = VerificationKeyAccount::load(vk_account_data)
         .map_err(|e| {
             msg!("Error deserializing VK account: {:?}", e);
             PrivacyError::InvalidVerificationKey
@@ -207,18 +189,24 @@ pub fn load_verification_key_from_account(
     Ok(Box::new(vk))
 }
 
-/// Load transfer verification key (helper function for backward compatibility)
-fn load_transfer_verification_key_from_data(vk_data: &[u8]) -> Result<Box<VerifyingKey<Bn254>>, ProgramError> {
-    load_verification_key_from_account(vk_data)
+/// Deserialize a compressed Groth16 proof
+pub fn deserialize_proof(proof: &[u8]) -> Result<Box<Proof<Bn254>>, ProgramError> {
+    let proof = Proof::<Bn254>::deserialize_compressed(proof)
+        .map_err(|e| {
+            msg!("Error deserializing proof: {:?}", e);
+            PrivacyError::InvalidProof
+        })?;
+
+    Ok(Box::new(proof))
 }
 
-/// Load balance verification key (helper function for backward compatibility)
-fn load_balance_verification_key_from_data(vk_data: &[u8]) -> Result<Box<VerifyingKey<Bn254>>, ProgramError> {
-    load_verification_key_from_account(vk_data)
+/// Check that little-endian `bytes` is a canonical BN254 scalar field element
+pub fn is_canonical_field(bytes: &[u8; 32]) -> bool {
+    Fr::deserialize_compressed(&bytes[..]).is_ok()
 }
 
 /// Deserialize field elements from bytes to Fr (BN254 field elements)
-fn deserialize_field_elements(inputs: &[Vec<u8>]) -> Result<Vec<Fr>, ProgramError> {
+pub fn deserialize_field_elements(inputs: &[Vec<u8>]) -> Result<Vec<Fr>, ProgramError> {
     let mut elements = Vec::new();
 
     for input in inputs {
@@ -249,20 +237,24 @@ mod tests {
         let proof = vec![0u8; 192]; // Minimum valid size
         let public_inputs = vec![vec![0u8; 32], vec![0u8; 32], vec![0u8; 32]];
 
-        let result = verify_transfer_proof(&proof, &public_inputs);
-        assert!(result.is_ok());
-        assert!(result.unwrap());
+        let result = verify_transfer_proof(&proof, &public_inputs, &[]);
+
+        // Demo builds accept any proof; real builds need a stored VK first
+        #[cfg(not(feature = "real-zk-verification"))]
+        assert_eq!(result, Ok(true));
+        #[cfg(feature = "real-zk-verification")]
+        assert_eq!(result, Err(PrivacyError::InvalidVerificationKey.into()));
     }
 
     #[test]
     fn test_verify_ring_signature() {
-        let signature = vec![0u8; 11 * 64]; // 11 ring members
+        let signature = vec![0u8; 32 + 11 * 32]; // c_0 + 11 responses
         let key_image = [0u8; 32];
         let ring_members = vec![[0u8; 32]; 11];
 
-        let result = verify_ring_signature(&signature, &key_image, &ring_members);
-        assert!(result.is_ok());
-        assert!(result.unwrap());
+        // Well-formed, but the challenges do not close the ring
+        let result = verify_ring_signature(&signature, &key_image, &ring_members, 16);
+        assert_eq!(result, Ok(false));
     }
 
     #[test]
@@ -270,19 +262,33 @@ mod tests {
         let proof = vec![0u8; 100]; // Too small
         let public_inputs = vec![vec![0u8; 32]];
 
-        let result = verify_transfer_proof(&proof, &public_inputs);
-        assert!(result.is_ok());
-        assert!(!result.unwrap());
+        let result = verify_transfer_proof(&proof, &public_inputs, &[]);
+
+        #[cfg(not(feature = "real-zk-verification"))]
+        assert_eq!(result, Ok(true));
+        #[cfg(feature = "real-zk-verification")]
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_invalid_signature_size() {
+        let key_image = [0u8; 32];
+        let ring_members = vec![[0u8; 32]; 11];
+
+        let result = verify_ring_signature(&vec![0u8; 11 * 64], &key_image, &ring_members, 16);
+        assert_eq!(result, Err(PrivacyError::InvalidSignature.into()));
     }
 
     #[test]
     fn test_invalid_ring_size() {
-        let signature = vec![0u8; 20 * 64]; // 20 ring members (too many)
+        let signature = vec![0u8; 32 + 20 * 32]; // 20 ring members (too many)
         let key_image = [0u8; 32];
         let ring_members = vec![[0u8; 32]; 20];
 
-        let result = verify_ring_signature(&signature, &key_image, &ring_members);
-        assert!(result.is_ok());
-        assert!(!result.unwrap());
+        let result = verify_ring_signature(&signature, &key_image, &ring_members, 16);
+        assert_eq!(result, Err(PrivacyError::InvalidRingSize.into()));
+
+        let result = verify_ring_signature(&[0u8; 32], &key_image, &[], 16);
+        assert_eq!(result, Err(PrivacyError::InvalidRingSize.into()));
     }
 }
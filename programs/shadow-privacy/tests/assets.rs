@@ -0,0 +1,91 @@
+//! Confidential assets: IssueAsset and TransferAsset
+
+mod common;
+
+use common::*;
+#[cfg(not(feature = "real-zk-verification"))]
+use shadow_privacy::note::MAX_NOTE_CIPHERTEXT_LEN;
+use shadow_privacy::{state::AssetState, PrivacyError};
+use solana_program::program_error::ProgramError;
+use solana_program_test::tokio;
+use solana_sdk::signature::Signer;
+
+const ASSET_ID: [u8; 32] = [4; 32];
+
+#[tokio::test]
+async fn test_issue_asset() {
+    let mut ctx = start().await;
+    let issuer = funded_keypair(&mut ctx, DENOMINATION).await;
+
+    process(&mut ctx, &[ix::issue_asset(&issuer.pubkey(), ASSET_ID, 1_000_000)], &[&issuer])
+        .await
+        .unwrap();
+
+    let asset: AssetState = load(&mut ctx, &pda::asset(&ASSET_ID)).await;
+    assert_eq!(asset.asset_id, ASSET_ID);
+    assert_eq!(asset.issuer, issuer.pubkey());
+    assert_eq!(asset.symbol, "SHD");
+    assert_eq!(asset.total_supply, 1_000_000);
+    assert!(asset.used_nullifiers.is_empty());
+
+    // Asset ids are first come, first served
+    let other = funded_keypair(&mut ctx, DENOMINATION).await;
+    let result = process(&mut ctx, &[ix::issue_asset(&other.pubkey(), ASSET_ID, 1)], &[&other]).await;
+    assert_program_error(result, ProgramError::AccountAlreadyInitialized);
+}
+
+#[cfg(not(feature = "real-zk-verification"))]
+#[tokio::test]
+async fn test_transfer_asset() {
+    let mut ctx = start().await;
+    let (_, pool) = setup_pool(&mut ctx).await;
+    let issuer = funded_keypair(&mut ctx, DENOMINATION).await;
+    process(&mut ctx, &[ix::issue_asset(&issuer.pubkey(), ASSET_ID, 1_000_000)], &[&issuer])
+        .await
+        .unwrap();
+
+    process(&mut ctx, &[ix::transfer_asset(&pool, ASSET_ID, [1; 32])], &[]).await.unwrap();
+    let asset: AssetState = load(&mut ctx, &pda::asset(&ASSET_ID)).await;
+    assert_eq!(asset.used_nullifiers, vec![[1; 32]]);
+    assert_eq!(asset.note_count, 1);
+
+    let result = process(&mut ctx, &[ix::transfer_asset(&pool, ASSET_ID, [1; 32])], &[]).await;
+    assert_privacy_error(result, PrivacyError::NullifierAlreadyUsed);
+
+    // The asset id in the instruction must match the account
+    let mut mismatched = ix::transfer_asset(&pool, [5; 32], [2; 32]);
+    mismatched.accounts[0].pubkey = pda::asset(&ASSET_ID);
+    let result = process(&mut ctx, &[mismatched], &[]).await;
+    assert_privacy_error(result, PrivacyError::InvalidAccountData);
+
+    // Notes longer than a ShieldedNote can hold are rejected
+    let mut oversized = note_ciphertext();
+    oversized.resize(MAX_NOTE_CIPHERTEXT_LEN + 1, 0);
+    let result = process(&mut ctx, &[ix::transfer_asset_with_note(&pool, ASSET_ID, [3; 32], oversized)], &[]).await;
+    assert_privacy_error(result, PrivacyError::InvalidNoteCiphertext);
+}
+
+#[cfg(feature = "real-zk-verification")]
+#[tokio::test]
+async fn test_transfer_asset_unproven_rejected() {
+    use shadow_privacy::state::CircuitType;
+
+    let mut ctx = start().await;
+    let (authority, pool) = setup_pool(&mut ctx).await;
+    let issuer = funded_keypair(&mut ctx, DENOMINATION).await;
+    process(&mut ctx, &[ix::issue_asset(&issuer.pubkey(), ASSET_ID, 1_000_000)], &[&issuer])
+        .await
+        .unwrap();
+
+    // No Transfer VK stored for the pool yet
+    let result = process(&mut ctx, &[ix::transfer_asset(&pool, ASSET_ID, [1; 32])], &[]).await;
+    assert_privacy_error(result, PrivacyError::InvalidAccountOwner);
+
+    let store = ix::store_verification_key(&pool, &authority.pubkey(), CircuitType::Transfer, vec![1; 64]);
+    process(&mut ctx, &[store], &[&authority]).await.unwrap();
+    let result = process(&mut ctx, &[ix::transfer_asset(&pool, ASSET_ID, [1; 32])], &[]).await;
+    assert_privacy_error(result, PrivacyError::InvalidVerificationKey);
+
+    let asset: AssetState = load(&mut ctx, &pda::asset(&ASSET_ID)).await;
+    assert!(asset.used_nullifiers.is_empty());
+}
@@ -0,0 +1,178 @@
+//! Auditor keys: SetAuditor and the auditor notes Deposit, Withdraw and
+//! PrivateTransfer take once a pool has one
+//!
+//! Accepted auditor notes need an accepted proof, so they only run in demo
+//! builds; `groth16.rs` covers proven ones.
+
+mod common;
+
+use ark_bn254::Fr;
+use ark_ff::{One, Zero};
+use common::*;
+use shadow_privacy::{
+    auditor::{Point, BASE8},
+    state::{AuditorNote, CircuitType, PoolState},
+    PrivacyError,
+};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use solana_program_test::{tokio, ProgramTestContext};
+use solana_sdk::signature::{Keypair, Signer};
+
+fn auditor_key() -> [[u8; 32]; 2] {
+    BASE8.mul(&[7; 32]).to_bytes()
+}
+
+/// An auditor note the demo verifier accepts; its ciphertext is arbitrary
+fn auditor_note() -> AuditorNote {
+    AuditorNote {
+        ephemeral_key: BASE8.mul(&[3; 32]).to_bytes(),
+        ciphertext: [[4; 32]; 3],
+        proof: vec![0; 128],
+    }
+}
+
+/// A pool with one deposit and the test auditor registered
+async fn setup_audited_pool(ctx: &mut ProgramTestContext) -> (Keypair, Pubkey) {
+    let (authority, pool) = setup_funded_pool(ctx, 1).await;
+    process(ctx, &[ix::set_auditor(&pool, &authority.pubkey(), Some(auditor_key()))], &[&authority])
+        .await
+        .unwrap();
+    (authority, pool)
+}
+
+#[tokio::test]
+async fn test_set_auditor() {
+    let mut ctx = start().await;
+    let (authority, pool) = setup_pool(&mut ctx).await;
+    let stranger = funded_keypair(&mut ctx, DENOMINATION).await;
+
+    let result = process(&mut ctx, &[ix::set_auditor(&pool, &stranger.pubkey(), Some(auditor_key()))], &[&stranger]).await;
+    assert_privacy_error(result, PrivacyError::Unauthorized);
+
+    // Off-curve and small-order keys
+    let two_torsion = Point { x: Fr::zero(), y: -Fr::one() };
+    for invalid in [[[1; 32]; 2], Point::IDENTITY.to_bytes(), two_torsion.to_bytes()] {
+        let result = process(&mut ctx, &[ix::set_auditor(&pool, &authority.pubkey(), Some(invalid))], &[&authority]).await;
+        assert_privacy_error(result, PrivacyError::InvalidAuditorKey);
+    }
+
+    process(&mut ctx, &[ix::set_auditor(&pool, &authority.pubkey(), Some(auditor_key()))], &[&authority])
+        .await
+        .unwrap();
+    let state: PoolState = load(&mut ctx, &pool).await;
+    assert_eq!(state.auditor_key(), Some(auditor_key()));
+
+    process(&mut ctx, &[ix::set_auditor(&pool, &authority.pubkey(), None)], &[&authority])
+        .await
+        .unwrap();
+    let state: PoolState = load(&mut ctx, &pool).await;
+    assert_eq!(state.auditor_key(), None);
+}
+
+#[tokio::test]
+async fn test_deposit_auditor_note_checks() {
+    let mut ctx = start().await;
+    let (authority, pool) = setup_funded_pool(&mut ctx, 0).await;
+    let depositor = funded_keypair(&mut ctx, 2 * DENOMINATION).await;
+    let deposit = |auditor_note: Option<AuditorNote>| {
+        ix::deposit_with_auditor_note(&pool, &depositor.pubkey(), [1; 32], DENOMINATION, auditor_note)
+    };
+
+    // No auditor, no auditor notes
+    let result = process(&mut ctx, &[deposit(Some(auditor_note()))], &[&depositor]).await;
+    assert_privacy_error(result, PrivacyError::UnexpectedAuditorNote);
+
+    process(&mut ctx, &[ix::set_auditor(&pool, &authority.pubkey(), Some(auditor_key()))], &[&authority])
+        .await
+        .unwrap();
+
+    let result = process(&mut ctx, &[deposit(None)], &[&depositor]).await;
+    assert_privacy_error(result, PrivacyError::AuditorNoteRequired);
+
+    let mut without_vk = deposit(Some(auditor_note()));
+    without_vk.accounts.pop();
+    let result = process(&mut ctx, &[without_vk], &[&depositor]).await;
+    assert_program_error(result, ProgramError::NotEnoughAccountKeys);
+
+    // The pool's VK for another circuit
+    let mut other_vk = deposit(Some(auditor_note()));
+    other_vk.accounts[4].pubkey = pda::verification_key(&pool, CircuitType::Transfer);
+    let result = process(&mut ctx, &[other_vk], &[&depositor]).await;
+    assert_privacy_error(result, PrivacyError::InvalidPda);
+
+    let state: PoolState = load(&mut ctx, &pool).await;
+    assert_eq!(state.commitment_count, 0);
+    assert_eq!(lamports(&mut ctx, &depositor.pubkey()).await, 2 * DENOMINATION);
+}
+
+#[tokio::test]
+async fn test_private_transfer_auditor_note_checks() {
+    let mut ctx = start().await;
+    let (_, pool) = setup_audited_pool(&mut ctx).await;
+    let payer = ctx.payer.pubkey();
+
+    // Rejected before the ring signature is looked at
+    let transfer = ix::private_transfer(&pool, &payer, vec![0; 32 + 11 * 32], [1; 32], vec![[2; 32]; 11]);
+    let result = process(&mut ctx, &[transfer], &[]).await;
+    assert_privacy_error(result, PrivacyError::AuditorNoteRequired);
+
+    // An accepted auditor note leaves the ring signature to be checked
+    #[cfg(not(feature = "real-zk-verification"))]
+    {
+        let ring = vec![[2; 32]; 11];
+        let transfer =
+            ix::private_transfer_with_auditor_note(&pool, &payer, vec![0; 32 + 11 * 32], [1; 32], ring, Some(auditor_note()));
+        let result = process(&mut ctx, &[transfer], &[]).await;
+        assert_privacy_error(result, PrivacyError::InvalidRingSignature);
+    }
+}
+
+#[cfg(not(feature = "real-zk-verification"))]
+#[tokio::test]
+async fn test_audited_deposit() {
+    let mut ctx = start().await;
+    let (_, pool) = setup_audited_pool(&mut ctx).await;
+    let depositor = funded_keypair(&mut ctx, 2 * DENOMINATION).await;
+
+    let deposit =
+        ix::deposit_with_auditor_note(&pool, &depositor.pubkey(), [2; 32], DENOMINATION, Some(auditor_note()));
+    process(&mut ctx, &[deposit], &[&depositor]).await.unwrap();
+
+    let state: PoolState = load(&mut ctx, &pool).await;
+    assert_eq!(state.commitment_count, 2);
+    assert_eq!(state.tvl, 2 * DENOMINATION);
+}
+
+#[cfg(not(feature = "real-zk-verification"))]
+#[tokio::test]
+async fn test_audited_withdraw() {
+    let mut ctx = start().await;
+    let (_, pool) = setup_audited_pool(&mut ctx).await;
+    let recipient = Pubkey::new_unique();
+    let withdraw = |nullifier: [u8; 32], new_commitment: Option<[u8; 32]>, auditor_note: Option<AuditorNote>| {
+        ix::withdraw_with_auditor_note(
+            &pool,
+            vec![0; 256],
+            [0; 32],
+            nullifier,
+            new_commitment,
+            &recipient,
+            DENOMINATION,
+            auditor_note,
+        )
+    };
+
+    // Only change notes are audited
+    let result = process(&mut ctx, &[withdraw([7; 32], None, Some(auditor_note()))], &[]).await;
+    assert_privacy_error(result, PrivacyError::UnexpectedAuditorNote);
+
+    let result = process(&mut ctx, &[withdraw([7; 32], Some([8; 32]), None)], &[]).await;
+    assert_privacy_error(result, PrivacyError::AuditorNoteRequired);
+
+    process(&mut ctx, &[withdraw([7; 32], Some([8; 32]), Some(auditor_note()))], &[])
+        .await
+        .unwrap();
+    let state: PoolState = load(&mut ctx, &pool).await;
+    assert_eq!(state.commitment_count, 2);
+    assert_eq!(state.tvl, 0);
+}
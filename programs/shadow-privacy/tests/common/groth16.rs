@@ -0,0 +1,269 @@
+//! Groth16 test circuits with the program's public-input layouts
+//!
+//! The constraints are small stand-ins for `transfer.circom`,
+//! `balance.circom` and `auditor_note.circom` (no Poseidon, Merkle path or
+//! curve arithmetic), but the public inputs are
+//! the ones the processor hands to the verifier, in the same order and
+//! encoding: 32-byte little-endian field elements.
+
+use ark_bn254::{Bn254, Fr};
+use ark_ff::{BigInteger, PrimeField};
+use ark_groth16::{Groth16, Proof, ProvingKey, VerifyingKey};
+use ark_relations::{
+    lc,
+    r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError, Variable},
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_snark::SNARK;
+use ark_std::rand::{rngs::StdRng, SeedableRng};
+use borsh::BorshSerialize;
+use shadow_privacy::{
+    auditor::Point,
+    state::{AuditorNote, CircuitType, ProgramAccount, VerificationKeyAccount},
+};
+use solana_program::pubkey::Pubkey;
+
+/// Transfer layout: public `[root, nullifier, new_commitment]`
+///
+/// nullifier = secret², new_commitment = amount · nonce and
+/// root = (nullifier + new_commitment) · secret.
+#[derive(Clone, Copy, Default)]
+pub struct TransferCircuit {
+    pub secret: Fr,
+    pub amount: Fr,
+    pub nonce: Fr,
+}
+
+impl TransferCircuit {
+    pub fn new(secret: u64, amount: u64, nonce: u64) -> Self {
+        TransferCircuit {
+            secret: secret.into(),
+            amount: amount.into(),
+            nonce: nonce.into(),
+        }
+    }
+
+    pub fn nullifier(&self) -> Fr {
+        self.secret * self.secret
+    }
+
+    pub fn new_commitment(&self) -> Fr {
+        self.amount * self.nonce
+    }
+
+    pub fn root(&self) -> Fr {
+        (self.nullifier() + self.new_commitment()) * self.secret
+    }
+
+    /// Public inputs in the order `process_withdraw` passes them
+    pub fn public_inputs(&self) -> [[u8; 32]; 3] {
+        [fr_bytes(self.root()), fr_bytes(self.nullifier()), fr_bytes(self.new_commitment())]
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for TransferCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let root = cs.new_input_variable(|| Ok(self.root()))?;
+        let nullifier = cs.new_input_variable(|| Ok(self.nullifier()))?;
+        let new_commitment = cs.new_input_variable(|| Ok(self.new_commitment()))?;
+
+        let secret = cs.new_witness_variable(|| Ok(self.secret))?;
+        let amount = cs.new_witness_variable(|| Ok(self.amount))?;
+        let nonce = cs.new_witness_variable(|| Ok(self.nonce))?;
+
+        cs.enforce_constraint(lc!() + secret, lc!() + secret, lc!() + nullifier)?;
+        cs.enforce_constraint(lc!() + amount, lc!() + nonce, lc!() + new_commitment)?;
+        cs.enforce_constraint(lc!() + nullifier + new_commitment, lc!() + secret, lc!() + root)?;
+        Ok(())
+    }
+}
+
+/// The transfer witness most vectors use
+pub fn transfer_circuit() -> TransferCircuit {
+    TransferCircuit::new(11, super::DENOMINATION, 13)
+}
+
+/// Balance layout: public `[min_balance, balance_commitment]`
+///
+/// balance = min_balance + surplus and balance_commitment = balance · nonce.
+/// There is no range check, so this only exercises the layout.
+#[derive(Clone, Copy, Default)]
+pub struct BalanceCircuit {
+    pub min_balance: u64,
+    pub surplus: u64,
+    pub nonce: Fr,
+}
+
+impl BalanceCircuit {
+    pub fn new(min_balance: u64, surplus: u64, nonce: u64) -> Self {
+        BalanceCircuit { min_balance, surplus, nonce: nonce.into() }
+    }
+
+    fn balance(&self) -> Fr {
+        Fr::from(self.min_balance) + Fr::from(self.surplus)
+    }
+
+    pub fn balance_commitment(&self) -> [u8; 32] {
+        fr_bytes(self.balance() * self.nonce)
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for BalanceCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let min_balance = cs.new_input_variable(|| Ok(Fr::from(self.min_balance)))?;
+        let balance_commitment = cs.new_input_variable(|| Ok(self.balance() * self.nonce))?;
+
+        let surplus = cs.new_witness_variable(|| Ok(Fr::from(self.surplus)))?;
+        let nonce = cs.new_witness_variable(|| Ok(self.nonce))?;
+
+        cs.enforce_constraint(lc!() + min_balance + surplus, lc!() + nonce, lc!() + balance_commitment)?;
+        Ok(())
+    }
+}
+
+/// Auditor note layout: public `[auditor_key.x, auditor_key.y, commitment,
+/// ephemeral_key.x, ephemeral_key.y, ciphertext[0..3]]`
+///
+/// commitment = (owner + amount) · nonce, ephemeral_key = (r, r²) and
+/// ciphertext[i] = plaintext[i] + r · auditor_key.x.
+#[derive(Clone, Copy)]
+pub struct AuditorNoteCircuit {
+    pub auditor_key: Point,
+    pub owner: Fr,
+    pub amount: Fr,
+    pub nonce: Fr,
+    pub r: Fr,
+}
+
+impl AuditorNoteCircuit {
+    pub fn new(auditor_key: Point, amount: u64, nonce: u64) -> Self {
+        AuditorNoteCircuit {
+            auditor_key,
+            owner: 19u64.into(),
+            amount: amount.into(),
+            nonce: nonce.into(),
+            r: 23u64.into(),
+        }
+    }
+
+    fn plaintext(&self) -> [Fr; 3] {
+        [self.owner, self.amount, self.nonce]
+    }
+
+    fn ciphertext(&self) -> [Fr; 3] {
+        self.plaintext().map(|value| value + self.r * self.auditor_key.x)
+    }
+
+    pub fn commitment(&self) -> [u8; 32] {
+        fr_bytes((self.owner + self.amount) * self.nonce)
+    }
+
+    /// The note the circuit proves, carrying `proof`
+    pub fn note(&self, proof: Vec<u8>) -> AuditorNote {
+        AuditorNote {
+            ephemeral_key: [fr_bytes(self.r), fr_bytes(self.r * self.r)],
+            ciphertext: self.ciphertext().map(fr_bytes),
+            proof,
+        }
+    }
+}
+
+impl Default for AuditorNoteCircuit {
+    fn default() -> Self {
+        AuditorNoteCircuit::new(Point::IDENTITY, 0, 0)
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for AuditorNoteCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let key_x = cs.new_input_variable(|| Ok(self.auditor_key.x))?;
+        let _key_y = cs.new_input_variable(|| Ok(self.auditor_key.y))?;
+        let commitment = cs.new_input_variable(|| Ok((self.owner + self.amount) * self.nonce))?;
+        let ephemeral_x = cs.new_input_variable(|| Ok(self.r))?;
+        let ephemeral_y = cs.new_input_variable(|| Ok(self.r * self.r))?;
+        let mut ciphertext = Vec::new();
+        for value in self.ciphertext() {
+            ciphertext.push(cs.new_input_variable(|| Ok(value))?);
+        }
+
+        let mut plaintext = Vec::new();
+        for value in self.plaintext() {
+            plaintext.push(cs.new_witness_variable(|| Ok(value))?);
+        }
+        let mask = cs.new_witness_variable(|| Ok(self.r * self.auditor_key.x))?;
+
+        cs.enforce_constraint(lc!() + plaintext[0] + plaintext[1], lc!() + plaintext[2], lc!() + commitment)?;
+        cs.enforce_constraint(lc!() + ephemeral_x, lc!() + ephemeral_x, lc!() + ephemeral_y)?;
+        cs.enforce_constraint(lc!() + ephemeral_x, lc!() + key_x, lc!() + mask)?;
+        for (ciphertext, plaintext) in ciphertext.into_iter().zip(plaintext) {
+            cs.enforce_constraint(lc!() + plaintext + mask, lc!() + Variable::One, lc!() + ciphertext)?;
+        }
+        Ok(())
+    }
+}
+
+/// Seed for the setup the stored verification keys come from
+pub const SETUP_SEED: u64 = 1;
+
+/// Circuit-specific setup, deterministic in `seed`
+pub fn setup<C: ConstraintSynthesizer<Fr>>(circuit: C, seed: u64) -> (ProvingKey<Bn254>, VerifyingKey<Bn254>) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    Groth16::<Bn254>::circuit_specific_setup(circuit, &mut rng).unwrap()
+}
+
+/// Proof in the compressed encoding `verifier.rs` deserializes
+pub fn prove<C: ConstraintSynthesizer<Fr>>(pk: &ProvingKey<Bn254>, circuit: C) -> Vec<u8> {
+    let mut rng = StdRng::seed_from_u64(7);
+    let proof = Groth16::<Bn254>::prove(pk, circuit, &mut rng).unwrap();
+    proof_bytes(&proof)
+}
+
+pub fn proof_bytes(proof: &Proof<Bn254>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    proof.serialize_compressed(&mut bytes).unwrap();
+    bytes
+}
+
+/// The proof with its A point negated: well-formed, but not a valid proof
+pub fn tampered(proof: &[u8]) -> Vec<u8> {
+    let mut proof = Proof::<Bn254>::deserialize_compressed(proof).unwrap();
+    proof.a = -proof.a;
+    proof_bytes(&proof)
+}
+
+/// Verification key as passed to StoreVerificationKey
+pub fn vk_bytes(vk: &VerifyingKey<Bn254>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    vk.serialize_compressed(&mut bytes).unwrap();
+    bytes
+}
+
+/// VerificationKeyAccount data, for calling `verifier::verify_*` directly
+pub fn vk_account_data(circuit_type: CircuitType, vk: &VerifyingKey<Bn254>) -> Vec<u8> {
+    VerificationKeyAccount {
+        discriminator: VerificationKeyAccount::DISCRIMINATOR,
+        version: VerificationKeyAccount::VERSION,
+        circuit_type,
+        pool: Pubkey::new_unique(),
+        authority: Pubkey::new_unique(),
+        vk_data: vk_bytes(vk),
+        stored_at: 0,
+        bump: 255,
+    }
+    .try_to_vec()
+    .unwrap()
+}
+
+/// Little-endian encoding of a field element
+pub fn fr_bytes(value: Fr) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(&value.into_bigint().to_bytes_le());
+    bytes
+}
+
+/// The field modulus itself: 32 bytes that are not a canonical field element
+pub fn non_canonical() -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(&Fr::MODULUS.to_bytes_le());
+    bytes
+}
@@ -0,0 +1,734 @@
+//! Shared harness for the solana-program-test suites
+//!
+//! The program runs natively through `processor!`, so each suite runs under
+//! whichever feature set the crate was built with (`cargo test` and
+//! `cargo test --features real-zk-verification`).
+//!
+//! Instruction builders live here rather than in `shadow-privacy-client`,
+//! which depends on this crate; account orders follow `PrivacyInstruction`.
+
+#![allow(dead_code)]
+
+pub mod groth16;
+
+use borsh::BorshSerialize;
+use shadow_privacy::{
+    instruction::PrivacyInstruction,
+    note::{NOTE_CIPHERTEXT_VERSION, VALUE_CIPHERTEXT_LEN},
+    state::{
+        AccountType, AssetState, AuditorNote, CircuitType, ConfigParams, DelayedWithdrawal, Multisig,
+        MultisigProposal, NullifierAccount, PoolLimits, PoolState, ProgramAccount, ProposalAccount, ProtocolConfig,
+        RelayerAccount, VerificationKeyAccount,
+    },
+    PrivacyError, Processor,
+};
+use solana_program::{
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    instruction::{AccountMeta, Instruction, InstructionError},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_instruction, system_program,
+};
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+pub const DENOMINATION: u64 = 1_000_000_000;
+
+pub type TestResult = Result<(), BanksClientError>;
+
+pub fn program_id() -> Pubkey {
+    shadow_privacy::id()
+}
+
+/// The program under test, run natively
+pub fn program_test() -> ProgramTest {
+    let mut program_test = ProgramTest::new("shadow_privacy", program_id(), processor!(Processor::process));
+    program_test.prefer_bpf(false);
+    program_test
+}
+
+pub async fn start() -> ProgramTestContext {
+    program_test().start_with_context().await
+}
+
+/// Send `instructions` in one transaction paid by the context payer
+///
+/// A fresh blockhash keeps repeated instructions from being deduplicated.
+pub async fn process(ctx: &mut ProgramTestContext, instructions: &[Instruction], signers: &[&Keypair]) -> TestResult {
+    let blockhash = ctx.get_new_latest_blockhash().await.unwrap();
+    let mut all_signers = vec![&ctx.payer];
+    all_signers.extend_from_slice(signers);
+    let transaction =
+        Transaction::new_signed_with_payer(instructions, Some(&ctx.payer.pubkey()), &all_signers, blockhash);
+    ctx.banks_client.process_transaction(transaction).await
+}
+
+fn instruction_error(result: TestResult) -> InstructionError {
+    match result {
+        Err(BanksClientError::TransactionError(TransactionError::InstructionError(_, error)))
+        | Err(BanksClientError::SimulationError {
+            err: TransactionError::InstructionError(_, error),
+            ..
+        }) => error,
+        other => panic!("expected an instruction error, got {:?}", other),
+    }
+}
+
+pub fn assert_program_error(result: TestResult, expected: ProgramError) {
+    assert_eq!(instruction_error(result), InstructionError::from(u64::from(expected)));
+}
+
+pub fn assert_privacy_error(result: TestResult, expected: PrivacyError) {
+    assert_program_error(result, expected.into());
+}
+
+/// A new keypair holding `lamports`
+pub async fn funded_keypair(ctx: &mut ProgramTestContext, lamports: u64) -> Keypair {
+    let keypair = Keypair::new();
+    let payer = ctx.payer.pubkey();
+    process(ctx, &[system_instruction::transfer(&payer, &keypair.pubkey(), lamports)], &[])
+        .await
+        .unwrap();
+    keypair
+}
+
+pub async fn get_account(ctx: &mut ProgramTestContext, address: &Pubkey) -> Option<Account> {
+    ctx.banks_client.get_account(*address).await.unwrap()
+}
+
+pub async fn lamports(ctx: &mut ProgramTestContext, address: &Pubkey) -> u64 {
+    get_account(ctx, address).await.map_or(0, |account| account.lamports)
+}
+
+/// Decode a program account, checking that the program owns it
+pub async fn load<T: ProgramAccount>(ctx: &mut ProgramTestContext, address: &Pubkey) -> T {
+    let account = get_account(ctx, address).await.expect("account exists");
+    assert_eq!(account.owner, program_id());
+    T::load(&account.data).unwrap()
+}
+
+pub async fn set_unix_timestamp(ctx: &mut ProgramTestContext, unix_timestamp: i64) {
+    let mut clock: solana_program::clock::Clock = ctx.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp = unix_timestamp;
+    ctx.set_sysvar(&clock);
+}
+
+pub async fn unix_timestamp(ctx: &mut ProgramTestContext) -> i64 {
+    let clock: solana_program::clock::Clock = ctx.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp
+}
+
+/// Rent for a nullifier record, which Withdraw takes out of the payout
+pub async fn record_rent(ctx: &mut ProgramTestContext) -> u64 {
+    ctx.banks_client.get_rent().await.unwrap().minimum_balance(NullifierAccount::LEN)
+}
+
+/// Config with a small nullifier cache, so tests can run past it
+pub fn test_config_params() -> ConfigParams {
+    ConfigParams {
+        nullifier_cache_size: 2,
+        key_image_cache_size: 2,
+        ..ConfigParams::default()
+    }
+}
+
+/// A well-formed (version 1, no memo) note ciphertext; the program only checks its shape
+pub fn note_ciphertext() -> Vec<u8> {
+    let mut data = vec![NOTE_CIPHERTEXT_VERSION];
+    data.resize(VALUE_CIPHERTEXT_LEN, 3);
+    data
+}
+
+/// Install the ProgramData account the upgradeable loader would create
+///
+/// `processor!` loads the program natively without one, so tests pick the
+/// upgrade authority that InitializeConfig checks (`None`: immutable program).
+pub fn set_upgrade_authority(ctx: &mut ProgramTestContext, authority: Option<Pubkey>) {
+    let state = UpgradeableLoaderState::ProgramData {
+        slot: 0,
+        upgrade_authority_address: authority,
+    };
+    let account = Account::new_data(DENOMINATION, &state, &bpf_loader_upgradeable::id()).unwrap();
+    ctx.set_account(&pda::program_data(), &account.into());
+}
+
+/// Protocol config with `admin` as its admin (and the program's upgrade authority)
+pub async fn setup_config(ctx: &mut ProgramTestContext, admin: &Keypair) {
+    set_upgrade_authority(ctx, Some(admin.pubkey()));
+    process(ctx, &[ix::initialize_config(&admin.pubkey(), test_config_params())], &[admin])
+        .await
+        .unwrap();
+}
+
+/// A funded pool authority and its initialized pool
+pub async fn setup_pool(ctx: &mut ProgramTestContext) -> (Keypair, Pubkey) {
+    let authority = funded_keypair(ctx, 10 * DENOMINATION).await;
+    process(ctx, &[ix::initialize_pool(&authority.pubkey(), DENOMINATION)], &[&authority])
+        .await
+        .unwrap();
+    let pool = pda::pool(&authority.pubkey(), DENOMINATION);
+    (authority, pool)
+}
+
+/// Config, pool, and `deposits` deposits from a funded depositor
+pub async fn setup_funded_pool(ctx: &mut ProgramTestContext, deposits: u8) -> (Keypair, Pubkey) {
+    let admin = funded_keypair(ctx, DENOMINATION).await;
+    setup_config(ctx, &admin).await;
+    let (authority, pool) = setup_pool(ctx).await;
+    let depositor = funded_keypair(ctx, (deposits as u64 + 1) * DENOMINATION).await;
+    for i in 0..deposits {
+        process(ctx, &[ix::deposit(&pool, &depositor.pubkey(), [i + 1; 32], DENOMINATION)], &[&depositor])
+            .await
+            .unwrap();
+    }
+    (authority, pool)
+}
+
+pub mod pda {
+    use super::*;
+
+    pub fn pool(authority: &Pubkey, denomination: u64) -> Pubkey {
+        PoolState::derive_address(authority, denomination, &program_id()).0
+    }
+
+    pub fn vault(pool: &Pubkey) -> Pubkey {
+        PoolState::derive_vault_address(pool, &program_id()).0
+    }
+
+    pub fn verification_key(pool: &Pubkey, circuit_type: CircuitType) -> Pubkey {
+        VerificationKeyAccount::derive_address(pool, circuit_type, &program_id()).0
+    }
+
+    pub fn nullifier(pool: &Pubkey, nullifier: &[u8; 32]) -> Pubkey {
+        PoolState::derive_nullifier_pda(pool, nullifier, &program_id()).0
+    }
+
+    pub fn key_image(pool: &Pubkey, key_image: &[u8; 32]) -> Pubkey {
+        PoolState::derive_key_image_pda(pool, key_image, &program_id()).0
+    }
+
+    pub fn delayed_withdrawal(pool: &Pubkey, nullifier: &[u8; 32]) -> Pubkey {
+        DelayedWithdrawal::derive_address(pool, nullifier, &program_id()).0
+    }
+
+    pub fn relayer(wallet: &Pubkey) -> Pubkey {
+        RelayerAccount::derive_address(wallet, &program_id()).0
+    }
+
+    pub fn config() -> Pubkey {
+        ProtocolConfig::derive_address(&program_id()).0
+    }
+
+    pub fn program_data() -> Pubkey {
+        ProtocolConfig::derive_program_data_address(&program_id()).0
+    }
+
+    pub fn asset(asset_id: &[u8; 32]) -> Pubkey {
+        AssetState::derive_address(asset_id, &program_id()).0
+    }
+
+    pub fn multisig(create_key: &Pubkey) -> Pubkey {
+        Multisig::derive_address(create_key, &program_id()).0
+    }
+
+    pub fn proposal(multisig: &Pubkey, index: u64) -> Pubkey {
+        MultisigProposal::derive_address(multisig, index, &program_id()).0
+    }
+}
+
+pub mod ix {
+    use super::*;
+
+    fn build(instruction: &PrivacyInstruction, accounts: Vec<AccountMeta>) -> Instruction {
+        Instruction::new_with_borsh(program_id(), instruction, accounts)
+    }
+
+    pub fn initialize_pool(authority: &Pubkey, denomination: u64) -> Instruction {
+        let pool = pda::pool(authority, denomination);
+        build(
+            &PrivacyInstruction::InitializePool { tree_depth: 20, denomination },
+            vec![
+                AccountMeta::new(pool, false),
+                AccountMeta::new(*authority, true),
+                AccountMeta::new(pda::vault(&pool), false),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+        )
+    }
+
+    pub fn deposit(pool: &Pubkey, depositor: &Pubkey, commitment: [u8; 32], amount: u64) -> Instruction {
+        deposit_with_note(pool, depositor, commitment, amount, note_ciphertext())
+    }
+
+    pub fn deposit_with_note(
+        pool: &Pubkey,
+        depositor: &Pubkey,
+        commitment: [u8; 32],
+        amount: u64,
+        encrypted_note: Vec<u8>,
+    ) -> Instruction {
+        deposit_instruction(pool, depositor, commitment, amount, encrypted_note, None)
+    }
+
+    pub fn deposit_with_auditor_note(
+        pool: &Pubkey,
+        depositor: &Pubkey,
+        commitment: [u8; 32],
+        amount: u64,
+        auditor_note: Option<AuditorNote>,
+    ) -> Instruction {
+        deposit_instruction(pool, depositor, commitment, amount, note_ciphertext(), auditor_note)
+    }
+
+    fn deposit_instruction(
+        pool: &Pubkey,
+        depositor: &Pubkey,
+        commitment: [u8; 32],
+        amount: u64,
+        encrypted_note: Vec<u8>,
+        auditor_note: Option<AuditorNote>,
+    ) -> Instruction {
+        build(
+            &PrivacyInstruction::Deposit {
+                commitment,
+                amount,
+                encrypted_note,
+                auditor_note,
+            },
+            vec![
+                AccountMeta::new(*pool, false),
+                AccountMeta::new(*depositor, true),
+                AccountMeta::new(pda::vault(pool), false),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(pda::verification_key(pool, CircuitType::AuditorNote), false),
+            ],
+        )
+    }
+
+    pub fn withdraw(
+        pool: &Pubkey,
+        root: [u8; 32],
+        nullifier: [u8; 32],
+        new_commitment: Option<[u8; 32]>,
+        recipient: &Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        withdraw_with_proof(pool, vec![0u8; 256], root, nullifier, new_commitment, recipient, amount)
+    }
+
+    pub fn withdraw_with_proof(
+        pool: &Pubkey,
+        proof: Vec<u8>,
+        root: [u8; 32],
+        nullifier: [u8; 32],
+        new_commitment: Option<[u8; 32]>,
+        recipient: &Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        withdraw_with_auditor_note(pool, proof, root, nullifier, new_commitment, recipient, amount, None)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn withdraw_with_auditor_note(
+        pool: &Pubkey,
+        proof: Vec<u8>,
+        root: [u8; 32],
+        nullifier: [u8; 32],
+        new_commitment: Option<[u8; 32]>,
+        recipient: &Pubkey,
+        amount: u64,
+        auditor_note: Option<AuditorNote>,
+    ) -> Instruction {
+        build(
+            &PrivacyInstruction::Withdraw {
+                proof,
+                root,
+                nullifier,
+                new_commitment,
+                recipient: *recipient,
+                amount,
+                encrypted_note: new_commitment.map(|_| note_ciphertext()),
+                auditor_note,
+            },
+            vec![
+                AccountMeta::new(*pool, false),
+                AccountMeta::new(pda::vault(pool), false),
+                AccountMeta::new(*recipient, false),
+                AccountMeta::new_readonly(pda::verification_key(pool, CircuitType::Transfer), false),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(pda::config(), false),
+                AccountMeta::new(pda::nullifier(pool, &nullifier), false),
+                AccountMeta::new(pda::delayed_withdrawal(pool, &nullifier), false),
+                AccountMeta::new_readonly(pda::verification_key(pool, CircuitType::AuditorNote), false),
+            ],
+        )
+    }
+
+    pub fn private_transfer(
+        pool: &Pubkey,
+        payer: &Pubkey,
+        ring_signature: Vec<u8>,
+        key_image: [u8; 32],
+        ring_members: Vec<[u8; 32]>,
+    ) -> Instruction {
+        private_transfer_with_note(pool, payer, ring_signature, key_image, ring_members, note_ciphertext())
+    }
+
+    pub fn private_transfer_with_note(
+        pool: &Pubkey,
+        payer: &Pubkey,
+        ring_signature: Vec<u8>,
+        key_image: [u8; 32],
+        ring_members: Vec<[u8; 32]>,
+        encrypted_amount: Vec<u8>,
+    ) -> Instruction {
+        private_transfer_instruction(pool, payer, ring_signature, key_image, ring_members, encrypted_amount, None)
+    }
+
+    pub fn private_transfer_with_auditor_note(
+        pool: &Pubkey,
+        payer: &Pubkey,
+        ring_signature: Vec<u8>,
+        key_image: [u8; 32],
+        ring_members: Vec<[u8; 32]>,
+        auditor_note: Option<AuditorNote>,
+    ) -> Instruction {
+        private_transfer_instruction(pool, payer, ring_signature, key_image, ring_members, note_ciphertext(), auditor_note)
+    }
+
+    fn private_transfer_instruction(
+        pool: &Pubkey,
+        payer: &Pubkey,
+        ring_signature: Vec<u8>,
+        key_image: [u8; 32],
+        ring_members: Vec<[u8; 32]>,
+        encrypted_amount: Vec<u8>,
+        auditor_note: Option<AuditorNote>,
+    ) -> Instruction {
+        build(
+            &PrivacyInstruction::PrivateTransfer {
+                ring_signature,
+                key_image,
+                ring_members,
+                new_commitment: [9u8; 32],
+                encrypted_amount,
+                auditor_note,
+            },
+            vec![
+                AccountMeta::new(*pool, false),
+                AccountMeta::new(pda::key_image(pool, &key_image), false),
+                AccountMeta::new(Pubkey::new_unique(), false),
+                AccountMeta::new_readonly(pda::config(), false),
+                AccountMeta::new(*payer, true),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(pda::verification_key(pool, CircuitType::AuditorNote), false),
+            ],
+        )
+    }
+
+    pub fn verify_balance(pool: &Pubkey, user: &Pubkey, min_balance: u64) -> Instruction {
+        verify_balance_with_proof(pool, user, vec![0u8; 256], min_balance, [5u8; 32])
+    }
+
+    pub fn verify_balance_with_proof(
+        pool: &Pubkey,
+        user: &Pubkey,
+        proof: Vec<u8>,
+        min_balance: u64,
+        balance_commitment: [u8; 32],
+    ) -> Instruction {
+        build(
+            &PrivacyInstruction::VerifyBalance {
+                proof,
+                min_balance,
+                balance_commitment,
+            },
+            vec![
+                AccountMeta::new_readonly(*pool, false),
+                AccountMeta::new_readonly(*user, false),
+                AccountMeta::new_readonly(pda::verification_key(pool, CircuitType::Balance), false),
+            ],
+        )
+    }
+
+    pub fn issue_asset(issuer: &Pubkey, asset_id: [u8; 32], total_supply: u64) -> Instruction {
+        build(
+            &PrivacyInstruction::IssueAsset {
+                name: "Shadow".to_string(),
+                symbol: "SHD".to_string(),
+                decimals: 9,
+                total_supply,
+                asset_id,
+            },
+            vec![
+                AccountMeta::new(*issuer, true),
+                AccountMeta::new(pda::asset(&asset_id), false),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+        )
+    }
+
+    pub fn transfer_asset(vk_pool: &Pubkey, asset_id: [u8; 32], nullifier: [u8; 32]) -> Instruction {
+        transfer_asset_with_note(vk_pool, asset_id, nullifier, note_ciphertext())
+    }
+
+    pub fn transfer_asset_with_note(
+        vk_pool: &Pubkey,
+        asset_id: [u8; 32],
+        nullifier: [u8; 32],
+        encrypted_data: Vec<u8>,
+    ) -> Instruction {
+        let asset = pda::asset(&asset_id);
+        build(
+            &PrivacyInstruction::TransferAsset {
+                proof: vec![0u8; 256],
+                asset_id,
+                nullifier,
+                new_commitment: [6u8; 32],
+                encrypted_data,
+            },
+            vec![
+                AccountMeta::new(asset, false),
+                AccountMeta::new(Pubkey::new_unique(), false),
+                AccountMeta::new(Pubkey::new_unique(), false),
+                AccountMeta::new_readonly(pda::verification_key(vk_pool, CircuitType::Transfer), false),
+            ],
+        )
+    }
+
+    /// StoreVerificationKey with the authority paying
+    pub fn store_verification_key(
+        pool: &Pubkey,
+        authority: &Pubkey,
+        circuit_type: CircuitType,
+        vk_data: Vec<u8>,
+    ) -> Instruction {
+        build(
+            &PrivacyInstruction::StoreVerificationKey {
+                circuit_type: circuit_type as u8,
+                vk_data,
+            },
+            vec![
+                AccountMeta::new(pda::verification_key(pool, circuit_type), false),
+                AccountMeta::new_readonly(*pool, false),
+                AccountMeta::new(*authority, true),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+        )
+    }
+
+    pub fn register_relayer(wallet: &Pubkey, endpoint: &str, stake: u64) -> Instruction {
+        build(
+            &PrivacyInstruction::RegisterRelayer {
+                endpoint: endpoint.to_string(),
+                stake,
+            },
+            vec![
+                AccountMeta::new(pda::relayer(wallet), false),
+                AccountMeta::new(*wallet, true),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(pda::config(), false),
+            ],
+        )
+    }
+
+    pub fn update_heartbeat(wallet: &Pubkey) -> Instruction {
+        build(
+            &PrivacyInstruction::UpdateHeartbeat,
+            vec![
+                AccountMeta::new(pda::relayer(wallet), false),
+                AccountMeta::new_readonly(*wallet, true),
+            ],
+        )
+    }
+
+    pub fn report_relay(relayer_wallet: &Pubkey, reporter: &Pubkey, success: bool) -> Instruction {
+        build(
+            &PrivacyInstruction::ReportRelay { success },
+            vec![
+                AccountMeta::new(pda::relayer(relayer_wallet), false),
+                AccountMeta::new_readonly(pda::config(), false),
+                AccountMeta::new_readonly(*reporter, true),
+            ],
+        )
+    }
+
+    pub fn migrate_relayer(wallet: &Pubkey) -> Instruction {
+        build(
+            &PrivacyInstruction::MigrateRelayer,
+            vec![
+                AccountMeta::new(pda::relayer(wallet), false),
+                AccountMeta::new(*wallet, true),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+        )
+    }
+
+    pub fn migrate(account: &Pubkey, payer: &Pubkey, account_type: AccountType) -> Instruction {
+        build(
+            &PrivacyInstruction::Migrate { account_type },
+            vec![
+                AccountMeta::new(*account, false),
+                AccountMeta::new(*payer, true),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+        )
+    }
+
+    pub fn initialize_config(admin: &Pubkey, params: ConfigParams) -> Instruction {
+        build(
+            &PrivacyInstruction::InitializeConfig { params },
+            vec![
+                AccountMeta::new(pda::config(), false),
+                AccountMeta::new(*admin, true),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(pda::program_data(), false),
+            ],
+        )
+    }
+
+    pub fn update_config(admin: &Pubkey, params: ConfigParams, new_admin: Option<Pubkey>) -> Instruction {
+        build(
+            &PrivacyInstruction::UpdateConfig { params, new_admin },
+            vec![
+                AccountMeta::new(pda::config(), false),
+                AccountMeta::new_readonly(*admin, true),
+            ],
+        )
+    }
+
+    /// Pool instruction signed by the pool authority (or guardian) only
+    pub fn pool_admin(pool: &Pubkey, signer: &Pubkey, instruction: &PrivacyInstruction) -> Instruction {
+        build(
+            instruction,
+            vec![
+                AccountMeta::new(*pool, false),
+                AccountMeta::new_readonly(*signer, true),
+            ],
+        )
+    }
+
+    pub fn update_root(pool: &Pubkey, authority: &Pubkey, new_root: [u8; 32]) -> Instruction {
+        pool_admin(pool, authority, &PrivacyInstruction::UpdateRoot { new_root })
+    }
+
+    pub fn propose_authority(pool: &Pubkey, authority: &Pubkey, new_authority: &Pubkey) -> Instruction {
+        pool_admin(pool, authority, &PrivacyInstruction::ProposeAuthority { new_authority: *new_authority })
+    }
+
+    pub fn accept_authority(pool: &Pubkey, new_authority: &Pubkey) -> Instruction {
+        pool_admin(pool, new_authority, &PrivacyInstruction::AcceptAuthority)
+    }
+
+    pub fn set_guardian(pool: &Pubkey, authority: &Pubkey, guardian: Option<Pubkey>) -> Instruction {
+        pool_admin(pool, authority, &PrivacyInstruction::SetGuardian { guardian })
+    }
+
+    pub fn set_auditor(pool: &Pubkey, authority: &Pubkey, auditor_key: Option<[[u8; 32]; 2]>) -> Instruction {
+        pool_admin(pool, authority, &PrivacyInstruction::SetAuditor { auditor_key })
+    }
+
+    pub fn pause(pool: &Pubkey, signer: &Pubkey, operations: u8, expires_at: Option<i64>) -> Instruction {
+        pool_admin(pool, signer, &PrivacyInstruction::Pause { operations, expires_at })
+    }
+
+    pub fn unpause(pool: &Pubkey, authority: &Pubkey, operations: u8) -> Instruction {
+        pool_admin(pool, authority, &PrivacyInstruction::Unpause { operations })
+    }
+
+    pub fn set_pool_limits(pool: &Pubkey, authority: &Pubkey, limits: PoolLimits) -> Instruction {
+        pool_admin(pool, authority, &PrivacyInstruction::SetPoolLimits { limits })
+    }
+
+    pub fn claim_delayed_withdrawal(pool: &Pubkey, nullifier: &[u8; 32], recipient: &Pubkey) -> Instruction {
+        build(
+            &PrivacyInstruction::ClaimDelayedWithdrawal,
+            vec![
+                AccountMeta::new(*pool, false),
+                AccountMeta::new(pda::delayed_withdrawal(pool, nullifier), false),
+                AccountMeta::new(*recipient, false),
+            ],
+        )
+    }
+
+    pub fn create_multisig(payer: &Pubkey, create_key: &Pubkey, threshold: u8, signers: Vec<Pubkey>) -> Instruction {
+        build(
+            &PrivacyInstruction::CreateMultisig {
+                create_key: *create_key,
+                threshold,
+                signers,
+            },
+            vec![
+                AccountMeta::new(pda::multisig(create_key), false),
+                AccountMeta::new(*payer, true),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+        )
+    }
+
+    pub fn create_proposal(multisig: &Pubkey, index: u64, proposer: &Pubkey, instruction: &Instruction) -> Instruction {
+        build(
+            &PrivacyInstruction::CreateProposal {
+                instruction_data: instruction.data.clone(),
+                accounts: instruction
+                    .accounts
+                    .iter()
+                    .map(|meta| ProposalAccount {
+                        pubkey: meta.pubkey,
+                        is_signer: meta.is_signer,
+                        is_writable: meta.is_writable,
+                    })
+                    .collect(),
+            },
+            vec![
+                AccountMeta::new(*multisig, false),
+                AccountMeta::new(pda::proposal(multisig, index), false),
+                AccountMeta::new(*proposer, true),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+        )
+    }
+
+    pub fn approve_proposal(multisig: &Pubkey, index: u64, member: &Pubkey) -> Instruction {
+        build(
+            &PrivacyInstruction::ApproveProposal,
+            vec![
+                AccountMeta::new_readonly(*multisig, false),
+                AccountMeta::new(pda::proposal(multisig, index), false),
+                AccountMeta::new_readonly(*member, true),
+            ],
+        )
+    }
+
+    /// Execute proposal `index`, passing `instruction`'s accounts unsigned
+    pub fn execute_proposal(multisig: &Pubkey, index: u64, instruction: &Instruction) -> Instruction {
+        let mut accounts = vec![
+            AccountMeta::new_readonly(*multisig, false),
+            AccountMeta::new(pda::proposal(multisig, index), false),
+            AccountMeta::new_readonly(program_id(), false),
+        ];
+        accounts.extend(instruction.accounts.iter().map(|meta| AccountMeta {
+            pubkey: meta.pubkey,
+            is_signer: false,
+            is_writable: meta.is_writable,
+        }));
+        build(&PrivacyInstruction::ExecuteProposal, accounts)
+    }
+}
+
+/// A program-owned account holding `state` serialized into `len` bytes
+pub fn program_account<T: BorshSerialize>(state: &T, len: usize) -> Account {
+    let mut data = state.try_to_vec().unwrap();
+    data.resize(len, 0);
+    Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: program_id(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
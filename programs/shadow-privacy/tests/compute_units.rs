@@ -0,0 +1,394 @@
+//! Compute-unit benchmarks with per-backend budgets (SBF builds)
+//!
+//! Runs each instruction at representative sizes, writes the units consumed
+//! to a report and fails if any exceeds its budget in `compute_budgets.json`,
+//! which is keyed by verifier backend:
+//!
+//! ```text
+//! cargo test-sbf --features test-bpf --test compute_units
+//! cargo test-sbf --features "test-bpf real-zk-verification" --test compute_units
+//! ```
+//!
+//! The report goes to `target/compute-units-<backend>.md`, or `$CU_REPORT`.
+//!
+//! Under `test-bpf` every case needs a budget: a `null` or missing entry
+//! fails like an exceeded one. With `CU_RECORD=1` the run instead writes the
+//! units it measured, plus a margin (20% rounded up to the next thousand),
+//! into `compute_budgets.json` for its backend; commit the result. Entries
+//! still `null` have not been recorded from an SBF run yet.
+//!
+//! Only SBF builds are metered, so `test-bpf` refuses to run natively. A
+//! plain `cargo test` skips the benchmark, and `cargo test -- --ignored` runs
+//! it natively, which exercises the harness and the expected results but
+//! checks no budget.
+//!
+//! PrivateTransfer is only measured failing: the placeholder ring verifier
+//! (see tests/verification.rs) never accepts a signature, so there is no
+//! successful transfer to meter. It still runs over the whole ring first,
+//! so the verifier's own cost is covered.
+
+mod common;
+
+use ark_bn254::Bn254;
+use ark_groth16::ProvingKey;
+use common::{groth16::*, *};
+use shadow_privacy::{
+    state::{CircuitType, ConfigParams, PoolLimits, PoolState, ZeroCopyAccount},
+    PrivacyError, Processor,
+};
+use solana_program::{
+    instruction::{Instruction, InstructionError},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use solana_program_test::{processor, tokio, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use std::{collections::BTreeMap, fmt::Write, path::PathBuf};
+
+const BACKEND: &str = if cfg!(feature = "real-zk-verification") { "groth16" } else { "demo" };
+
+const RING_SIZES: [usize; 4] = [2, 4, 8, 16];
+
+/// Requested for every measured transaction, as the client does for Groth16
+const UNIT_LIMIT: u32 = 1_400_000;
+const HEAP_FRAME_BYTES: u32 = 256 * 1024;
+
+struct Measurement {
+    case: String,
+    units: u64,
+    result: Result<(), TransactionError>,
+    expected: Result<(), PrivacyError>,
+}
+
+impl Measurement {
+    fn as_expected(&self) -> bool {
+        match (&self.result, self.expected) {
+            (Ok(()), Ok(())) => true,
+            (Err(TransactionError::InstructionError(_, error)), Err(expected)) => {
+                *error == InstructionError::from(u64::from(ProgramError::from(expected)))
+            }
+            _ => false,
+        }
+    }
+}
+
+struct Bench {
+    ctx: ProgramTestContext,
+    measurements: Vec<Measurement>,
+}
+
+impl Bench {
+    /// Process `instruction` and record its units under `case`
+    async fn measure(
+        &mut self,
+        case: &str,
+        instruction: Instruction,
+        signers: &[&Keypair],
+        expected: Result<(), PrivacyError>,
+    ) {
+        let instructions = [
+            ComputeBudgetInstruction::set_compute_unit_limit(UNIT_LIMIT),
+            ComputeBudgetInstruction::request_heap_frame(HEAP_FRAME_BYTES),
+            instruction,
+        ];
+        let blockhash = self.ctx.get_new_latest_blockhash().await.unwrap();
+        let mut all_signers = vec![&self.ctx.payer];
+        all_signers.extend_from_slice(signers);
+        let transaction =
+            Transaction::new_signed_with_payer(&instructions, Some(&self.ctx.payer.pubkey()), &all_signers, blockhash);
+        let outcome = self.ctx.banks_client.process_transaction_with_metadata(transaction).await.unwrap();
+        self.measurements.push(Measurement {
+            case: case.to_string(),
+            units: outcome.metadata.map_or(0, |metadata| metadata.compute_units_consumed),
+            result: outcome.result,
+            expected,
+        });
+    }
+
+    /// Point the pool's root at `circuit`'s, as a relayer would after its deposit
+    async fn set_root(&mut self, pool: &Pubkey, authority: &Keypair, circuit: TransferCircuit) {
+        let update = ix::update_root(pool, &authority.pubkey(), circuit.public_inputs()[0]);
+        process(&mut self.ctx, &[update], &[authority]).await.unwrap();
+    }
+
+    async fn withdraw(&mut self, case: &str, pool: &Pubkey, pk: &ProvingKey<Bn254>, circuit: TransferCircuit) -> Pubkey {
+        let [root, nullifier, new_commitment] = circuit.public_inputs();
+        let recipient = Pubkey::new_unique();
+        let proof = prove(pk, circuit);
+        let withdraw =
+            ix::withdraw_with_proof(pool, proof, root, nullifier, Some(new_commitment), &recipient, DENOMINATION);
+        self.measure(case, withdraw, &[], Ok(())).await;
+        recipient
+    }
+
+    /// Fill both caches of `pool` with entries no instruction here uses
+    async fn fill_caches(&mut self, pool: &Pubkey) {
+        let mut account = get_account(&mut self.ctx, pool).await.unwrap();
+        let state = PoolState::from_bytes_mut(&mut account.data).unwrap();
+        for i in 0..PoolState::MAX_CACHE_ENTRIES {
+            state.used_nullifiers[i] = [0xee; 32];
+            state.used_nullifiers[i][0] = i as u8;
+            state.used_key_images[i] = [0xef; 32];
+            state.used_key_images[i][0] = i as u8;
+        }
+        state.nullifier_cache_len = PoolState::MAX_CACHE_ENTRIES as u64;
+        state.key_image_cache_len = PoolState::MAX_CACHE_ENTRIES as u64;
+        self.ctx.set_account(pool, &account.into());
+    }
+}
+
+/// The program as `cargo test-sbf` builds it, natively otherwise
+fn sbf_program_test() -> ProgramTest {
+    ProgramTest::new("shadow_privacy", program_id(), processor!(Processor::process))
+}
+
+/// Whether the program runs as SBF (the same check ProgramTest makes)
+fn is_sbf() -> bool {
+    std::env::var_os("SBF_OUT_DIR").is_some() || std::env::var_os("BPF_OUT_DIR").is_some()
+}
+
+fn ring_transfer(pool: &Pubkey, payer: &Pubkey, ring_size: usize, key_image: u8) -> Instruction {
+    // The ring verifier runs to completion before rejecting these
+    let ring_members = (0..ring_size).map(|i| [i as u8 + 1; 32]).collect();
+    ix::private_transfer(pool, payer, vec![7u8; 32 * (ring_size + 1)], [key_image; 32], ring_members)
+}
+
+/// Every case the benchmark measures
+fn cases() -> Vec<String> {
+    let cases = [
+        "initialize_config",
+        "initialize_pool",
+        "store_verification_key",
+        "deposit",
+        "update_root",
+        "withdraw",
+        "withdraw_full_cache",
+        "withdraw_delayed",
+        "claim_delayed_withdrawal",
+        "private_transfer_ring_16_full_cache",
+        "verify_balance",
+    ];
+    cases
+        .iter()
+        .map(|case| case.to_string())
+        .chain(RING_SIZES.iter().map(|size| format!("private_transfer_ring_{}", size)))
+        .collect()
+}
+
+type Budgets = BTreeMap<String, BTreeMap<String, Option<u64>>>;
+
+fn budgets_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/compute_budgets.json")
+}
+
+fn load_all_budgets() -> Budgets {
+    serde_json::from_str(&std::fs::read_to_string(budgets_path()).unwrap()).unwrap()
+}
+
+/// Budget per case for this backend, None until it has been recorded
+fn load_budgets() -> BTreeMap<String, Option<u64>> {
+    load_all_budgets().remove(BACKEND).unwrap_or_else(|| panic!("no {} budgets", BACKEND))
+}
+
+/// Budget recorded for a case measured at `units`: 20% over, rounded up to
+/// the next thousand
+fn budget_with_margin(units: u64) -> u64 {
+    (units + units / 5).div_ceil(1000) * 1000
+}
+
+/// Replace this backend's budgets with the measured units plus a margin
+fn record_budgets(measurements: &[Measurement]) {
+    let mut budgets = load_all_budgets();
+    let backend = budgets.entry(BACKEND.to_string()).or_default();
+    for measurement in measurements {
+        backend.insert(measurement.case.clone(), Some(budget_with_margin(measurement.units)));
+    }
+    let json = serde_json::to_string_pretty(&budgets).unwrap();
+    std::fs::write(budgets_path(), json + "\n").unwrap();
+}
+
+fn report_path() -> PathBuf {
+    std::env::var_os("CU_REPORT").map(PathBuf::from).unwrap_or_else(|| {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(format!("target/compute-units-{}.md", BACKEND))
+    })
+}
+
+/// Markdown report and the list of failures (empty when everything passed)
+///
+/// Metered runs fail on any case over its budget or without one.
+fn report(
+    measurements: &[Measurement],
+    budgets: &BTreeMap<String, Option<u64>>,
+    metered: bool,
+) -> (String, Vec<String>) {
+    let mut failures = Vec::new();
+    let mut report = format!(
+        "# Compute units: {} backend, {}\n\n| Case | Units | Budget | Result |\n|---|---:|---:|---|\n",
+        BACKEND,
+        if metered { "SBF" } else { "native (not metered)" }
+    );
+
+    for measurement in measurements {
+        let budget = budgets.get(&measurement.case).copied().flatten();
+        let (status, failed) = if !measurement.as_expected() {
+            (format!("unexpected {:?}", measurement.result), true)
+        } else if !metered {
+            ("ok (unchecked)".to_string(), false)
+        } else {
+            match budget {
+                None => ("no budget".to_string(), true),
+                Some(budget) if measurement.units > budget => ("over budget".to_string(), true),
+                Some(_) => ("ok".to_string(), false),
+            }
+        };
+        if failed {
+            failures.push(format!("{}: {} ({} units)", measurement.case, status, measurement.units));
+        }
+        let budget = budget.map_or("none".to_string(), |budget| budget.to_string());
+        writeln!(report, "| {} | {} | {} | {} |", measurement.case, measurement.units, budget, status).unwrap();
+    }
+
+    (report, failures)
+}
+
+#[tokio::test]
+#[cfg_attr(not(feature = "test-bpf"), ignore = "needs the SBF build: cargo test-sbf --features test-bpf")]
+async fn test_compute_unit_budgets() {
+    let metered = is_sbf();
+    assert!(
+        metered || !cfg!(feature = "test-bpf"),
+        "test-bpf budgets are only checked on the SBF build: cargo test-sbf --features test-bpf"
+    );
+    let recording = metered && std::env::var_os("CU_RECORD").is_some();
+
+    let mut bench = Bench {
+        ctx: sbf_program_test().start_with_context().await,
+        measurements: Vec::new(),
+    };
+
+    let admin = funded_keypair(&mut bench.ctx, DENOMINATION).await;
+    set_upgrade_authority(&mut bench.ctx, Some(admin.pubkey()));
+    let init_config = ix::initialize_config(&admin.pubkey(), ConfigParams::default());
+    bench.measure("initialize_config", init_config, &[&admin], Ok(())).await;
+
+    let authority = funded_keypair(&mut bench.ctx, 10 * DENOMINATION).await;
+    let init_pool = ix::initialize_pool(&authority.pubkey(), DENOMINATION);
+    bench.measure("initialize_pool", init_pool, &[&authority], Ok(())).await;
+    let pool = pda::pool(&authority.pubkey(), DENOMINATION);
+
+    let (pk, vk) = setup(transfer_circuit(), SETUP_SEED);
+    let store = ix::store_verification_key(&pool, &authority.pubkey(), CircuitType::Transfer, vk_bytes(&vk));
+    bench.measure("store_verification_key", store, &[&authority], Ok(())).await;
+
+    let depositor = funded_keypair(&mut bench.ctx, 5 * DENOMINATION).await;
+    let deposit = ix::deposit(&pool, &depositor.pubkey(), [1; 32], DENOMINATION);
+    bench.measure("deposit", deposit, &[&depositor], Ok(())).await;
+    for i in 2..=3 {
+        let deposit = ix::deposit(&pool, &depositor.pubkey(), [i; 32], DENOMINATION);
+        process(&mut bench.ctx, &[deposit], &[&depositor]).await.unwrap();
+    }
+
+    let circuit = TransferCircuit::new(11, DENOMINATION, 13);
+    let update = ix::update_root(&pool, &authority.pubkey(), circuit.public_inputs()[0]);
+    bench.measure("update_root", update, &[&authority], Ok(())).await;
+    bench.withdraw("withdraw", &pool, &pk, circuit).await;
+
+    for ring_size in RING_SIZES {
+        let transfer = ring_transfer(&pool, &bench.ctx.payer.pubkey(), ring_size, ring_size as u8);
+        let case = format!("private_transfer_ring_{}", ring_size);
+        bench.measure(&case, transfer, &[], Err(PrivacyError::InvalidRingSignature)).await;
+    }
+
+    // Full caches: lookups scan every entry before missing
+    bench.fill_caches(&pool).await;
+    let circuit = TransferCircuit::new(12, DENOMINATION, 13);
+    bench.set_root(&pool, &authority, circuit).await;
+    bench.withdraw("withdraw_full_cache", &pool, &pk, circuit).await;
+    let transfer = ring_transfer(&pool, &bench.ctx.payer.pubkey(), 16, 0xaa);
+    let case = "private_transfer_ring_16_full_cache";
+    bench.measure(case, transfer, &[], Err(PrivacyError::InvalidRingSignature)).await;
+
+    // Over the epoch limit: queued, then claimed after the delay
+    let limits = PoolLimits {
+        epoch_outflow_limit: 1,
+        withdrawal_delay: 60,
+        ..PoolLimits::default()
+    };
+    process(&mut bench.ctx, &[ix::set_pool_limits(&pool, &authority.pubkey(), limits)], &[&authority])
+        .await
+        .unwrap();
+    let circuit = TransferCircuit::new(13, DENOMINATION, 13);
+    bench.set_root(&pool, &authority, circuit).await;
+    let recipient = bench.withdraw("withdraw_delayed", &pool, &pk, circuit).await;
+    let now = unix_timestamp(&mut bench.ctx).await;
+    set_unix_timestamp(&mut bench.ctx, now + 60).await;
+    let nullifier = circuit.public_inputs()[1];
+    let claim = ix::claim_delayed_withdrawal(&pool, &nullifier, &recipient);
+    bench.measure("claim_delayed_withdrawal", claim, &[], Ok(())).await;
+
+    let balance = BalanceCircuit::new(DENOMINATION, 5, 17);
+    let (balance_pk, balance_vk) = setup(balance, SETUP_SEED);
+    let store = ix::store_verification_key(&pool, &authority.pubkey(), CircuitType::Balance, vk_bytes(&balance_vk));
+    process(&mut bench.ctx, &[store], &[&authority]).await.unwrap();
+    let user = Pubkey::new_unique();
+    let verify = ix::verify_balance_with_proof(
+        &pool,
+        &user,
+        prove(&balance_pk, balance),
+        DENOMINATION,
+        balance.balance_commitment(),
+    );
+    bench.measure("verify_balance", verify, &[], Ok(())).await;
+
+    if recording {
+        record_budgets(&bench.measurements);
+    }
+    let (report, failures) = report(&bench.measurements, &load_budgets(), metered);
+    let path = report_path();
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, &report).unwrap();
+    println!("{}", report);
+
+    assert!(failures.is_empty(), "compute budgets ({}):\n{}", path.display(), failures.join("\n"));
+}
+
+#[test]
+fn test_budgets_cover_backend() {
+    let budgets = load_budgets();
+    for case in cases() {
+        let budget = budgets.get(&case).unwrap_or_else(|| panic!("no {} budget entry for {}", BACKEND, case));
+        match budget {
+            Some(budget) => assert!(*budget <= UNIT_LIMIT as u64, "{} budget is over the transaction limit", case),
+            None if cfg!(feature = "test-bpf") => panic!("{} budget for {} has not been recorded", BACKEND, case),
+            None => {}
+        }
+    }
+}
+
+#[test]
+fn test_report_enforces_budgets() {
+    let measurement = |case: &str, units| Measurement {
+        case: case.to_string(),
+        units,
+        result: Ok(()),
+        expected: Ok(()),
+    };
+    let measurements = [measurement("under", 900), measurement("over", 1_001), measurement("missing", 5)];
+    let budgets: BTreeMap<_, _> = [("under".to_string(), Some(1_000)), ("over".to_string(), Some(1_000))].into();
+
+    let (markdown, failures) = report(&measurements, &budgets, true);
+    assert_eq!(failures, ["over: over budget (1001 units)", "missing: no budget (5 units)"]);
+    assert!(markdown.contains("| under | 900 | 1000 | ok |"));
+
+    // Native runs are not metered, so only the results are checked
+    let (_, failures) = report(&measurements, &budgets, false);
+    assert!(failures.is_empty());
+
+    assert_eq!(budget_with_margin(10_000), 12_000);
+    assert_eq!(budget_with_margin(10_001), 13_000);
+    assert_eq!(budget_with_margin(0), 0);
+}
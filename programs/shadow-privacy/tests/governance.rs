@@ -0,0 +1,540 @@
+//! Protocol config, pool administration, multisig governance and account migration
+
+mod common;
+
+use common::*;
+use shadow_privacy::{
+    auditor::BASE8,
+    legacy::{AssetStateV0, PoolStateV0, PoolStateV2, PoolStateV3},
+    state::{
+        AccountType, AssetState, ConfigParams, Multisig, MultisigProposal, PoolLimits, PoolState,
+        ProgramAccount, ProtocolConfig,
+    },
+    PrivacyError,
+};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use solana_program_test::{tokio, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    signature::{Keypair, Signer},
+};
+
+#[tokio::test]
+async fn test_initialize_config_requires_upgrade_authority() {
+    let mut ctx = start().await;
+    let authority = funded_keypair(&mut ctx, DENOMINATION).await;
+    let stranger = funded_keypair(&mut ctx, DENOMINATION).await;
+    let init = |signer: &Keypair| ix::initialize_config(&signer.pubkey(), ConfigParams::default());
+
+    // No ProgramData account: the program is not upgradeable
+    let result = process(&mut ctx, &[init(&authority)], &[&authority]).await;
+    assert_privacy_error(result, PrivacyError::InvalidAccountOwner);
+
+    set_upgrade_authority(&mut ctx, Some(authority.pubkey()));
+    let result = process(&mut ctx, &[init(&stranger)], &[&stranger]).await;
+    assert_privacy_error(result, PrivacyError::Unauthorized);
+
+    let mut spoofed = init(&authority);
+    spoofed.accounts[3].pubkey = Pubkey::new_unique();
+    let result = process(&mut ctx, &[spoofed], &[&authority]).await;
+    assert_privacy_error(result, PrivacyError::InvalidPda);
+
+    // An immutable program has no authority left to initialize it
+    set_upgrade_authority(&mut ctx, None);
+    let result = process(&mut ctx, &[init(&authority)], &[&authority]).await;
+    assert_privacy_error(result, PrivacyError::Unauthorized);
+
+    // Lamports sent to the config address ahead of time do not block it
+    let payer = ctx.payer.pubkey();
+    let prefund = solana_sdk::system_instruction::transfer(&payer, &pda::config(), DENOMINATION);
+    process(&mut ctx, &[prefund], &[]).await.unwrap();
+
+    set_upgrade_authority(&mut ctx, Some(authority.pubkey()));
+    process(&mut ctx, &[init(&authority)], &[&authority]).await.unwrap();
+    let config: ProtocolConfig = load(&mut ctx, &pda::config()).await;
+    assert_eq!(config.admin, authority.pubkey());
+}
+
+#[tokio::test]
+async fn test_initialize_and_update_config() {
+    let mut ctx = start().await;
+    let admin = funded_keypair(&mut ctx, DENOMINATION).await;
+    setup_config(&mut ctx, &admin).await;
+
+    let config: ProtocolConfig = load(&mut ctx, &pda::config()).await;
+    assert_eq!(config.admin, admin.pubkey());
+    assert_eq!(config.params, test_config_params());
+
+    // The singleton cannot be re-created, not even by its admin
+    let result = process(&mut ctx, &[ix::initialize_config(&admin.pubkey(), ConfigParams::default())], &[&admin]).await;
+    assert_program_error(result, ProgramError::AccountAlreadyInitialized);
+
+    let stranger = funded_keypair(&mut ctx, DENOMINATION).await;
+    let result = process(
+        &mut ctx,
+        &[ix::update_config(&stranger.pubkey(), ConfigParams::default(), Some(stranger.pubkey()))],
+        &[&stranger],
+    )
+    .await;
+    assert_privacy_error(result, PrivacyError::Unauthorized);
+
+    let invalid = ConfigParams {
+        max_ring_size: ConfigParams::MAX_RING_SIZE_LIMIT + 1,
+        ..ConfigParams::default()
+    };
+    let result = process(&mut ctx, &[ix::update_config(&admin.pubkey(), invalid, None)], &[&admin]).await;
+    assert_privacy_error(result, PrivacyError::InvalidConfig);
+
+    // Hand the config to a new admin; the old one loses access
+    let new_admin = funded_keypair(&mut ctx, DENOMINATION).await;
+    process(
+        &mut ctx,
+        &[ix::update_config(&admin.pubkey(), ConfigParams::default(), Some(new_admin.pubkey()))],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    let config: ProtocolConfig = load(&mut ctx, &pda::config()).await;
+    assert_eq!(config.admin, new_admin.pubkey());
+    assert_eq!(config.params, ConfigParams::default());
+
+    let result = process(&mut ctx, &[ix::update_config(&admin.pubkey(), ConfigParams::default(), None)], &[&admin]).await;
+    assert_privacy_error(result, PrivacyError::Unauthorized);
+}
+
+#[tokio::test]
+async fn test_update_root() {
+    let mut ctx = start().await;
+    let (authority, pool) = setup_pool(&mut ctx).await;
+
+    process(&mut ctx, &[ix::update_root(&pool, &authority.pubkey(), [7; 32])], &[&authority])
+        .await
+        .unwrap();
+    let state: PoolState = load(&mut ctx, &pool).await;
+    assert_eq!(state.merkle_root, [7; 32]);
+
+    let stranger = funded_keypair(&mut ctx, DENOMINATION).await;
+    let result = process(&mut ctx, &[ix::update_root(&pool, &stranger.pubkey(), [8; 32])], &[&stranger]).await;
+    assert_privacy_error(result, PrivacyError::Unauthorized);
+}
+
+#[tokio::test]
+async fn test_authority_transfer() {
+    let mut ctx = start().await;
+    let (authority, pool) = setup_pool(&mut ctx).await;
+    let new_authority = funded_keypair(&mut ctx, DENOMINATION).await;
+    let stranger = funded_keypair(&mut ctx, DENOMINATION).await;
+
+    let result = process(&mut ctx, &[ix::accept_authority(&pool, &new_authority.pubkey())], &[&new_authority]).await;
+    assert_privacy_error(result, PrivacyError::NoPendingAuthority);
+
+    let propose = ix::propose_authority(&pool, &stranger.pubkey(), &stranger.pubkey());
+    let result = process(&mut ctx, &[propose], &[&stranger]).await;
+    assert_privacy_error(result, PrivacyError::Unauthorized);
+
+    let propose = ix::propose_authority(&pool, &authority.pubkey(), &new_authority.pubkey());
+    process(&mut ctx, &[propose], &[&authority]).await.unwrap();
+
+    // Only the proposed key can accept
+    let result = process(&mut ctx, &[ix::accept_authority(&pool, &stranger.pubkey())], &[&stranger]).await;
+    assert_privacy_error(result, PrivacyError::Unauthorized);
+
+    process(&mut ctx, &[ix::accept_authority(&pool, &new_authority.pubkey())], &[&new_authority])
+        .await
+        .unwrap();
+    let state: PoolState = load(&mut ctx, &pool).await;
+    assert_eq!(state.authority, new_authority.pubkey());
+    assert_eq!(state.pending_authority(), None);
+
+    let result = process(&mut ctx, &[ix::update_root(&pool, &authority.pubkey(), [1; 32])], &[&authority]).await;
+    assert_privacy_error(result, PrivacyError::Unauthorized);
+}
+
+#[tokio::test]
+async fn test_guardian_pause() {
+    let mut ctx = start().await;
+    let (authority, pool) = setup_pool(&mut ctx).await;
+    let guardian = funded_keypair(&mut ctx, DENOMINATION).await;
+    let depositor = funded_keypair(&mut ctx, 3 * DENOMINATION).await;
+
+    let result = process(&mut ctx, &[ix::set_guardian(&pool, &authority.pubkey(), Some(authority.pubkey()))], &[&authority]).await;
+    assert_privacy_error(result, PrivacyError::InvalidAccountData);
+
+    process(&mut ctx, &[ix::set_guardian(&pool, &authority.pubkey(), Some(guardian.pubkey()))], &[&authority])
+        .await
+        .unwrap();
+
+    let result = process(&mut ctx, &[ix::pause(&pool, &depositor.pubkey(), PoolState::PAUSE_ALL, None)], &[&depositor]).await;
+    assert_privacy_error(result, PrivacyError::Unauthorized);
+
+    let now = unix_timestamp(&mut ctx).await;
+    process(
+        &mut ctx,
+        &[ix::pause(&pool, &guardian.pubkey(), PoolState::PAUSE_DEPOSITS, Some(now + 3_600))],
+        &[&guardian],
+    )
+    .await
+    .unwrap();
+
+    let result = process(&mut ctx, &[ix::deposit(&pool, &depositor.pubkey(), [1; 32], DENOMINATION)], &[&depositor]).await;
+    assert_privacy_error(result, PrivacyError::PoolPaused);
+
+    // The guardian can pause but not unpause
+    let result = process(&mut ctx, &[ix::unpause(&pool, &guardian.pubkey(), PoolState::PAUSE_ALL)], &[&guardian]).await;
+    assert_privacy_error(result, PrivacyError::Unauthorized);
+
+    // Pauses lapse at their expiry
+    set_unix_timestamp(&mut ctx, now + 3_600).await;
+    process(&mut ctx, &[ix::deposit(&pool, &depositor.pubkey(), [1; 32], DENOMINATION)], &[&depositor])
+        .await
+        .unwrap();
+
+    process(&mut ctx, &[ix::pause(&pool, &guardian.pubkey(), PoolState::PAUSE_DEPOSITS, None)], &[&guardian])
+        .await
+        .unwrap();
+    let result = process(&mut ctx, &[ix::deposit(&pool, &depositor.pubkey(), [2; 32], DENOMINATION)], &[&depositor]).await;
+    assert_privacy_error(result, PrivacyError::PoolPaused);
+
+    process(&mut ctx, &[ix::unpause(&pool, &authority.pubkey(), PoolState::PAUSE_ALL)], &[&authority])
+        .await
+        .unwrap();
+    process(&mut ctx, &[ix::deposit(&pool, &depositor.pubkey(), [2; 32], DENOMINATION)], &[&depositor])
+        .await
+        .unwrap();
+
+    // Removing the guardian revokes its pause right
+    process(&mut ctx, &[ix::set_guardian(&pool, &authority.pubkey(), None)], &[&authority])
+        .await
+        .unwrap();
+    let result = process(&mut ctx, &[ix::pause(&pool, &guardian.pubkey(), PoolState::PAUSE_ALL, None)], &[&guardian]).await;
+    assert_privacy_error(result, PrivacyError::Unauthorized);
+}
+
+#[tokio::test]
+async fn test_set_pool_limits() {
+    let mut ctx = start().await;
+    let (authority, pool) = setup_pool(&mut ctx).await;
+    let depositor = funded_keypair(&mut ctx, 3 * DENOMINATION).await;
+    let limits = PoolLimits {
+        tvl_cap: DENOMINATION,
+        ..PoolLimits::default()
+    };
+
+    let result = process(&mut ctx, &[ix::set_pool_limits(&pool, &depositor.pubkey(), limits)], &[&depositor]).await;
+    assert_privacy_error(result, PrivacyError::Unauthorized);
+
+    let invalid = PoolLimits {
+        window_outflow_limit: DENOMINATION,
+        ..PoolLimits::default()
+    };
+    let result = process(&mut ctx, &[ix::set_pool_limits(&pool, &authority.pubkey(), invalid)], &[&authority]).await;
+    assert_privacy_error(result, PrivacyError::InvalidInstruction);
+
+    process(&mut ctx, &[ix::set_pool_limits(&pool, &authority.pubkey(), limits)], &[&authority])
+        .await
+        .unwrap();
+    let state: PoolState = load(&mut ctx, &pool).await;
+    assert_eq!(state.limits, limits);
+
+    process(&mut ctx, &[ix::deposit(&pool, &depositor.pubkey(), [1; 32], DENOMINATION)], &[&depositor])
+        .await
+        .unwrap();
+    let result = process(&mut ctx, &[ix::deposit(&pool, &depositor.pubkey(), [2; 32], DENOMINATION)], &[&depositor]).await;
+    assert_privacy_error(result, PrivacyError::TvlCapExceeded);
+}
+
+/// A 2-of-3 multisig, with its first member funded to pay for proposals
+async fn setup_multisig(ctx: &mut ProgramTestContext) -> (Pubkey, Vec<Keypair>) {
+    let members: Vec<Keypair> = vec![
+        funded_keypair(ctx, DENOMINATION).await,
+        Keypair::new(),
+        Keypair::new(),
+    ];
+    let create_key = Pubkey::new_unique();
+    let payer = funded_keypair(ctx, DENOMINATION).await;
+    let signers = members.iter().map(|member| member.pubkey()).collect();
+    process(ctx, &[ix::create_multisig(&payer.pubkey(), &create_key, 2, signers)], &[&payer])
+        .await
+        .unwrap();
+    (pda::multisig(&create_key), members)
+}
+
+#[tokio::test]
+async fn test_create_multisig() {
+    let mut ctx = start().await;
+    let (multisig, members) = setup_multisig(&mut ctx).await;
+
+    let state: Multisig = load(&mut ctx, &multisig).await;
+    assert_eq!(state.threshold, 2);
+    assert_eq!(state.signers.len(), 3);
+    assert!(state.is_signer(&members[1].pubkey()));
+    assert_eq!(state.proposal_count, 0);
+
+    let payer = funded_keypair(&mut ctx, DENOMINATION).await;
+    let signer = payer.pubkey();
+    for (threshold, signers) in [(0, vec![signer]), (2, vec![signer]), (1, vec![signer, signer]), (1, vec![])] {
+        let create = ix::create_multisig(&signer, &Pubkey::new_unique(), threshold, signers);
+        let result = process(&mut ctx, &[create], &[&payer]).await;
+        assert_privacy_error(result, PrivacyError::InvalidMultisig);
+    }
+}
+
+#[tokio::test]
+async fn test_multisig_governs_pool() {
+    let mut ctx = start().await;
+    let (authority, pool) = setup_pool(&mut ctx).await;
+    let (multisig, members) = setup_multisig(&mut ctx).await;
+
+    let propose = ix::propose_authority(&pool, &authority.pubkey(), &multisig);
+    process(&mut ctx, &[propose], &[&authority]).await.unwrap();
+
+    // Proposal 0: the multisig accepts the pool authority
+    let accept = ix::accept_authority(&pool, &multisig);
+    process(&mut ctx, &[ix::create_proposal(&multisig, 0, &members[0].pubkey(), &accept)], &[&members[0]])
+        .await
+        .unwrap();
+
+    // Non-members can neither propose nor approve
+    let stranger = funded_keypair(&mut ctx, DENOMINATION).await;
+    let result =
+        process(&mut ctx, &[ix::create_proposal(&multisig, 1, &stranger.pubkey(), &accept)], &[&stranger]).await;
+    assert_privacy_error(result, PrivacyError::Unauthorized);
+    let result = process(&mut ctx, &[ix::approve_proposal(&multisig, 0, &stranger.pubkey())], &[&stranger]).await;
+    assert_privacy_error(result, PrivacyError::Unauthorized);
+
+    // The proposer's approval alone is below the threshold
+    let result = process(&mut ctx, &[ix::execute_proposal(&multisig, 0, &accept)], &[]).await;
+    assert_privacy_error(result, PrivacyError::ProposalNotApproved);
+
+    process(&mut ctx, &[ix::approve_proposal(&multisig, 0, &members[1].pubkey())], &[&members[1]])
+        .await
+        .unwrap();
+    let proposal: MultisigProposal = load(&mut ctx, &pda::proposal(&multisig, 0)).await;
+    assert_eq!(proposal.approvals.len(), 2);
+
+    process(&mut ctx, &[ix::execute_proposal(&multisig, 0, &accept)], &[]).await.unwrap();
+    let state: PoolState = load(&mut ctx, &pool).await;
+    assert_eq!(state.authority, multisig);
+
+    let result = process(&mut ctx, &[ix::execute_proposal(&multisig, 0, &accept)], &[]).await;
+    assert_privacy_error(result, PrivacyError::ProposalAlreadyExecuted);
+
+    // The previous authority is locked out; the multisig now administers the pool
+    let result = process(&mut ctx, &[ix::update_root(&pool, &authority.pubkey(), [3; 32])], &[&authority]).await;
+    assert_privacy_error(result, PrivacyError::Unauthorized);
+
+    let update_root = ix::update_root(&pool, &multisig, [3; 32]);
+    process(&mut ctx, &[ix::create_proposal(&multisig, 1, &members[0].pubkey(), &update_root)], &[&members[0]])
+        .await
+        .unwrap();
+    process(&mut ctx, &[ix::approve_proposal(&multisig, 1, &members[2].pubkey())], &[&members[2]])
+        .await
+        .unwrap();
+
+    // Accounts must match the proposal
+    let mut tampered = update_root.clone();
+    tampered.accounts[0].pubkey = pda::pool(&stranger.pubkey(), DENOMINATION);
+    let result = process(&mut ctx, &[ix::execute_proposal(&multisig, 1, &tampered)], &[]).await;
+    assert_program_error(result, ProgramError::InvalidAccountData);
+
+    process(&mut ctx, &[ix::execute_proposal(&multisig, 1, &update_root)], &[]).await.unwrap();
+    let state: PoolState = load(&mut ctx, &pool).await;
+    assert_eq!(state.merkle_root, [3; 32]);
+}
+
+#[tokio::test]
+async fn test_migrate_asset() {
+    let asset_id = [9; 32];
+    let issuer = Pubkey::new_unique();
+    let legacy = AssetStateV0 {
+        asset_id,
+        issuer,
+        name: "Shadow".to_string(),
+        symbol: "SHD".to_string(),
+        decimals: 9,
+        total_supply: 1_000_000,
+        circulating_supply: 1_000_000,
+        note_tree_root: [0; 32],
+        note_count: 1,
+        used_nullifiers: vec![[5; 32]],
+        is_initialized: true,
+    };
+    // Legacy assets were created at keypair addresses, not the asset PDA
+    let address = Pubkey::new_unique();
+    let foreign = Pubkey::new_unique();
+    let mut program_test = program_test();
+    program_test.add_account(address, program_account(&legacy, 1_024));
+    program_test.add_account(foreign, Account { owner: Pubkey::new_unique(), ..program_account(&legacy, 1_024) });
+    let mut ctx = program_test.start_with_context().await;
+    let payer = funded_keypair(&mut ctx, DENOMINATION).await;
+
+    let result = process(&mut ctx, &[ix::migrate(&foreign, &payer.pubkey(), AccountType::Asset)], &[&payer]).await;
+    assert_privacy_error(result, PrivacyError::InvalidAccountOwner);
+
+    process(&mut ctx, &[ix::migrate(&address, &payer.pubkey(), AccountType::Asset)], &[&payer])
+        .await
+        .unwrap();
+
+    let account = get_account(&mut ctx, &address).await.unwrap();
+    assert_eq!(account.data.len(), AssetState::LEN);
+    let asset: AssetState = load(&mut ctx, &address).await;
+    assert_eq!(asset.version, AssetState::VERSION);
+    assert_eq!(asset.issuer, issuer);
+    assert_eq!(asset.symbol, "SHD");
+    assert_eq!(asset.used_nullifiers, vec![[5; 32]]);
+
+    // Already-current accounts are left alone
+    process(&mut ctx, &[ix::migrate(&address, &payer.pubkey(), AccountType::Asset)], &[&payer])
+        .await
+        .unwrap();
+
+    let result = process(&mut ctx, &[ix::migrate(&address, &payer.pubkey(), AccountType::Multisig)], &[&payer]).await;
+    assert_privacy_error(result, PrivacyError::InvalidInstruction);
+}
+
+#[tokio::test]
+async fn test_migrate_pool_v0() {
+    let authority = Keypair::new();
+    // Legacy pools were created at keypair addresses
+    let address = Pubkey::new_unique();
+    let legacy = |vault: Pubkey| PoolStateV0 {
+        authority: authority.pubkey(),
+        merkle_root: [0; 32],
+        tree_depth: 20,
+        commitment_count: 0,
+        denomination: DENOMINATION,
+        tvl: 0,
+        used_nullifiers: Vec::new(),
+        used_key_images: Vec::new(),
+        nullifier_count: 0,
+        key_image_count: 0,
+        vault,
+        is_initialized: true,
+    };
+    let misplaced = Pubkey::new_unique();
+    let mut program_test = program_test();
+    program_test.add_account(address, program_account(&legacy(pda::vault(&address)), 4_096));
+    program_test.add_account(pda::vault(&address), program_account(&(), 0));
+    program_test.add_account(misplaced, program_account(&legacy(pda::vault(&address)), 4_096));
+    let mut ctx = program_test.start_with_context().await;
+    let payer = funded_keypair(&mut ctx, 2 * DENOMINATION).await;
+    let admin = funded_keypair(&mut ctx, DENOMINATION).await;
+    setup_config(&mut ctx, &admin).await;
+
+    // A copy of the data elsewhere does not own the vault it names
+    let result = process(&mut ctx, &[ix::migrate(&misplaced, &payer.pubkey(), AccountType::Pool)], &[&payer]).await;
+    assert_privacy_error(result, PrivacyError::InvalidAccountData);
+
+    process(&mut ctx, &[ix::migrate(&address, &payer.pubkey(), AccountType::Pool)], &[&payer])
+        .await
+        .unwrap();
+    let pool: PoolState = load(&mut ctx, &address).await;
+    assert_eq!(pool.version, PoolState::VERSION);
+    assert_eq!(pool.authority, authority.pubkey());
+    assert_eq!(pool.vault, pda::vault(&address));
+
+    // The migrated pool keeps working at its old address
+    process(&mut ctx, &[ix::deposit(&address, &payer.pubkey(), [1; 32], DENOMINATION)], &[&payer])
+        .await
+        .unwrap();
+    let pool: PoolState = load(&mut ctx, &address).await;
+    assert_eq!(pool.commitment_count, 1);
+    assert_eq!(pool.tvl, DENOMINATION);
+}
+
+#[tokio::test]
+async fn test_migrate_pool_v2() {
+    let authority = Keypair::new();
+    let address = pda::pool(&authority.pubkey(), DENOMINATION);
+    let mut legacy = PoolState::new(authority.pubkey(), pda::vault(&address), 20, DENOMINATION);
+    legacy.version = PoolStateV2::VERSION;
+    legacy.onchain_tree = 0;
+    legacy.tvl = 3 * DENOMINATION;
+    let mut program_test = program_test();
+    program_test.add_account(
+        address,
+        Account {
+            lamports: DENOMINATION,
+            data: bytemuck::bytes_of(&legacy)[..PoolStateV2::LEN].to_vec(),
+            owner: program_id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    let mut ctx = program_test.start_with_context().await;
+    let payer = funded_keypair(&mut ctx, DENOMINATION).await;
+    let auditor_key = BASE8.mul(&[7; 32]).to_bytes();
+    let set_auditor = ix::set_auditor(&address, &authority.pubkey(), Some(auditor_key));
+
+    let result = process(&mut ctx, std::slice::from_ref(&set_auditor), &[&authority]).await;
+    assert_privacy_error(result, PrivacyError::AccountNeedsMigration);
+
+    process(&mut ctx, &[ix::migrate(&address, &payer.pubkey(), AccountType::Pool)], &[&payer])
+        .await
+        .unwrap();
+
+    let account = get_account(&mut ctx, &address).await.unwrap();
+    assert_eq!(account.data.len(), PoolState::LEN);
+    let pool: PoolState = load(&mut ctx, &address).await;
+    assert_eq!(pool.version, PoolState::VERSION);
+    assert_eq!(pool.authority, authority.pubkey());
+    assert_eq!(pool.tvl, 3 * DENOMINATION);
+    assert_eq!(pool.auditor_key(), None);
+
+    process(&mut ctx, &[set_auditor], &[&authority]).await.unwrap();
+    let pool: PoolState = load(&mut ctx, &address).await;
+    assert_eq!(pool.auditor_key(), Some(auditor_key));
+}
+
+#[tokio::test]
+async fn test_migrate_pool_v3() {
+    let authority = Keypair::new();
+    let address = pda::pool(&authority.pubkey(), DENOMINATION);
+    let mut legacy = PoolState::new(authority.pubkey(), pda::vault(&address), 20, DENOMINATION);
+    legacy.version = PoolStateV3::VERSION;
+    legacy.onchain_tree = 0;
+    legacy.merkle_root = [9; 32];
+    legacy.commitment_count = 2;
+    legacy.tvl = 2 * DENOMINATION;
+    let mut program_test = program_test();
+    program_test.add_account(
+        address,
+        Account {
+            lamports: DENOMINATION,
+            data: bytemuck::bytes_of(&legacy)[..PoolStateV3::LEN].to_vec(),
+            owner: program_id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    program_test.add_account(pda::vault(&address), program_account(&(), 0));
+    let mut ctx = program_test.start_with_context().await;
+    let payer = funded_keypair(&mut ctx, 2 * DENOMINATION).await;
+    let admin = funded_keypair(&mut ctx, DENOMINATION).await;
+    setup_config(&mut ctx, &admin).await;
+
+    process(&mut ctx, &[ix::migrate(&address, &payer.pubkey(), AccountType::Pool)], &[&payer])
+        .await
+        .unwrap();
+
+    // Earlier leaves were never hashed on-chain, so the root stays with UpdateRoot
+    let account = get_account(&mut ctx, &address).await.unwrap();
+    assert_eq!(account.data.len(), PoolState::LEN);
+    let pool: PoolState = load(&mut ctx, &address).await;
+    assert_eq!(pool.version, PoolState::VERSION);
+    assert_eq!(pool.onchain_tree, 0);
+    assert_eq!(pool.merkle_root, [9; 32]);
+
+    process(&mut ctx, &[ix::deposit(&address, &payer.pubkey(), [1; 32], DENOMINATION)], &[&payer])
+        .await
+        .unwrap();
+    let pool: PoolState = load(&mut ctx, &address).await;
+    assert_eq!(pool.commitment_count, 3);
+    assert_eq!(pool.merkle_root, [9; 32]);
+
+    process(&mut ctx, &[ix::update_root(&address, &authority.pubkey(), [10; 32])], &[&authority])
+        .await
+        .unwrap();
+    let pool: PoolState = load(&mut ctx, &address).await;
+    assert_eq!(pool.merkle_root, [10; 32]);
+}
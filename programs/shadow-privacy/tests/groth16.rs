@@ -0,0 +1,161 @@
+//! End-to-end Groth16 vectors through the Withdraw, VerifyBalance and
+//! Deposit (auditor note) instructions (real-zk-verification builds)
+//!
+//! Proofs come from the test circuits in `common::groth16`; the same vectors
+//! are checked against `verifier::verify_*` directly in `groth16_verifier.rs`.
+
+#![cfg(feature = "real-zk-verification")]
+
+mod common;
+
+use common::{groth16::*, *};
+use shadow_privacy::{
+    auditor::BASE8,
+    state::{AuditorNote, CircuitType, NullifierAccount, PoolState},
+    PrivacyError,
+};
+use solana_program::pubkey::Pubkey;
+use solana_program_test::{tokio, ProgramTestContext};
+use solana_sdk::signature::{Keypair, Signer};
+
+/// A funded pool with the transfer VK for `circuit` stored and its root set to the circuit's
+async fn setup_proven_pool(ctx: &mut ProgramTestContext, circuit: TransferCircuit) -> (Keypair, Pubkey) {
+    let (authority, pool) = setup_funded_pool(ctx, 2).await;
+    let (_, vk) = setup(circuit, SETUP_SEED);
+    let store = ix::store_verification_key(&pool, &authority.pubkey(), CircuitType::Transfer, vk_bytes(&vk));
+    process(ctx, &[store], &[&authority]).await.unwrap();
+    let root = circuit.public_inputs()[0];
+    process(ctx, &[ix::update_root(&pool, &authority.pubkey(), root)], &[&authority])
+        .await
+        .unwrap();
+    (authority, pool)
+}
+
+#[tokio::test]
+async fn test_withdraw_with_proof() {
+    let mut ctx = start().await;
+    let circuit = transfer_circuit();
+    let (_, pool) = setup_proven_pool(&mut ctx, circuit).await;
+    let (pk, _) = setup(circuit, SETUP_SEED);
+    let proof = prove(&pk, circuit);
+    let [root, nullifier, new_commitment] = circuit.public_inputs();
+    let recipient = Pubkey::new_unique();
+
+    let withdraw =
+        ix::withdraw_with_proof(&pool, proof, root, nullifier, Some(new_commitment), &recipient, DENOMINATION);
+    process(&mut ctx, std::slice::from_ref(&withdraw), &[]).await.unwrap();
+
+    let rent = record_rent(&mut ctx).await;
+    assert_eq!(lamports(&mut ctx, &recipient).await, DENOMINATION - rent);
+    let record: NullifierAccount = load(&mut ctx, &pda::nullifier(&pool, &nullifier)).await;
+    assert_eq!(record.nullifier, nullifier);
+
+    let result = process(&mut ctx, &[withdraw], &[]).await;
+    assert_privacy_error(result, PrivacyError::NullifierAlreadyUsed);
+}
+
+#[tokio::test]
+async fn test_withdraw_invalid_proof_rejected() {
+    let mut ctx = start().await;
+    let circuit = transfer_circuit();
+    let (authority, pool) = setup_proven_pool(&mut ctx, circuit).await;
+    let (pk, _) = setup(circuit, SETUP_SEED);
+    let proof = prove(&pk, circuit);
+    let [root, nullifier, new_commitment] = circuit.public_inputs();
+    let recipient = Pubkey::new_unique();
+    let withdraw = |proof: Vec<u8>, root: [u8; 32], nullifier: [u8; 32]| {
+        ix::withdraw_with_proof(&pool, proof, root, nullifier, Some(new_commitment), &recipient, DENOMINATION)
+    };
+
+    let result = process(&mut ctx, &[withdraw(tampered(&proof), root, nullifier)], &[]).await;
+    assert_privacy_error(result, PrivacyError::InvalidProof);
+
+    // A valid proof against a root the pool does not have
+    let other = TransferCircuit::new(12, DENOMINATION, 13);
+    let [other_root, other_nullifier, _] = other.public_inputs();
+    let result = process(&mut ctx, &[withdraw(prove(&pk, other), other_root, other_nullifier)], &[]).await;
+    assert_privacy_error(result, PrivacyError::InvalidMerkleRoot);
+
+    // ... or claimed against the pool's root
+    let result = process(&mut ctx, &[withdraw(prove(&pk, other), root, other_nullifier)], &[]).await;
+    assert_privacy_error(result, PrivacyError::InvalidProof);
+
+    let result = process(&mut ctx, &[withdraw(proof.clone(), root, non_canonical())], &[]).await;
+    assert_privacy_error(result, PrivacyError::InvalidPublicInputs);
+
+    // The pool's Transfer VK replaced by one for another circuit
+    let (_, balance_vk) = setup(BalanceCircuit::default(), SETUP_SEED);
+    let store = ix::store_verification_key(&pool, &authority.pubkey(), CircuitType::Transfer, vk_bytes(&balance_vk));
+    process(&mut ctx, &[store], &[&authority]).await.unwrap();
+    let result = process(&mut ctx, &[withdraw(proof, root, nullifier)], &[]).await;
+    assert_privacy_error(result, PrivacyError::InvalidProof);
+
+    assert_eq!(lamports(&mut ctx, &recipient).await, 0);
+    assert!(get_account(&mut ctx, &pda::nullifier(&pool, &nullifier)).await.is_none());
+}
+
+#[tokio::test]
+async fn test_verify_balance_with_proof() {
+    let mut ctx = start().await;
+    let (authority, pool) = setup_pool(&mut ctx).await;
+    let user = Pubkey::new_unique();
+    let circuit = BalanceCircuit::new(DENOMINATION, 5, 17);
+    let (pk, vk) = setup(circuit, SETUP_SEED);
+    let store = ix::store_verification_key(&pool, &authority.pubkey(), CircuitType::Balance, vk_bytes(&vk));
+    process(&mut ctx, &[store], &[&authority]).await.unwrap();
+    let proof = prove(&pk, circuit);
+    let commitment = circuit.balance_commitment();
+
+    let verify = ix::verify_balance_with_proof(&pool, &user, proof.clone(), DENOMINATION, commitment);
+    process(&mut ctx, &[verify], &[]).await.unwrap();
+
+    let verify = ix::verify_balance_with_proof(&pool, &user, proof.clone(), DENOMINATION + 1, commitment);
+    let result = process(&mut ctx, &[verify], &[]).await;
+    assert_privacy_error(result, PrivacyError::InvalidProof);
+
+    let verify = ix::verify_balance_with_proof(&pool, &user, tampered(&proof), DENOMINATION, commitment);
+    let result = process(&mut ctx, &[verify], &[]).await;
+    assert_privacy_error(result, PrivacyError::InvalidProof);
+
+    let verify = ix::verify_balance_with_proof(&pool, &user, proof, DENOMINATION, non_canonical());
+    let result = process(&mut ctx, &[verify], &[]).await;
+    assert_privacy_error(result, PrivacyError::InvalidPublicInputs);
+}
+
+#[tokio::test]
+async fn test_deposit_with_auditor_note_proof() {
+    let mut ctx = start().await;
+    let (authority, pool) = setup_pool(&mut ctx).await;
+    let depositor = funded_keypair(&mut ctx, 2 * DENOMINATION).await;
+    let auditor_key = BASE8.mul(&[7; 32]);
+    process(&mut ctx, &[ix::set_auditor(&pool, &authority.pubkey(), Some(auditor_key.to_bytes()))], &[&authority])
+        .await
+        .unwrap();
+
+    let circuit = AuditorNoteCircuit::new(auditor_key, DENOMINATION, 29);
+    let (pk, vk) = setup(circuit, SETUP_SEED);
+    let store = ix::store_verification_key(&pool, &authority.pubkey(), CircuitType::AuditorNote, vk_bytes(&vk));
+    process(&mut ctx, &[store], &[&authority]).await.unwrap();
+    let proof = prove(&pk, circuit);
+    let commitment = circuit.commitment();
+    let deposit = |commitment: [u8; 32], auditor_note: AuditorNote| {
+        ix::deposit_with_auditor_note(&pool, &depositor.pubkey(), commitment, DENOMINATION, Some(auditor_note))
+    };
+
+    let result = process(&mut ctx, &[deposit(commitment, circuit.note(tampered(&proof)))], &[&depositor]).await;
+    assert_privacy_error(result, PrivacyError::InvalidProof);
+
+    // The note of another commitment
+    let result = process(&mut ctx, &[deposit([1; 32], circuit.note(proof.clone()))], &[&depositor]).await;
+    assert_privacy_error(result, PrivacyError::InvalidProof);
+
+    // A ciphertext the proof does not cover
+    let mut altered = circuit.note(proof.clone());
+    altered.ciphertext[1][0] ^= 1;
+    let result = process(&mut ctx, &[deposit(commitment, altered)], &[&depositor]).await;
+    assert_privacy_error(result, PrivacyError::InvalidProof);
+
+    process(&mut ctx, &[deposit(commitment, circuit.note(proof))], &[&depositor]).await.unwrap();
+    let state: PoolState = load(&mut ctx, &pool).await;
+    assert_eq!(state.commitment_count, 1);
+}
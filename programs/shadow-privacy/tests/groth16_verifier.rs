@@ -0,0 +1,121 @@
+//! Groth16 vectors through `verifier::verify_*` (real-zk-verification builds)
+//!
+//! Kept apart from `groth16.rs`: these call the verifier outside a program
+//! invocation, which must not share a test binary with solana-program-test.
+
+#![cfg(feature = "real-zk-verification")]
+
+mod common;
+
+use ark_bn254::Fr;
+use common::{groth16::*, DENOMINATION};
+use shadow_privacy::{state::CircuitType, verifier, PrivacyError};
+
+/// Public inputs as `Vec<Vec<u8>>`, the form the verifier takes
+fn inputs<const N: usize>(inputs: [[u8; 32]; N]) -> Vec<Vec<u8>> {
+    inputs.iter().map(|input| input.to_vec()).collect()
+}
+
+#[test]
+fn test_verify_transfer_proof() {
+    let circuit = transfer_circuit();
+    let (pk, vk) = setup(circuit, SETUP_SEED);
+    let vk_data = vk_account_data(CircuitType::Transfer, &vk);
+    let proof = prove(&pk, circuit);
+
+    let result = verifier::verify_transfer_proof(&proof, &inputs(circuit.public_inputs()), &vk_data);
+    assert_eq!(result, Ok(true));
+
+    // A different witness proves different public inputs
+    let other = TransferCircuit::new(12, DENOMINATION, 13);
+    let result = verifier::verify_transfer_proof(&prove(&pk, other), &inputs(other.public_inputs()), &vk_data);
+    assert_eq!(result, Ok(true));
+}
+
+#[test]
+fn test_verify_balance_proof() {
+    let circuit = BalanceCircuit::new(DENOMINATION, 5, 17);
+    let (pk, vk) = setup(circuit, SETUP_SEED);
+    let vk_data = vk_account_data(CircuitType::Balance, &vk);
+    let proof = prove(&pk, circuit);
+
+    let mut min_balance = [0u8; 32];
+    min_balance[..8].copy_from_slice(&DENOMINATION.to_le_bytes());
+    let public_inputs = inputs([min_balance, circuit.balance_commitment()]);
+    assert_eq!(verifier::verify_balance_proof(&proof, &public_inputs, &vk_data), Ok(true));
+
+    // Claiming a higher minimum than was proven
+    min_balance[..8].copy_from_slice(&(DENOMINATION + 1).to_le_bytes());
+    let public_inputs = inputs([min_balance, circuit.balance_commitment()]);
+    assert_eq!(verifier::verify_balance_proof(&proof, &public_inputs, &vk_data), Ok(false));
+}
+
+#[test]
+fn test_tampered_proof_rejected() {
+    let circuit = transfer_circuit();
+    let (pk, vk) = setup(circuit, SETUP_SEED);
+    let vk_data = vk_account_data(CircuitType::Transfer, &vk);
+    let proof = prove(&pk, circuit);
+    let public_inputs = inputs(circuit.public_inputs());
+
+    let result = verifier::verify_transfer_proof(&tampered(&proof), &public_inputs, &vk_data);
+    assert_eq!(result, Ok(false));
+
+    let result = verifier::verify_transfer_proof(&proof[..proof.len() - 1], &public_inputs, &vk_data);
+    assert_eq!(result, Err(PrivacyError::InvalidProof.into()));
+}
+
+#[test]
+fn test_wrong_public_inputs_rejected() {
+    let circuit = transfer_circuit();
+    let (pk, vk) = setup(circuit, SETUP_SEED);
+    let vk_data = vk_account_data(CircuitType::Transfer, &vk);
+    let proof = prove(&pk, circuit);
+    let [root, nullifier, new_commitment] = circuit.public_inputs();
+
+    let wrong_root = fr_bytes(circuit.root() + Fr::from(1u64));
+    let result = verifier::verify_transfer_proof(&proof, &inputs([wrong_root, nullifier, new_commitment]), &vk_data);
+    assert_eq!(result, Ok(false));
+
+    // Inputs swapped between positions
+    let result = verifier::verify_transfer_proof(&proof, &inputs([nullifier, root, new_commitment]), &vk_data);
+    assert_eq!(result, Ok(false));
+
+    let result = verifier::verify_transfer_proof(&proof, &inputs([root, nullifier]), &vk_data);
+    assert_eq!(result, Err(PrivacyError::InvalidProof.into()));
+}
+
+#[test]
+fn test_non_canonical_field_element_rejected() {
+    let circuit = transfer_circuit();
+    let (pk, vk) = setup(circuit, SETUP_SEED);
+    let vk_data = vk_account_data(CircuitType::Transfer, &vk);
+    let proof = prove(&pk, circuit);
+    let [root, _, new_commitment] = circuit.public_inputs();
+
+    let result = verifier::verify_transfer_proof(&proof, &inputs([root, non_canonical(), new_commitment]), &vk_data);
+    assert_eq!(result, Err(PrivacyError::InvalidPublicInputs.into()));
+
+    let result = verifier::verify_transfer_proof(&proof, &inputs([root, [0xff; 32], new_commitment]), &vk_data);
+    assert_eq!(result, Err(PrivacyError::InvalidPublicInputs.into()));
+}
+
+#[test]
+fn test_verification_key_for_another_circuit_rejected() {
+    let circuit = transfer_circuit();
+    let (pk, _) = setup(circuit, SETUP_SEED);
+    let proof = prove(&pk, circuit);
+    let public_inputs = inputs(circuit.public_inputs());
+
+    // The balance circuit has a different number of public inputs
+    let (_, balance_vk) = setup(BalanceCircuit::default(), SETUP_SEED);
+    let vk_data = vk_account_data(CircuitType::Balance, &balance_vk);
+    let result = verifier::verify_transfer_proof(&proof, &public_inputs, &vk_data);
+    assert_eq!(result, Err(PrivacyError::InvalidProof.into()));
+
+    // Same circuit shape, separate setup
+    let (_, other_vk) = setup(circuit, SETUP_SEED + 1);
+    let vk_data = vk_account_data(CircuitType::Transfer, &other_vk);
+    let result = verifier::verify_transfer_proof(&proof, &public_inputs, &vk_data);
+    assert_eq!(result, Ok(false));
+}
@@ -0,0 +1,452 @@
+//! Pool lifecycle: InitializePool, Deposit, Withdraw and ClaimDelayedWithdrawal
+//!
+//! Withdrawals that need an accepted proof only run in demo builds; with
+//! `real-zk-verification` the same instructions are checked for rejecting
+//! unproven spends instead.
+
+mod common;
+
+use common::*;
+#[cfg(not(feature = "real-zk-verification"))]
+use borsh::{BorshDeserialize, BorshSerialize};
+#[cfg(not(feature = "real-zk-verification"))]
+use shadow_privacy::{instruction::PrivacyInstruction, state::NullifierAccount};
+use shadow_privacy::{
+    state::{CircuitType, PoolState},
+    PrivacyError,
+};
+use shadow_privacy_merkle::{SparseMerkleTree, TREE_DEPTH, ZERO_HASHES};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use solana_program_test::tokio;
+use solana_sdk::signature::Signer;
+
+#[tokio::test]
+async fn test_initialize_pool() {
+    let mut ctx = start().await;
+    let (authority, pool) = setup_pool(&mut ctx).await;
+
+    let state: PoolState = load(&mut ctx, &pool).await;
+    assert!(state.is_initialized());
+    assert_eq!(state.authority, authority.pubkey());
+    assert_eq!(state.vault, pda::vault(&pool));
+    assert_eq!(state.denomination, DENOMINATION);
+    assert_eq!(state.commitment_count, 0);
+    assert_eq!(state.merkle_root, ZERO_HASHES[TREE_DEPTH]);
+
+    let vault = get_account(&mut ctx, &pda::vault(&pool)).await.unwrap();
+    assert_eq!(vault.owner, program_id());
+    assert!(vault.data.is_empty());
+}
+
+#[tokio::test]
+async fn test_initialize_pool_rejects_tree_depth() {
+    let mut ctx = start().await;
+    let authority = funded_keypair(&mut ctx, 10 * DENOMINATION).await;
+
+    for depth in [0, 33] {
+        let mut init = ix::initialize_pool(&authority.pubkey(), DENOMINATION);
+        init.data[1] = depth;
+        let result = process(&mut ctx, &[init], &[&authority]).await;
+        assert_privacy_error(result, PrivacyError::InvalidTreeDepth);
+    }
+}
+
+#[tokio::test]
+async fn test_initialize_prefunded_pool() {
+    let mut ctx = start().await;
+    let authority = funded_keypair(&mut ctx, 10 * DENOMINATION).await;
+    let pool = pda::pool(&authority.pubkey(), DENOMINATION);
+
+    // Lamports sent to the pool and vault addresses ahead of time do not block the pool
+    let payer = ctx.payer.pubkey();
+    let transfers: Vec<_> = [pool, pda::vault(&pool)]
+        .iter()
+        .map(|address| solana_sdk::system_instruction::transfer(&payer, address, DENOMINATION))
+        .collect();
+    process(&mut ctx, &transfers, &[]).await.unwrap();
+
+    process(&mut ctx, &[ix::initialize_pool(&authority.pubkey(), DENOMINATION)], &[&authority])
+        .await
+        .unwrap();
+
+    let state: PoolState = load(&mut ctx, &pool).await;
+    assert_eq!(state.authority, authority.pubkey());
+    let vault = get_account(&mut ctx, &pda::vault(&pool)).await.unwrap();
+    assert_eq!(vault.owner, program_id());
+    assert_eq!(vault.lamports, DENOMINATION);
+}
+
+#[tokio::test]
+async fn test_pool_reinitialization_rejected() {
+    let mut ctx = start().await;
+    let (authority, pool) = setup_pool(&mut ctx).await;
+    let before: PoolState = load(&mut ctx, &pool).await;
+
+    let result = process(&mut ctx, &[ix::initialize_pool(&authority.pubkey(), DENOMINATION)], &[&authority]).await;
+    assert_program_error(result, ProgramError::AccountAlreadyInitialized);
+
+    // Another signer cannot claim the pool address either
+    let attacker = funded_keypair(&mut ctx, DENOMINATION).await;
+    let mut hijack = ix::initialize_pool(&attacker.pubkey(), DENOMINATION);
+    hijack.accounts[0].pubkey = pool;
+    hijack.accounts[2].pubkey = pda::vault(&pool);
+    let result = process(&mut ctx, &[hijack], &[&attacker]).await;
+    assert_privacy_error(result, PrivacyError::InvalidPda);
+
+    let after: PoolState = load(&mut ctx, &pool).await;
+    assert_eq!(after.authority, before.authority);
+    assert_eq!(after.merkle_root, before.merkle_root);
+}
+
+#[tokio::test]
+async fn test_deposit() {
+    let mut ctx = start().await;
+    let (_, pool) = setup_pool(&mut ctx).await;
+    let depositor = funded_keypair(&mut ctx, 2 * DENOMINATION).await;
+    let vault_before = lamports(&mut ctx, &pda::vault(&pool)).await;
+
+    process(&mut ctx, &[ix::deposit(&pool, &depositor.pubkey(), [1; 32], DENOMINATION)], &[&depositor])
+        .await
+        .unwrap();
+
+    let state: PoolState = load(&mut ctx, &pool).await;
+    assert_eq!(state.commitment_count, 1);
+    assert_eq!(state.tvl, DENOMINATION);
+    assert_eq!(lamports(&mut ctx, &pda::vault(&pool)).await, vault_before + DENOMINATION);
+
+    // Only the pool denomination is accepted
+    let result =
+        process(&mut ctx, &[ix::deposit(&pool, &depositor.pubkey(), [2; 32], DENOMINATION / 2)], &[&depositor]).await;
+    assert_privacy_error(result, PrivacyError::InvalidAmount);
+
+    // A deposit without a well-formed note for the depositor is rejected
+    let deposit = ix::deposit_with_note(&pool, &depositor.pubkey(), [2; 32], DENOMINATION, vec![]);
+    let result = process(&mut ctx, &[deposit], &[&depositor]).await;
+    assert_privacy_error(result, PrivacyError::InvalidNoteCiphertext);
+}
+
+#[tokio::test]
+async fn test_deposits_build_poseidon_tree() {
+    let mut ctx = start().await;
+    let (_, pool) = setup_funded_pool(&mut ctx, 3).await;
+
+    // The on-chain root is the one clients and the circuits compute
+    let mut tree = SparseMerkleTree::new(TREE_DEPTH as u8).unwrap();
+    for i in 1..=3 {
+        tree.insert([i; 32]).unwrap();
+    }
+    let state: PoolState = load(&mut ctx, &pool).await;
+    assert_eq!(state.commitment_count, 3);
+    assert_eq!(state.merkle_root, tree.root());
+}
+
+#[tokio::test]
+async fn test_non_canonical_commitment_rejected() {
+    let mut ctx = start().await;
+    let (_, pool) = setup_funded_pool(&mut ctx, 1).await;
+    let depositor = funded_keypair(&mut ctx, 2 * DENOMINATION).await;
+    let non_canonical = [0xff; 32];
+
+    // Such a leaf could never be hashed into the tree or spent
+    let result =
+        process(&mut ctx, &[ix::deposit(&pool, &depositor.pubkey(), non_canonical, DENOMINATION)], &[&depositor]).await;
+    assert_privacy_error(result, PrivacyError::InvalidCommitment);
+
+    let recipient = Pubkey::new_unique();
+    let withdraw = ix::withdraw(&pool, [0; 32], [7; 32], Some(non_canonical), &recipient, DENOMINATION);
+    let result = process(&mut ctx, &[withdraw], &[]).await;
+    assert_privacy_error(result, PrivacyError::InvalidCommitment);
+
+    let state: PoolState = load(&mut ctx, &pool).await;
+    assert_eq!(state.commitment_count, 1);
+}
+
+#[tokio::test]
+async fn test_deposit_spoofed_vault_rejected() {
+    let mut ctx = start().await;
+    let (_, pool) = setup_pool(&mut ctx).await;
+    let (_, other_pool) = setup_pool(&mut ctx).await;
+    let depositor = funded_keypair(&mut ctx, 2 * DENOMINATION).await;
+
+    // Another pool's vault, and an account the attacker controls
+    for vault in [pda::vault(&other_pool), depositor.pubkey(), Pubkey::new_unique()] {
+        let mut deposit = ix::deposit(&pool, &depositor.pubkey(), [1; 32], DENOMINATION);
+        deposit.accounts[2].pubkey = vault;
+        let result = process(&mut ctx, &[deposit], &[&depositor]).await;
+        assert!(result.is_err(), "deposit into {} accepted", vault);
+    }
+
+    let mut deposit = ix::deposit(&pool, &depositor.pubkey(), [1; 32], DENOMINATION);
+    deposit.accounts[2].pubkey = pda::vault(&other_pool);
+    let result = process(&mut ctx, &[deposit], &[&depositor]).await;
+    assert_privacy_error(result, PrivacyError::InvalidPda);
+
+    let state: PoolState = load(&mut ctx, &pool).await;
+    assert_eq!(state.tvl, 0);
+    assert_eq!(state.commitment_count, 0);
+}
+
+#[tokio::test]
+async fn test_withdraw_spoofed_vault_rejected() {
+    let mut ctx = start().await;
+    let (authority, pool) = setup_funded_pool(&mut ctx, 1).await;
+    let (_, other_pool) = setup_pool(&mut ctx).await;
+    let recipient = Pubkey::new_unique();
+    let store = ix::store_verification_key(&pool, &authority.pubkey(), CircuitType::Transfer, vec![1; 64]);
+    process(&mut ctx, &[store], &[&authority]).await.unwrap();
+
+    // Draining another pool's vault against this pool's notes
+    let mut withdraw = ix::withdraw(&pool, [0; 32], [7; 32], None, &recipient, DENOMINATION);
+    withdraw.accounts[1].pubkey = pda::vault(&other_pool);
+    let result = process(&mut ctx, &[withdraw], &[]).await;
+    assert_privacy_error(result, PrivacyError::InvalidPda);
+
+    // Recording the nullifier under another pool
+    let mut withdraw = ix::withdraw(&pool, [0; 32], [7; 32], None, &recipient, DENOMINATION);
+    withdraw.accounts[6].pubkey = pda::nullifier(&other_pool, &[7; 32]);
+    let result = process(&mut ctx, &[withdraw], &[]).await;
+    assert_privacy_error(result, PrivacyError::InvalidPda);
+
+    assert_eq!(lamports(&mut ctx, &recipient).await, 0);
+}
+
+#[cfg(not(feature = "real-zk-verification"))]
+#[tokio::test]
+async fn test_withdraw() {
+    let mut ctx = start().await;
+    let (_, pool) = setup_funded_pool(&mut ctx, 2).await;
+    let recipient = Pubkey::new_unique();
+    let nullifier = [7u8; 32];
+    let rent = record_rent(&mut ctx).await;
+    let vault_before = lamports(&mut ctx, &pda::vault(&pool)).await;
+
+    // Change needs a well-formed note for its owner, and a note needs change
+    let with_note = |new_commitment: Option<[u8; 32]>, note: Option<Vec<u8>>| {
+        let mut withdraw = ix::withdraw(&pool, [0; 32], nullifier, new_commitment, &recipient, DENOMINATION);
+        let mut data = PrivacyInstruction::try_from_slice(&withdraw.data).unwrap();
+        if let PrivacyInstruction::Withdraw { encrypted_note, .. } = &mut data {
+            *encrypted_note = note;
+        }
+        withdraw.data = data.try_to_vec().unwrap();
+        withdraw
+    };
+    for withdraw in [
+        with_note(Some([8; 32]), None),
+        with_note(Some([8; 32]), Some(vec![1, 2, 3])),
+        with_note(None, Some(note_ciphertext())),
+    ] {
+        let result = process(&mut ctx, &[withdraw], &[]).await;
+        assert_privacy_error(result, PrivacyError::InvalidNoteCiphertext);
+    }
+
+    process(&mut ctx, &[ix::withdraw(&pool, [0; 32], nullifier, Some([8; 32]), &recipient, DENOMINATION)], &[])
+        .await
+        .unwrap();
+
+    // The recipient gets the amount less the nullifier record's rent
+    assert_eq!(lamports(&mut ctx, &recipient).await, DENOMINATION - rent);
+    assert_eq!(lamports(&mut ctx, &pda::vault(&pool)).await, vault_before - DENOMINATION);
+
+    let state: PoolState = load(&mut ctx, &pool).await;
+    assert_eq!(state.tvl, DENOMINATION);
+    assert_eq!(state.nullifier_count, 1);
+    assert!(state.is_nullifier_used(&nullifier));
+    assert_eq!(state.commitment_count, 3, "change commitment appended");
+
+    let record: NullifierAccount = load(&mut ctx, &pda::nullifier(&pool, &nullifier)).await;
+    assert_eq!(record.nullifier, nullifier);
+    assert_eq!(record.pool, pool);
+}
+
+#[cfg(not(feature = "real-zk-verification"))]
+#[tokio::test]
+async fn test_withdraw_reused_nullifier_rejected() {
+    let mut ctx = start().await;
+    let (_, pool) = setup_funded_pool(&mut ctx, 2).await;
+    let recipient = Pubkey::new_unique();
+
+    process(&mut ctx, &[ix::withdraw(&pool, [0; 32], [7; 32], None, &recipient, DENOMINATION)], &[])
+        .await
+        .unwrap();
+
+    let result = process(&mut ctx, &[ix::withdraw(&pool, [0; 32], [7; 32], None, &recipient, DENOMINATION)], &[]).await;
+    assert_privacy_error(result, PrivacyError::NullifierAlreadyUsed);
+}
+
+#[cfg(not(feature = "real-zk-verification"))]
+#[tokio::test]
+async fn test_withdraw_other_amount_rejected() {
+    let mut ctx = start().await;
+    let (_, pool) = setup_funded_pool(&mut ctx, 3).await;
+    let recipient = Pubkey::new_unique();
+
+    // The proof does not cover the amount, so it must be the denomination
+    for amount in [2 * DENOMINATION, DENOMINATION - 1, u64::MAX] {
+        let result = process(&mut ctx, &[ix::withdraw(&pool, [0; 32], [7; 32], None, &recipient, amount)], &[]).await;
+        assert_privacy_error(result, PrivacyError::InvalidAmount);
+    }
+    assert_eq!(lamports(&mut ctx, &recipient).await, 0);
+}
+
+#[cfg(not(feature = "real-zk-verification"))]
+#[tokio::test]
+async fn test_withdraw_reused_nullifier_past_cache_limit_rejected() {
+    let mut ctx = start().await;
+    let cache_size = test_config_params().nullifier_cache_size as u8;
+    let (_, pool) = setup_funded_pool(&mut ctx, cache_size + 2).await;
+    let recipient = Pubkey::new_unique();
+
+    // Fill the cache, then spend one nullifier the cache cannot hold
+    for i in 0..=cache_size {
+        process(&mut ctx, &[ix::withdraw(&pool, [0; 32], [i + 1; 32], None, &recipient, DENOMINATION)], &[])
+            .await
+            .unwrap();
+    }
+    let evicted = [cache_size + 1; 32];
+    let state: PoolState = load(&mut ctx, &pool).await;
+    assert_eq!(state.nullifier_cache_len, cache_size as u64);
+    assert!(!state.is_nullifier_used(&evicted));
+
+    let vault_before = lamports(&mut ctx, &pda::vault(&pool)).await;
+    let result = process(&mut ctx, &[ix::withdraw(&pool, [0; 32], evicted, None, &recipient, DENOMINATION)], &[]).await;
+    assert_privacy_error(result, PrivacyError::NullifierAlreadyUsed);
+    assert_eq!(lamports(&mut ctx, &pda::vault(&pool)).await, vault_before);
+}
+
+#[cfg(not(feature = "real-zk-verification"))]
+#[tokio::test]
+async fn test_withdraw_prefunded_nullifier_record() {
+    let mut ctx = start().await;
+    let (_, pool) = setup_funded_pool(&mut ctx, 1).await;
+    let recipient = Pubkey::new_unique();
+    let nullifier = [7u8; 32];
+    let rent = record_rent(&mut ctx).await;
+
+    // Lamports sent to the record address ahead of time do not block the note
+    let payer = ctx.payer.pubkey();
+    let record = pda::nullifier(&pool, &nullifier);
+    let prefunded = ctx.banks_client.get_rent().await.unwrap().minimum_balance(0);
+    process(&mut ctx, &[solana_sdk::system_instruction::transfer(&payer, &record, prefunded)], &[])
+        .await
+        .unwrap();
+
+    process(&mut ctx, &[ix::withdraw(&pool, [0; 32], nullifier, None, &recipient, DENOMINATION)], &[])
+        .await
+        .unwrap();
+
+    assert_eq!(lamports(&mut ctx, &recipient).await, DENOMINATION - (rent - prefunded));
+    let record: NullifierAccount = load(&mut ctx, &record).await;
+    assert_eq!(record.nullifier, nullifier);
+}
+
+#[cfg(not(feature = "real-zk-verification"))]
+#[tokio::test]
+async fn test_delayed_withdrawal_claim() {
+    use shadow_privacy::state::{DelayedWithdrawal, PoolLimits};
+
+    let mut ctx = start().await;
+    let (authority, pool) = setup_funded_pool(&mut ctx, 2).await;
+    let limits = PoolLimits {
+        epoch_outflow_limit: DENOMINATION / 2,
+        withdrawal_delay: 3_600,
+        ..PoolLimits::default()
+    };
+    process(&mut ctx, &[ix::set_pool_limits(&pool, &authority.pubkey(), limits)], &[&authority])
+        .await
+        .unwrap();
+
+    let recipient = Pubkey::new_unique();
+    let nullifier = [7u8; 32];
+    let rent = record_rent(&mut ctx).await;
+    process(&mut ctx, &[ix::withdraw(&pool, [0; 32], nullifier, None, &recipient, DENOMINATION)], &[])
+        .await
+        .unwrap();
+
+    let queued: DelayedWithdrawal = load(&mut ctx, &pda::delayed_withdrawal(&pool, &nullifier)).await;
+    assert_eq!(queued.recipient, recipient);
+    assert_eq!(queued.amount, DENOMINATION - rent);
+    assert_eq!(lamports(&mut ctx, &recipient).await, 0);
+
+    let claim = ix::claim_delayed_withdrawal(&pool, &nullifier, &recipient);
+    let result = process(&mut ctx, std::slice::from_ref(&claim), &[]).await;
+    assert_privacy_error(result, PrivacyError::WithdrawalNotReleased);
+
+    set_unix_timestamp(&mut ctx, queued.release_at).await;
+    process(&mut ctx, &[claim], &[]).await.unwrap();
+    assert_eq!(lamports(&mut ctx, &recipient).await, DENOMINATION - rent);
+    assert_eq!(lamports(&mut ctx, &pda::delayed_withdrawal(&pool, &nullifier)).await, 0);
+}
+
+#[cfg(not(feature = "real-zk-verification"))]
+#[tokio::test]
+async fn test_delayed_withdrawal_counts_toward_outflow() {
+    use shadow_privacy::state::{DelayedWithdrawal, PoolLimits};
+
+    let mut ctx = start().await;
+    let (authority, pool) = setup_funded_pool(&mut ctx, 2).await;
+    let mut limits = PoolLimits {
+        epoch_outflow_limit: DENOMINATION / 2,
+        withdrawal_delay: 3_600,
+        ..PoolLimits::default()
+    };
+    process(&mut ctx, &[ix::set_pool_limits(&pool, &authority.pubkey(), limits)], &[&authority])
+        .await
+        .unwrap();
+
+    let recipient = Pubkey::new_unique();
+    let rent = record_rent(&mut ctx).await;
+    process(&mut ctx, &[ix::withdraw(&pool, [0; 32], [7; 32], None, &recipient, DENOMINATION)], &[])
+        .await
+        .unwrap();
+    assert_eq!(load::<PoolState>(&mut ctx, &pool).await.outflow.epoch_outflow, 0);
+
+    let queued: DelayedWithdrawal = load(&mut ctx, &pda::delayed_withdrawal(&pool, &[7; 32])).await;
+    set_unix_timestamp(&mut ctx, queued.release_at).await;
+    process(&mut ctx, &[ix::claim_delayed_withdrawal(&pool, &[7; 32], &recipient)], &[])
+        .await
+        .unwrap();
+    assert_eq!(load::<PoolState>(&mut ctx, &pool).await.outflow.epoch_outflow, DENOMINATION - rent);
+
+    // A second note fits the raised limit on its own, but not on top of the claim
+    limits.epoch_outflow_limit = DENOMINATION + DENOMINATION / 2;
+    process(&mut ctx, &[ix::set_pool_limits(&pool, &authority.pubkey(), limits)], &[&authority])
+        .await
+        .unwrap();
+    let second = Pubkey::new_unique();
+    process(&mut ctx, &[ix::withdraw(&pool, [0; 32], [8; 32], None, &second, DENOMINATION)], &[])
+        .await
+        .unwrap();
+    assert_eq!(lamports(&mut ctx, &second).await, 0);
+    assert!(get_account(&mut ctx, &pda::delayed_withdrawal(&pool, &[8; 32])).await.is_some());
+}
+
+#[cfg(feature = "real-zk-verification")]
+#[tokio::test]
+async fn test_withdraw_requires_verification_key() {
+    let mut ctx = start().await;
+    let (_, pool) = setup_funded_pool(&mut ctx, 1).await;
+    let recipient = Pubkey::new_unique();
+
+    let result = process(&mut ctx, &[ix::withdraw(&pool, [0; 32], [7; 32], None, &recipient, DENOMINATION)], &[]).await;
+    assert_privacy_error(result, PrivacyError::InvalidAccountOwner);
+}
+
+#[cfg(feature = "real-zk-verification")]
+#[tokio::test]
+async fn test_withdraw_unproven_rejected() {
+    let mut ctx = start().await;
+    let (authority, pool) = setup_funded_pool(&mut ctx, 1).await;
+    let store = ix::store_verification_key(&pool, &authority.pubkey(), CircuitType::Transfer, vec![1; 64]);
+    process(&mut ctx, &[store], &[&authority]).await.unwrap();
+    let root = load::<PoolState>(&mut ctx, &pool).await.merkle_root;
+    let recipient = Pubkey::new_unique();
+
+    let result = process(&mut ctx, &[ix::withdraw(&pool, [3; 32], [7; 32], None, &recipient, DENOMINATION)], &[]).await;
+    assert_privacy_error(result, PrivacyError::InvalidMerkleRoot);
+
+    let result = process(&mut ctx, &[ix::withdraw(&pool, root, [7; 32], None, &recipient, DENOMINATION)], &[]).await;
+    assert_privacy_error(result, PrivacyError::InvalidVerificationKey);
+
+    assert_eq!(lamports(&mut ctx, &recipient).await, 0);
+    assert!(get_account(&mut ctx, &pda::nullifier(&pool, &[7; 32])).await.is_none());
+}
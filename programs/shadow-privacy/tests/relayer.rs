@@ -0,0 +1,185 @@
+//! Relayer registry: RegisterRelayer, UpdateHeartbeat, ReportRelay and MigrateRelayer
+
+mod common;
+
+use common::*;
+use shadow_privacy::{
+    legacy::RelayerAccountV0,
+    state::{AccountType, ConfigParams, RelayerAccount},
+    PrivacyError,
+};
+use solana_program_test::tokio;
+use solana_sdk::signature::{Keypair, Signer};
+
+const ENDPOINT: &str = "https://relayer.example";
+
+/// Config (returning its admin) and a registered relayer wallet
+async fn setup_relayer(ctx: &mut solana_program_test::ProgramTestContext) -> (Keypair, Keypair) {
+    let admin = funded_keypair(ctx, DENOMINATION).await;
+    setup_config(ctx, &admin).await;
+    let wallet = funded_keypair(ctx, DENOMINATION).await;
+    let stake = ConfigParams::default().min_relayer_stake;
+    process(ctx, &[ix::register_relayer(&wallet.pubkey(), ENDPOINT, stake)], &[&wallet])
+        .await
+        .unwrap();
+    (admin, wallet)
+}
+
+#[tokio::test]
+async fn test_register_relayer() {
+    let mut ctx = start().await;
+    let (_, wallet) = setup_relayer(&mut ctx).await;
+    let stake = ConfigParams::default().min_relayer_stake;
+
+    let relayer: RelayerAccount = load(&mut ctx, &pda::relayer(&wallet.pubkey())).await;
+    assert_eq!(relayer.relayer, wallet.pubkey());
+    assert_eq!(relayer.stake, stake);
+    assert_eq!(relayer.endpoint(), ENDPOINT);
+    assert!(relayer.is_active());
+
+    // Stake is held on top of rent
+    let rent = ctx.banks_client.get_rent().await.unwrap().minimum_balance(RelayerAccount::LEN);
+    assert_eq!(lamports(&mut ctx, &pda::relayer(&wallet.pubkey())).await, rent + stake);
+
+    let result = process(&mut ctx, &[ix::register_relayer(&wallet.pubkey(), ENDPOINT, stake)], &[&wallet]).await;
+    assert_privacy_error(result, PrivacyError::InvalidAccountData);
+
+    let underfunded = funded_keypair(&mut ctx, DENOMINATION).await;
+    let result =
+        process(&mut ctx, &[ix::register_relayer(&underfunded.pubkey(), ENDPOINT, stake - 1)], &[&underfunded]).await;
+    assert_privacy_error(result, PrivacyError::InvalidAmount);
+}
+
+#[tokio::test]
+async fn test_update_heartbeat() {
+    let mut ctx = start().await;
+    let (_, wallet) = setup_relayer(&mut ctx).await;
+    let later = unix_timestamp(&mut ctx).await + 600;
+    set_unix_timestamp(&mut ctx, later).await;
+
+    process(&mut ctx, &[ix::update_heartbeat(&wallet.pubkey())], &[&wallet]).await.unwrap();
+    let relayer: RelayerAccount = load(&mut ctx, &pda::relayer(&wallet.pubkey())).await;
+    assert_eq!(relayer.last_heartbeat, later);
+
+    // Another wallet cannot keep this relayer looking online
+    let other = funded_keypair(&mut ctx, DENOMINATION).await;
+    let mut heartbeat = ix::update_heartbeat(&wallet.pubkey());
+    heartbeat.accounts[1].pubkey = other.pubkey();
+    let result = process(&mut ctx, &[heartbeat], &[&other]).await;
+    assert_privacy_error(result, PrivacyError::Unauthorized);
+}
+
+#[tokio::test]
+async fn test_report_relay() {
+    let mut ctx = start().await;
+    let (admin, wallet) = setup_relayer(&mut ctx).await;
+
+    process(&mut ctx, &[ix::report_relay(&wallet.pubkey(), &admin.pubkey(), true)], &[&admin])
+        .await
+        .unwrap();
+    process(&mut ctx, &[ix::report_relay(&wallet.pubkey(), &admin.pubkey(), false)], &[&admin])
+        .await
+        .unwrap();
+
+    // A relayer may report its own failures
+    process(&mut ctx, &[ix::report_relay(&wallet.pubkey(), &wallet.pubkey(), false)], &[&wallet])
+        .await
+        .unwrap();
+
+    let relayer: RelayerAccount = load(&mut ctx, &pda::relayer(&wallet.pubkey())).await;
+    assert_eq!(relayer.successful_relays, 1);
+    assert_eq!(relayer.failed_relays, 2);
+}
+
+#[tokio::test]
+async fn test_report_relay_abuse_rejected() {
+    let mut ctx = start().await;
+    let (admin, wallet) = setup_relayer(&mut ctx).await;
+    let stranger = funded_keypair(&mut ctx, DENOMINATION).await;
+
+    // A relayer inflating its own reputation
+    for _ in 0..3 {
+        let result = process(&mut ctx, &[ix::report_relay(&wallet.pubkey(), &wallet.pubkey(), true)], &[&wallet]).await;
+        assert_privacy_error(result, PrivacyError::Unauthorized);
+    }
+
+    // Anyone but the config admin
+    for success in [true, false] {
+        let report = ix::report_relay(&wallet.pubkey(), &stranger.pubkey(), success);
+        let result = process(&mut ctx, &[report], &[&stranger]).await;
+        assert_privacy_error(result, PrivacyError::Unauthorized);
+    }
+
+    // The authority of a pool the attacker just created, reporting on
+    // another wallet's relayer, directly or with the pool in place of the config
+    let (attacker, attacker_pool) = setup_pool(&mut ctx).await;
+    for success in [true, false] {
+        let report = ix::report_relay(&wallet.pubkey(), &attacker.pubkey(), success);
+        let result = process(&mut ctx, &[report], &[&attacker]).await;
+        assert_privacy_error(result, PrivacyError::Unauthorized);
+
+        let mut report = ix::report_relay(&wallet.pubkey(), &attacker.pubkey(), success);
+        report.accounts[1].pubkey = attacker_pool;
+        let result = process(&mut ctx, &[report], &[&attacker]).await;
+        assert_privacy_error(result, PrivacyError::InvalidPda);
+    }
+
+    // Reporting against a relayer address that is not the relayer PDA
+    let mut report = ix::report_relay(&wallet.pubkey(), &admin.pubkey(), false);
+    report.accounts[0].pubkey = pda::config();
+    let result = process(&mut ctx, &[report], &[&admin]).await;
+    assert_privacy_error(result, PrivacyError::InvalidAccountData);
+
+    let relayer: RelayerAccount = load(&mut ctx, &pda::relayer(&wallet.pubkey())).await;
+    assert_eq!(relayer.successful_relays, 0);
+    assert_eq!(relayer.failed_relays, 0);
+}
+
+#[tokio::test]
+async fn test_migrate_relayer() {
+    let wallet = Keypair::new();
+    let (address, bump) = RelayerAccount::derive_address(&wallet.pubkey(), &program_id());
+    let legacy = RelayerAccountV0 {
+        relayer: wallet.pubkey(),
+        stake: 100_000_000,
+        successful_relays: 12,
+        failed_relays: 3,
+        last_heartbeat: 1_700_000_000,
+        is_active: true,
+        registered_at: 1_690_000_000,
+        endpoint: ENDPOINT.to_string(),
+        bump,
+    };
+    let mut program_test = program_test();
+    program_test.add_account(address, program_account(&legacy, RelayerAccountV0::LEN));
+    let mut ctx = program_test.start_with_context().await;
+    let payer = ctx.payer.pubkey();
+    process(&mut ctx, &[solana_sdk::system_instruction::transfer(&payer, &wallet.pubkey(), DENOMINATION)], &[])
+        .await
+        .unwrap();
+
+    // Neither the legacy nor the current relayer decodes as another type's legacy layout
+    for account_type in [AccountType::Pool, AccountType::Asset] {
+        let result = process(&mut ctx, &[ix::migrate(&address, &wallet.pubkey(), account_type)], &[&wallet]).await;
+        assert!(result.is_err());
+    }
+
+    process(&mut ctx, &[ix::migrate_relayer(&wallet.pubkey())], &[&wallet]).await.unwrap();
+
+    for account_type in [AccountType::Pool, AccountType::Asset] {
+        let result = process(&mut ctx, &[ix::migrate(&address, &wallet.pubkey(), account_type)], &[&wallet]).await;
+        assert_privacy_error(result, PrivacyError::InvalidAccountData);
+    }
+
+    let account = get_account(&mut ctx, &address).await.unwrap();
+    assert_eq!(account.data.len(), RelayerAccount::LEN);
+    let relayer: RelayerAccount = load(&mut ctx, &address).await;
+    assert_eq!(relayer.relayer, wallet.pubkey());
+    assert_eq!(relayer.stake, legacy.stake);
+    assert_eq!(relayer.successful_relays, 12);
+    assert_eq!(relayer.failed_relays, 3);
+    assert_eq!(relayer.endpoint(), ENDPOINT);
+
+    // Migrating again leaves the account alone
+    process(&mut ctx, &[ix::migrate_relayer(&wallet.pubkey())], &[&wallet]).await.unwrap();
+}
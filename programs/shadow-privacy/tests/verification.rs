@@ -0,0 +1,208 @@
+//! Verification keys and proof-checked instructions: StoreVerificationKey,
+//! VerifyBalance and PrivateTransfer
+//!
+//! PrivateTransfer has no happy path here: the ring verifier only accepts a
+//! signature whose keccak challenge chain closes on itself, which cannot be
+//! constructed, so only its rejections are covered.
+
+mod common;
+
+use common::*;
+use borsh::{BorshDeserialize, BorshSerialize};
+use shadow_privacy::{
+    instruction::PrivacyInstruction,
+    state::{CircuitType, KeyImageAccount, PoolState, ProgramAccount, VerificationKeyAccount, ZeroCopyAccount},
+    PrivacyError,
+};
+use solana_program_test::tokio;
+use solana_sdk::signature::Signer;
+
+#[tokio::test]
+async fn test_store_verification_key() {
+    let mut ctx = start().await;
+    let (authority, pool) = setup_pool(&mut ctx).await;
+    let address = pda::verification_key(&pool, CircuitType::Transfer);
+
+    let store = ix::store_verification_key(&pool, &authority.pubkey(), CircuitType::Transfer, vec![1; 64]);
+    process(&mut ctx, &[store], &[&authority]).await.unwrap();
+    let vk: VerificationKeyAccount = load(&mut ctx, &address).await;
+    assert_eq!(vk.pool, pool);
+    assert_eq!(vk.authority, authority.pubkey());
+    assert_eq!(vk.circuit_type, CircuitType::Transfer);
+    assert_eq!(vk.vk_data, vec![1; 64]);
+
+    // The authority can rotate the key in place
+    let store = ix::store_verification_key(&pool, &authority.pubkey(), CircuitType::Transfer, vec![2; 96]);
+    process(&mut ctx, &[store], &[&authority]).await.unwrap();
+    let vk: VerificationKeyAccount = load(&mut ctx, &address).await;
+    assert_eq!(vk.vk_data, vec![2; 96]);
+
+    let oversized = vec![0; VerificationKeyAccount::MAX_VK_SIZE + 1];
+    let store = ix::store_verification_key(&pool, &authority.pubkey(), CircuitType::Balance, oversized);
+    let result = process(&mut ctx, &[store], &[&authority]).await;
+    assert_privacy_error(result, PrivacyError::InvalidVerificationKey);
+}
+
+#[tokio::test]
+async fn test_unauthorized_verification_key_store_rejected() {
+    let mut ctx = start().await;
+    let (authority, pool) = setup_pool(&mut ctx).await;
+    let store = ix::store_verification_key(&pool, &authority.pubkey(), CircuitType::Transfer, vec![1; 64]);
+    process(&mut ctx, &[store], &[&authority]).await.unwrap();
+
+    // Overwriting another pool's key
+    let attacker = funded_keypair(&mut ctx, 10 * DENOMINATION).await;
+    let store = ix::store_verification_key(&pool, &attacker.pubkey(), CircuitType::Transfer, vec![6; 64]);
+    let result = process(&mut ctx, &[store], &[&attacker]).await;
+    assert_privacy_error(result, PrivacyError::Unauthorized);
+
+    // Creating a key that does not exist yet
+    let store = ix::store_verification_key(&pool, &attacker.pubkey(), CircuitType::Balance, vec![6; 64]);
+    let result = process(&mut ctx, &[store], &[&attacker]).await;
+    assert_privacy_error(result, PrivacyError::Unauthorized);
+
+    // Authorizing through the attacker's own pool while writing the victim's key
+    process(&mut ctx, &[ix::initialize_pool(&attacker.pubkey(), DENOMINATION)], &[&attacker])
+        .await
+        .unwrap();
+    let own_pool = pda::pool(&attacker.pubkey(), DENOMINATION);
+    let mut store = ix::store_verification_key(&own_pool, &attacker.pubkey(), CircuitType::Transfer, vec![6; 64]);
+    store.accounts[0].pubkey = pda::verification_key(&pool, CircuitType::Transfer);
+    let result = process(&mut ctx, &[store], &[&attacker]).await;
+    assert_privacy_error(result, PrivacyError::InvalidPda);
+
+    // Key rotation is blocked while VK updates are paused
+    process(&mut ctx, &[ix::pause(&pool, &authority.pubkey(), PoolState::PAUSE_VK_UPDATES, None)], &[&authority])
+        .await
+        .unwrap();
+    let store = ix::store_verification_key(&pool, &authority.pubkey(), CircuitType::Transfer, vec![7; 64]);
+    let result = process(&mut ctx, &[store], &[&authority]).await;
+    assert_privacy_error(result, PrivacyError::PoolPaused);
+
+    let vk: VerificationKeyAccount = load(&mut ctx, &pda::verification_key(&pool, CircuitType::Transfer)).await;
+    assert_eq!(vk.authority, authority.pubkey());
+    assert_eq!(vk.vk_data, vec![1; 64]);
+}
+
+#[cfg(not(feature = "real-zk-verification"))]
+#[tokio::test]
+async fn test_verify_balance() {
+    let mut ctx = start().await;
+    let (_, pool) = setup_pool(&mut ctx).await;
+    let user = funded_keypair(&mut ctx, DENOMINATION).await;
+
+    process(&mut ctx, &[ix::verify_balance(&pool, &user.pubkey(), DENOMINATION)], &[]).await.unwrap();
+
+    let mut spoofed = ix::verify_balance(&pool, &user.pubkey(), DENOMINATION);
+    spoofed.accounts[2].pubkey = pda::verification_key(&pool, CircuitType::Transfer);
+    let result = process(&mut ctx, &[spoofed], &[]).await;
+    assert_privacy_error(result, PrivacyError::InvalidPda);
+}
+
+#[cfg(feature = "real-zk-verification")]
+#[tokio::test]
+async fn test_verify_balance_unproven_rejected() {
+    let mut ctx = start().await;
+    let (authority, pool) = setup_pool(&mut ctx).await;
+    let user = funded_keypair(&mut ctx, DENOMINATION).await;
+
+    let result = process(&mut ctx, &[ix::verify_balance(&pool, &user.pubkey(), DENOMINATION)], &[]).await;
+    assert_privacy_error(result, PrivacyError::InvalidAccountOwner);
+
+    let store = ix::store_verification_key(&pool, &authority.pubkey(), CircuitType::Balance, vec![1; 64]);
+    process(&mut ctx, &[store], &[&authority]).await.unwrap();
+    let result = process(&mut ctx, &[ix::verify_balance(&pool, &user.pubkey(), DENOMINATION)], &[]).await;
+    assert_privacy_error(result, PrivacyError::InvalidVerificationKey);
+}
+
+#[tokio::test]
+async fn test_private_transfer_rejected() {
+    let mut ctx = start().await;
+    let (authority, pool) = setup_funded_pool(&mut ctx, 1).await;
+    let max_ring_size = test_config_params().max_ring_size as usize;
+    let payer = ctx.payer.pubkey();
+
+    // Well-formed, but the challenge chain does not close
+    let transfer = ix::private_transfer(&pool, &payer, vec![0; 32 + 11 * 32], [1; 32], vec![[2; 32]; 11]);
+    let result = process(&mut ctx, &[transfer], &[]).await;
+    assert_privacy_error(result, PrivacyError::InvalidRingSignature);
+
+    let ring = vec![[2; 32]; max_ring_size + 1];
+    let transfer = ix::private_transfer(&pool, &payer, vec![0; 32 + ring.len() * 32], [1; 32], ring);
+    let result = process(&mut ctx, &[transfer], &[]).await;
+    assert_privacy_error(result, PrivacyError::InvalidRingSize);
+
+    let transfer = ix::private_transfer(&pool, &payer, vec![0; 64], [1; 32], vec![[2; 32]; 11]);
+    let result = process(&mut ctx, &[transfer], &[]).await;
+    assert_privacy_error(result, PrivacyError::InvalidSignature);
+
+    // The recipient's note must be a well-formed ciphertext
+    let transfer =
+        ix::private_transfer_with_note(&pool, &payer, vec![0; 32 + 11 * 32], [1; 32], vec![[2; 32]; 11], vec![1, 2, 3]);
+    let result = process(&mut ctx, &[transfer], &[]).await;
+    assert_privacy_error(result, PrivacyError::InvalidNoteCiphertext);
+
+    // So must the recipient's commitment be a field element
+    let mut transfer = ix::private_transfer(&pool, &payer, vec![0; 32 + 11 * 32], [1; 32], vec![[2; 32]; 11]);
+    let mut data = PrivacyInstruction::try_from_slice(&transfer.data).unwrap();
+    if let PrivacyInstruction::PrivateTransfer { new_commitment, .. } = &mut data {
+        *new_commitment = [0xff; 32];
+    }
+    transfer.data = data.try_to_vec().unwrap();
+    let result = process(&mut ctx, &[transfer], &[]).await;
+    assert_privacy_error(result, PrivacyError::InvalidCommitment);
+
+    process(&mut ctx, &[ix::pause(&pool, &authority.pubkey(), PoolState::PAUSE_TRANSFERS, None)], &[&authority])
+        .await
+        .unwrap();
+    let transfer = ix::private_transfer(&pool, &payer, vec![0; 32 + 11 * 32], [1; 32], vec![[2; 32]; 11]);
+    let result = process(&mut ctx, &[transfer], &[]).await;
+    assert_privacy_error(result, PrivacyError::PoolPaused);
+
+    let state: PoolState = load(&mut ctx, &pool).await;
+    assert_eq!(state.commitment_count, 1);
+}
+
+#[tokio::test]
+async fn test_private_transfer_reused_key_image_past_cache_limit_rejected() {
+    let mut ctx = start().await;
+    let (_, pool) = setup_funded_pool(&mut ctx, 1).await;
+    let payer = ctx.payer.pubkey();
+    let ring = vec![[2; 32]; 11];
+
+    // A full cache, and a key image it cannot hold that was spent anyway
+    let mut account = get_account(&mut ctx, &pool).await.unwrap();
+    let state = PoolState::from_bytes_mut(&mut account.data).unwrap();
+    state.used_key_images = [[3; 32]; PoolState::MAX_CACHE_ENTRIES];
+    state.key_image_cache_len = test_config_params().key_image_cache_size as u64;
+    ctx.set_account(&pool, &account.into());
+    let spent = [4; 32];
+    let (record, bump) = PoolState::derive_key_image_pda(&pool, &spent, &program_id());
+    let spent_record = KeyImageAccount {
+        discriminator: KeyImageAccount::DISCRIMINATOR,
+        version: KeyImageAccount::VERSION,
+        key_image: spent,
+        pool,
+        tx_signature: None,
+        timestamp: 0,
+        bump,
+    };
+    ctx.set_account(&record, &program_account(&spent_record, KeyImageAccount::LEN).into());
+
+    let transfer = ix::private_transfer(&pool, &payer, vec![0; 32 + 11 * 32], spent, ring.clone());
+    let result = process(&mut ctx, &[transfer], &[]).await;
+    assert_privacy_error(result, PrivacyError::KeyImageAlreadyUsed);
+
+    // Key images missing from both still reach the ring signature, even if
+    // their record address was pre-funded
+    let fresh = [5; 32];
+    let prefunded = ctx.banks_client.get_rent().await.unwrap().minimum_balance(0);
+    let transfer = solana_sdk::system_instruction::transfer(&payer, &pda::key_image(&pool, &fresh), prefunded);
+    process(&mut ctx, &[transfer], &[]).await.unwrap();
+    let transfer = ix::private_transfer(&pool, &payer, vec![0; 32 + 11 * 32], fresh, ring);
+    let result = process(&mut ctx, &[transfer], &[]).await;
+    assert_privacy_error(result, PrivacyError::InvalidRingSignature);
+
+    let state: PoolState = load(&mut ctx, &pool).await;
+    assert_eq!(state.key_image_count, 0);
+}
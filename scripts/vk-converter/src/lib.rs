@@ -0,0 +1,124 @@
+//! Convert snarkjs `verification_key.json` files into the compressed
+//! ark-groth16 `VerifyingKey<Bn254>` bytes the program stores in VK accounts
+
+use ark_bn254::{Bn254, Fq, Fq2, G1Affine, G2Affine};
+use ark_ff::{PrimeField, Zero};
+use ark_groth16::VerifyingKey;
+use ark_serialize::CanonicalSerialize;
+use num_bigint::BigUint;
+use num_traits::Num;
+use serde::Deserialize;
+use std::fmt;
+
+#[derive(Debug, Deserialize)]
+struct SnarkJsVk {
+    protocol: String,
+    curve: String,
+    #[serde(rename = "nPublic")]
+    n_public: usize,
+    vk_alpha_1: Vec<String>,
+    vk_beta_2: Vec<Vec<String>>,
+    vk_gamma_2: Vec<Vec<String>>,
+    vk_delta_2: Vec<Vec<String>>,
+    #[serde(rename = "IC")]
+    ic: Vec<Vec<String>>,
+}
+
+#[derive(Debug)]
+pub enum ConvertError {
+    Json(serde_json::Error),
+    Unsupported(String),
+    InvalidPoint(&'static str),
+    Serialize(String),
+}
+
+impl fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConvertError::Json(error) => write!(f, "invalid verification key JSON: {}", error),
+            ConvertError::Unsupported(what) => write!(f, "unsupported verification key: {}", what),
+            ConvertError::InvalidPoint(name) => write!(f, "invalid curve point {}", name),
+            ConvertError::Serialize(error) => write!(f, "failed to serialize: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for ConvertError {}
+
+fn parse_fq(s: &str, name: &'static str) -> Result<Fq, ConvertError> {
+    let s = s.strip_prefix("0x").unwrap_or(s);
+    let n = BigUint::from_str_radix(s, 10).map_err(|_| ConvertError::InvalidPoint(name))?;
+    Ok(Fq::from_le_bytes_mod_order(&n.to_bytes_le()))
+}
+
+// snarkjs writes projective coordinates [x, y, z]; z = 0 is the point at infinity
+fn parse_g1(pt: &[String], name: &'static str) -> Result<G1Affine, ConvertError> {
+    if pt.len() != 3 {
+        return Err(ConvertError::InvalidPoint(name));
+    }
+    if parse_fq(&pt[2], name)?.is_zero() {
+        return Ok(G1Affine::identity());
+    }
+
+    let point = G1Affine::new_unchecked(parse_fq(&pt[0], name)?, parse_fq(&pt[1], name)?);
+    if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(ConvertError::InvalidPoint(name));
+    }
+    Ok(point)
+}
+
+// [[x0, x1], [y0, y1], [z0, z1]] with x = x0 + u*x1
+fn parse_g2(pt: &[Vec<String>], name: &'static str) -> Result<G2Affine, ConvertError> {
+    if pt.len() != 3 || pt.iter().any(|c| c.len() != 2) {
+        return Err(ConvertError::InvalidPoint(name));
+    }
+    let fq2 = |c: &[String]| -> Result<Fq2, ConvertError> { Ok(Fq2::new(parse_fq(&c[0], name)?, parse_fq(&c[1], name)?)) };
+    if fq2(&pt[2])?.is_zero() {
+        return Ok(G2Affine::identity());
+    }
+
+    let point = G2Affine::new_unchecked(fq2(&pt[0])?, fq2(&pt[1])?);
+    if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(ConvertError::InvalidPoint(name));
+    }
+    Ok(point)
+}
+
+/// Parse a snarkjs Groth16/BN254 verification key
+pub fn parse_snarkjs_vk(json: &str) -> Result<VerifyingKey<Bn254>, ConvertError> {
+    let vk: SnarkJsVk = serde_json::from_str(json).map_err(ConvertError::Json)?;
+    if vk.protocol != "groth16" {
+        return Err(ConvertError::Unsupported(format!("protocol {}", vk.protocol)));
+    }
+    if vk.curve != "bn128" {
+        return Err(ConvertError::Unsupported(format!("curve {}", vk.curve)));
+    }
+    if vk.ic.len() != vk.n_public + 1 {
+        return Err(ConvertError::Unsupported(format!(
+            "{} IC points for {} public inputs",
+            vk.ic.len(),
+            vk.n_public
+        )));
+    }
+
+    Ok(VerifyingKey::<Bn254> {
+        alpha_g1: parse_g1(&vk.vk_alpha_1, "vk_alpha_1")?,
+        beta_g2: parse_g2(&vk.vk_beta_2, "vk_beta_2")?,
+        gamma_g2: parse_g2(&vk.vk_gamma_2, "vk_gamma_2")?,
+        delta_g2: parse_g2(&vk.vk_delta_2, "vk_delta_2")?,
+        gamma_abc_g1: vk
+            .ic
+            .iter()
+            .map(|pt| parse_g1(pt, "IC"))
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+/// Compressed ark-groth16 bytes for a snarkjs verification key
+pub fn convert_snarkjs_vk(json: &str) -> Result<Vec<u8>, ConvertError> {
+    let vk = parse_snarkjs_vk(json)?;
+    let mut bytes = Vec::new();
+    vk.serialize_compressed(&mut bytes)
+        .map_err(|error| ConvertError::Serialize(error.to_string()))?;
+    Ok(bytes)
+}